@@ -0,0 +1,51 @@
+//! Wall-clock benchmarks of whole gpufetch runs on the golden-test fixtures.
+//!
+//! gpufetch is a binary, so detection and rendering are timed through it,
+//! cold start included: `--output json` detects and serializes, the default
+//! card detects and renders. In a shell prompt a run should stay under
+//! about 20 ms when no external tools are called, as with the AMD and Intel
+//! fixtures; the NVIDIA ones replay nvidia-smi output instead of running it.
+
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// Fixtures covering each vendor and a multi-GPU machine
+const FIXTURES: [&str; 4] = ["rx_6700_xt", "uhd_620", "rtx_3060_laptop", "a100_nvlink"];
+
+/// Run gpufetch on a fixture the way the golden tests do
+fn gpufetch(fixture: &str, args: &[&str]) {
+    let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(fixture);
+    let status = Command::new(env!("CARGO_BIN_EXE_gpufetch"))
+        .arg("--replay")
+        .arg(&fixture)
+        .args(args)
+        .env_clear()
+        .env("LANG", "C.UTF-8")
+        .env("HOME", std::env::temp_dir().join("gpufetch-bench-no-home"))
+        .env("PATH", "")
+        .stdout(Stdio::null())
+        .status()
+        .expect("failed to run gpufetch");
+    assert!(status.success(), "gpufetch failed on {}", fixture.display());
+}
+
+fn detection(c: &mut Criterion) {
+    let mut group = c.benchmark_group("detect");
+    for fixture in FIXTURES {
+        group.bench_function(fixture, |b| b.iter(|| gpufetch(fixture, &["--output", "json"])));
+    }
+    group.finish();
+}
+
+fn rendering(c: &mut Criterion) {
+    let mut group = c.benchmark_group("card");
+    for fixture in FIXTURES {
+        group.bench_function(fixture, |b| b.iter(|| gpufetch(fixture, &[])));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, detection, rendering);
+criterion_main!(benches);
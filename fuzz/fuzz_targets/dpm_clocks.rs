@@ -0,0 +1,16 @@
+//! amdgpu DPM clock tables such as `pp_dpm_sclk`.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+#[path = "../../src/gpu/parse.rs"]
+mod parse;
+
+fuzz_target!(|table: &str| {
+    if let Some((current, max)) = parse::parse_dpm_clocks(table) {
+        assert!(max > 0);
+        assert!(current.map_or(true, |current| current <= max));
+    }
+});
@@ -0,0 +1,17 @@
+//! PCI `modalias` strings from sysfs.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+#[path = "../../src/gpu/parse.rs"]
+mod parse;
+
+fuzz_target!(|modalias: &str| {
+    if let Some((vendor, device)) = parse::parse_modalias(modalias) {
+        // What was read must have been spelled as the kernel spells it
+        let prefix = format!("pci:v0000{:04X}d0000{:04X}", vendor, device);
+        assert!(modalias.trim().to_ascii_uppercase().starts_with(&prefix.to_ascii_uppercase()));
+    }
+});
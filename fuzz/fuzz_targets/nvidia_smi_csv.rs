@@ -0,0 +1,17 @@
+//! Rows of `nvidia-smi --query-gpu` CSV output.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+#[path = "../../src/gpu/parse.rs"]
+mod parse;
+
+fuzz_target!(|output: &str| {
+    for row in output.lines().filter_map(parse::parse_nvidia_smi_row) {
+        assert!(!row.name.is_empty());
+        assert!(parse::parse_pci_address(row.bus_id).is_some());
+        assert!(row.temperature_c.map_or(true, f64::is_finite));
+    }
+});
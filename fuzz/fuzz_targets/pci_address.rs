@@ -0,0 +1,17 @@
+//! PCI addresses from sysfs paths, lspci and nvidia-smi.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+#[path = "../../src/gpu/parse.rs"]
+mod parse;
+
+fuzz_target!(|address: &str| {
+    if let Some((domain, bus, device, function)) = parse::parse_pci_address(address) {
+        // The address gpufetch prints for it reads back the same
+        let canonical = format!("{:04x}:{:02x}:{:02x}.{:x}", domain, bus, device, function);
+        assert_eq!(parse::parse_pci_address(&canonical), Some((domain, bus, device, function)));
+    }
+});
@@ -0,0 +1,207 @@
+//! Alerts on values crossing thresholds in `--watch` and `--daemon` mode.
+//!
+//! Thresholds come from `[[alerts]]` tables in the config file:
+//!
+//! ```toml
+//! [[alerts]]
+//! metric = "temperature"
+//! above = 90
+//!
+//! [[alerts]]
+//! metric = "power"
+//! above = 300
+//! command = "logger -t gpufetch \"$GPUFETCH_MESSAGE\""
+//! ```
+//!
+//! An alert fires when a GPU's value crosses its threshold and again only
+//! after the value has gone back. Without a `command` it is a desktop
+//! notification through `notify-send`; commands run through `sh -c` with the
+//! details in `GPUFETCH_*` environment variables.
+
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::process::{Command, Stdio};
+use std::thread;
+use tracing::{info, warn};
+
+use crate::gpu::common::GpuInfo;
+use crate::gpu::tools;
+
+/// A value alerts can watch, in the unit thresholds are given in
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    /// Edge temperature, °C
+    Temperature,
+    /// Hotspot temperature, °C
+    JunctionTemperature,
+    /// Memory temperature, °C
+    MemoryTemperature,
+    /// Power draw, W
+    Power,
+    /// Fan speed, percent
+    Fan,
+    /// Core clock, MHz
+    Clock,
+}
+
+impl Metric {
+    /// The name in the config file
+    fn key(&self) -> &'static str {
+        match self {
+            Metric::Temperature => "temperature",
+            Metric::JunctionTemperature => "junction_temperature",
+            Metric::MemoryTemperature => "memory_temperature",
+            Metric::Power => "power",
+            Metric::Fan => "fan",
+            Metric::Clock => "clock",
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Metric::Temperature => "temperature",
+            Metric::JunctionTemperature => "junction temperature",
+            Metric::MemoryTemperature => "memory temperature",
+            Metric::Power => "power draw",
+            Metric::Fan => "fan speed",
+            Metric::Clock => "core clock",
+        }
+    }
+
+    fn value(&self, gpu: &GpuInfo) -> Option<f64> {
+        match self {
+            Metric::Temperature => gpu.temperature_c,
+            Metric::JunctionTemperature => gpu.junction_temperature_c,
+            Metric::MemoryTemperature => gpu.memory_temperature_c,
+            Metric::Power => gpu.power_draw_w,
+            Metric::Fan => gpu.fan.and_then(|fan| fan.percent).map(f64::from),
+            Metric::Clock => (gpu.freq_mhz > 0).then(|| f64::from(gpu.freq_mhz)),
+        }
+    }
+
+    fn format(&self, value: f64) -> String {
+        match self {
+            Metric::Temperature | Metric::JunctionTemperature | Metric::MemoryTemperature => format!("{:.0}°C", value),
+            Metric::Power => format!("{:.0} W", value),
+            Metric::Fan => format!("{:.0}%", value),
+            Metric::Clock => format!("{:.0} MHz", value),
+        }
+    }
+}
+
+/// One `[[alerts]]` table
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRule {
+    pub metric: Metric,
+    /// Fire when the value goes over this
+    pub above: Option<f64>,
+    /// Fire when the value drops under this
+    pub below: Option<f64>,
+    /// Shell command to run instead of a desktop notification
+    pub command: Option<String>,
+}
+
+impl AlertRule {
+    /// Whether `value` is past the threshold
+    fn is_crossed(&self, value: f64) -> bool {
+        match (self.above, self.below) {
+            (Some(above), _) => value > above,
+            (None, Some(below)) => value < below,
+            (None, None) => false,
+        }
+    }
+
+    /// "above 90°C" or "below 500 MHz"
+    fn threshold(&self) -> String {
+        match (self.above, self.below) {
+            (Some(above), _) => format!("above {}", self.metric.format(above)),
+            (_, Some(below)) => format!("below {}", self.metric.format(below)),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// The rules and which of them each GPU is past
+#[derive(Debug, Default)]
+pub struct Alerts {
+    rules: Vec<AlertRule>,
+    /// GPU and rule index of every threshold currently crossed
+    crossed: HashSet<(String, usize)>,
+}
+
+impl Alerts {
+    /// Alerts for the usable `rules`, warning about the others
+    pub fn new(rules: &[AlertRule]) -> Self {
+        let rules = rules
+            .iter()
+            .filter(|rule| match (rule.above, rule.below) {
+                (Some(_), None) | (None, Some(_)) => true,
+                _ => {
+                    eprintln!("Warning: ignoring the {} alert: it needs either `above` or `below`", rule.metric.name());
+                    false
+                }
+            })
+            .cloned()
+            .collect();
+        Alerts { rules, crossed: HashSet::new() }
+    }
+
+    /// Fire the alerts whose thresholds the GPUs crossed since the last check
+    pub fn check(&mut self, gpus: &[GpuInfo]) {
+        for (index, gpu) in gpus.iter().enumerate() {
+            let key = gpu.pci_info.as_ref().map_or_else(|| format!("{}#{}", gpu.name, index), |pci_info| pci_info.address());
+            for (rule_index, rule) in self.rules.iter().enumerate() {
+                // An unknown value keeps the last state rather than rearming
+                let Some(value) = rule.metric.value(gpu) else {
+                    continue;
+                };
+                let state = (key.clone(), rule_index);
+                if !rule.is_crossed(value) {
+                    self.crossed.remove(&state);
+                } else if self.crossed.insert(state) {
+                    fire(rule, gpu, value);
+                }
+            }
+        }
+    }
+}
+
+/// Notify about `gpu` having crossed the threshold of `rule` with `value`
+fn fire(rule: &AlertRule, gpu: &GpuInfo, value: f64) {
+    let message = format!("{}: {} {} is {}", gpu.name, rule.metric.name(), rule.metric.format(value), rule.threshold());
+    info!("alert: {}", message);
+
+    let mut command = match rule.command {
+        Some(ref command) => {
+            let mut shell = Command::new("sh");
+            shell.arg("-c").arg(command);
+            shell
+        }
+        None if tools::find("notify-send").is_some() => {
+            let mut notify = Command::new("notify-send");
+            notify.args(["--app-name=gpufetch", "GPU alert", &message]);
+            notify
+        }
+        None => {
+            warn!("{} (install notify-send for desktop notifications)", message);
+            return;
+        }
+    };
+    command
+        .env("GPUFETCH_MESSAGE", &message)
+        .env("GPUFETCH_GPU", &gpu.name)
+        .env("GPUFETCH_PCI_ADDRESS", gpu.pci_info.as_ref().map(|pci_info| pci_info.address()).unwrap_or_default())
+        .env("GPUFETCH_METRIC", rule.metric.key())
+        .env("GPUFETCH_VALUE", value.to_string())
+        .stdin(Stdio::null());
+
+    // The command may take its time; the next refresh should not wait for it
+    match command.spawn() {
+        Ok(mut child) => {
+            thread::spawn(move || child.wait());
+        }
+        Err(e) => warn!("failed to run the alert command: {}", e),
+    }
+}
@@ -1,4 +1,16 @@
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use crate::display::graphics::GraphicsProtocol;
+use crate::display::oneline::OnelineField;
+use crate::display::Field;
+use crate::display::theme::Theme;
+use crate::gpu::backend::BackendSelection;
+use crate::gpu::common::{GpuInfo, GpuVendor};
+use crate::gpu::selector::GpuSelector;
+use crate::gpu::{hybrid, icd, update};
+use crate::units::{FrequencyUnit, MemoryUnit, TemperatureUnit, Units};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
 pub enum ColorScheme {
@@ -26,12 +38,318 @@ pub enum LogoVariant {
     None,
 }
 
+/// Where the logo goes relative to the info
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Layout {
+    /// Beside the info when the terminal is wide enough, otherwise above it
+    Auto,
+    /// Beside the info
+    Horizontal,
+    /// Above the info
+    Vertical,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LogoMode {
+    /// Draw a picture if the terminal supports an image protocol, else text art
+    Auto,
+    /// Always use text art
+    Ascii,
+    /// Kitty graphics protocol (kitty, Ghostty, WezTerm)
+    Kitty,
+    /// iTerm2 inline images (iTerm2, WezTerm)
+    Iterm2,
+    /// Sixel graphics (foot, mlterm, xterm -ti vt340)
+    Sixel,
+}
+
+impl LogoMode {
+    /// Protocol to draw the logo with, given what was detected for the terminal
+    ///
+    /// Builds without the `image` feature cannot draw pictures and always
+    /// use text art.
+    pub fn protocol(&self, detected: Option<GraphicsProtocol>) -> Option<GraphicsProtocol> {
+        if !cfg!(feature = "image") {
+            return None;
+        }
+        match self {
+            LogoMode::Auto => detected,
+            LogoMode::Ascii => None,
+            LogoMode::Kitty => Some(GraphicsProtocol::Kitty),
+            LogoMode::Iterm2 => Some(GraphicsProtocol::Iterm2),
+            LogoMode::Sixel => Some(GraphicsProtocol::Sixel),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum VendorFilter {
+    /// NVIDIA GPUs
+    Nvidia,
+    /// AMD GPUs
+    Amd,
+    /// Intel GPUs
+    Intel,
+}
+
+impl VendorFilter {
+    /// The vendor this filter selects
+    pub fn vendor(&self) -> GpuVendor {
+        match self {
+            VendorFilter::Nvidia => GpuVendor::Nvidia,
+            VendorFilter::Amd => GpuVendor::Amd,
+            VendorFilter::Intel => GpuVendor::Intel,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum KindFilter {
+    /// GPUs built into the CPU or chipset
+    Integrated,
+    /// GPUs on their own card or MXM module
+    Discrete,
+}
+
+impl KindFilter {
+    /// Whether `gpu` is of this kind
+    pub fn matches(&self, gpu: &GpuInfo) -> bool {
+        match self {
+            KindFilter::Integrated => hybrid::is_integrated(gpu),
+            KindFilter::Discrete => !hybrid::is_integrated(gpu),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    /// Alphabetically by name
+    Name,
+    /// Most video memory first
+    Vram,
+    /// Highest peak FP32 performance first
+    Performance,
+}
+
+impl SortKey {
+    /// Reorder `gpus` by this key, keeping PCI order among equal ones
+    pub fn sort(&self, gpus: &mut [GpuInfo]) {
+        match self {
+            SortKey::Name => gpus.sort_by(|a, b| a.name.cmp(&b.name)),
+            SortKey::Vram => gpus.sort_by_key(|gpu| std::cmp::Reverse(gpu.memory.as_ref().map(|memory| memory.size_bytes))),
+            SortKey::Performance => gpus.sort_by(|a, b| {
+                let gflops = |gpu: &GpuInfo| gpu.peak_performance_gflops.unwrap_or(0.0);
+                gflops(b).total_cmp(&gflops(a))
+            }),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Requirement {
+    /// A GPU on its own card or MXM module
+    Discrete,
+    /// A GPU built into the CPU or chipset
+    Integrated,
+    /// An NVIDIA GPU
+    Nvidia,
+    /// An AMD GPU
+    Amd,
+    /// An Intel GPU
+    Intel,
+    /// A GPU with an installed Vulkan driver
+    Vulkan,
+}
+
+impl Requirement {
+    /// Whether `gpu` satisfies this requirement; `vulkan_icds` are the installed ICD manifests
+    pub fn is_met_by(&self, gpu: &GpuInfo, vulkan_icds: &[String]) -> bool {
+        match self {
+            Requirement::Discrete => !hybrid::is_integrated(gpu),
+            Requirement::Integrated => hybrid::is_integrated(gpu),
+            Requirement::Nvidia => gpu.vendor == GpuVendor::Nvidia,
+            Requirement::Amd => gpu.vendor == GpuVendor::Amd,
+            Requirement::Intel => gpu.vendor == GpuVendor::Intel,
+            Requirement::Vulkan => icd::has_vulkan_driver(gpu, vulkan_icds),
+        }
+    }
+}
+
+/// What gpufetch prints
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// The logo and spec card
+    Text,
+    /// One comma-separated row per GPU with a fixed set of columns
+    Csv,
+    /// The spec card as a Markdown table, e.g. for GitHub issues
+    Markdown,
+    /// The spec card as an HTML table
+    Html,
+    /// Everything detected about every GPU, as a JSON array
+    Json,
+}
+
+/// How failures are reported
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ErrorFormat {
+    /// A message on stderr
+    Human,
+    /// One JSON object on stderr with the error kind, exit status and backend diagnostics
+    Json,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DbCommand {
+    /// Print database entries as TOML, in the format of data/specs.toml
+    Dump {
+        /// Only print entries for this vendor
+        #[arg(long, value_enum)]
+        vendor: Option<VendorFilter>,
+    },
+    /// Find entries by model name, chip, architecture or PCI device ID
+    Search {
+        /// Text to look for, e.g. "navi 22", "3080" or "73df"
+        query: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Remember a GPU to show by default (index, PCI address, or name substring)
+    Use {
+        /// GPU to remember, e.g. "1", "0000:01:00.0" or "3080"
+        #[arg(required_unless_present = "clear")]
+        selector: Option<String>,
+
+        /// Forget the remembered GPU
+        #[arg(long, conflicts_with = "selector")]
+        clear: bool,
+    },
+    /// Inspect the GPU specification database
+    Db {
+        #[command(subcommand)]
+        action: DbCommand,
+    },
+    /// Save sysfs, tool output and detection results to a tarball for bug reports
+    Dump {
+        /// Where to write the snapshot
+        #[arg(short, long, default_value = "gpufetch-dump.tar.gz")]
+        output: PathBuf,
+    },
+    /// Measure achieved FP32 throughput and memory bandwidth with OpenCL
+    /// and compare them to the theoretical peaks
+    Bench,
+    /// Download the latest pci.ids and GPU spec database into the cache,
+    /// to be used instead of the copies built into gpufetch
+    UpdateDb {
+        /// Where to fetch pci.ids from (any URL curl accepts, including file://)
+        #[arg(long, value_name = "URL", default_value = update::DEFAULT_PCI_IDS_URL)]
+        pci_ids_url: String,
+
+        /// Where to fetch the spec database from
+        #[arg(long, value_name = "URL", default_value = update::DEFAULT_SPECS_URL)]
+        specs_url: String,
+    },
+}
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct Args {
-    /// Which GPU to display (default: 0, -1 for all GPUs)
-    #[arg(short, long, default_value_t = 0)]
-    pub gpu_index: i32,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Which GPU to display (default: the one set with `gpufetch use`, else 0; -1 for all GPUs)
+    #[arg(short, long, allow_negative_numbers = true)]
+    pub gpu_index: Option<i32>,
+
+    /// Which GPU to display by name substring or PCI address, e.g. "3080" or "0000:01:00.0"
+    #[arg(long, value_name = "SELECTOR", conflicts_with = "gpu_index")]
+    pub gpu: Option<GpuSelector>,
+
+    /// Only consider GPUs from this vendor
+    #[arg(long, value_enum)]
+    pub vendor: Option<VendorFilter>,
+
+    /// Only consider integrated or discrete GPUs
+    #[arg(long, value_enum)]
+    pub only: Option<KindFilter>,
+
+    /// Only show values read from the system, leaving out everything guessed from
+    /// the model name or taken from the spec database
+    #[arg(long)]
+    pub no_heuristics: bool,
+
+    /// Order GPUs by this instead of PCI address; indices follow the new order
+    #[arg(long, value_enum)]
+    pub sort: Option<SortKey>,
+
+    /// Detection backends in priority order (sysfs, smi, nvml, vulkan, udev, lspci; default: smi,nvml,sysfs);
+    /// "+vulkan" adds one to the defaults and "-nvml" excludes one
+    #[arg(long, value_name = "LIST", allow_hyphen_values = true)]
+    pub backend: Option<BackendSelection>,
+
+    /// Read /sys and /proc under this directory, e.g. a captured tree or the host's
+    /// sysfs mounted into a container (default: $GPUFETCH_SYSFS_ROOT, else /)
+    #[arg(long, value_name = "DIR")]
+    pub sysfs_root: Option<PathBuf>,
+
+    /// Show the machine a `gpufetch dump` snapshot was taken on instead of this one;
+    /// takes the tarball or a directory it was unpacked to
+    #[arg(long, value_name = "PATH", conflicts_with = "sysfs_root")]
+    pub replay: Option<PathBuf>,
+
+    /// Keep running and answer JSON queries on a Unix socket instead of printing
+    #[arg(long)]
+    pub daemon: bool,
+
+    /// Keep running and serve /gpus and /gpus/{index} as JSON over HTTP on this address,
+    /// e.g. 127.0.0.1:8800; with --daemon, alongside the socket
+    #[arg(long, value_name = "ADDRESS")]
+    pub serve: Option<SocketAddr>,
+
+    /// Socket for --daemon (default: $XDG_RUNTIME_DIR/gpufetch.sock)
+    #[arg(long, value_name = "PATH", requires = "daemon")]
+    pub socket: Option<PathBuf>,
+
+    /// Redraw the card with fresh clocks, temperatures and power until interrupted
+    #[arg(long, conflicts_with_all = ["daemon", "serve", "output", "list_only", "summary", "compare", "screenshot", "oneline"])]
+    pub watch: bool,
+
+    /// Append the changing values of each --watch refresh to this file, as CSV
+    /// when it ends in .csv and JSON Lines otherwise
+    #[arg(long, value_name = "FILE", requires = "watch")]
+    pub record: Option<PathBuf>,
+
+    /// Seconds between detections in --daemon, --serve and --watch mode
+    #[arg(long, value_name = "SECONDS", default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+    pub refresh_interval: u64,
+
+    /// Output format; csv prints every GPU, one row each, json every GPU in full, and
+    /// markdown and html the GPU that would be shown (all of them with -g -1)
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+
+    /// Save the fetch card as a PNG or SVG picture instead of printing it
+    #[arg(long, value_name = "FILE")]
+    pub screenshot: Option<PathBuf>,
+
+    /// Put the text art logo above markdown and html tables
+    #[arg(long)]
+    pub embed_logo: bool,
+
+    /// Compare all GPUs in a table, one column each (the default for -g -1 with several GPUs)
+    #[arg(long)]
+    pub compare: bool,
+
+    /// Print the combined VRAM, peak performance and interconnect of all GPUs
+    /// instead of a card; with --compare or -g -1 the table follows
+    #[arg(long)]
+    pub summary: bool,
+
+    /// Exit with status 5 unless one GPU meets all of these, e.g. "discrete,vulkan"
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "REQUIREMENTS")]
+    pub require: Vec<Requirement>,
 
     /// Just list available GPUs and exit
     #[arg(short = 'l', long)]
@@ -42,23 +360,133 @@ pub struct Args {
     pub color_scheme: ColorScheme,
 
     /// Custom colors in RGB format: "r,g,b:r,g,b:r,g,b:r,g,b" 
-    /// (4 colors: logo primary, logo secondary, text primary, text secondary)
-    #[arg(short = 'C', long)]
-    pub custom_colors: Option<String>,
+    /// (4 colors: logo primary, logo secondary, text primary, text secondary);
+    /// used unless another color scheme is chosen
+    #[arg(short = 'C', long, value_parser = Theme::parse_custom, required_if_eq("color_scheme", "custom"))]
+    pub custom_colors: Option<Theme>,
+
+    /// Show the ASCII art in this file instead of the vendor logo
+    #[arg(long, value_name = "PATH")]
+    pub logo_file: Option<PathBuf>,
+
+    /// Show this PNG or JPEG picture, converted to terminal art, as the logo
+    #[arg(long, value_name = "PATH", conflicts_with = "logo_file")]
+    pub logo_image: Option<PathBuf>,
+
+    /// Width of the --logo-image art in terminal columns
+    #[arg(long, value_name = "COLUMNS", default_value_t = 40)]
+    pub logo_width: u32,
 
     /// Logo size variant
     #[arg(short = 'L', long, value_enum, default_value_t = LogoVariant::Normal)]
     pub logo_variant: LogoVariant,
 
+    /// How to draw the logo; auto uses an image protocol when the terminal has one
+    #[arg(long, value_enum, default_value_t = LogoMode::Auto)]
+    pub logo_mode: LogoMode,
+
+    /// Put the logo beside or above the info; auto goes above on narrow terminals
+    #[arg(long, value_enum, default_value_t = Layout::Auto)]
+    pub layout: Layout,
+
+    /// Print each GPU's IOMMU group and VFIO passthrough readiness and exit
+    #[arg(long)]
+    pub passthrough: bool,
+
+    /// Print the PCIe tree from each root port to the GPUs behind it and exit
+    #[arg(long)]
+    pub topology: bool,
+
+    /// List each GPU's VA-API decode and encode profiles (needs vainfo)
+    #[arg(long)]
+    pub vaapi: bool,
+
+    /// Show the display server and compositor, and whether they render on the GPU
+    #[arg(long)]
+    pub session: bool,
+
+    /// Suggest packages to install for GPUs without a working driver
+    #[arg(long)]
+    pub suggest: bool,
+
+    /// Head the card with the hostname, distribution, kernel, CPU and memory
+    #[arg(long)]
+    pub system_info: bool,
+
+    /// Draw a rounded box around the info panel, titled with the GPU name
+    #[arg(long)]
+    pub frame: bool,
+
+    /// Info fields to show, in this order, e.g. "name,memory,clock,driver"
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub fields: Option<Vec<Field>>,
+
+    /// Draw a block diagram of the GPU's clusters and compute units: GPCs and SMs,
+    /// Shader Engines and CUs, or slices, subslices and EUs
+    #[arg(long, conflicts_with_all = ["output", "list_only", "summary", "compare", "screenshot", "oneline", "watch"])]
+    pub diagram: bool,
+
+    /// Print the GPU on one short line for shell prompts, e.g. "RTX 3080 10GB · 1710MHz · 62°C"
+    #[arg(long, conflicts_with_all = ["output", "list_only", "summary", "compare", "screenshot"])]
+    pub oneline: bool,
+
+    /// Values on the --oneline line, in this order (default: name,memory,clock,temperature)
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "FIELDS", requires = "oneline")]
+    pub oneline_fields: Option<Vec<OnelineField>>,
+
+    /// Leave out the line break after --oneline, for prompts that add their own
+    #[arg(long, requires = "oneline")]
+    pub no_newline: bool,
+
     /// Display detailed information
     #[arg(short, long)]
     pub detailed: bool,
 
+    /// Mask serial numbers, UUIDs and hostnames so output can be shared publicly (not with `dump`)
+    #[arg(long)]
+    pub anonymize: bool,
+
+    /// Unit for memory and cache sizes (default: gib on screen and bytes in JSON, CSV and --record
+    /// files, or `memory` in the [units] config table)
+    #[arg(long, value_enum, value_name = "UNIT")]
+    pub memory_unit: Option<MemoryUnit>,
+
+    /// Unit for clocks (default: mhz)
+    #[arg(long, value_enum, value_name = "UNIT")]
+    pub frequency_unit: Option<FrequencyUnit>,
+
+    /// Unit for temperatures (default: c)
+    #[arg(long, value_enum, value_name = "UNIT")]
+    pub temperature_unit: Option<TemperatureUnit>,
+
     /// Disable color output
     #[arg(long)]
     pub no_color: bool,
 
-    /// Enable verbose output with debugging information
-    #[arg(short, long)]
-    pub verbose: bool,
+    /// Draw logos, frames and bars in plain ASCII, as on terminals without UTF-8
+    #[arg(long)]
+    pub ascii: bool,
+
+    /// Log what detection tries to stderr; repeat for more detail (-vv, -vvv)
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// How to report failures such as no GPUs being found
+    #[arg(long, value_enum, default_value_t = ErrorFormat::Human)]
+    pub error_format: ErrorFormat,
+
+    /// Write a detailed diagnostic log to this file, e.g. to attach to a bug report
+    #[arg(long, value_name = "FILE")]
+    pub log_file: Option<PathBuf>,
+}
+
+impl Args {
+    /// Units from the flags, falling back to the config file's
+    pub fn units(&self, configured: Units) -> Units {
+        Units {
+            memory: self.memory_unit.or(configured.memory),
+            frequency: self.frequency_unit.unwrap_or(configured.frequency),
+            temperature: self.temperature_unit.unwrap_or(configured.temperature),
+        }
+    }
 }
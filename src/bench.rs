@@ -0,0 +1,526 @@
+//! `gpufetch bench`: a short OpenCL workload measuring what a GPU achieves.
+//!
+//! Two kernels run on the selected GPU: independent chains of FP32
+//! multiply-adds for throughput, and a buffer copy for memory bandwidth.
+//! Each is sized to take a fraction of a second and the best of a few runs
+//! counts, so the numbers land close to the hardware limits on an idle GPU.
+//!
+//! libOpenCL (the ICD loader) is loaded at runtime like NVML, so gpufetch
+//! runs without it and only `bench` needs it.
+
+use anyhow::{anyhow, bail, Context, Result};
+use std::ffi::{c_char, c_void, CStr};
+use std::ptr;
+use std::time::{Duration, Instant};
+
+use crate::gpu::common::GpuInfo;
+use crate::gpu::snapshot;
+
+const LIBRARY: &CStr = c"libOpenCL.so.1";
+
+const CL_SUCCESS: ClInt = 0;
+const CL_DEVICE_TYPE_GPU: u64 = 1 << 2;
+const CL_DEVICE_VENDOR_ID: ClUint = 0x1001;
+const CL_DEVICE_MAX_MEM_ALLOC_SIZE: ClUint = 0x1010;
+const CL_DEVICE_NAME: ClUint = 0x102B;
+const CL_DEVICE_PCI_BUS_INFO_KHR: ClUint = 0x410F;
+const CL_PLATFORM_NAME: ClUint = 0x0902;
+const CL_PROGRAM_BUILD_LOG: ClUint = 0x1183;
+const CL_MEM_READ_WRITE: u64 = 1;
+
+/// Work items of the throughput kernel
+const FLOPS_WORK_ITEMS: usize = 1 << 18;
+/// FP32 operations per work item and loop iteration: four float4
+/// multiply-adds of two operations per lane
+const FLOPS_PER_ITERATION: f64 = 32.0;
+/// Largest buffer copied for the bandwidth measurement
+const COPY_BYTES: usize = 256 << 20;
+/// A run has to take this long before its timing is trusted
+const MIN_RUN_TIME: Duration = Duration::from_millis(100);
+/// Timed runs per workload; the fastest counts
+const RUNS: usize = 5;
+
+const KERNELS: &CStr = cr#"
+__kernel void fma_chains(__global float *out, float factor, int iterations) {
+    float4 x = (float4)(get_global_id(0), 1.0f, 2.0f, 3.0f) * 1e-7f;
+    float4 y = x + 0.5f;
+    float4 z = x + 0.25f;
+    float4 w = x + 0.125f;
+    for (int i = 0; i < iterations; i++) {
+        x = mad(x, factor, 0.5f);
+        y = mad(y, factor, 0.5f);
+        z = mad(z, factor, 0.5f);
+        w = mad(w, factor, 0.5f);
+    }
+    out[get_global_id(0)] = dot(x + y + z + w, (float4)(1.0f));
+}
+
+__kernel void copy(__global const float4 *src, __global float4 *dst) {
+    dst[get_global_id(0)] = src[get_global_id(0)];
+}
+"#;
+
+type ClInt = i32;
+type ClUint = u32;
+type Handle = *mut c_void;
+
+/// `cl_device_pci_bus_info_khr`
+#[repr(C)]
+struct PciBusInfo {
+    domain: ClUint,
+    bus: ClUint,
+    device: ClUint,
+    function: ClUint,
+}
+
+/// What the workloads achieved on one GPU
+pub struct BenchResult {
+    /// OpenCL platform the GPU was driven through, e.g. "NVIDIA CUDA"
+    pub platform: String,
+    pub gflops: f64,
+    /// Copy bandwidth in GB/s, counting both the read and the write
+    pub bandwidth_gbs: f64,
+}
+
+/// The OpenCL entry points the benchmark uses
+struct OpenCl {
+    library: *mut c_void,
+    platform_ids: unsafe extern "C" fn(ClUint, *mut Handle, *mut ClUint) -> ClInt,
+    platform_info: unsafe extern "C" fn(Handle, ClUint, usize, *mut c_void, *mut usize) -> ClInt,
+    device_ids: unsafe extern "C" fn(Handle, u64, ClUint, *mut Handle, *mut ClUint) -> ClInt,
+    device_info: unsafe extern "C" fn(Handle, ClUint, usize, *mut c_void, *mut usize) -> ClInt,
+    create_context:
+        unsafe extern "C" fn(*const isize, ClUint, *const Handle, *const c_void, *mut c_void, *mut ClInt) -> Handle,
+    create_queue: unsafe extern "C" fn(Handle, Handle, u64, *mut ClInt) -> Handle,
+    create_program: unsafe extern "C" fn(Handle, ClUint, *const *const c_char, *const usize, *mut ClInt) -> Handle,
+    build_program: unsafe extern "C" fn(Handle, ClUint, *const Handle, *const c_char, *const c_void, *mut c_void) -> ClInt,
+    build_info: unsafe extern "C" fn(Handle, Handle, ClUint, usize, *mut c_void, *mut usize) -> ClInt,
+    create_kernel: unsafe extern "C" fn(Handle, *const c_char, *mut ClInt) -> Handle,
+    create_buffer: unsafe extern "C" fn(Handle, u64, usize, *mut c_void, *mut ClInt) -> Handle,
+    set_kernel_arg: unsafe extern "C" fn(Handle, ClUint, usize, *const c_void) -> ClInt,
+    enqueue_kernel: unsafe extern "C" fn(
+        Handle,
+        Handle,
+        ClUint,
+        *const usize,
+        *const usize,
+        *const usize,
+        ClUint,
+        *const Handle,
+        *mut Handle,
+    ) -> ClInt,
+    finish: unsafe extern "C" fn(Handle) -> ClInt,
+    release_mem: unsafe extern "C" fn(Handle) -> ClInt,
+    release_kernel: unsafe extern "C" fn(Handle) -> ClInt,
+    release_program: unsafe extern "C" fn(Handle) -> ClInt,
+    release_queue: unsafe extern "C" fn(Handle) -> ClInt,
+    release_context: unsafe extern "C" fn(Handle) -> ClInt,
+}
+
+impl OpenCl {
+    /// Load the OpenCL ICD loader
+    fn open() -> Result<Self> {
+        if snapshot::is_replaying() {
+            bail!("Benchmarks run on this machine's GPUs and cannot be replayed from a snapshot");
+        }
+
+        // SAFETY: dlopen with a constant, NUL-terminated library name
+        let library = unsafe { libc::dlopen(LIBRARY.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        if library.is_null() {
+            bail!("Benchmarks need OpenCL, but {} is not installed", LIBRARY.to_string_lossy());
+        }
+
+        // SAFETY: the signatures match cl.h for each symbol
+        match unsafe { Self::resolve(library) } {
+            Ok(opencl) => Ok(opencl),
+            Err(e) => {
+                // SAFETY: the handle came from dlopen and nothing from it is kept
+                unsafe { libc::dlclose(library) };
+                Err(e)
+            }
+        }
+    }
+
+    /// Look up the entry points
+    ///
+    /// # Safety
+    /// `library` must be a handle returned by dlopen for libOpenCL.
+    unsafe fn resolve(library: *mut c_void) -> Result<Self> {
+        Ok(OpenCl {
+            library,
+            platform_ids: symbol(library, c"clGetPlatformIDs")?,
+            platform_info: symbol(library, c"clGetPlatformInfo")?,
+            device_ids: symbol(library, c"clGetDeviceIDs")?,
+            device_info: symbol(library, c"clGetDeviceInfo")?,
+            create_context: symbol(library, c"clCreateContext")?,
+            create_queue: symbol(library, c"clCreateCommandQueue")?,
+            create_program: symbol(library, c"clCreateProgramWithSource")?,
+            build_program: symbol(library, c"clBuildProgram")?,
+            build_info: symbol(library, c"clGetProgramBuildInfo")?,
+            create_kernel: symbol(library, c"clCreateKernel")?,
+            create_buffer: symbol(library, c"clCreateBuffer")?,
+            set_kernel_arg: symbol(library, c"clSetKernelArg")?,
+            enqueue_kernel: symbol(library, c"clEnqueueNDRangeKernel")?,
+            finish: symbol(library, c"clFinish")?,
+            release_mem: symbol(library, c"clReleaseMemObject")?,
+            release_kernel: symbol(library, c"clReleaseKernel")?,
+            release_program: symbol(library, c"clReleaseProgram")?,
+            release_queue: symbol(library, c"clReleaseCommandQueue")?,
+            release_context: symbol(library, c"clReleaseContext")?,
+        })
+    }
+
+    /// Every GPU device with the platform it belongs to
+    fn gpu_devices(&self) -> Result<Vec<(Handle, Handle)>> {
+        // SAFETY: the ID arrays are sized from the counts OpenCL reports
+        unsafe {
+            let platforms = list(|count, ids, found| (self.platform_ids)(count, ids, found))
+                .context("No OpenCL platform is installed")?;
+            Ok(platforms
+                .into_iter()
+                .flat_map(|platform| {
+                    // Platforms without GPUs report an error rather than none
+                    list(|count, ids, found| (self.device_ids)(platform, CL_DEVICE_TYPE_GPU, count, ids, found))
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(move |device| (platform, device))
+                })
+                .collect())
+        }
+    }
+
+    /// The OpenCL device driving `gpu`: the one at its PCI address, else the
+    /// only device of its vendor
+    fn find_device(&self, gpu: &GpuInfo) -> Result<(Handle, Handle)> {
+        let devices = self.gpu_devices()?;
+        let pci_info = gpu.pci_info.as_ref();
+
+        // SAFETY: each query gets a value of the type cl.h documents for it
+        let at_address = devices.iter().find(|&&(_, device)| unsafe {
+            let mut bus_info: PciBusInfo = std::mem::zeroed();
+            let found = (self.device_info)(
+                device,
+                CL_DEVICE_PCI_BUS_INFO_KHR,
+                std::mem::size_of::<PciBusInfo>(),
+                &mut bus_info as *mut PciBusInfo as *mut c_void,
+                ptr::null_mut(),
+            ) == CL_SUCCESS;
+            found
+                && pci_info.is_some_and(|pci| {
+                    (bus_info.domain, bus_info.bus, bus_info.device, bus_info.function)
+                        == (pci.domain.into(), pci.bus.into(), pci.device.into(), pci.function.into())
+                })
+        });
+        if let Some(&found) = at_address {
+            return Ok(found);
+        }
+
+        let vendor_id = pci_info.map(|pci| ClUint::from(pci.vendor_id));
+        // SAFETY: CL_DEVICE_VENDOR_ID is a cl_uint
+        let of_vendor: Vec<&(Handle, Handle)> = devices
+            .iter()
+            .filter(|&&(_, device)| unsafe {
+                let mut id: ClUint = 0;
+                let found = (self.device_info)(
+                    device,
+                    CL_DEVICE_VENDOR_ID,
+                    std::mem::size_of::<ClUint>(),
+                    &mut id as *mut ClUint as *mut c_void,
+                    ptr::null_mut(),
+                ) == CL_SUCCESS;
+                found && Some(id) == vendor_id
+            })
+            .collect();
+        match of_vendor.as_slice() {
+            [&found] => Ok(found),
+            [] => bail!("No OpenCL driver is installed for {}", gpu.name),
+            _ => bail!("Several OpenCL devices could be {}; install a driver that reports PCI addresses", gpu.name),
+        }
+    }
+
+    /// A string property of a platform or device
+    ///
+    /// # Safety
+    /// `query` must be clGetPlatformInfo or clGetDeviceInfo with a string `param`.
+    unsafe fn info_string(
+        &self,
+        query: unsafe extern "C" fn(Handle, ClUint, usize, *mut c_void, *mut usize) -> ClInt,
+        handle: Handle,
+        param: ClUint,
+    ) -> Option<String> {
+        let mut size = 0;
+        if query(handle, param, 0, ptr::null_mut(), &mut size) != CL_SUCCESS || size == 0 {
+            return None;
+        }
+        let mut buffer = vec![0u8; size];
+        if query(handle, param, size, buffer.as_mut_ptr() as *mut c_void, ptr::null_mut()) != CL_SUCCESS {
+            return None;
+        }
+        let value = CStr::from_bytes_until_nul(&buffer).ok()?.to_string_lossy().trim().to_string();
+        (!value.is_empty()).then_some(value)
+    }
+}
+
+impl Drop for OpenCl {
+    fn drop(&mut self) {
+        // SAFETY: every object created through the library is released
+        // before this, and the handle is not used after it
+        unsafe {
+            libc::dlclose(self.library);
+        }
+    }
+}
+
+/// A context, queue and compiled kernels on one device, released on drop
+struct Session<'a> {
+    opencl: &'a OpenCl,
+    device: Handle,
+    context: Handle,
+    queue: Handle,
+    program: Handle,
+    kernels: Vec<Handle>,
+    buffers: Vec<Handle>,
+}
+
+impl<'a> Session<'a> {
+    fn new(opencl: &'a OpenCl, device: Handle) -> Result<Self> {
+        let mut session = Session {
+            opencl,
+            device,
+            context: ptr::null_mut(),
+            queue: ptr::null_mut(),
+            program: ptr::null_mut(),
+            kernels: Vec::new(),
+            buffers: Vec::new(),
+        };
+
+        // SAFETY: handles passed on are the ones OpenCL just returned, and
+        // the program source is a NUL-terminated string
+        unsafe {
+            let mut status = CL_SUCCESS;
+            session.context =
+                (opencl.create_context)(ptr::null(), 1, &device, ptr::null(), ptr::null_mut(), &mut status);
+            check(status, "clCreateContext")?;
+            session.queue = (opencl.create_queue)(session.context, device, 0, &mut status);
+            check(status, "clCreateCommandQueue")?;
+
+            let source = KERNELS.as_ptr();
+            session.program = (opencl.create_program)(session.context, 1, &source, ptr::null(), &mut status);
+            check(status, "clCreateProgramWithSource")?;
+            let status = (opencl.build_program)(session.program, 1, &device, c"".as_ptr(), ptr::null(), ptr::null_mut());
+            if status != CL_SUCCESS {
+                let log = session.build_log().unwrap_or_default();
+                bail!("Failed to compile the benchmark kernels (error {}): {}", status, log);
+            }
+        }
+        Ok(session)
+    }
+
+    /// The compiler output for the device
+    fn build_log(&self) -> Option<String> {
+        // SAFETY: the buffer is sized from the length OpenCL reports
+        unsafe {
+            let query = self.opencl.build_info;
+            let mut size = 0;
+            if query(self.program, self.device, CL_PROGRAM_BUILD_LOG, 0, ptr::null_mut(), &mut size) != CL_SUCCESS {
+                return None;
+            }
+            let mut buffer = vec![0u8; size];
+            let status = query(
+                self.program,
+                self.device,
+                CL_PROGRAM_BUILD_LOG,
+                size,
+                buffer.as_mut_ptr() as *mut c_void,
+                ptr::null_mut(),
+            );
+            (status == CL_SUCCESS).then(|| String::from_utf8_lossy(&buffer).trim_end_matches('\0').trim().to_string())
+        }
+    }
+
+    fn kernel(&mut self, name: &CStr) -> Result<Handle> {
+        let mut status = CL_SUCCESS;
+        // SAFETY: the program was built and the name is NUL-terminated
+        let kernel = unsafe { (self.opencl.create_kernel)(self.program, name.as_ptr(), &mut status) };
+        check(status, "clCreateKernel")?;
+        self.kernels.push(kernel);
+        Ok(kernel)
+    }
+
+    fn buffer(&mut self, size: usize) -> Result<Handle> {
+        let mut status = CL_SUCCESS;
+        // SAFETY: no host pointer is passed, so OpenCL allocates on its own
+        let buffer =
+            unsafe { (self.opencl.create_buffer)(self.context, CL_MEM_READ_WRITE, size, ptr::null_mut(), &mut status) };
+        check(status, "clCreateBuffer")?;
+        self.buffers.push(buffer);
+        Ok(buffer)
+    }
+
+    /// Set the argument at `index` to a plain value or a buffer handle
+    fn arg<T>(&self, kernel: Handle, index: ClUint, value: &T) -> Result<()> {
+        // SAFETY: the value outlives the call, which copies it
+        let status = unsafe {
+            (self.opencl.set_kernel_arg)(kernel, index, std::mem::size_of::<T>(), value as *const T as *const c_void)
+        };
+        check(status, "clSetKernelArg")
+    }
+
+    /// Run `kernel` over `work_items` and wait for it
+    fn run(&self, kernel: Handle, work_items: usize) -> Result<Duration> {
+        let start = Instant::now();
+        // SAFETY: one dimension, so one global size; the driver picks the
+        // work-group size
+        unsafe {
+            let status = (self.opencl.enqueue_kernel)(
+                self.queue,
+                kernel,
+                1,
+                ptr::null(),
+                &work_items,
+                ptr::null(),
+                0,
+                ptr::null(),
+                ptr::null_mut(),
+            );
+            check(status, "clEnqueueNDRangeKernel")?;
+            check((self.opencl.finish)(self.queue), "clFinish")?;
+        }
+        Ok(start.elapsed())
+    }
+
+    /// Fastest of `RUNS` runs, after one to warm up
+    fn best_run(&self, kernel: Handle, work_items: usize) -> Result<Duration> {
+        self.run(kernel, work_items)?;
+        (0..RUNS).map(|_| self.run(kernel, work_items)).try_fold(Duration::MAX, |best, run| Ok(best.min(run?)))
+    }
+
+    /// Achieved FP32 throughput in GFLOPS
+    fn measure_flops(&mut self) -> Result<f64> {
+        let kernel = self.kernel(c"fma_chains")?;
+        let out = self.buffer(FLOPS_WORK_ITEMS * std::mem::size_of::<f32>())?;
+        self.arg(kernel, 0, &out)?;
+        self.arg(kernel, 1, &0.999f32)?;
+
+        // Lengthen the loop until a run is long enough to time
+        let mut iterations: i32 = 64;
+        loop {
+            self.arg(kernel, 2, &iterations)?;
+            if self.run(kernel, FLOPS_WORK_ITEMS)? >= MIN_RUN_TIME || iterations >= 1 << 20 {
+                break;
+            }
+            iterations *= 2;
+        }
+
+        let time = self.best_run(kernel, FLOPS_WORK_ITEMS)?;
+        let operations = FLOPS_WORK_ITEMS as f64 * f64::from(iterations) * FLOPS_PER_ITERATION;
+        Ok(operations / time.as_secs_f64() / 1e9)
+    }
+
+    /// Achieved copy bandwidth in GB/s
+    fn measure_bandwidth(&mut self) -> Result<f64> {
+        let mut max_alloc: u64 = 0;
+        // SAFETY: CL_DEVICE_MAX_MEM_ALLOC_SIZE is a cl_ulong
+        unsafe {
+            (self.opencl.device_info)(
+                self.device,
+                CL_DEVICE_MAX_MEM_ALLOC_SIZE,
+                std::mem::size_of::<u64>(),
+                &mut max_alloc as *mut u64 as *mut c_void,
+                ptr::null_mut(),
+            );
+        }
+        // Whole float4 elements, within what a single buffer may hold
+        let bytes = (COPY_BYTES as u64).min(max_alloc.max(1 << 20)) as usize / 16 * 16;
+
+        let kernel = self.kernel(c"copy")?;
+        let src = self.buffer(bytes)?;
+        let dst = self.buffer(bytes)?;
+        self.arg(kernel, 0, &src)?;
+        self.arg(kernel, 1, &dst)?;
+
+        let time = self.best_run(kernel, bytes / 16)?;
+        Ok(2.0 * bytes as f64 / time.as_secs_f64() / 1e9)
+    }
+}
+
+impl Drop for Session<'_> {
+    fn drop(&mut self) {
+        // SAFETY: every handle was created in this session and is released once
+        unsafe {
+            for &buffer in &self.buffers {
+                (self.opencl.release_mem)(buffer);
+            }
+            for &kernel in &self.kernels {
+                (self.opencl.release_kernel)(kernel);
+            }
+            if !self.program.is_null() {
+                (self.opencl.release_program)(self.program);
+            }
+            if !self.queue.is_null() {
+                (self.opencl.release_queue)(self.queue);
+            }
+            if !self.context.is_null() {
+                (self.opencl.release_context)(self.context);
+            }
+        }
+    }
+}
+
+/// Run both workloads on `gpu`
+pub fn run(gpu: &GpuInfo) -> Result<BenchResult> {
+    let opencl = OpenCl::open()?;
+    let (platform, device) = opencl.find_device(gpu)?;
+
+    // SAFETY: both are string properties
+    let (platform_name, device_name) = unsafe {
+        (
+            opencl.info_string(opencl.platform_info, platform, CL_PLATFORM_NAME),
+            opencl.info_string(opencl.device_info, device, CL_DEVICE_NAME),
+        )
+    };
+    tracing::debug!("benchmarking {} on {:?}", device_name.as_deref().unwrap_or("unknown device"), platform_name);
+
+    let mut session = Session::new(&opencl, device)?;
+    let gflops = session.measure_flops().context("Throughput benchmark failed")?;
+    let bandwidth_gbs = session.measure_bandwidth().context("Bandwidth benchmark failed")?;
+    drop(session);
+
+    Ok(BenchResult {
+        platform: platform_name.unwrap_or_else(|| String::from("Unknown platform")),
+        gflops,
+        bandwidth_gbs,
+    })
+}
+
+/// Look up a function in the library
+///
+/// # Safety
+/// `T` must be a function pointer type matching the symbol's C signature.
+unsafe fn symbol<T: Copy>(library: *mut c_void, name: &CStr) -> Result<T> {
+    let address = libc::dlsym(library, name.as_ptr());
+    if address.is_null() {
+        bail!("{} lacks {}", LIBRARY.to_string_lossy(), name.to_string_lossy());
+    }
+    Ok(std::mem::transmute_copy(&address))
+}
+
+/// Turn an OpenCL status into an error naming the call
+fn check(status: ClInt, call: &str) -> Result<()> {
+    match status {
+        CL_SUCCESS => Ok(()),
+        status => Err(anyhow!("{} failed with error {}", call, status)),
+    }
+}
+
+/// Call a clGet*IDs function twice: for the count, then for the IDs
+///
+/// # Safety
+/// `call` must write at most `count` handles into the array it is given.
+unsafe fn list(call: impl Fn(ClUint, *mut Handle, *mut ClUint) -> ClInt) -> Result<Vec<Handle>> {
+    let mut count: ClUint = 0;
+    check(call(0, ptr::null_mut(), &mut count), "listing OpenCL objects")?;
+    let mut ids = vec![ptr::null_mut(); count as usize];
+    check(call(count, ids.as_mut_ptr(), ptr::null_mut()), "listing OpenCL objects")?;
+    Ok(ids)
+}
@@ -0,0 +1,88 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::alerts::AlertRule;
+use crate::display::Field;
+use crate::gpu::common::GpuVendor;
+use crate::units::Units;
+
+/// User preferences from `~/.config/gpufetch/config.toml`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// ASCII art files replacing the built-in vendor logos
+    pub logos: LogoFiles,
+    pub display: DisplaySettings,
+    /// Units for memory sizes, clocks and temperatures, as for `--memory-unit` and friends
+    pub units: Units,
+    /// Thresholds to alert on in `--watch` and `--daemon` mode
+    pub alerts: Vec<AlertRule>,
+}
+
+/// Defaults for the fetch card that flags override
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    /// Info fields to show, in order, as for `--fields`
+    pub fields: Option<Vec<Field>>,
+    /// Draw a bar under the memory usage lines; on unless set to false
+    pub memory_bar: Option<bool>,
+}
+
+/// Paths of per-vendor logo files; `~/` is expanded to the home directory
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct LogoFiles {
+    pub nvidia: Option<String>,
+    pub amd: Option<String>,
+    pub intel: Option<String>,
+}
+
+impl LogoFiles {
+    /// Logo file configured for `vendor`
+    pub fn for_vendor(&self, vendor: &GpuVendor) -> Option<PathBuf> {
+        let path = match vendor {
+            GpuVendor::Nvidia => self.nvidia.as_ref(),
+            GpuVendor::Amd => self.amd.as_ref(),
+            GpuVendor::Intel => self.intel.as_ref(),
+            _ => None,
+        }?;
+        Some(expand_home(path))
+    }
+}
+
+impl Config {
+    /// Location of the config file
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("gpufetch").join("config.toml"))
+    }
+
+    /// Read the config file, treating a missing file as empty
+    pub fn load() -> Result<Self> {
+        let path = match Self::path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Config::default()),
+        };
+
+        let content = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Read the config file, warning and using defaults if it is broken
+    pub fn load_or_warn() -> Self {
+        Self::load().unwrap_or_else(|e| {
+            eprintln!("Warning: ignoring config file: {:#}", e);
+            Config::default()
+        })
+    }
+}
+
+/// Expand a leading `~/` to the home directory
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
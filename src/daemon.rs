@@ -0,0 +1,549 @@
+//! `--daemon`: answer GPU queries over a Unix domain socket.
+//!
+//! Detection runs in the background every `--refresh-interval` seconds and
+//! queries are answered from the last result, so status bars can poll as
+//! often as they like without each poll running nvidia-smi or walking sysfs.
+//! Where libudev is available, DRM hotplug events trigger a detection right
+//! away, so an eGPU or monitor being plugged in shows up without waiting.
+//!
+//! Clients send one JSON request per line and get one JSON response per
+//! line back:
+//!
+//! ```text
+//! {"command":"ping"}            {"ok":true}
+//! {"command":"gpus"}            {"ok":true,"updated":1712345678,"gpus":[...]}
+//! {"command":"gpu","index":0}   {"ok":true,"updated":1712345678,"gpu":{...}}
+//! {"command":"refresh"}         detects again, then answers like "gpus"
+//! ```
+//!
+//! Failures are `{"ok":false,"error":{"message":...,"kind":...}}`, with the
+//! kinds of `--error-format json`: `parse_failure` for a malformed request,
+//! `not_found` for a GPU index past the last GPU, `no_gpus` and the like
+//! when detection failed.
+//!
+//! `--serve ADDRESS` answers HTTP GET requests from the same results, for
+//! dashboards and home automation: `/gpus` is every GPU and `/gpus/{index}`
+//! one of them, both in the full model of `--output json`. Failures carry
+//! the same error body with a 4xx or 5xx status.
+//!
+//! Clients are served on threads of their own, `MAX_CLIENTS` at most. HTTP
+//! clients have `HTTP_TIMEOUT` to send a request of at most
+//! `MAX_HTTP_HEAD_BYTES` before the headers end; socket clients are dropped
+//! after `IDLE_TIMEOUT` without a request, or on a request line longer than
+//! `MAX_REQUEST_LINE_BYTES`.
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+use crate::alerts::Alerts;
+use crate::error::{Entry, GpufetchError};
+use crate::gpu::common::{GpuInfo, Topology};
+#[cfg(feature = "udev")]
+use crate::gpu::udev;
+use crate::gpu::GpuManager;
+use crate::pipeline::Pipeline;
+use crate::units::Units;
+
+/// Socket file name under `$XDG_RUNTIME_DIR` (or the temp directory)
+const SOCKET_NAME: &str = "gpufetch.sock";
+
+/// Longest HTTP request line and headers together; past it the request is
+/// answered with 431
+const MAX_HTTP_HEAD_BYTES: u64 = 16 * 1024;
+
+/// How long an HTTP client may take to send its request or read the answer
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest request line on the socket; a longer one gets an error and the
+/// connection is closed, since the rest of it can't be told from the next
+/// request
+const MAX_REQUEST_LINE_BYTES: u64 = 64 * 1024;
+
+/// How long a socket client may stay connected without sending a request
+const IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Clients served at once, over the socket and HTTP together; the ones
+/// past it are disconnected right away
+const MAX_CLIENTS: usize = 64;
+
+/// Where the daemon listens unless `--socket` is given
+pub fn default_socket_path() -> PathBuf {
+    dirs::runtime_dir().unwrap_or_else(std::env::temp_dir).join(SOCKET_NAME)
+}
+
+/// The last detection result
+struct Snapshot {
+    result: Result<Vec<GpuInfo>, GpufetchError>,
+    /// Seconds since the Unix epoch
+    updated: u64,
+    sampled: Instant,
+}
+
+/// Energy used between two detections of the same GPU over the time
+/// between them
+///
+/// The counter starts over when the driver reloads, which shows as less
+/// energy than before.
+pub fn average_power(gpu: &GpuInfo, previous: &GpuInfo, seconds: f64) -> Option<f64> {
+    let address = |gpu: &GpuInfo| gpu.pci_info.as_ref().map(|pci_info| pci_info.address());
+    if address(gpu) != address(previous) || seconds <= 0.0 {
+        return None;
+    }
+    let joules = gpu.energy_j? - previous.energy_j?;
+    (joules >= 0.0).then(|| joules / seconds)
+}
+
+/// Detection state shared between the refresh thread and clients
+struct Daemon {
+    manager: GpuManager,
+    pipeline: Pipeline,
+    units: Units,
+    alerts: Mutex<Alerts>,
+    snapshot: Mutex<Snapshot>,
+    /// Clients being served
+    clients: AtomicUsize,
+}
+
+impl Daemon {
+    /// Run detection and keep its result for queries
+    fn refresh(&self) {
+        let mut result = self
+            .manager
+            .detect_gpus()
+            .and_then(|detection| self.pipeline.refine(detection.gpus));
+        match result {
+            Ok(ref gpus) => {
+                debug!("refreshed {} GPU(s)", gpus.len());
+                self.alerts.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).check(gpus);
+            }
+            Err(ref e) => info!("refresh failed: {}", e),
+        }
+
+        let updated = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+        let sampled = Instant::now();
+        let mut snapshot = self.snapshot.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let (Ok(gpus), Ok(previous)) = (&mut result, &snapshot.result) {
+            let seconds = sampled.duration_since(snapshot.sampled).as_secs_f64();
+            for (gpu, previous) in gpus.iter_mut().zip(previous) {
+                gpu.average_power_w = average_power(gpu, previous, seconds);
+            }
+        }
+        *snapshot = Snapshot { result, updated, sampled };
+    }
+
+    /// Answer one request line
+    fn respond(&self, line: &str) -> String {
+        let request = match serde_json::from_str::<Request>(line) {
+            Ok(request) => request,
+            Err(e) => return failure(&GpufetchError::parse_failure("request", e.to_string())),
+        };
+
+        if request == Request::Ping {
+            return r#"{"ok":true}"#.to_string();
+        }
+        if request == Request::Refresh {
+            self.refresh();
+        }
+
+        let snapshot = self.snapshot.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let gpus = match snapshot.result {
+            Ok(ref gpus) => gpus,
+            Err(ref e) => return failure(e),
+        };
+        let response = match request {
+            Request::Gpu { index } => match gpus.get(index) {
+                Some(gpu) => self.units.json(&GpuResponse {
+                    ok: true,
+                    updated: snapshot.updated,
+                    gpu: GpuStatus::new(index, gpu, self.units),
+                }),
+                None => {
+                    let resource = format!("GPU {} ({} detected)", index, gpus.len());
+                    return failure(&GpufetchError::not_found(resource));
+                }
+            },
+            _ => self.units.json(&GpusResponse {
+                ok: true,
+                updated: snapshot.updated,
+                gpus: gpus
+                    .iter()
+                    .enumerate()
+                    .map(|(index, gpu)| GpuStatus::new(index, gpu, self.units))
+                    .collect(),
+            }),
+        };
+        response
+            .and_then(|json| serde_json::to_string(&json))
+            .unwrap_or_else(|e| failure(&GpufetchError::parse_failure("response", e.to_string())))
+    }
+
+    /// Answer an HTTP request for `path`: `/gpus`, or `/gpus/{index}` for one
+    /// GPU, as the full GPU model of `--output json`
+    fn route(&self, method: &str, path: &str) -> (u16, String) {
+        let not_found = || (404, failure(&GpufetchError::not_found(path)));
+        if method != "GET" {
+            let reason = format!("{} is not supported, only GET", method);
+            return (405, failure(&GpufetchError::invalid_argument("method", reason)));
+        }
+        let path = path.split('?').next().unwrap_or_default().trim_end_matches('/');
+        let index = match path.strip_prefix("/gpus") {
+            Some("") => None,
+            Some(rest) => match rest.strip_prefix('/').and_then(|index| index.parse::<usize>().ok()) {
+                Some(index) => Some(index),
+                None => return not_found(),
+            },
+            None => return not_found(),
+        };
+
+        let snapshot = self.snapshot.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let gpus = match snapshot.result {
+            Ok(ref gpus) => gpus,
+            Err(ref e) => return (503, failure(e)),
+        };
+        let body = match index {
+            None => self.units.json(gpus),
+            Some(index) => match gpus.get(index) {
+                Some(gpu) => self.units.json(gpu),
+                None => return (404, failure(&GpufetchError::not_found(format!("GPU {} ({} detected)", index, gpus.len())))),
+            },
+        };
+        match body.and_then(|json| serde_json::to_string(&json)) {
+            Ok(body) => (200, body),
+            Err(e) => (500, failure(&GpufetchError::parse_failure("response", e.to_string()))),
+        }
+    }
+
+    /// Answer one HTTP/1.1 request and close the connection
+    fn serve_http(&self, stream: TcpStream) -> Result<()> {
+        stream.set_read_timeout(Some(HTTP_TIMEOUT))?;
+        stream.set_write_timeout(Some(HTTP_TIMEOUT))?;
+        let mut writer = stream.try_clone()?;
+        let (status, body) = match read_http_head(&mut BufReader::new(stream).take(MAX_HTTP_HEAD_BYTES))? {
+            HttpHead::Request(request_line) => {
+                let mut parts = request_line.split_whitespace();
+                match (parts.next(), parts.next()) {
+                    (Some(method), Some(path)) => self.route(method, path),
+                    _ => (400, failure(&GpufetchError::parse_failure("request", "malformed HTTP request line"))),
+                }
+            }
+            HttpHead::TooLarge => {
+                let reason = format!("request line and headers are longer than {} bytes", MAX_HTTP_HEAD_BYTES);
+                (431, failure(&GpufetchError::parse_failure("request", reason)))
+            }
+        };
+        let reason = match status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            431 => "Request Header Fields Too Large",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        };
+        write!(
+            writer,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            reason,
+            body.len(),
+            body
+        )?;
+        Ok(())
+    }
+
+    /// Answer requests from one client until it disconnects or goes idle
+    fn serve(&self, stream: UnixStream) -> Result<()> {
+        stream.set_read_timeout(Some(IDLE_TIMEOUT))?;
+        let mut writer = stream.try_clone()?;
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.by_ref().take(MAX_REQUEST_LINE_BYTES).read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+            if !line.ends_with('\n') && line.len() as u64 == MAX_REQUEST_LINE_BYTES {
+                let reason = format!("request is longer than {} bytes", MAX_REQUEST_LINE_BYTES);
+                writeln!(writer, "{}", failure(&GpufetchError::parse_failure("request", reason)))?;
+                return Ok(());
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            writeln!(writer, "{}", self.respond(line.trim_end()))?;
+        }
+    }
+}
+
+/// The start of an HTTP request, as far as the size cap lets it be read
+enum HttpHead {
+    Request(String),
+    TooLarge,
+}
+
+/// Read the request line and skip the headers, which say nothing a GET here
+/// depends on
+fn read_http_head(reader: &mut io::Take<BufReader<TcpStream>>) -> io::Result<HttpHead> {
+    let mut request_line = String::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line)?;
+        // A line cut short by the cap rather than by the client hanging up
+        if !line.ends_with('\n') && reader.limit() == 0 {
+            return Ok(HttpHead::TooLarge);
+        }
+        if request_line.is_empty() {
+            request_line = line.clone();
+        }
+        if line.trim().is_empty() {
+            return Ok(HttpHead::Request(request_line));
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum Request {
+    Ping,
+    Gpus,
+    Gpu { index: usize },
+    Refresh,
+}
+
+#[derive(Serialize)]
+struct GpusResponse<'a> {
+    ok: bool,
+    updated: u64,
+    gpus: Vec<GpuStatus<'a>>,
+}
+
+#[derive(Serialize)]
+struct GpuResponse<'a> {
+    ok: bool,
+    updated: u64,
+    gpu: GpuStatus<'a>,
+}
+
+#[derive(Serialize)]
+struct FailureResponse<'a> {
+    ok: bool,
+    error: Entry<'a>,
+}
+
+fn failure(error: &GpufetchError) -> String {
+    let response = FailureResponse { ok: false, error: Entry::new(error) };
+    serde_json::to_string(&response).unwrap_or_else(|_| r#"{"ok":false}"#.to_string())
+}
+
+/// What clients get told about a GPU; every key is always present, unknown
+/// values are null
+///
+/// Numbers are in the unit their key names, e.g. `vram_bytes`, renamed to
+/// the units chosen with `--memory-unit` and friends as in `--output json`;
+/// `display` has the same values formatted for people.
+#[derive(Serialize)]
+struct GpuStatus<'a> {
+    index: usize,
+    name: &'a str,
+    vendor: String,
+    architecture: &'a str,
+    chip: &'a str,
+    /// Nominal feature size; `display.process` names the foundry's node
+    process_nm: Option<u32>,
+    pci_address: Option<String>,
+    integrated: bool,
+    vram_bytes: Option<u64>,
+    memory_type: Option<String>,
+    memory_bus_width: Option<u32>,
+    clock_mhz: u32,
+    max_clock_mhz: u32,
+    temperature_c: Option<f64>,
+    power_draw_w: Option<f64>,
+    energy_j: Option<f64>,
+    /// Over the last refresh interval
+    average_power_w: Option<f64>,
+    core_voltage_mv: Option<u32>,
+    compute_units: Option<u32>,
+    driver_version: Option<&'a str>,
+    kernel_driver: Option<&'a str>,
+    uuid: Option<&'a str>,
+    display: DisplayValues,
+}
+
+#[derive(Serialize)]
+struct DisplayValues {
+    vram: Option<String>,
+    clock: String,
+    max_clock: String,
+    temperature: Option<String>,
+    process: Option<String>,
+}
+
+impl<'a> GpuStatus<'a> {
+    fn new(index: usize, gpu: &'a GpuInfo, units: Units) -> Self {
+        GpuStatus {
+            index,
+            name: &gpu.name,
+            vendor: gpu.vendor.to_string(),
+            architecture: &gpu.architecture,
+            chip: &gpu.chip,
+            process_nm: gpu.process.as_ref().map(|process| process.nm),
+            pci_address: gpu.pci_info.as_ref().map(|pci| pci.address()),
+            integrated: gpu.is_integrated,
+            vram_bytes: gpu.memory.as_ref().map(|memory| memory.size_bytes),
+            memory_type: gpu.memory.as_ref().map(|memory| memory.memory_type.to_string()),
+            memory_bus_width: gpu.memory.as_ref().map(|memory| memory.bus_width).filter(|&width| width > 0),
+            clock_mhz: gpu.freq_mhz,
+            max_clock_mhz: gpu.max_freq_mhz,
+            temperature_c: gpu.temperature_c,
+            power_draw_w: gpu.power_draw_w,
+            energy_j: gpu.energy_j,
+            average_power_w: gpu.average_power_w,
+            core_voltage_mv: gpu.core_voltage_mv,
+            compute_units: gpu.topology.as_ref().map(Topology::compute_units),
+            driver_version: gpu.driver_version.as_deref(),
+            kernel_driver: gpu.kernel_driver.as_deref(),
+            uuid: gpu.uuid.as_deref(),
+            display: DisplayValues {
+                vram: gpu.memory.as_ref().map(|memory| units.memory(memory.size_bytes)),
+                clock: units.frequency(gpu.freq_mhz),
+                max_clock: units.frequency(gpu.max_freq_mhz),
+                temperature: gpu.temperature_c.map(|celsius| units.temperature(celsius)),
+                process: gpu.process.as_ref().map(|process| process.to_string()),
+            },
+        }
+    }
+}
+
+/// Bind the socket, replacing the file a previous daemon left behind
+fn bind(socket: &Path) -> Result<UnixListener> {
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            bail!("A gpufetch daemon is already listening on {}", socket.display());
+        }
+        fs::remove_file(socket).with_context(|| format!("Failed to remove stale socket {}", socket.display()))?;
+    }
+    UnixListener::bind(socket).with_context(|| format!("Failed to listen on {}", socket.display()))
+}
+
+/// Something that waits for the next refresh: the interval, or less when a
+/// GPU or monitor is plugged in or out and udev is there to say so
+#[cfg(feature = "udev")]
+fn hotplug_waiter() -> Box<dyn Fn(Duration)> {
+    match udev::Monitor::open() {
+        Ok(monitor) => Box::new(move |interval| {
+            if monitor.wait(interval) {
+                info!("DRM devices changed, detecting again");
+            }
+        }),
+        Err(e) => {
+            debug!("no hotplug events: {:#}", e);
+            Box::new(thread::sleep)
+        }
+    }
+}
+
+#[cfg(not(feature = "udev"))]
+fn hotplug_waiter() -> Box<dyn Fn(Duration)> {
+    Box::new(thread::sleep)
+}
+
+/// Serve queries on `socket` and over HTTP on `http` until killed,
+/// detecting again every `interval` and putting each result through
+/// `pipeline`
+pub fn run(
+    manager: GpuManager,
+    pipeline: Pipeline,
+    units: Units,
+    alerts: Alerts,
+    socket: Option<&Path>,
+    http: Option<SocketAddr>,
+    interval: Duration,
+) -> Result<()> {
+    let unix_listener = socket.map(bind).transpose()?;
+    let http_listener = http
+        .map(|address| TcpListener::bind(address).with_context(|| format!("Failed to listen on {}", address)))
+        .transpose()?;
+    let daemon = Arc::new(Daemon {
+        manager,
+        pipeline,
+        units,
+        alerts: Mutex::new(alerts),
+        snapshot: Mutex::new(Snapshot {
+            result: Ok(Vec::new()),
+            updated: 0,
+            sampled: Instant::now(),
+        }),
+        clients: AtomicUsize::new(0),
+    });
+    daemon.refresh();
+    if let Some(socket) = socket {
+        println!("Listening on {}", socket.display());
+    }
+    if let Some(ref listener) = http_listener {
+        println!("Serving http://{}/gpus", listener.local_addr()?);
+    }
+
+    let refresher = Arc::clone(&daemon);
+    thread::spawn(move || {
+        let wait = hotplug_waiter();
+        loop {
+            wait(interval);
+            refresher.refresh();
+        }
+    });
+
+    let http_thread = http_listener.map(|listener| {
+        let daemon = Arc::clone(&daemon);
+        thread::spawn(move || accept(listener.incoming(), &daemon, Daemon::serve_http))
+    });
+    match unix_listener {
+        Some(listener) => accept(listener.incoming(), &daemon, Daemon::serve),
+        None => {
+            if let Some(http_thread) = http_thread {
+                let _ = http_thread.join();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Serve each client of a listener on a thread of its own, up to
+/// `MAX_CLIENTS` at once
+fn accept<S: Send + 'static>(
+    incoming: impl Iterator<Item = io::Result<S>>,
+    daemon: &Arc<Daemon>,
+    serve: fn(&Daemon, S) -> Result<()>,
+) {
+    for stream in incoming {
+        match stream {
+            Ok(stream) => {
+                if daemon.clients.fetch_add(1, Ordering::SeqCst) >= MAX_CLIENTS {
+                    daemon.clients.fetch_sub(1, Ordering::SeqCst);
+                    warn!("turned a client away: {} are already connected", MAX_CLIENTS);
+                    continue;
+                }
+                let daemon = Arc::clone(daemon);
+                thread::spawn(move || {
+                    if let Err(e) = serve(&daemon, stream) {
+                        debug!("client disconnected: {:#}", e);
+                    }
+                    daemon.clients.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => warn!("failed to accept a client: {}", e),
+        }
+    }
+}
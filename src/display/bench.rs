@@ -0,0 +1,45 @@
+//! Results of `gpufetch bench` next to the theoretical peaks.
+
+use colored::Colorize;
+
+use super::fields::format_flops;
+use crate::bench::BenchResult;
+use crate::gpu::common::GpuInfo;
+
+/// Print measured throughput and bandwidth, with how much of the
+/// theoretical peak they reach where it is known
+pub fn print_bench(gpu: &GpuInfo, result: &BenchResult) {
+    println!("{} (OpenCL: {})", gpu.name.bold(), result.platform);
+
+    let peak = gpu.peak_performance_gflops.filter(|&gflops| gflops > 0.0);
+    println!(
+        "FP32:              {}",
+        compare(format_flops(result.gflops), result.gflops, peak.map(|gflops| (format_flops(gflops), gflops)))
+    );
+
+    let peak = theoretical_bandwidth_gbs(gpu);
+    println!(
+        "Memory Bandwidth:  {}",
+        compare(
+            format!("{:.0} GB/s", result.bandwidth_gbs),
+            result.bandwidth_gbs,
+            peak.map(|gbs| (format!("{:.0} GB/s", gbs), gbs))
+        )
+    );
+}
+
+/// "X measured, Y theoretical (Z%)", or just the measurement
+fn compare(measured: String, value: f64, peak: Option<(String, f64)>) -> String {
+    match peak {
+        Some((peak, peak_value)) => {
+            format!("{} measured, {} theoretical ({:.0}%)", measured, peak, value / peak_value * 100.0)
+        }
+        None => format!("{} measured", measured),
+    }
+}
+
+/// Bus width times data rate, for memory whose data rate is known
+fn theoretical_bandwidth_gbs(gpu: &GpuInfo) -> Option<f64> {
+    let bus_width = gpu.memory.as_ref().map(|memory| memory.bus_width).filter(|&width| width > 0)?;
+    gpu.memory_data_rate_gbps().map(|data_rate| f64::from(bus_width) / 8.0 * data_rate)
+}
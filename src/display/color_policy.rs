@@ -0,0 +1,67 @@
+//! The single place that decides whether output is colorized.
+//!
+//! Every output path asks this module instead of checking flags or the
+//! environment itself. In precedence order:
+//!
+//! 1. `--no-color` always disables color
+//! 2. `NO_COLOR` (any non-empty value) disables color
+//! 3. `CLICOLOR_FORCE` (any value other than `0`) enables color, even when piped
+//! 4. A monochrome terminal (see [`super::terminal`]) or `CLICOLOR=0` disables color
+//! 5. Otherwise color is used only when the stream is a terminal
+
+use std::env;
+use std::io::{self, IsTerminal};
+
+use super::terminal::ColorDepth;
+
+/// Resolved color decision for one output stream
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ColorPolicy {
+    enabled: bool,
+}
+
+impl ColorPolicy {
+    /// Policy for standard output on a terminal with `color_depth`
+    pub fn for_stdout(no_color_flag: bool, color_depth: ColorDepth) -> Self {
+        Self::resolve(no_color_flag, io::stdout().is_terminal(), color_depth, |key| env::var(key).ok())
+    }
+
+    /// Policy for standard error, where diagnostics go
+    pub fn for_stderr(no_color_flag: bool, color_depth: ColorDepth) -> Self {
+        Self::resolve(no_color_flag, io::stderr().is_terminal(), color_depth, |key| env::var(key).ok())
+    }
+
+    /// Policy for pictures of the output, which show color like a truecolor terminal
+    pub fn for_picture(no_color_flag: bool) -> Self {
+        Self::resolve(no_color_flag, true, ColorDepth::TrueColor, |key| env::var(key).ok())
+    }
+
+    /// Apply the precedence rules given the stream type and an environment lookup
+    fn resolve(
+        no_color_flag: bool,
+        is_terminal: bool,
+        color_depth: ColorDepth,
+        var: impl Fn(&str) -> Option<String>,
+    ) -> Self {
+        let enabled = if no_color_flag || var("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            false
+        } else if var("CLICOLOR_FORCE").is_some_and(|value| value != "0") {
+            true
+        } else if color_depth == ColorDepth::Monochrome || var("CLICOLOR").as_deref() == Some("0") {
+            false
+        } else {
+            is_terminal
+        };
+
+        ColorPolicy { enabled }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Make this policy the global default for the `colored` crate
+    pub fn apply_globally(&self) {
+        colored::control::set_override(self.enabled);
+    }
+}
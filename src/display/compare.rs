@@ -0,0 +1,98 @@
+use colored::Colorize;
+
+use super::fields::Field;
+use super::{get_theme, DisplayOptions};
+use crate::gpu::common::GpuInfo;
+
+/// Fields compared across GPUs, one table row per info line; the PCIe rows
+/// tell identical cards apart by address and slot
+const COMPARED_FIELDS: [Field; 9] = [
+    Field::Vendor,
+    Field::Architecture,
+    Field::Memory,
+    Field::Pcie,
+    Field::Clock,
+    Field::Compute,
+    Field::Cache,
+    Field::Performance,
+    Field::Features,
+];
+
+/// Label for info lines that have none, such as "8704 CUDA Cores"
+const UNLABELED: &str = "Cores";
+
+/// Print a table with one column per GPU
+///
+/// Rows are the labelled info lines of the compared fields, in field order;
+/// GPUs without a value show "-".
+pub fn print_comparison(gpus: &[GpuInfo], options: &DisplayOptions) {
+    let columns: Vec<Vec<(Field, String, String)>> = gpus
+        .iter()
+        .map(|gpu| {
+            COMPARED_FIELDS
+                .iter()
+                .flat_map(|field| field.lines(gpu, options).into_iter().map(move |line| (*field, line)))
+                .map(|(field, line)| {
+                    let line = options.charset.render(&line).into_owned();
+                    match line.split_once(": ") {
+                        Some((label, value)) => (field, label.to_string(), value.to_string()),
+                        None => (field, UNLABELED.to_string(), line),
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    // Group rows by field so e.g. AMD's compute units sit with NVIDIA's SMs
+    let mut labels: Vec<&str> = Vec::new();
+    for field in COMPARED_FIELDS {
+        for (_, label, _) in columns.iter().flatten().filter(|(f, _, _)| *f == field) {
+            if !labels.contains(&label.as_str()) {
+                labels.push(label);
+            }
+        }
+    }
+
+    let value = |column: usize, label: &str| -> &str {
+        columns[column]
+            .iter()
+            .find(|(_, l, _)| l == label)
+            .map_or("-", |(_, _, value)| value.as_str())
+    };
+
+    let names: Vec<String> = gpus.iter().map(|gpu| options.charset.render(&gpu.name).into_owned()).collect();
+
+    let label_width = labels.iter().map(|label| label.chars().count()).max().unwrap_or(0);
+    let widths: Vec<usize> = names
+        .iter()
+        .enumerate()
+        .map(|(column, name)| {
+            labels
+                .iter()
+                .map(|label| value(column, label).chars().count())
+                .chain([name.chars().count()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    // GPU names head their columns in each vendor's color
+    let mut header = " ".repeat(label_width);
+    for ((gpu, name), width) in gpus.iter().zip(&names).zip(&widths) {
+        let theme = get_theme(gpu, options.color_scheme, options.custom_colors.as_ref()).for_depth(options.color_depth);
+        let padding = " ".repeat(width - name.chars().count());
+        header.push_str(&format!("  {}{}", theme.paint_bold(name, theme.accent_color()), padding));
+    }
+    println!("{}", header.trim_end());
+
+    let rule_width = label_width + widths.iter().map(|width| width + 2).sum::<usize>();
+    println!("{}", options.charset.render(&"─".repeat(rule_width)));
+
+    for label in &labels {
+        let mut row = format!("{:<width$}", label, width = label_width).bold().to_string();
+        for (column, width) in widths.iter().enumerate() {
+            row.push_str(&format!("  {:<width$}", value(column, label), width = width));
+        }
+        println!("{}", row.trim_end());
+    }
+}
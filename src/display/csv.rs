@@ -0,0 +1,84 @@
+//! `--output csv`: one row per GPU for inventory spreadsheets.
+//!
+//! The columns and their order are fixed, so rows collected from many
+//! machines line up; new columns are only ever added at the end. Values
+//! are in the unit their column names, which follows the units chosen, e.g.
+//! `vram_gib` with `--memory-unit gib`, and unknown ones are left empty.
+
+use crate::gpu::common::{GpuInfo, Topology};
+use crate::units::Units;
+
+/// Column names, in output order
+const COLUMNS: [&str; 20] = [
+    "index",
+    "name",
+    "vendor",
+    "architecture",
+    "chip",
+    "pci_address",
+    "integrated",
+    "vram_bytes",
+    "memory_type",
+    "memory_bus_width",
+    "clock_mhz",
+    "max_clock_mhz",
+    "compute_units",
+    "cores",
+    "l2_cache_bytes",
+    "peak_gflops",
+    "driver_version",
+    "kernel_driver",
+    "uuid",
+    "temperature_c",
+];
+
+/// Print a header and a row for every GPU
+pub fn print_csv(gpus: &[GpuInfo], units: Units) {
+    let header: Vec<String> = COLUMNS.iter().map(|column| units.rename(column)).collect();
+    println!("{}", header.join(","));
+    for (idx, gpu) in gpus.iter().enumerate() {
+        let row: Vec<String> = row(idx, gpu, units).iter().map(|value| escape(value)).collect();
+        println!("{}", row.join(","));
+    }
+}
+
+fn row(idx: usize, gpu: &GpuInfo, units: Units) -> [String; 20] {
+    let known = |value: &str| if value == "Unknown" { String::new() } else { value.to_string() };
+    let optional = |value: Option<String>| value.unwrap_or_default();
+    let measure = |column: &str, value: f64| units.convert(column, value).unwrap_or(value).to_string();
+    let nonzero = |column: &str, value: u32| if value > 0 { measure(column, f64::from(value)) } else { String::new() };
+    let memory = gpu.memory.as_ref();
+    let topology = gpu.topology.as_ref();
+
+    [
+        idx.to_string(),
+        gpu.name.clone(),
+        gpu.vendor.to_string(),
+        known(&gpu.architecture),
+        known(&gpu.chip),
+        optional(gpu.pci_info.as_ref().map(|pci| pci.address())),
+        gpu.is_integrated.to_string(),
+        optional(memory.map(|memory| measure("vram_bytes", memory.size_bytes as f64))),
+        optional(memory.map(|memory| memory.memory_type.to_string()).filter(|name| name != "Unknown")),
+        optional(memory.map(|memory| memory.bus_width).filter(|&width| width > 0).map(|width| width.to_string())),
+        nonzero("clock_mhz", gpu.freq_mhz),
+        nonzero("max_clock_mhz", gpu.max_freq_mhz),
+        optional(topology.map(|topology| topology.compute_units().to_string())),
+        optional(topology.and_then(Topology::shader_cores).map(|cores| cores.to_string())),
+        optional(gpu.cache.as_ref().and_then(|cache| cache.l2_size).map(|size| measure("l2_cache_bytes", size as f64))),
+        optional(gpu.peak_performance_gflops.filter(|&gflops| gflops > 0.0).map(|gflops| format!("{:.0}", gflops))),
+        optional(gpu.driver_version.clone()),
+        optional(gpu.kernel_driver.clone()),
+        optional(gpu.uuid.clone()),
+        optional(gpu.temperature_c.map(|celsius| format!("{:.0}", units.convert("temperature_c", celsius).unwrap_or(celsius)))),
+    ]
+}
+
+/// Quote a value if it holds a separator, quote or line break (RFC 4180)
+pub fn escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
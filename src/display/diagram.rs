@@ -0,0 +1,188 @@
+//! `--diagram`: a block diagram of the GPU's compute units.
+//!
+//! Each cluster (GPC, Shader Engine or slice) is a box holding one cell per
+//! unit (SM, CU or EU); Intel slices hold a row per subslice. Units are
+//! spread evenly across clusters, as in the `Layout` line, since which ones
+//! are fused off is not exposed. Below the boxes are the unit totals and the
+//! cache and memory all clusters share.
+
+use super::frame::frame_lines;
+use super::layout::visible_width;
+use super::terminal::Charset;
+use super::fields::cache;
+use super::{get_theme, DisplayOptions};
+use crate::gpu::common::{GpuInfo, MemoryType, Topology};
+use crate::utils;
+
+/// Cells per row inside a cluster box
+const CELLS_PER_ROW: u32 = 8;
+
+/// Width to lay boxes out in when stdout is not a terminal
+const DEFAULT_WIDTH: usize = 80;
+
+/// How a GPU's units are grouped, with the vendor's names for the groups
+struct Organization {
+    /// 1 when the GPU does not say how its units are grouped
+    clusters: u32,
+    clusters_known: bool,
+    cluster_name: &'static str,
+    /// Rows of each cluster, for Intel's subslices
+    rows_per_cluster: Option<u32>,
+    units: u32,
+    unit_name: &'static str,
+}
+
+impl Organization {
+    fn of(topology: &Topology) -> Self {
+        let (clusters, cluster_name, rows_per_cluster, units, unit_name) = match topology {
+            Topology::Nvidia(nvidia) => (nvidia.gpc_count, "GPC", None, nvidia.sm_count, "SM"),
+            Topology::Amd(amd) => (amd.shader_engines, "Shader Engine", None, amd.compute_units, "CU"),
+            Topology::Intel(intel) => {
+                let subslices = intel.slices.zip(intel.subslices).map(|(slices, subslices)| subslices / slices.max(1));
+                (intel.slices, "Slice", subslices, intel.execution_units, "EU")
+            }
+            Topology::Generic { compute_units } => (None, "GPU", None, *compute_units, "CU"),
+        };
+        let clusters = clusters.filter(|&clusters| clusters > 0 && clusters <= units);
+        Organization {
+            clusters: clusters.unwrap_or(1),
+            clusters_known: clusters.is_some(),
+            cluster_name,
+            rows_per_cluster: rows_per_cluster.filter(|&rows| rows > 0),
+            units,
+            unit_name,
+        }
+    }
+
+    /// Units in cluster `idx`, the remainder going to the first clusters
+    fn units_in(&self, idx: u32) -> u32 {
+        self.units / self.clusters + u32::from(idx < self.units % self.clusters)
+    }
+
+    /// Unit counts of the rows of a cluster holding `units`
+    fn rows(&self, units: u32) -> Vec<u32> {
+        match self.rows_per_cluster {
+            Some(rows) => (0..rows).map(|idx| units / rows + u32::from(idx < units % rows)).filter(|&count| count > 0).collect(),
+            None => (0..units).step_by(CELLS_PER_ROW as usize).map(|start| (units - start).min(CELLS_PER_ROW)).collect(),
+        }
+    }
+}
+
+/// Print the block diagram of `gpu`
+pub fn print_diagram(gpu: &GpuInfo, options: &DisplayOptions) {
+    let theme = get_theme(gpu, options.color_scheme, options.custom_colors.as_ref()).for_depth(options.color_depth);
+    let charset = options.charset;
+    println!("{}", theme.paint_bold(&charset.render(&gpu.name), theme.value_color()));
+
+    let Some(topology) = gpu.topology.as_ref().filter(|topology| topology.compute_units() > 0) else {
+        println!("The layout of this GPU's compute units is not known");
+        return;
+    };
+    let organization = Organization::of(topology);
+    let cell = match charset {
+        Charset::Utf8 => "■",
+        Charset::Ascii => "#",
+    };
+
+    let boxes: Vec<Vec<String>> = (0..organization.clusters)
+        .map(|idx| {
+            let rows: Vec<String> = organization
+                .rows(organization.units_in(idx))
+                .into_iter()
+                .map(|count| theme.paint(&vec![cell; count as usize].join(" "), theme.accent_color()))
+                .collect();
+            let title = if organization.clusters_known {
+                format!("{} {}", organization.cluster_name, idx)
+            } else {
+                format!("{} {}s", organization.units, organization.unit_name)
+            };
+            frame_lines(&title, &rows, &theme)
+                .iter()
+                .map(|line| charset.render(line).into_owned())
+                .collect()
+        })
+        .collect();
+
+    let width = utils::get_terminal_width().unwrap_or(DEFAULT_WIDTH);
+    for line in side_by_side(&boxes, width) {
+        println!("{}", line);
+    }
+    for line in totals(gpu, topology, &organization, options) {
+        println!("{}", theme.paint_info_line(&charset.render(&line)));
+    }
+}
+
+/// Lay boxes out left to right, starting a new row of boxes where the next
+/// one would not fit in `width`
+fn side_by_side(boxes: &[Vec<String>], width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut row: Vec<&Vec<String>> = Vec::new();
+    let mut row_width = 0;
+    for block in boxes {
+        let block_width = block.iter().map(|line| visible_width(line)).max().unwrap_or(0);
+        if !row.is_empty() && row_width + 1 + block_width > width {
+            lines.extend(join_row(&row));
+            row.clear();
+            row_width = 0;
+        }
+        row_width += if row.is_empty() { block_width } else { block_width + 1 };
+        row.push(block);
+    }
+    lines.extend(join_row(&row));
+    lines
+}
+
+/// One line per line of the tallest box, shorter boxes padded below
+fn join_row(row: &[&Vec<String>]) -> Vec<String> {
+    let height = row.iter().map(|block| block.len()).max().unwrap_or(0);
+    (0..height)
+        .map(|line| {
+            let parts: Vec<String> = row
+                .iter()
+                .map(|block| {
+                    let block_width = block.iter().map(|line| visible_width(line)).max().unwrap_or(0);
+                    match block.get(line) {
+                        Some(text) => format!("{}{}", text, " ".repeat(block_width - visible_width(text))),
+                        None => " ".repeat(block_width),
+                    }
+                })
+                .collect();
+            parts.join(" ").trim_end().to_string()
+        })
+        .collect()
+}
+
+/// Unit counts, and the caches and memory all clusters share
+fn totals(gpu: &GpuInfo, topology: &Topology, organization: &Organization, options: &DisplayOptions) -> Vec<String> {
+    let plural = |count: u32, name: &str| format!("{} {}{}", count, name, if count == 1 { "" } else { "s" });
+    let mut units = Vec::new();
+    if organization.clusters_known {
+        units.push(plural(organization.clusters, organization.cluster_name));
+    }
+    if let Topology::Intel(intel) = topology {
+        if let Some(subslices) = intel.subslices {
+            units.push(plural(subslices, "Subslice"));
+        }
+    }
+    units.push(plural(organization.units, organization.unit_name));
+    let counts = match topology {
+        Topology::Nvidia(nvidia) => vec![(nvidia.cuda_cores, "CUDA core"), (nvidia.tensor_cores, "Tensor core"), (nvidia.rt_cores, "RT core")],
+        Topology::Amd(amd) => vec![(amd.stream_processors, "Stream Processor"), (amd.rops, "ROP"), (amd.tmus, "TMU")],
+        Topology::Intel(_) | Topology::Generic { .. } => Vec::new(),
+    };
+    units.extend(counts.into_iter().filter_map(|(count, name)| Some(plural(count?, name))));
+
+    let mut lines = vec![format!("Units: {}", units.join(", "))];
+    lines.extend(cache(gpu, options));
+    if let Some(ref memory) = gpu.memory {
+        let mut line = format!("Memory: {}", options.units.memory(memory.size_bytes));
+        if !matches!(memory.memory_type, MemoryType::Unknown) {
+            line.push_str(&format!(" {}", memory.memory_type));
+        }
+        if memory.bus_width > 0 {
+            line.push_str(&format!(", {}-bit", memory.bus_width));
+        }
+        lines.push(line);
+    }
+    lines
+}
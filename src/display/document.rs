@@ -0,0 +1,96 @@
+//! `--output markdown` and `--output html`: the spec card as a table to
+//! paste into GitHub issues and wikis.
+//!
+//! Documents are written without colors and always in UTF-8, whatever the
+//! terminal supports, since they are read elsewhere.
+
+use anyhow::Result;
+use std::io::{self, Write};
+
+use super::{text_logo, DisplayOptions, Field};
+use crate::args::{LogoVariant, OutputFormat};
+use crate::gpu::common::GpuInfo;
+
+/// Print the spec card of every GPU in `gpus`, one after the other
+///
+/// With `embed_logo` the text art logo goes above each table as
+/// preformatted text.
+pub fn print_document(gpus: &[GpuInfo], format: OutputFormat, options: &DisplayOptions, embed_logo: bool) -> Result<()> {
+    let stdout = io::stdout();
+    let mut handle = stdout.lock();
+
+    for (idx, gpu) in gpus.iter().enumerate() {
+        let logo = (embed_logo && options.logo_variant != LogoVariant::None)
+            .then(|| text_logo(gpu, options, options.logo_variant).trim_matches('\n'));
+        let heading = options.fields.contains(&Field::Name).then_some(gpu.name.as_str());
+        let rows = rows(gpu, options);
+
+        let document = match format {
+            OutputFormat::Html => html(heading, logo, &rows),
+            _ => markdown(heading, logo, &rows),
+        };
+        if idx > 0 {
+            writeln!(handle)?;
+        }
+        write!(handle, "{}", document)?;
+    }
+
+    Ok(())
+}
+
+/// The card's lines split into label and value; lines without a label are
+/// labelled with their field, e.g. "Compute" for "8704 CUDA Cores"
+fn rows(gpu: &GpuInfo, options: &DisplayOptions) -> Vec<(String, String)> {
+    options
+        .fields
+        .iter()
+        .flat_map(|field| {
+            field.lines(gpu, options).into_iter().map(move |line| match line.split_once(": ") {
+                Some((label, value)) => (label.to_string(), value.to_string()),
+                None => (format!("{:?}", field), line),
+            })
+        })
+        .collect()
+}
+
+fn markdown(heading: Option<&str>, logo: Option<&str>, rows: &[(String, String)]) -> String {
+    let mut out = String::new();
+    if let Some(heading) = heading {
+        out.push_str(&format!("## {}\n\n", heading));
+    }
+    if let Some(logo) = logo {
+        out.push_str(&format!("```text\n{}\n```\n\n", logo));
+    }
+
+    out.push_str("| Field | Value |\n| --- | --- |\n");
+    for (label, value) in rows {
+        out.push_str(&format!("| {} | {} |\n", escape_markdown(label), escape_markdown(value)));
+    }
+    out
+}
+
+fn html(heading: Option<&str>, logo: Option<&str>, rows: &[(String, String)]) -> String {
+    let mut out = String::from("<section class=\"gpufetch\">\n");
+    if let Some(heading) = heading {
+        out.push_str(&format!("<h2>{}</h2>\n", escape_html(heading)));
+    }
+    if let Some(logo) = logo {
+        out.push_str(&format!("<pre>{}</pre>\n", escape_html(logo)));
+    }
+
+    out.push_str("<table>\n");
+    for (label, value) in rows {
+        out.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>\n", escape_html(label), escape_html(value)));
+    }
+    out.push_str("</table>\n</section>\n");
+    out
+}
+
+/// Keep a table cell from ending early or being read as markup
+fn escape_markdown(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('|', "\\|").replace('*', "\\*").replace('_', "\\_")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,752 @@
+//! The lines of the info panel, grouped into fields users can pick and
+//! reorder with `--fields` or `fields` in the `[display]` config table.
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use super::{sparkline, DisplayOptions};
+use crate::gpu::common::{DisplayInfo, GpuInfo, GpuVendor, MemoryType, Source, Topology};
+use crate::gpu::{hybrid, vaapi};
+use crate::suggestions;
+
+/// A group of related info lines
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Field {
+    /// GPU name heading
+    Name,
+    Vendor,
+    Architecture,
+    /// Chip name, die size, transistor count and launch year
+    Chip,
+    /// Manufacturing process, by the foundry's node name where known
+    Process,
+    /// Memory size, type, bus width and clocks
+    Memory,
+    /// Memory error correction
+    Ecc,
+    Nvlink,
+    /// PCIe link generation and width, the fastest link (detailed mode), and
+    /// the PCI address and slot
+    Pcie,
+    /// NUMA node and the CPUs local to the GPU, on multi-socket machines
+    Numa,
+    /// Core and boost clocks, voltage, performance state and throttling
+    Clock,
+    /// Current GPU temperatures and the slowdown and shutdown limits
+    Temperature,
+    /// Power draw against the rated power, energy used, and runtime power
+    /// management of discrete GPUs
+    Power,
+    /// Fan speed
+    Fan,
+    /// How busy each engine is (detailed mode)
+    Engines,
+    /// Cores, compute units and their layout
+    Compute,
+    /// L2 and L3 sizes, and L1 per SM or CU (detailed mode)
+    Cache,
+    /// Peak FP32 and FP64 throughput
+    Performance,
+    /// Hardware ray tracing and supported upscalers
+    Features,
+    /// Driver, kernel driver and Mesa versions, and OpenGL, VBIOS, firmware,
+    /// kernel parameters and module options (detailed mode)
+    Driver,
+    /// Installed Vulkan and OpenCL drivers (detailed mode) and problems with them
+    Runtime,
+    /// Driver conflicts and GPUs left without a driver
+    Warnings,
+    Hybrid,
+    /// Connected monitors
+    Displays,
+    /// Display server, when asked for with --session
+    Session,
+    /// Packages to install for a working driver, when asked for with --suggest
+    Suggestions,
+    /// VA-API codecs, when asked for with --vaapi, and encoder load (detailed mode)
+    Video,
+    Sriov,
+    /// UUID and serial number (detailed mode)
+    Identifiers,
+    /// Values taken from overrides.toml, the spec database or the model
+    /// name instead of the hardware (detailed mode)
+    Overrides,
+}
+
+impl Field {
+    /// Every field, in the order shown by default
+    pub const DEFAULT: [Field; 30] = [
+        Field::Name,
+        Field::Vendor,
+        Field::Architecture,
+        Field::Chip,
+        Field::Process,
+        Field::Memory,
+        Field::Ecc,
+        Field::Nvlink,
+        Field::Pcie,
+        Field::Numa,
+        Field::Clock,
+        Field::Temperature,
+        Field::Power,
+        Field::Fan,
+        Field::Engines,
+        Field::Compute,
+        Field::Cache,
+        Field::Performance,
+        Field::Features,
+        Field::Driver,
+        Field::Runtime,
+        Field::Warnings,
+        Field::Hybrid,
+        Field::Displays,
+        Field::Session,
+        Field::Suggestions,
+        Field::Video,
+        Field::Sriov,
+        Field::Identifiers,
+        Field::Overrides,
+    ];
+
+    /// Info lines of this field, as "Label: value" where there is a label
+    ///
+    /// The name heading is styled differently, so it is left to the caller
+    /// and yields nothing here.
+    pub fn lines(&self, gpu: &GpuInfo, options: &DisplayOptions) -> Vec<String> {
+        let detailed = options.detailed;
+        match self {
+            Field::Name => Vec::new(),
+            Field::Vendor => vec![format!("Vendor: {}", gpu.vendor)],
+            Field::Architecture => vec![format!("Architecture: {}", gpu.architecture)],
+            Field::Chip if gpu.chip != "Unknown" => vec![chip(gpu)],
+            Field::Chip => Vec::new(),
+            Field::Process => gpu.process.iter().map(|process| format!("Process: {}", process)).collect(),
+            Field::Memory => memory(gpu, options),
+            Field::Ecc => ecc(gpu),
+            Field::Nvlink => gpu
+                .nvlink
+                .as_ref()
+                .map(|nvlink| format!("NVLink: {} links, {:.1} GB/s", nvlink.active_links, nvlink.bandwidth_gbs))
+                .into_iter()
+                .collect(),
+            Field::Pcie => pcie(gpu, detailed),
+            Field::Numa => numa(gpu),
+            Field::Clock => clock(gpu, options),
+            Field::Temperature => temperature(gpu, options),
+            Field::Power => power(gpu),
+            Field::Fan => fan(gpu),
+            Field::Engines if detailed => engines(gpu),
+            Field::Engines => Vec::new(),
+            Field::Compute => compute(gpu, detailed),
+            Field::Cache => cache(gpu, options),
+            Field::Performance => performance(gpu, detailed),
+            Field::Features => features(gpu),
+            Field::Driver => driver(gpu, detailed),
+            Field::Runtime => runtime(gpu, detailed),
+            Field::Warnings => gpu.warnings.iter().map(|warning| format!("Warning: {}", warning)).collect(),
+            Field::Hybrid => hybrid(gpu),
+            Field::Displays => gpu
+                .displays
+                .iter()
+                .map(|display| format!("Display ({}): {}", display.connector, describe_display(display)))
+                .collect(),
+            Field::Session => session(gpu, options),
+            Field::Suggestions if options.suggest => suggestions::suggest(gpu, options.distro)
+                .into_iter()
+                .map(|suggestion| format!("Suggestion: {}", suggestion))
+                .collect(),
+            Field::Suggestions => Vec::new(),
+            Field::Video => video(gpu, detailed),
+            Field::Sriov => sriov(gpu, detailed),
+            Field::Identifiers if detailed => identifiers(gpu),
+            Field::Overrides if detailed => sources(gpu),
+            Field::Identifiers | Field::Overrides => Vec::new(),
+        }
+    }
+}
+
+fn memory(gpu: &GpuInfo, options: &DisplayOptions) -> Vec<String> {
+    let Some(ref memory) = gpu.memory else {
+        return Vec::new();
+    };
+
+    let units = options.units;
+    // Zero when the board doesn't report it, as on Arc cards under i915
+    let size = Some(memory.size_bytes).filter(|&size| size > 0).map(|size| units.memory(size));
+    let mut line = match (size, &memory.memory_type) {
+        (Some(size), MemoryType::Unknown) => format!("Memory: {}", size),
+        (Some(size), memory_type) => format!("Memory: {} {}", size, memory_type),
+        (None, memory_type) => format!("Memory: {}", memory_type),
+    };
+    if let Some(ref vendor) = gpu.memory_vendor {
+        line.push_str(&format!(" ({})", vendor));
+    }
+    let mut lines = vec![line];
+    if let Some(shared) = gpu.shared_memory_bytes.filter(|_| gpu.is_integrated) {
+        lines.push(format!("Shared Memory: {} of system memory", units.memory(shared)));
+    }
+    // Zero when unknown, such as with --no-heuristics
+    if memory.bus_width > 0 {
+        lines.push(format!("Memory Bus: {} bit", memory.bus_width));
+    }
+    // Integrated GPUs share system memory, whose clock is only guessed
+    if memory.clock_mhz > 0 && !matches!(memory.memory_type, MemoryType::Ddr3 | MemoryType::Ddr4 | MemoryType::Lpddr5) {
+        lines.push(format!("Memory Clock: {}", units.frequency(memory.clock_mhz)));
+    }
+    if let Some(data_rate) = gpu.memory_data_rate_gbps() {
+        lines.push(format!("Memory Data Rate: {:.1} Gbps", data_rate));
+    }
+    if let Some(fabric_clock) = gpu.fabric_clock_mhz {
+        lines.push(format!("Fabric Clock: {}", units.frequency(fabric_clock)));
+    }
+    lines.extend(memory_usage(gpu, options).into_iter().map(|(line, _)| line));
+    lines
+}
+
+/// "VRAM Used: 17.9 GB of 24.0 GB (75%)" lines, with the fraction used that
+/// the card draws a bar for
+pub(super) fn memory_usage(gpu: &GpuInfo, options: &DisplayOptions) -> Vec<(String, f64)> {
+    let units = options.units;
+    let dedicated = gpu.memory.as_ref().map(|memory| memory.size_bytes);
+    let shared = gpu.shared_memory_bytes.filter(|_| gpu.is_integrated);
+    [("VRAM Used", gpu.memory_used_bytes, dedicated), ("Shared Memory Used", gpu.shared_memory_used_bytes, shared)]
+        .into_iter()
+        .filter_map(|(label, used, total)| {
+            let (used, total) = (used?, total.filter(|&total| total > 0)?);
+            let fraction = (used as f64 / total as f64).min(1.0);
+            let line = format!("{}: {} of {} ({:.0}%)", label, units.memory(used), units.memory(total), fraction * 100.0);
+            Some((line, fraction))
+        })
+        .collect()
+}
+
+/// The PCIe link, and in detailed mode the fastest one the GPU supports,
+/// since idle GPUs drop to a slower one; then where the GPU is, to tell
+/// identical cards apart
+fn pcie(gpu: &GpuInfo, detailed: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(link) = gpu.pcie_link {
+        let mut line = format!("PCIe: Gen {} x{}", link.generation, link.width);
+        if let Some(max) = gpu.pcie_max_link.filter(|_| detailed) {
+            line.push_str(&format!(" (max Gen {} x{})", max.generation, max.width));
+        }
+        lines.push(line);
+    }
+    if let Some(ref pci_info) = gpu.pci_info {
+        let mut line = format!("PCI Address: {}", pci_info.address());
+        if let Some(ref slot) = gpu.pci_slot {
+            line.push_str(&format!(" (slot {})", slot));
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Memory error correction, which compute cards always report
+fn ecc(gpu: &GpuInfo) -> Vec<String> {
+    let line = match gpu.ecc {
+        Some(ref ecc) => {
+            let state = if ecc.enabled { "Enabled" } else { "Disabled" };
+            match (ecc.corrected_errors, ecc.uncorrected_errors) {
+                (Some(corrected), Some(uncorrected)) if ecc.enabled => format!(
+                    "ECC: {} ({} corrected, {} uncorrected errors)",
+                    state, corrected, uncorrected
+                ),
+                _ => format!("ECC: {}", state),
+            }
+        }
+        None if gpu.compute_only => String::from("ECC: Unknown"),
+        None => return Vec::new(),
+    };
+    vec![line]
+}
+
+fn clock(gpu: &GpuInfo, options: &DisplayOptions) -> Vec<String> {
+    let units = options.units;
+    let mut lines = vec![format!("Core Clock: {}", units.frequency(gpu.freq_mhz))];
+    if gpu.max_freq_mhz > gpu.freq_mhz {
+        lines.push(format!("Boost Clock: {}", units.frequency(gpu.max_freq_mhz)));
+    }
+    // Offsets move the top of the clock range, so they go on its line
+    if let (Some(offset), Some(line)) = (overclock(gpu), lines.last_mut()) {
+        let label = if offset > 0 { "OC" } else { "UC" };
+        line.push_str(&format!(" ({} {:+})", label, offset));
+    }
+    if let Some(millivolts) = gpu.core_voltage_mv.filter(|_| options.detailed) {
+        lines.push(format!("Core Voltage: {} mV", millivolts));
+    }
+
+    // Why the clocks above may be low
+    if let Some(ref state) = gpu.performance_state {
+        if let Some(ref pstate) = state.pstate {
+            lines.push(format!("Performance State: {}", pstate));
+        }
+        if let Some(ref power_profile) = state.power_profile {
+            lines.push(format!("Power Profile: {}", power_profile));
+        }
+        if !state.throttle_reasons.is_empty() {
+            lines.push(format!("Throttling: {}", state.throttle_reasons.join(", ")));
+        }
+    }
+    lines
+}
+
+/// Current temperature, with the limits it is heading for
+fn temperature(gpu: &GpuInfo, options: &DisplayOptions) -> Vec<String> {
+    let Some(celsius) = gpu.temperature_c else {
+        return Vec::new();
+    };
+
+    let units = options.units;
+    let mut line = format!("Temperature: {}", units.temperature(celsius));
+    for (label, sensor) in [("junction", gpu.junction_temperature_c), ("memory", gpu.memory_temperature_c)] {
+        if let Some(sensor) = sensor {
+            line.push_str(&format!(", {} {}", label, units.temperature(sensor)));
+        }
+    }
+    if let Some(limits) = gpu.thermal_limits {
+        let limits: Vec<String> = [("slowdown", limits.slowdown_c), ("shutdown", limits.shutdown_c)]
+            .into_iter()
+            .filter_map(|(label, limit)| Some(format!("{} at {}", label, units.temperature(limit?))))
+            .collect();
+        line.push_str(&format!(" ({})", limits.join(", ")));
+    }
+    vec![line]
+}
+
+/// "NUMA Node: 1 (CPUs 32-63,96-127)", the CPUs to pin work on the GPU to
+fn numa(gpu: &GpuInfo) -> Vec<String> {
+    let Some(ref numa) = gpu.numa else {
+        return Vec::new();
+    };
+    let line = match (numa.node, &numa.cpus) {
+        (Some(node), Some(cpus)) => format!("NUMA Node: {} (CPUs {})", node, cpus),
+        (Some(node), None) => format!("NUMA Node: {}", node),
+        (None, Some(cpus)) => format!("CPU Affinity: {}", cpus),
+        (None, None) => return Vec::new(),
+    };
+    vec![line]
+}
+
+/// Chip name with its die size, transistor count and launch year, e.g.
+/// "Chip: AD102 (609 mm², 76.3 billion transistors, 2022)"
+fn chip(gpu: &GpuInfo) -> String {
+    let mut facts = Vec::new();
+    if let Some(mm2) = gpu.die_size_mm2 {
+        facts.push(format!("{} mm²", mm2));
+    }
+    match gpu.transistors_million {
+        Some(million) if million >= 1000 => facts.push(format!("{:.1} billion transistors", f64::from(million) / 1000.0)),
+        Some(million) => facts.push(format!("{} million transistors", million)),
+        None => {}
+    }
+    facts.extend(gpu.release_year.map(|year| year.to_string()));
+
+    if facts.is_empty() {
+        format!("Chip: {}", gpu.chip)
+    } else {
+        format!("Chip: {} ({})", gpu.chip, facts.join(", "))
+    }
+}
+
+/// Power draw against the rated board power, average power and energy used, and for discrete GPUs whether they sleep when
+/// idle, e.g. "Power State: D3cold, auto (runtime suspended)"
+fn power(gpu: &GpuInfo) -> Vec<String> {
+    let mut lines = Vec::new();
+    match (gpu.power_draw_w, gpu.rated_power_w) {
+        (Some(watts), Some(rated)) => lines.push(format!("Power Draw: {:.1} W of {} W rated", watts, rated)),
+        (Some(watts), None) => lines.push(format!("Power Draw: {:.1} W", watts)),
+        (None, Some(rated)) => lines.push(format!("Rated Power: {} W", rated)),
+        (None, None) => {}
+    }
+    if let Some(watts) = gpu.average_power_w {
+        lines.push(format!("Average Power: {:.1} W since the last refresh", watts));
+    }
+    if let Some(joules) = gpu.energy_j {
+        // Counters start over when the driver loads
+        let energy = match joules / 3600.0 {
+            watt_hours if watt_hours >= 1000.0 => format!("{:.2} kWh", watt_hours / 1000.0),
+            watt_hours => format!("{:.1} Wh", watt_hours),
+        };
+        lines.push(format!("Energy Used: {} since the driver loaded", energy));
+    }
+
+    // Integrated GPUs power down with the rest of the SoC
+    if let Some(runtime_pm) = gpu.runtime_pm.as_ref().filter(|_| !gpu.is_integrated) {
+        let state = match runtime_pm.status.as_str() {
+            "suspended" => "runtime suspended",
+            "active" if runtime_pm.control == "on" => "active, never suspends",
+            status => status,
+        };
+        let control = match runtime_pm.d_state {
+            Some(ref d_state) => format!("{}, {}", d_state, runtime_pm.control),
+            None => runtime_pm.control.clone(),
+        };
+        lines.push(format!("Power State: {} ({})", control, state));
+    }
+    lines
+}
+
+/// "Fan: 35% (1200 RPM)", or whichever of the two is known
+fn fan(gpu: &GpuInfo) -> Vec<String> {
+    let Some(fan) = gpu.fan else {
+        return Vec::new();
+    };
+
+    let line = match (fan.percent, fan.rpm) {
+        (Some(percent), Some(rpm)) => format!("Fan: {}% ({} RPM)", percent, rpm),
+        (Some(percent), None) => format!("Fan: {}%", percent),
+        (None, Some(rpm)) => format!("Fan: {} RPM", rpm),
+        (None, None) => return Vec::new(),
+    };
+    vec![line]
+}
+
+/// "Engine Utilization: Render/3D 12%, Video 0%, ..."
+fn engines(gpu: &GpuInfo) -> Vec<String> {
+    if gpu.engine_load.is_empty() {
+        return Vec::new();
+    }
+
+    let engines: Vec<String> = gpu
+        .engine_load
+        .iter()
+        .map(|engine| format!("{} {:.0}%", engine.name, engine.busy_percent))
+        .collect();
+    vec![format!("Engine Utilization: {}", engines.join(", "))]
+}
+
+/// MHz the GPU runs above (or below) reference clocks, ignoring differences
+/// within `CLOCK_TOLERANCE_MHZ`
+///
+/// An offset set in the driver is exact. Otherwise the maximum clock is
+/// compared to the reference boost clock, except on NVIDIA cards, whose
+/// GPU Boost runs past the rated boost clock by design.
+fn overclock(gpu: &GpuInfo) -> Option<i32> {
+    const CLOCK_TOLERANCE_MHZ: i32 = 10;
+
+    let offset = gpu.clock_offset_mhz.or_else(|| {
+        let reference = gpu.reference_boost_mhz.filter(|_| gpu.vendor != GpuVendor::Nvidia)?;
+        (gpu.max_freq_mhz > 0).then(|| gpu.max_freq_mhz as i32 - reference as i32)
+    })?;
+    (offset.abs() >= CLOCK_TOLERANCE_MHZ).then_some(offset)
+}
+
+fn compute(gpu: &GpuInfo, detailed: bool) -> Vec<String> {
+    let compute_capability = gpu
+        .compute_capability
+        .as_ref()
+        .map(|compute_capability| format!("Compute Capability: {}", compute_capability));
+    let Some(ref topology) = gpu.topology else {
+        return compute_capability.into_iter().collect();
+    };
+
+    let mut lines = vec![gpu.get_compute_units_readable()];
+    match topology {
+        Topology::Nvidia(nvidia) => {
+            lines.push(format!("Streaming Multiprocessors: {}", nvidia.sm_count));
+            if let Some(tensor_cores) = nvidia.tensor_cores {
+                lines.push(format!("Tensor Cores: {}", tensor_cores));
+            }
+            if let Some(rt_cores) = nvidia.rt_cores.filter(|_| !gpu.compute_only) {
+                lines.push(format!("RT Cores: {}", rt_cores));
+            }
+            lines.extend(compute_capability);
+        }
+        Topology::Amd(amd) => {
+            lines.push(format!("Compute Units: {}", amd.compute_units));
+            // Raster and texture units are idle on headless compute cards
+            if !gpu.compute_only {
+                if let Some(rops) = amd.rops {
+                    lines.push(format!("ROPs: {}", rops));
+                }
+                if let Some(tmus) = amd.tmus {
+                    lines.push(format!("TMUs: {}", tmus));
+                }
+            }
+        }
+        Topology::Intel(intel) => {
+            if let Some(slices) = intel.slices {
+                if let Some(subslices) = intel.subslices {
+                    lines.push(format!("Slices: {} (Subslices: {})", slices, subslices));
+                } else {
+                    lines.push(format!("Slices: {}", slices));
+                }
+            }
+        }
+        Topology::Generic { .. } => {}
+    }
+
+    if detailed {
+        if let Some(sparkline) = sparkline::topology_sparkline(gpu) {
+            lines.push(format!("Layout: {}", sparkline));
+        }
+    }
+    lines
+}
+
+pub(super) fn cache(gpu: &GpuInfo, options: &DisplayOptions) -> Vec<String> {
+    let Some(ref cache) = gpu.cache else {
+        return Vec::new();
+    };
+
+    let units = options.units;
+    let mut lines = Vec::new();
+    // A per-unit size alone says little, so the total comes with it
+    if let (Some(l1_size), Some(topology), true) = (cache.l1_size, gpu.topology.as_ref(), options.detailed) {
+        let unit = match topology {
+            Topology::Nvidia(_) => "SM",
+            _ => "CU",
+        };
+        let total = units.scaled(l1_size * u64::from(topology.compute_units()), 2);
+        lines.push(format!(
+            "L1 Cache: {:.0} {} per {} ({:.1} {} total)",
+            units.scaled(l1_size, 1),
+            units.size_label(1),
+            unit,
+            total,
+            units.size_label(2)
+        ));
+    }
+    if let Some(l2_size) = cache.l2_size {
+        let l2_mb = units.scaled(l2_size, 2);
+        if l2_mb >= 1.0 {
+            lines.push(format!("L2 Cache: {:.1} {}", l2_mb, units.size_label(2)));
+        } else {
+            let l2_kb = units.scaled(l2_size, 1);
+            lines.push(format!("L2 Cache: {:.0} {}", l2_kb, units.size_label(1)));
+        }
+    }
+
+    if let Some(l3_size) = cache.l3_size {
+        let l3_mb = units.scaled(l3_size, 2);
+        if l3_mb >= 1.0 {
+            lines.push(format!("L3 Cache: {:.0} {}", l3_mb, units.size_label(2)));
+        } else {
+            let l3_kb = units.scaled(l3_size, 1);
+            lines.push(format!("L3 Cache: {:.0} {}", l3_kb, units.size_label(1)));
+        }
+    }
+    lines
+}
+
+/// Peak throughput; FP64 is what compute cards are bought for
+fn performance(gpu: &GpuInfo, detailed: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(perf) = gpu.peak_performance_gflops {
+        lines.push(format!("Peak Performance: {}", format_flops(perf)));
+    }
+    if let Some(fp64) = gpu.peak_fp64_gflops().filter(|_| gpu.compute_only || detailed) {
+        lines.push(format!("Peak FP64: {}", format_flops(fp64)));
+    }
+    lines
+}
+
+/// Ray tracing first, then the upscalers, e.g. "Ray Tracing, DLSS 3, FSR 3, XeSS"
+fn features(gpu: &GpuInfo) -> Vec<String> {
+    let Some(ref features) = gpu.features else {
+        return Vec::new();
+    };
+
+    let mut names = Vec::new();
+    if features.ray_tracing {
+        names.push("Ray Tracing");
+    }
+    names.extend(&features.upscalers);
+
+    let mut line = format!("Features: {}", names.join(", "));
+    if features.missing_vulkan_driver {
+        line.push_str(" (no Vulkan driver installed)");
+    }
+    vec![line]
+}
+
+/// Driver versions, and in detailed mode the firmware it loaded
+fn driver(gpu: &GpuInfo, detailed: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(ref driver) = gpu.driver_version {
+        lines.push(format!("Driver: {}", driver));
+    }
+    if let Some(ref kernel_driver) = gpu.kernel_driver {
+        lines.push(format!("Kernel Driver: {}", kernel_driver));
+    }
+    if let Some(ref mesa_version) = gpu.mesa_version {
+        lines.push(format!("Mesa: {}", mesa_version));
+    }
+    if detailed {
+        if let Some(ref opengl) = gpu.opengl_version {
+            lines.push(format!("OpenGL: {}", opengl));
+        }
+        if let Some(ref vbios) = gpu.vbios_version {
+            lines.push(format!("VBIOS: {}", vbios));
+        }
+        for firmware in &gpu.firmware {
+            let state: Vec<&str> = [firmware.version.as_deref(), firmware.status.as_deref()].into_iter().flatten().collect();
+            lines.push(format!("Firmware ({}): {}", firmware.name, state.join(", ")));
+        }
+        if !gpu.kernel_parameters.is_empty() {
+            lines.push(format!("Kernel Parameters: {}", gpu.kernel_parameters.join(" ")));
+        }
+        if !gpu.module_options.is_empty() {
+            lines.push(format!("Module Options: {}", gpu.module_options.join(", ")));
+        }
+    }
+    lines
+}
+
+/// The installed ICDs, marking those that can run on this GPU, and what is
+/// wrong with them
+fn runtime(gpu: &GpuInfo, detailed: bool) -> Vec<String> {
+    let Some(ref runtime) = gpu.runtime else {
+        return Vec::new();
+    };
+
+    let mut lines = Vec::new();
+    if detailed {
+        for (api, icds) in [("Vulkan", &runtime.vulkan), ("OpenCL", &runtime.opencl)] {
+            if icds.is_empty() {
+                continue;
+            }
+            let names: Vec<String> = icds
+                .iter()
+                .map(|icd| if icd.serves_gpu { format!("{} (this GPU)", icd.name) } else { icd.name.clone() })
+                .collect();
+            lines.push(format!("{} ICDs: {}", api, names.join(", ")));
+        }
+    }
+    lines.extend(runtime.warnings.iter().map(|warning| format!("Warning: {}", warning)));
+    lines
+}
+
+fn hybrid(gpu: &GpuInfo) -> Vec<String> {
+    let Some(ref hybrid) = gpu.hybrid else {
+        return Vec::new();
+    };
+
+    let mut details = vec![hybrid.mode.to_string()];
+    if hybrid.drives_panel == Some(true) {
+        details.push("drives the panel".to_string());
+    }
+    if hybrid.d3cold == Some(true) {
+        details.push("D3cold when idle".to_string());
+    }
+    details.extend(hybrid::requested_offload());
+    vec![format!("Hybrid: {} ({})", hybrid.label(), details.join(", "))]
+}
+
+/// Display server and whether it draws on this GPU
+fn session(gpu: &GpuInfo, options: &DisplayOptions) -> Vec<String> {
+    let Some(ref session) = options.session else {
+        return Vec::new();
+    };
+
+    let mut line = format!("Session: {}", session.kind);
+    if let Some(ref compositor) = session.compositor {
+        line.push_str(&format!(" ({})", compositor));
+    }
+    if gpu.pci_info.as_ref().is_some_and(|pci_info| session.renders_on(&pci_info.address())) {
+        line.push_str(", renders on this GPU");
+    }
+    vec![line]
+}
+
+/// Video acceleration, listed per codec with the full profiles in detailed mode
+fn video(gpu: &GpuInfo, detailed: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(percent) = gpu.encoder_utilization_percent.filter(|_| detailed) {
+        lines.push(format!("Encoder Load: {}%", percent));
+    }
+    let Some(ref vaapi) = gpu.vaapi else {
+        return lines;
+    };
+
+    for (label, profiles) in [("Decode", &vaapi.decode), ("Encode", &vaapi.encode)] {
+        if profiles.is_empty() {
+            continue;
+        }
+        lines.push(format!("Video {}: {}", label, vaapi::codec_families(profiles).join(", ")));
+        if detailed {
+            lines.push(format!("{} Profiles: {}", label, profiles.join(", ")));
+        }
+    }
+    if detailed {
+        if let Some(ref driver) = vaapi.driver {
+            lines.push(format!("VA-API Driver: {}", driver));
+        }
+    }
+    lines
+}
+
+fn sriov(gpu: &GpuInfo, detailed: bool) -> Vec<String> {
+    if let Some(ref physical_function) = gpu.physical_function {
+        return vec![format!("SR-IOV: virtual function of {}", physical_function)];
+    }
+    let Some(ref sriov) = gpu.sriov else {
+        return Vec::new();
+    };
+
+    let mut lines = vec![format!("SR-IOV: {} of {} VFs enabled", sriov.enabled_vfs, sriov.total_vfs)];
+    if detailed && !sriov.vf_addresses.is_empty() {
+        lines.push(format!("VFs: {}", sriov.vf_addresses.join(", ")));
+    }
+    lines
+}
+
+fn identifiers(gpu: &GpuInfo) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(ref uuid) = gpu.uuid {
+        lines.push(format!("UUID: {}", uuid));
+    }
+    if let Some(ref serial) = gpu.serial {
+        lines.push(format!("Serial: {}", serial));
+    }
+    lines
+}
+
+/// Values not read from the hardware, by where they came from: the user's
+/// overrides.toml, the spec database or the model name
+fn sources(gpu: &GpuInfo) -> Vec<String> {
+    [(Source::Override, "Overridden"), (Source::SpecDb, "Looked Up"), (Source::Heuristic, "Guessed")]
+        .into_iter()
+        .filter_map(|(source, label)| {
+            let fields: Vec<&str> = gpu
+                .provenance
+                .iter()
+                .filter(|(_, field_source)| **field_source == source)
+                .map(|(field, _)| *field)
+                .collect();
+            (!fields.is_empty()).then(|| format!("{}: {} ({})", label, fields.join(", "), source))
+        })
+        .collect()
+}
+
+/// Monitor name, native mode and size, e.g. "DELL U2720Q, 3840x2160 @ 60 Hz, 27\""
+fn describe_display(display: &DisplayInfo) -> String {
+    let Some(ref edid) = display.edid else {
+        return String::from("Unknown monitor");
+    };
+
+    let name = edid
+        .name
+        .clone()
+        .unwrap_or_else(|| format!("{} {:04X}", edid.manufacturer, edid.product_code));
+    let mut description = name;
+    if let Some(mode) = edid.native_mode {
+        description.push_str(&format!(", {}x{} @ {} Hz", mode.width, mode.height, mode.refresh_hz));
+    }
+    if let Some((width, height)) = edid.size_cm {
+        let diagonal_in = f64::from(width).hypot(f64::from(height)) / 2.54;
+        description.push_str(&format!(", {:.0}\"", diagonal_in));
+    }
+    description
+}
+
+/// Throughput in TFLOPS, or GFLOPS below one TFLOPS
+pub(super) fn format_flops(gflops: f64) -> String {
+    if gflops >= 1000.0 {
+        format!("{:.2} TFLOPS", gflops / 1000.0)
+    } else {
+        format!("{:.1} GFLOPS", gflops)
+    }
+}
@@ -0,0 +1,40 @@
+use super::layout::visible_width;
+use super::theme::Theme;
+
+/// Wrap info lines in a rounded box with `title` set into the top border
+///
+/// The box is sized to the widest of the title and the lines, which may
+/// already be colored. The border is drawn in the theme's accent color and
+/// the title in its value color; the returned lines contain the color escapes.
+pub fn frame_lines(title: &str, lines: &[String], theme: &Theme) -> Vec<String> {
+    let border_color = theme.accent_color();
+    let title_width = visible_width(title);
+    let content_width = lines.iter().map(|line| visible_width(line)).max().unwrap_or(0);
+    // The title sits between "─ " and " ─" inside the corners
+    let inner_width = content_width.max(title_width + 2);
+
+    let mut framed = Vec::with_capacity(lines.len() + 2);
+
+    let top_rule = "─".repeat(inner_width - title_width - 1);
+    framed.push(format!(
+        "{}{}{}",
+        theme.paint("╭─ ", border_color),
+        theme.paint_bold(title, theme.value_color()),
+        theme.paint(&format!(" {}╮", top_rule), border_color)
+    ));
+
+    for line in lines {
+        let padding = " ".repeat(inner_width - visible_width(line));
+        framed.push(format!(
+            "{}{}{}{}",
+            theme.paint("│ ", border_color),
+            line,
+            padding,
+            theme.paint(" │", border_color)
+        ));
+    }
+
+    framed.push(theme.paint(&format!("╰{}╯", "─".repeat(inner_width + 2)), border_color));
+
+    framed
+}
@@ -0,0 +1,274 @@
+//! Logos drawn as real pictures through terminal graphics protocols.
+//!
+//! Kitty, iTerm2 and Sixel terminals can show bitmaps inline. The picture
+//! takes the same block of cells the ASCII logo would, so the info column
+//! lines up the same way in both modes.
+//!
+//! Drawing needs the `image` feature; builds without it only know the
+//! protocols, to parse `--logo-mode`, and always show text art.
+
+#[cfg(feature = "image")]
+use base64::engine::general_purpose::STANDARD as BASE64;
+#[cfg(feature = "image")]
+use base64::Engine;
+#[cfg(feature = "image")]
+use image::imageops::FilterType;
+#[cfg(feature = "image")]
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+#[cfg(feature = "image")]
+use std::collections::BTreeSet;
+#[cfg(feature = "image")]
+use std::io::Cursor;
+
+#[cfg(feature = "image")]
+use super::theme::Theme;
+
+#[cfg(feature = "image")]
+/// Pixels per cell assumed when the terminal does not report its size
+const DEFAULT_CELL_PIXELS: (u32, u32) = (10, 20);
+
+#[cfg(feature = "image")]
+/// Largest base64 payload the kitty protocol accepts per escape
+const KITTY_CHUNK: usize = 4096;
+
+/// Inline image protocols gpufetch can speak
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GraphicsProtocol {
+    Kitty,
+    Iterm2,
+    Sixel,
+}
+
+/// A picture ready to be drawn over a block of cells
+#[derive(Debug, Clone)]
+pub struct Graphic {
+    /// Escape sequence drawing the picture at the cursor
+    pub escape: String,
+    pub columns: usize,
+    pub rows: usize,
+}
+
+#[cfg(feature = "image")]
+impl GraphicsProtocol {
+    /// Encode `image` to cover `columns` x `rows` cells
+    pub fn encode(&self, image: &RgbaImage, columns: usize, rows: usize) -> Graphic {
+        let (cell_width, cell_height) = cell_pixels();
+        let image = image::imageops::resize(
+            image,
+            (columns as u32 * cell_width).max(1),
+            (rows as u32 * cell_height).max(1),
+            FilterType::Triangle,
+        );
+
+        let escape = match self {
+            GraphicsProtocol::Kitty => kitty(&image, columns, rows),
+            GraphicsProtocol::Iterm2 => iterm2(&image, columns, rows),
+            GraphicsProtocol::Sixel => sixel(&image),
+        };
+        Graphic { escape, columns, rows }
+    }
+}
+
+#[cfg(feature = "image")]
+/// A PNG file sent in chunks; kitty scales it to the cell block
+fn kitty(image: &RgbaImage, columns: usize, rows: usize) -> String {
+    let Some(png) = encode_png(image) else {
+        return String::new();
+    };
+    let payload = BASE64.encode(png);
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(KITTY_CHUNK).collect();
+
+    let mut escape = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        // Base64 is ASCII, so every chunk is valid UTF-8
+        let chunk = std::str::from_utf8(chunk).unwrap_or_default();
+        if i == 0 {
+            escape.push_str(&format!(
+                "\x1b_Ga=T,f=100,q=2,c={},r={},m={};{}\x1b\\",
+                columns, rows, more, chunk
+            ));
+        } else {
+            escape.push_str(&format!("\x1b_Gm={};{}\x1b\\", more, chunk));
+        }
+    }
+    escape
+}
+
+#[cfg(feature = "image")]
+/// A PNG file shown inline, sized in cells
+fn iterm2(image: &RgbaImage, columns: usize, rows: usize) -> String {
+    let Some(png) = encode_png(image) else {
+        return String::new();
+    };
+
+    format!(
+        "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=0:{}\x07",
+        png.len(),
+        columns,
+        rows,
+        BASE64.encode(&png)
+    )
+}
+
+#[cfg(feature = "image")]
+/// The picture as a PNG file
+fn encode_png(image: &RgbaImage) -> Option<Vec<u8>> {
+    let mut png = Vec::new();
+    DynamicImage::ImageRgba8(image.clone())
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .ok()?;
+    Some(png)
+}
+
+#[cfg(feature = "image")]
+/// Sixel bands with colors from the 6x6x6 cube
+///
+/// Transparent pixels are left untouched so the terminal background shows
+/// through.
+fn sixel(image: &RgbaImage) -> String {
+    let index = |pixel: &Rgba<u8>| -> Option<u16> {
+        if pixel[3] < 128 {
+            return None;
+        }
+        let level = |c: u8| (u16::from(c) * 5 + 127) / 255;
+        Some(36 * level(pixel[0]) + 6 * level(pixel[1]) + level(pixel[2]))
+    };
+
+    let mut out = format!("\x1bP0;1;0q\"1;1;{};{}", image.width(), image.height());
+
+    let used: BTreeSet<u16> = image.pixels().filter_map(index).collect();
+    for &color in &used {
+        let percent = |level: u16| level * 20;
+        out.push_str(&format!(
+            "#{};2;{};{};{}",
+            color,
+            percent(color / 36),
+            percent(color / 6 % 6),
+            percent(color % 6)
+        ));
+    }
+
+    for band in (0..image.height()).step_by(6) {
+        let rows = band..(band + 6).min(image.height());
+        let band_colors: BTreeSet<u16> = rows
+            .clone()
+            .flat_map(|y| (0..image.width()).map(move |x| (x, y)))
+            .filter_map(|(x, y)| index(image.get_pixel(x, y)))
+            .collect();
+
+        for color in band_colors {
+            out.push_str(&format!("#{}", color));
+            let sixels: Vec<u8> = (0..image.width())
+                .map(|x| {
+                    let bits = rows
+                        .clone()
+                        .filter(|&y| index(image.get_pixel(x, y)) == Some(color))
+                        .fold(0, |bits, y| bits | (1 << (y - band)));
+                    63 + bits
+                })
+                .collect();
+            push_run_length(&mut out, &sixels);
+            out.push('$');
+        }
+        out.push('-');
+    }
+
+    out.push_str("\x1b\\");
+    out
+}
+
+#[cfg(feature = "image")]
+/// Append sixel characters, collapsing runs with the `!` repeat introducer
+fn push_run_length(out: &mut String, sixels: &[u8]) {
+    let mut i = 0;
+    while i < sixels.len() {
+        let run = sixels[i..].iter().take_while(|&&c| c == sixels[i]).count();
+        if run > 3 {
+            out.push_str(&format!("!{}{}", run, sixels[i] as char));
+        } else {
+            out.extend(std::iter::repeat_n(sixels[i] as char, run));
+        }
+        i += run;
+    }
+}
+
+#[cfg(feature = "image")]
+/// Size of a terminal cell in pixels from the tty, or a common default
+fn cell_pixels() -> (u32, u32) {
+    let mut size = libc::winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: TIOCGWINSZ only writes a winsize into the struct we pass
+    let ok = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+
+    if ok && size.ws_col > 0 && size.ws_row > 0 && size.ws_xpixel > 0 && size.ws_ypixel > 0 {
+        (
+            u32::from(size.ws_xpixel / size.ws_col).max(1),
+            u32::from(size.ws_ypixel / size.ws_row).max(1),
+        )
+    } else {
+        DEFAULT_CELL_PIXELS
+    }
+}
+
+#[cfg(feature = "image")]
+/// Draw text art as a bitmap, one 2x4 dot grid per character
+///
+/// Braille characters map dot for dot. Other characters get a dot pattern
+/// roughly matching their shape, so the punctuation that shades the
+/// built-in logos stays light and letters become solid cells.
+pub fn rasterize_art(art: &str, theme: &Theme) -> (RgbaImage, usize, usize) {
+    const DOT: u32 = 5;
+
+    let lines: Vec<&str> = art.lines().collect();
+    let columns = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+    let rows = lines.len();
+
+    let mut image = RgbaImage::new((columns as u32 * 2 * DOT).max(1), (rows as u32 * 4 * DOT).max(1));
+    for (row, line) in lines.iter().enumerate() {
+        let color = theme.logo_rgb(row, rows);
+        let pixel = Rgba([color.r, color.g, color.b, 255]);
+        for (column, c) in line.chars().enumerate() {
+            let dots = braille_dots(c);
+            for (bit, (dx, dy)) in BRAILLE_DOTS.iter().enumerate() {
+                if dots & (1 << bit) == 0 {
+                    continue;
+                }
+                let x0 = (column as u32 * 2 + dx) * DOT;
+                let y0 = (row as u32 * 4 + dy) * DOT;
+                for y in y0..y0 + DOT {
+                    for x in x0..x0 + DOT {
+                        image.put_pixel(x, y, pixel);
+                    }
+                }
+            }
+        }
+    }
+
+    (image, columns, rows)
+}
+
+#[cfg(feature = "image")]
+/// Position (column, row) of each bit of a braille pattern
+pub(super) const BRAILLE_DOTS: [(u32, u32); 8] = [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2), (0, 3), (1, 3)];
+
+#[cfg(feature = "image")]
+/// Braille dot bits approximating a character
+pub(super) fn braille_dots(c: char) -> u8 {
+    match c {
+        '\u{2800}'..='\u{28ff}' => (c as u32 - 0x2800) as u8,
+        ' ' => 0,
+        '.' | ',' => 0b1100_0000,
+        '\'' | '`' => 0b0000_1001,
+        ':' => 0b0101_0010,
+        '-' | '─' => 0b0001_0010,
+        '=' => 0b0011_0110,
+        '_' => 0b1100_0000,
+        '|' | '│' => 0b0100_0111,
+        _ => 0xff,
+    }
+}
@@ -0,0 +1,151 @@
+#[cfg(feature = "image")]
+use anyhow::Context;
+use anyhow::Result;
+#[cfg(feature = "image")]
+use image::imageops::FilterType;
+#[cfg(feature = "image")]
+use image::{Rgba, RgbaImage};
+use std::path::Path;
+
+use super::terminal::{Charset, ColorDepth};
+
+#[cfg(feature = "image")]
+/// Characters from dark to bright for terminals without block glyphs
+const ASCII_RAMP: &[u8] = b" .:-=+*#%@";
+
+#[cfg(feature = "image")]
+/// Pixels more transparent than this show the terminal background
+const ALPHA_CUTOFF: u8 = 128;
+
+/// An image converted to terminal art
+#[derive(Debug, Clone)]
+pub struct ImageLogo {
+    /// Lines with their color escapes already applied
+    pub lines: Vec<String>,
+    /// Width of every line in terminal columns
+    pub width: usize,
+    /// The picture as loaded, for terminals that can draw it directly
+    #[cfg(feature = "image")]
+    pub source: RgbaImage,
+}
+
+/// How the art may use the terminal
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(not(feature = "image"), allow(dead_code))]
+pub struct ImageStyle {
+    pub charset: Charset,
+    pub color_depth: ColorDepth,
+    pub color: bool,
+}
+
+impl ImageLogo {
+    /// Load an image and scale it to `width` columns
+    ///
+    /// UTF-8 terminals with color get half-block art, two pixels per cell with
+    /// the upper one in the foreground and the lower one in the background.
+    /// Otherwise each cell is a character from a brightness ramp, colored
+    /// when color is allowed.
+    #[cfg(feature = "image")]
+    pub fn load(path: &Path, width: u32, style: ImageStyle) -> Result<Self> {
+        let image = image::open(path)
+            .with_context(|| format!("Failed to load logo image {}", path.display()))?
+            .to_rgba8();
+        let width = width.clamp(1, image.width().max(1));
+
+        let lines = if style.charset == Charset::Utf8 && style.color {
+            // Cells are about twice as tall as wide, so two pixel rows fill one
+            let height = scaled_height(&image, width).div_ceil(2) * 2;
+            let scaled = image::imageops::resize(&image, width, height, FilterType::Triangle);
+            half_block_lines(&scaled, style.color_depth)
+        } else {
+            let height = scaled_height(&image, width).div_ceil(2);
+            let scaled = image::imageops::resize(&image, width, height, FilterType::Triangle);
+            ramp_lines(&scaled, style)
+        };
+
+        Ok(ImageLogo {
+            lines,
+            width: width as usize,
+            source: image,
+        })
+    }
+
+    /// Builds without the `image` feature cannot decode pictures
+    #[cfg(not(feature = "image"))]
+    pub fn load(path: &Path, _width: u32, _style: ImageStyle) -> Result<Self> {
+        anyhow::bail!("gpufetch was built without the \"image\" feature, which --logo-image needs ({})", path.display())
+    }
+}
+
+#[cfg(feature = "image")]
+/// Height keeping the aspect ratio at `width`
+fn scaled_height(image: &RgbaImage, width: u32) -> u32 {
+    let height = u64::from(image.height()) * u64::from(width) / u64::from(image.width().max(1));
+    (height as u32).max(1)
+}
+
+#[cfg(feature = "image")]
+fn half_block_lines(image: &RgbaImage, depth: ColorDepth) -> Vec<String> {
+    (0..image.height() / 2)
+        .map(|row| {
+            let mut line = String::new();
+            for x in 0..image.width() {
+                let top = visible(image.get_pixel(x, row * 2));
+                let bottom = visible(image.get_pixel(x, row * 2 + 1));
+                match (top, bottom) {
+                    (Some(top), Some(bottom)) => {
+                        line.push_str(&format!("{}{}▀", color_code(top, depth, 38), color_code(bottom, depth, 48)))
+                    }
+                    (Some(top), None) => line.push_str(&format!("{}▀", color_code(top, depth, 38))),
+                    (None, Some(bottom)) => line.push_str(&format!("{}▄", color_code(bottom, depth, 38))),
+                    (None, None) => line.push(' '),
+                }
+                line.push_str("\x1b[0m");
+            }
+            line
+        })
+        .collect()
+}
+
+#[cfg(feature = "image")]
+fn ramp_lines(image: &RgbaImage, style: ImageStyle) -> Vec<String> {
+    (0..image.height())
+        .map(|y| {
+            let mut line = String::new();
+            for x in 0..image.width() {
+                let Some(pixel) = visible(image.get_pixel(x, y)) else {
+                    line.push(' ');
+                    continue;
+                };
+                let luma = (0.299 * f64::from(pixel[0]) + 0.587 * f64::from(pixel[1]) + 0.114 * f64::from(pixel[2])) / 255.0;
+                let glyph = ASCII_RAMP[(luma * (ASCII_RAMP.len() - 1) as f64).round() as usize] as char;
+                if style.color && glyph != ' ' {
+                    line.push_str(&format!("{}{}\x1b[0m", color_code(pixel, style.color_depth, 38), glyph));
+                } else {
+                    line.push(glyph);
+                }
+            }
+            line
+        })
+        .collect()
+}
+
+#[cfg(feature = "image")]
+/// The pixel, unless it is transparent
+fn visible(pixel: &Rgba<u8>) -> Option<Rgba<u8>> {
+    (pixel[3] >= ALPHA_CUTOFF).then_some(*pixel)
+}
+
+#[cfg(feature = "image")]
+/// SGR escape selecting `pixel` as foreground (38) or background (48)
+///
+/// Terminals without truecolor get the nearest color of the 256-color cube.
+fn color_code(pixel: Rgba<u8>, depth: ColorDepth, layer: u8) -> String {
+    let [r, g, b, _] = pixel.0;
+    if depth == ColorDepth::TrueColor {
+        return format!("\x1b[{};2;{};{};{}m", layer, r, g, b);
+    }
+
+    let level = |c: u8| (u16::from(c) * 5 / 255) as u8;
+    format!("\x1b[{};5;{}m", layer, 16 + 36 * level(r) + 6 * level(g) + level(b))
+}
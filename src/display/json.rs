@@ -0,0 +1,20 @@
+//! `--output json`: the full GPU model for scripts and other tools.
+//!
+//! Every field of every GPU is printed under its name in the model, with
+//! unknown values as `null`, so the output reads back with serde as it was
+//! detected. With `--memory-unit`, `--frequency-unit` or
+//! `--temperature-unit`, keys naming a unit are renamed to the chosen one,
+//! e.g. `freq_ghz`, and hold their numbers in it; only output in the base
+//! units reads back.
+
+use anyhow::{Context, Result};
+
+use crate::gpu::common::GpuInfo;
+use crate::units::Units;
+
+/// Print the GPUs as a pretty-printed JSON array
+pub fn print_json(gpus: &[GpuInfo], units: Units) -> Result<()> {
+    let json = units.json(gpus).and_then(|json| serde_json::to_string_pretty(&json));
+    println!("{}", json.context("Failed to serialize the GPUs")?);
+    Ok(())
+}
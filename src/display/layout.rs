@@ -0,0 +1,142 @@
+//! Fitting the card into the terminal.
+//!
+//! Widths are measured in terminal columns: color escapes take none, and
+//! wide characters such as CJK take two. When the logo leaves too little
+//! room beside it, the short logo is tried, then the logo goes above the
+//! info, then away; info lines that are still too long are wrapped at
+//! spaces, indented under their value.
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::args::{Layout, LogoVariant};
+
+/// Columns between the logo and the info lines
+pub const GAP: usize = 2;
+
+/// Columns the info lines should get before the logo is shrunk or dropped
+const MIN_INFO_WIDTH: usize = 40;
+
+/// Columns a line takes up on screen, skipping SGR color escapes
+pub fn visible_width(line: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in line.chars() {
+        match c {
+            '\x1b' => in_escape = true,
+            'm' if in_escape => in_escape = false,
+            _ if in_escape => {}
+            _ => width += c.width().unwrap_or(0),
+        }
+    }
+    width
+}
+
+/// Which logo to show, and whether it goes above the info
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    pub variant: LogoVariant,
+    pub vertical: bool,
+}
+
+/// Place the largest logo, starting from the requested one, in a terminal
+/// `width` columns wide, or anywhere the layout allows when the width is
+/// unknown
+///
+/// Beside the info, the logo must leave it `MIN_INFO_WIDTH` columns; above
+/// it, the logo must fit on its own. `logo_width` measures the logo of a
+/// variant.
+pub fn place_logo(
+    requested: LogoVariant,
+    layout: Layout,
+    width: Option<usize>,
+    logo_width: impl Fn(LogoVariant) -> usize,
+) -> Placement {
+    let Some(width) = width else {
+        return Placement { variant: requested, vertical: layout == Layout::Vertical };
+    };
+
+    let sizes: &[LogoVariant] = match requested {
+        LogoVariant::None => &[],
+        LogoVariant::Short => &[LogoVariant::Short],
+        _ => &[requested, LogoVariant::Short],
+    };
+    let beside = sizes
+        .iter()
+        .filter(|_| layout != Layout::Vertical)
+        .find(|&&variant| logo_width(variant) + GAP + MIN_INFO_WIDTH <= width)
+        .map(|&variant| Placement { variant, vertical: false });
+    let above = || {
+        sizes
+            .iter()
+            .filter(|_| layout != Layout::Horizontal)
+            .find(|&&variant| logo_width(variant) <= width)
+            .map(|&variant| Placement { variant, vertical: true })
+    };
+    beside
+        .or_else(above)
+        .unwrap_or(Placement { variant: LogoVariant::None, vertical: false })
+}
+
+/// Break an uncolored line into lines at most `width` columns wide
+///
+/// Continuation lines are indented to where the value after "Label: "
+/// starts, or by two columns for long labels. Words that fit on no line are
+/// cut short with an ellipsis.
+pub fn wrap(line: &str, width: usize) -> Vec<String> {
+    if visible_width(line) <= width {
+        return vec![line.to_string()];
+    }
+
+    let indent = line
+        .find(": ")
+        .map(|idx| visible_width(&line[..idx + 2]))
+        .filter(|&indent| indent <= width / 2)
+        .unwrap_or(2.min(width / 2));
+
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in line.split(' ') {
+        let prefix = if lines.is_empty() { 0 } else { indent };
+        let separator = usize::from(!current.is_empty());
+        if !current.is_empty() && prefix + visible_width(&current) + separator + visible_width(word) > width {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    lines.push(current);
+
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(idx, text)| {
+            let prefix = if idx == 0 { 0 } else { indent };
+            format!("{}{}", " ".repeat(prefix), truncate(&text, width - prefix))
+        })
+        .collect()
+}
+
+/// Cut an uncolored line to `width` columns, ending it with an ellipsis
+/// when anything was cut
+pub fn truncate(text: &str, width: usize) -> String {
+    if visible_width(text) <= width {
+        return text.to_string();
+    }
+
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let columns = c.width().unwrap_or(0);
+        if used + columns + 1 > width {
+            break;
+        }
+        truncated.push(c);
+        used += columns;
+    }
+    if width > 0 {
+        truncated.push('…');
+    }
+    truncated
+}
@@ -0,0 +1,461 @@
+//! Built-in text art logos.
+//!
+//! Every vendor has a logo, with a graphics card for those without one,
+//! and some product lines have their own art, picked from the GPU's name;
+//! anything else gets its vendor's logo.
+
+use crate::args::LogoVariant;
+use crate::gpu::common::{GpuInfo, GpuVendor};
+use crate::gpu::intel;
+
+/// ASCII art logos for different vendors
+const NVIDIA_LOGO: &str = r#"
+               ⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀
+               ⢸⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿
+           .⣿⣿⣿.     ⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿
+      .⣿⣿⣿⣿.   ,⣿⣿⣿.     ⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿
+   ,⣿⣿⣿'      ⣿.   ⣿⣿⣿⣿:     ⣿⣿⣿⣿⣿⣿⣿⣿⣿
+.⣿⣿⣿⣿    ⣿⣿⣿⣿⣿ .      .⣿⣿⣿.    ⣿⣿⣿⣿⣿⣿⣿⣿
+⣿⣿⣿⣿   :⣿⣿,    ⣿⣿⣿.    ⣿⣿⣿⣿    :⣿⣿⣿⣿⣿⣿⣿
+ ⣿⣿⣿⣿   ⣿⣿⣿.   ⣿⣿⣿⣿⣿.⣿⣿⣿⣿    :⣿⣿⣿⣿⣿⣿⣿⣿
+  :⣿⣿⣿   ,⣿⣿⣿.  ⣿⣿⣿⣿⣿⣿⣿.   .⣿⣿⣿⣿.     ⣿⣿⣿⣿
+    ⣿⣿⣿⣿.   .⣿⣿⣿.       ,⣿⣿⣿⣿⣿        .⣿⣿⣿⣿
+      ⣿⣿⣿⣿⣿:.    ,⣿⣿⣿⣿::::::::::⣿⣿⣿.        :⣿⣿⣿⣿⣿⣿⣿⣿
+         ⣿⣿⣿⣿⣿⣿⣿⣿⣿.            '⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿
+               ⢸⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿
+
+######.  ##   ##  ##  ######   ##    ###    
+##   ##  ##   ##  ##  ##   ##  ##   #: :#   
+##   ##   ## ##   ##  ##   ##  ##  #######  
+##   ##    ###    ##  ######   ## ##     ## "#;
+
+const NVIDIA_LOGO_SHORT: &str = r#"
+               ⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀
+               ⢸⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿
+           .⣿⣿⣿.     ⣿⣿⣿⣿⣿⣿⣿⣿
+      .⣿⣿⣿⣿.   ,⣿⣿⣿.     ⣿⣿⣿⣿
+   ,⣿⣿⣿'      ⣿.   ⣿⣿⣿⣿:     ⣿
+.⣿⣿⣿⣿    ⣿⣿⣿⣿⣿ .      .⣿⣿⣿.    
+⣿⣿⣿⣿   :⣿⣿,    ⣿⣿⣿.    ⣿⣿⣿⣿    
+ ⣿⣿⣿⣿   ⣿⣿⣿.   ⣿⣿⣿⣿⣿.⣿⣿⣿⣿    
+  :⣿⣿⣿   ,⣿⣿⣿.  ⣿⣿⣿⣿⣿⣿⣿.   .⣿⣿
+    ⣿⣿⣿⣿.   .⣿⣿⣿.       ,⣿⣿⣿⣿
+               ⢸⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿"#;
+
+const INTEL_LOGO: &str = r#"
+                   .#################.          
+              .####                   ####.     
+          .##                             ###   
+       ##                          :##     ###  
+    #                ##            :##      ##  
+  ##   ##  ######.   ####  ######  :##      ##  
+ ##    ##  ##:  ##:  ##   ##   ### :##     ###  
+##     ##  ##:  ##:  ##  :######## :##    ##    
+##     ##  ##:  ##:  ##   ##.   .  :## ####     
+##      #  ##:  ##:  ####  #####:   ##          
+ ##                                             
+  ###.                         ..o####.         
+   ######oo...         ..oo#######              
+          o###############o                     "#;
+
+const INTEL_LOGO_SHORT: &str = r#"
+                   .########.          
+              .####         ####.     
+          .##                   ###   
+       ##                 ##     ###  
+  ##   ##  ######. ####  ##      ##  
+ ##    ##  ##:  ##  ##  ###     ###  
+##     ##  ##:  ##  ##  ##    ##    
+##     ##  ##:  ##  ##   ## ####     
+ ##                                   
+   ######o..     ..o#######           "#;
+
+const AMD_LOGO: &str = r#"
+                  :+++++++++++++++++:                  
+              -++++.                .++++:              
+          .++++.                        -++++.          
+       -++++-                               :++++-       
+     -+++:                                     -+++-     
+   .+++.                                         .+++.   
+  -++-                                             -++-  
+ -++-                                               -++- 
+.++:                                                 :++.
++++                                                   +++
++++                                                   +++
++++                                                   +++
++++               ..-=++++++++==-..                   +++
++++            -+++=:.          .:=+++=.              +++
++++         .+++:                     :+++.           +++
++++        =++-                         -++=          +++
++++       =++.                           .++=         +++
++++       +++                             +++         +++
++++       =++.                           .++=         +++
++++        =++-                         -++=          +++
++++         .+++:                     :+++.           +++
++++            -+++=:.          .:=+++=.              +++
++++               ..-=++++++++==-..                   +++
++++                                                   +++
++++                                                   +++
++++                                                   +++
+.++:                                                 :++.
+ -++-                                               -++- 
+  -++-                                             -++-  
+   .+++.                                         .+++.   
+     -+++:                                     -+++-     
+       -++++-                               :++++-       
+          .++++.                        -++++.          
+              -++++.                .++++:              
+                  :+++++++++++++++++:                  "#;
+
+const AMD_LOGO_SHORT: &str = r#"
+                  :++++++++++++:                  
+              -++++.        .++++:              
+          .++++.                -++++.          
+       -++++-                       :++++-       
+  -++-                                 -++-  
+.++:                                     :++.
++++                                       +++
++++         ..-=++++++++==-..            +++
++++      -+++=:.          .:=+++=.       +++
++++   .+++:                     :+++.    +++
++++   =++-                         -++=  +++
++++   +++                             +++ +++
++++   =++-                         -++=  +++
++++   .+++:                     :+++.    +++
++++      -+++=:.          .:=+++=.       +++
++++         ..-=++++++++==-..            +++
+.++:                                     :++.
+  -++-                                 -++-   "#;
+
+/// Server rack shown for headless compute cards, in the vendor's colors
+const DATACENTER_LOGO: &str = r#"
+ .---------------------------------------------------------. 
+ | :::::::::::::::::::::::::::::::::::::::::::::  o o  [=] | 
+ |---------------------------------------------------------| 
+ | :::::::::::::::::::::::::::::::::::::::::::::  o o  [=] | 
+ |---------------------------------------------------------| 
+ | :::::::::::::::::::::::::::::::::::::::::::::  o o  [=] | 
+ |---------------------------------------------------------| 
+ | :::::::::::::::::::::::::::::::::::::::::::::  o o  [=] | 
+ |---------------------------------------------------------| 
+ | :::::::::::::::::::::::::::::::::::::::::::::  o o  [=] | 
+ '---------------------------------------------------------' 
+    ||                                                 ||    
+                                                             
+####   ###  #####  ###     #### ##### #   # ##### ##### #### 
+#   # #   #   #   #   #   #     #     ##  #   #   #     #   #
+#   # #####   #   #####   #     ###   # # #   #   ###   #### 
+####  #   #   #   #   #    #### ##### #  ##   #   ##### #   #"#;
+
+const DATACENTER_LOGO_SHORT: &str = r#"
+ .--------------------------.
+ | :::::::::::::::: o o [=] |
+ |--------------------------|
+ | :::::::::::::::: o o [=] |
+ |--------------------------|
+ | :::::::::::::::: o o [=] |
+ |--------------------------|
+ | :::::::::::::::: o o [=] |
+ '--------------------------'
+    ||                  ||   "#;
+
+/// GeForce RTX cards: the NVIDIA eye over the product name
+const GEFORCE_RTX_LOGO: &str = r#"
+               ⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀                                 
+               ⢸⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿                                 
+           .⣿⣿⣿.     ⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿                                   
+      .⣿⣿⣿⣿.   ,⣿⣿⣿.     ⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿                                   
+   ,⣿⣿⣿'      ⣿.   ⣿⣿⣿⣿:     ⣿⣿⣿⣿⣿⣿⣿⣿⣿                                   
+.⣿⣿⣿⣿    ⣿⣿⣿⣿⣿ .      .⣿⣿⣿.    ⣿⣿⣿⣿⣿⣿⣿⣿                                  
+⣿⣿⣿⣿   :⣿⣿,    ⣿⣿⣿.    ⣿⣿⣿⣿    :⣿⣿⣿⣿⣿⣿⣿                                  
+ ⣿⣿⣿⣿   ⣿⣿⣿.   ⣿⣿⣿⣿⣿.⣿⣿⣿⣿    :⣿⣿⣿⣿⣿⣿⣿⣿                                   
+  :⣿⣿⣿   ,⣿⣿⣿.  ⣿⣿⣿⣿⣿⣿⣿.   .⣿⣿⣿⣿.     ⣿⣿⣿⣿                               
+    ⣿⣿⣿⣿.   .⣿⣿⣿.       ,⣿⣿⣿⣿⣿        .⣿⣿⣿⣿                              
+      ⣿⣿⣿⣿⣿:.    ,⣿⣿⣿⣿::::::::::⣿⣿⣿.        :⣿⣿⣿⣿⣿⣿⣿⣿                    
+         ⣿⣿⣿⣿⣿⣿⣿⣿⣿.            '⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿                         
+               ⢸⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿                                 
+                                                                         
+ ####  #####  #####   ###   ####    ####  #####      ####   ######  ## ##
+##     ##     ##     ## ##  ## ##  ##     ##         ## ##    ##     ### 
+## ##  ####   ####   ## ##  ####   ##     ####       ####     ##     ### 
+ ####  #####  ##      ###   ## ##   ####  #####      ## ##    ##    ## ##"#;
+
+const GEFORCE_RTX_LOGO_SHORT: &str = r#"
+               ⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀ 
+               ⢸⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿
+           .⣿⣿⣿.     ⣿⣿⣿⣿⣿⣿⣿⣿  
+      .⣿⣿⣿⣿.   ,⣿⣿⣿.     ⣿⣿⣿⣿  
+   ,⣿⣿⣿'      ⣿.   ⣿⣿⣿⣿:     ⣿ 
+.⣿⣿⣿⣿    ⣿⣿⣿⣿⣿ .      .⣿⣿⣿.    
+⣿⣿⣿⣿   :⣿⣿,    ⣿⣿⣿.    ⣿⣿⣿⣿    
+ ⣿⣿⣿⣿   ⣿⣿⣿.   ⣿⣿⣿⣿⣿.⣿⣿⣿⣿      
+  :⣿⣿⣿   ,⣿⣿⣿.  ⣿⣿⣿⣿⣿⣿⣿.   .⣿⣿ 
+    ⣿⣿⣿⣿.   .⣿⣿⣿.       ,⣿⣿⣿⣿  
+               ⢸⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿   
+                               
+          G E F O R C E   R T X"#;
+
+/// Quadro and RTX A-series workstation cards
+const NVIDIA_WORKSTATION_LOGO: &str = r#"
+               ⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀             
+               ⢸⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿             
+           .⣿⣿⣿.     ⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿               
+      .⣿⣿⣿⣿.   ,⣿⣿⣿.     ⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿               
+   ,⣿⣿⣿'      ⣿.   ⣿⣿⣿⣿:     ⣿⣿⣿⣿⣿⣿⣿⣿⣿               
+.⣿⣿⣿⣿    ⣿⣿⣿⣿⣿ .      .⣿⣿⣿.    ⣿⣿⣿⣿⣿⣿⣿⣿              
+⣿⣿⣿⣿   :⣿⣿,    ⣿⣿⣿.    ⣿⣿⣿⣿    :⣿⣿⣿⣿⣿⣿⣿              
+ ⣿⣿⣿⣿   ⣿⣿⣿.   ⣿⣿⣿⣿⣿.⣿⣿⣿⣿    :⣿⣿⣿⣿⣿⣿⣿⣿               
+  :⣿⣿⣿   ,⣿⣿⣿.  ⣿⣿⣿⣿⣿⣿⣿.   .⣿⣿⣿⣿.     ⣿⣿⣿⣿           
+    ⣿⣿⣿⣿.   .⣿⣿⣿.       ,⣿⣿⣿⣿⣿        .⣿⣿⣿⣿          
+      ⣿⣿⣿⣿⣿:.    ,⣿⣿⣿⣿::::::::::⣿⣿⣿.        :⣿⣿⣿⣿⣿⣿⣿⣿
+         ⣿⣿⣿⣿⣿⣿⣿⣿⣿.            '⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿     
+               ⢸⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿             
+                                                     
+     ####   ######  ## ##      ####   ####    ###    
+     ## ##    ##     ###       ## ##  ## ##  ## ##   
+     ####     ##     ###       ####   ####   ## ##   
+     ## ##    ##    ## ##      ##     ## ##   ###"#;
+
+const NVIDIA_WORKSTATION_LOGO_SHORT: &str = r#"
+               ⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀ 
+               ⢸⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿
+           .⣿⣿⣿.     ⣿⣿⣿⣿⣿⣿⣿⣿  
+      .⣿⣿⣿⣿.   ,⣿⣿⣿.     ⣿⣿⣿⣿  
+   ,⣿⣿⣿'      ⣿.   ⣿⣿⣿⣿:     ⣿ 
+.⣿⣿⣿⣿    ⣿⣿⣿⣿⣿ .      .⣿⣿⣿.    
+⣿⣿⣿⣿   :⣿⣿,    ⣿⣿⣿.    ⣿⣿⣿⣿    
+ ⣿⣿⣿⣿   ⣿⣿⣿.   ⣿⣿⣿⣿⣿.⣿⣿⣿⣿      
+  :⣿⣿⣿   ,⣿⣿⣿.  ⣿⣿⣿⣿⣿⣿⣿.   .⣿⣿ 
+    ⣿⣿⣿⣿.   .⣿⣿⣿.       ,⣿⣿⣿⣿  
+               ⢸⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿⣿   
+                               
+              R T X   P R O"#;
+
+/// Radeon cards: the AMD arrow over the product name
+const RADEON_LOGO: &str = r#"
+          ########################      
+               ###################      
+              ###            #####      
+            #####            #####      
+          #######            #####      
+          #######            #####      
+          #######################       
+          ####################          
+                                        
+####    ###   ####   #####   ###   ##  #
+## ##  ## ##  ## ##  ##     ## ##  ### #
+####   #####  ## ##  ####   ## ##  ## ##
+## ##  ## ##  ####   #####   ###   ##  #"#;
+
+const RADEON_LOGO_SHORT: &str = r#"
+    ########################
+         ###################
+        ###            #####
+      #####            #####
+    #######            #####
+    #######            #####
+    ####################### 
+    ####################    
+                            
+         R A D E O N"#;
+
+/// Instinct accelerators
+const INSTINCT_LOGO: &str = r#"
+                ########################          
+                     ###################          
+                    ###            #####          
+                  #####            #####          
+                #######            #####          
+                #######            #####          
+                #######################           
+                ####################              
+                                                  
+##  ##  #   ####  ######  ##  ##  #   ####  ######
+##  ### #  ###      ##    ##  ### #  ##       ##  
+##  ## ##    ###    ##    ##  ## ##  ##       ##  
+##  ##  #  ####     ##    ##  ##  #   ####    ##"#;
+
+const INSTINCT_LOGO_SHORT: &str = r#"
+    ########################
+         ###################
+        ###            #####
+      #####            #####
+    #######            #####
+    #######            #####
+    ####################### 
+    ####################    
+                            
+       I N S T I N C T"#;
+
+/// Iris and Iris Xe integrated graphics
+const IRIS_LOGO: &str = r#"
+             .-######-.               
+          .##:        :##.            
+        .##    .####.    ##.          
+       ##    .########.    ##         
+       ##    ##########    ##         
+       ##    '########'    ##         
+        '##    '####'    ##'          
+          '##:        :##'            
+             '-######-'               
+                                      
+##  ####   ##   ####      ## ##       
+##  ## ##  ##  ###         ###    ### 
+##  ####   ##    ###       ###   #### 
+##  ## ##  ##  ####       ## ##   ####"#;
+
+const IRIS_LOGO_SHORT: &str = r#"
+     .-####-.     
+   .#:      :#.   
+  #:  .####.  :#  
+  #:  ######  :#  
+  #:  '####'  :#  
+   '#:      :#'   
+     '-####-'     
+                  
+  I R I S   X e"#;
+
+/// Intel Arc discrete cards
+const ARC_LOGO: &str = r#"
+            .-=########=-.            
+        .###:'          ':###.        
+      ##'                    '##      
+    ##'     .-=########=-.     '##    
+   ##    .##:'          ':##.    ##   
+  ##    ##'                '##    ##  
+ ##    ##     .-=####=-.     ##    ## 
+ ##   ##    .#:'      ':#.    ##   ## 
+                                      
+          ###   ####    ####          
+         ## ##  ## ##  ##             
+         #####  ####   ##             
+         ## ##  ## ##   ####"#;
+
+const ARC_LOGO_SHORT: &str = r#"
+       .-=####=-.       
+    .##:'      ':##.    
+  ##'   .-=##=-.   '##  
+ ##   .#:'    ':#.   ## 
+ ##  ##'  .##.  '##  ## 
+                        
+         A R C"#;
+
+/// Arm Mali and Immortalis GPUs
+const ARM_LOGO: &str = r#"
+   .#####.   ##.####   ##.####.  .####.  
+  ##'   '##  ###'      ###'  '####'  '## 
+  ##     ##  ##        ##      ##      ##
+  ##     ##  ##        ##      ##      ##
+  '##. .###  ##        ##      ##      ##
+    '###' ## ##        ##      ##      ##"#;
+
+const ARM_LOGO_SHORT: &str = r#"
+  .###.  #.###  #.##.##.  
+ ##   ## ##'    ##  ##  ##
+ ##   ## ##     ##  ##  ##
+  '###'# ##     ##  ##  ##"#;
+
+/// Graphics card for vendors without a logo of their own
+const GENERIC_LOGO: &str = r#"
+ .---------------------------------------------------. 
+ |    .-''''-.        .-''''-.      .------------.   |=
+ |   /  \  /  \      /  \  /  \     |            |   |=
+ |  |    ()    |    |    ()    |    |    GPU     |   |=
+ |   \  /  \  /      \  /  \  /     |            |   |=
+ |    '-....-'        '-....-'      '------------'   |=
+ '---------------------------------------------------' 
+     ||||||||||||||||||||||||||||||||||||"#;
+
+const GENERIC_LOGO_SHORT: &str = r#"
+ .-----------------------------. 
+ |    .-''''-.     .-------.   |=
+ |   /  \  /  \    |       |   |=
+ |  |    ()    |   |  GPU  |   |=
+ |   \  /  \  /    |       |   |=
+ |    '-....-'     '-------'   |=
+ '-----------------------------' 
+     ||||||||||||||||||||"#;
+
+/// Product lines with art of their own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProductLine {
+    GeforceRtx,
+    NvidiaWorkstation,
+    Radeon,
+    Instinct,
+    Arc,
+    Iris,
+}
+
+impl ProductLine {
+    /// The product line of a GPU, from its marketing name
+    ///
+    /// Workstation cards are told apart by their "Quadro", "RTX A4000" or
+    /// "RTX 6000 Ada Generation" style names, so plain RTX is GeForce. Arc
+    /// cards are also known by their PCI device ID, since i915 and xe give
+    /// them no product name.
+    fn of(gpu: &GpuInfo) -> Option<Self> {
+        let name = gpu.name.to_lowercase();
+        match gpu.vendor {
+            GpuVendor::Nvidia => {
+                let workstation = name.contains("quadro")
+                    || name.contains("rtx pro")
+                    || name.contains("ada generation")
+                    || name.split_whitespace().any(|word| {
+                        word.len() == 5 && word.starts_with('a') && word[1..].bytes().all(|b| b.is_ascii_digit())
+                    });
+                if workstation {
+                    Some(ProductLine::NvidiaWorkstation)
+                } else {
+                    name.contains("rtx").then_some(ProductLine::GeforceRtx)
+                }
+            }
+            GpuVendor::Amd if name.contains("instinct") => Some(ProductLine::Instinct),
+            GpuVendor::Amd => name.contains("radeon").then_some(ProductLine::Radeon),
+            GpuVendor::Intel
+                if name.split_whitespace().any(|word| word == "arc")
+                    || gpu.pci_info.as_ref().is_some_and(|pci_info| intel::is_arc(pci_info.device_id)) =>
+            {
+                Some(ProductLine::Arc)
+            }
+            GpuVendor::Intel => name.contains("iris").then_some(ProductLine::Iris),
+            _ => None,
+        }
+    }
+
+    /// Full and short art
+    fn art(self) -> (&'static str, &'static str) {
+        match self {
+            ProductLine::GeforceRtx => (GEFORCE_RTX_LOGO, GEFORCE_RTX_LOGO_SHORT),
+            ProductLine::NvidiaWorkstation => (NVIDIA_WORKSTATION_LOGO, NVIDIA_WORKSTATION_LOGO_SHORT),
+            ProductLine::Radeon => (RADEON_LOGO, RADEON_LOGO_SHORT),
+            ProductLine::Instinct => (INSTINCT_LOGO, INSTINCT_LOGO_SHORT),
+            ProductLine::Arc => (ARC_LOGO, ARC_LOGO_SHORT),
+            ProductLine::Iris => (IRIS_LOGO, IRIS_LOGO_SHORT),
+        }
+    }
+}
+
+/// Get ASCII art for the given GPU and logo variant
+///
+/// The product line's art comes first, then the server rack for other
+/// headless compute cards, then the vendor logo.
+pub fn ascii_art(gpu: &GpuInfo, logo_variant: LogoVariant) -> &'static str {
+    let (full, short) = match ProductLine::of(gpu) {
+        Some(product_line) => product_line.art(),
+        None if gpu.compute_only => (DATACENTER_LOGO, DATACENTER_LOGO_SHORT),
+        None => match gpu.vendor {
+            GpuVendor::Nvidia => (NVIDIA_LOGO, NVIDIA_LOGO_SHORT),
+            GpuVendor::Amd => (AMD_LOGO, AMD_LOGO_SHORT),
+            GpuVendor::Intel => (INTEL_LOGO, INTEL_LOGO_SHORT),
+            GpuVendor::Arm => (ARM_LOGO, ARM_LOGO_SHORT),
+            GpuVendor::Other(_) => (GENERIC_LOGO, GENERIC_LOGO_SHORT),
+        },
+    };
+
+    match logo_variant {
+        LogoVariant::None => "",
+        LogoVariant::Short => short,
+        _ => full,
+    }
+}
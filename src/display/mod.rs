@@ -0,0 +1,501 @@
+#[cfg(feature = "opencl")]
+pub mod bench;
+pub mod color_policy;
+pub mod compare;
+pub mod csv;
+pub mod diagram;
+pub mod document;
+mod fields;
+mod frame;
+pub mod graphics;
+pub mod image_logo;
+pub mod json;
+mod layout;
+mod logos;
+pub mod oneline;
+pub mod passthrough;
+pub mod pcie_tree;
+pub mod screenshot;
+mod sparkline;
+pub mod summary;
+pub mod terminal;
+pub mod theme;
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::args::{ColorScheme, Layout, LogoVariant};
+use crate::config::Config;
+use crate::gpu::common::{GpuInfo, GpuVendor};
+use crate::session::SessionInfo;
+use crate::suggestions::Distro;
+use crate::system::SystemInfo;
+use crate::units::Units;
+use crate::utils;
+pub use fields::Field;
+use graphics::{Graphic, GraphicsProtocol};
+use layout::visible_width;
+use image_logo::ImageLogo;
+use terminal::{Charset, ColorDepth};
+use theme::Theme;
+
+/// User-supplied ASCII art replacing the built-in logos
+#[derive(Debug, Clone, Default)]
+pub struct CustomLogos {
+    /// Picture from `--logo-image`, shown for every GPU
+    pub image: Option<ImageLogo>,
+    /// Art from `--logo-file`, shown for every GPU
+    pub all: Option<String>,
+    /// Per-vendor art from the `[logos]` table of the config file
+    pub by_vendor: Vec<(GpuVendor, String)>,
+}
+
+impl CustomLogos {
+    /// Read `--logo-file` and the logo files named in the config
+    ///
+    /// An unreadable `--logo-file` is an error; broken config entries are
+    /// skipped with a warning so the built-in logo is shown instead.
+    pub fn load(logo_file: Option<&Path>, image: Option<ImageLogo>, config: &Config) -> Result<Self> {
+        let all = match logo_file {
+            Some(path) => Some(read_logo_file(path)?),
+            None => None,
+        };
+
+        let by_vendor = [GpuVendor::Nvidia, GpuVendor::Amd, GpuVendor::Intel]
+            .into_iter()
+            .filter_map(|vendor| {
+                let path = config.logos.for_vendor(&vendor)?;
+                match read_logo_file(&path) {
+                    Ok(art) => Some((vendor, art)),
+                    Err(e) => {
+                        eprintln!("Warning: ignoring {} logo: {:#}", vendor, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        Ok(CustomLogos { image, all, by_vendor })
+    }
+
+    /// Custom art to show for `vendor`, if any
+    fn for_vendor(&self, vendor: &GpuVendor) -> Option<&str> {
+        self.all.as_deref().or_else(|| {
+            self.by_vendor
+                .iter()
+                .find(|(v, _)| v == vendor)
+                .map(|(_, art)| art.as_str())
+        })
+    }
+}
+
+/// Read a logo file, expanding tabs and dropping trailing blank lines
+fn read_logo_file(path: &Path) -> Result<String> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read logo file {}", path.display()))?;
+    Ok(content.replace('\t', "    ").trim_end().to_string())
+}
+
+/// Options controlling how the fetch card is rendered
+#[derive(Debug, Clone)]
+pub struct DisplayOptions {
+    pub color_scheme: ColorScheme,
+    /// Palette from `--custom-colors`
+    pub custom_colors: Option<Theme>,
+    pub logo_variant: LogoVariant,
+    pub layout: Layout,
+    pub detailed: bool,
+    pub frame: bool,
+    /// Info fields to show, in order
+    pub fields: Vec<Field>,
+    /// Characters the terminal can show; braille and box drawing degrade to ASCII
+    pub charset: Charset,
+    /// Colors the terminal can show; themes drop to named colors below truecolor
+    pub color_depth: ColorDepth,
+    /// Graphical session to describe, when asked for with `--session`
+    pub session: Option<SessionInfo>,
+    /// Machine to head the card with, when asked for with `--system-info`
+    pub system: Option<SystemInfo>,
+    /// Whether to suggest driver packages, when asked for with `--suggest`
+    pub suggest: bool,
+    /// Distribution family the suggested packages are named for
+    pub distro: Option<Distro>,
+    pub custom_logos: CustomLogos,
+    /// Image protocol to draw the logo with instead of text art
+    pub graphics: Option<GraphicsProtocol>,
+    pub units: Units,
+    /// Whether to draw a bar under the memory usage lines
+    pub memory_bar: bool,
+}
+
+/// Print gpufetch output for a GPU
+///
+/// On a terminal the card is fitted to its width: the logo shrinks or goes
+/// away on narrow ones, and info lines wrap.
+pub fn print_gpufetch(gpu: &GpuInfo, options: &DisplayOptions) -> Result<()> {
+    // Determine colors based on vendor and color scheme
+    let theme = get_theme(gpu, options.color_scheme, options.custom_colors.as_ref()).for_depth(options.color_depth);
+    
+    // Pick the largest logo that leaves the info room, beside or above it,
+    // and give the info what is left
+    let terminal_width = utils::get_terminal_width();
+    let placement = layout::place_logo(options.logo_variant, options.layout, terminal_width, |variant| {
+        logo_width(gpu, options, variant)
+    });
+    let logo_variant = placement.variant;
+    let info_width = terminal_width.map(|width| {
+        let logo_columns = match logo_variant {
+            LogoVariant::None => 0,
+            _ if placement.vertical => 0,
+            variant => logo_width(gpu, options, variant) + layout::GAP,
+        };
+        // A frame takes two columns on either side
+        let frame_columns = if options.frame { 4 } else { 0 };
+        width.saturating_sub(logo_columns + frame_columns).max(1)
+    });
+    
+    // Get appropriate ASCII art, preferring the user's own
+    let raw_art = text_logo(gpu, options, logo_variant);
+    
+    // Build the info panel, either boxed with the GPU name as its title or
+    // as plain lines under an underlined name
+    let mut info_lines = create_info_lines(gpu, options, &theme, info_width);
+    if options.frame {
+        info_lines = frame::frame_lines(&gpu.name, &info_lines, &theme);
+    }
+    
+    // Degrade logos, frames and names for terminals without UTF-8
+    let ascii_art = options.charset.render(raw_art);
+    let info_lines: Vec<String> = info_lines
+        .iter()
+        .map(|line| options.charset.render(line).into_owned())
+        .collect();
+    
+    if logo_variant == LogoVariant::None {
+        // Print info only
+        print_lines(&info_lines)?;
+    } else if let Some(graphic) = logo_graphic(options, raw_art, &theme) {
+        if placement.vertical {
+            print_graphic_above_info(&graphic, &info_lines)?;
+        } else {
+            print_graphic_with_info(&graphic, &info_lines)?;
+        }
+    } else {
+        // Print ASCII art with info
+        print_lines(&art_with_info(&ascii_art, &info_lines, options, &theme, placement.vertical))?;
+    }
+    
+    Ok(())
+}
+
+/// The picture itself, or the text art as a bitmap, to draw over the block
+/// of cells the text art would take, when there is an image protocol
+#[cfg(feature = "image")]
+fn logo_graphic(options: &DisplayOptions, raw_art: &str, theme: &Theme) -> Option<Graphic> {
+    let protocol = options.graphics?;
+    Some(match options.custom_logos.image {
+        Some(ref image) => protocol.encode(&image.source, image.width, image.lines.len()),
+        None => {
+            let (bitmap, columns, rows) = graphics::rasterize_art(raw_art, theme);
+            protocol.encode(&bitmap, columns, rows)
+        }
+    })
+}
+
+/// Builds without the `image` feature have no protocol to draw with; see
+/// `LogoMode::protocol`
+#[cfg(not(feature = "image"))]
+fn logo_graphic(options: &DisplayOptions, _raw_art: &str, _theme: &Theme) -> Option<Graphic> {
+    debug_assert!(options.graphics.is_none());
+    None
+}
+
+/// Columns the logo of a variant takes up
+fn logo_width(gpu: &GpuInfo, options: &DisplayOptions, variant: LogoVariant) -> usize {
+    match options.custom_logos.image {
+        Some(ref image) => image.width,
+        None => text_logo(gpu, options, variant).lines().map(visible_width).max().unwrap_or(0),
+    }
+}
+
+/// The card as text: the colored logo art beside the info panel, or the
+/// panel alone without a logo
+///
+/// This is what `print_gpufetch` shows on terminals without an image
+/// protocol, for anything that draws the card elsewhere.
+pub fn card_lines(gpu: &GpuInfo, options: &DisplayOptions) -> Vec<String> {
+    let theme = get_theme(gpu, options.color_scheme, options.custom_colors.as_ref()).for_depth(options.color_depth);
+    let mut info_lines = create_info_lines(gpu, options, &theme, None);
+    if options.frame {
+        info_lines = frame::frame_lines(&gpu.name, &info_lines, &theme);
+    }
+
+    if options.logo_variant == LogoVariant::None {
+        info_lines
+    } else {
+        let vertical = options.layout == Layout::Vertical;
+        art_with_info(text_logo(gpu, options, options.logo_variant), &info_lines, options, &theme, vertical)
+    }
+}
+
+/// Color the logo art and lay it out beside the info lines, or above them
+/// when `vertical`; image logos bring their own colors
+fn art_with_info(
+    ascii_art: &str,
+    info_lines: &[String],
+    options: &DisplayOptions,
+    theme: &Theme,
+    vertical: bool,
+) -> Vec<String> {
+    let (art_lines, art_width) = match options.custom_logos.image {
+        Some(ref image) => (image.lines.clone(), image.width),
+        None => {
+            let lines = ascii_art.lines().count();
+            (
+                ascii_art
+                    .lines()
+                    .enumerate()
+                    .map(|(i, line)| theme.paint(line, theme.logo_color(i, lines)))
+                    .collect(),
+                ascii_art.lines().map(visible_width).max().unwrap_or(0),
+            )
+        }
+    };
+    if vertical {
+        lay_out_above_info(&art_lines, info_lines)
+    } else {
+        lay_out_with_info(&art_lines, art_width, info_lines)
+    }
+}
+
+/// Text art logo of a variant for a GPU, preferring the user's own
+fn text_logo<'a>(gpu: &GpuInfo, options: &'a DisplayOptions, variant: LogoVariant) -> &'a str {
+    match options.custom_logos.for_vendor(&gpu.vendor) {
+        Some(art) if variant != LogoVariant::None => art,
+        _ => logos::ascii_art(gpu, variant),
+    }
+}
+
+/// Get the theme for the GPU's vendor or the chosen color scheme
+///
+/// Custom colors apply to the custom scheme and to the default system
+/// scheme, so `--custom-colors` works on its own.
+fn get_theme(gpu: &GpuInfo, color_scheme: ColorScheme, custom_colors: Option<&Theme>) -> Theme {
+    match (color_scheme, custom_colors) {
+        (ColorScheme::Custom | ColorScheme::System, Some(custom)) => custom.clone(),
+        (ColorScheme::Nvidia, _) => Theme::vendor(&GpuVendor::Nvidia),
+        (ColorScheme::Amd, _) => Theme::vendor(&GpuVendor::Amd),
+        (ColorScheme::Intel, _) => Theme::vendor(&GpuVendor::Intel),
+        _ => Theme::vendor(&gpu.vendor),
+    }
+}
+
+/// Lay out GPU info alongside colored logo lines `art_width` columns wide
+///
+/// The art may have any size: every line is padded to the widest one so the
+/// info column stays straight, and the shorter of the two is centered
+/// against the taller.
+fn lay_out_with_info(ascii_lines: &[String], art_width: usize, info_lines: &[String]) -> Vec<String> {
+    // Determine the maximum number of lines between ASCII art and info
+    let max_lines = ascii_lines.len().max(info_lines.len());
+    
+    // Calculate where each column starts to center it against the other
+    let art_start = (max_lines - ascii_lines.len()) / 2;
+    let info_start = (max_lines - info_lines.len()) / 2;
+    
+    (0..max_lines)
+        .map(|i| {
+            // The ASCII line, or blank space matching the width of the art
+            let ascii_line = i.checked_sub(art_start).and_then(|idx| ascii_lines.get(idx)).map_or("", |line| line.as_str());
+            let padding = " ".repeat(art_width.saturating_sub(visible_width(ascii_line)));
+            
+            // Info line if available
+            match i.checked_sub(info_start).and_then(|idx| info_lines.get(idx)) {
+                Some(info_line) => format!("{}{}{}{}", ascii_line, padding, " ".repeat(layout::GAP), info_line),
+                None => format!("{}{}", ascii_line, padding),
+            }
+        })
+        .collect()
+}
+
+/// Stack the logo lines over the info lines, a blank line between them
+fn lay_out_above_info(ascii_lines: &[String], info_lines: &[String]) -> Vec<String> {
+    ascii_lines.iter().cloned().chain([String::new()]).chain(info_lines.iter().cloned()).collect()
+}
+
+/// Print GPU info alongside a picture drawn with an image protocol
+///
+/// The rows are scrolled into view first so the picture can be drawn at a
+/// saved cursor position; the info lines then skip over it with cursor
+/// movement, because writing spaces would erase Sixel pixels.
+fn print_graphic_with_info(graphic: &Graphic, info_lines: &[String]) -> Result<()> {
+    let stdout = io::stdout();
+    let mut handle = stdout.lock();
+
+    // Print empty line for spacing
+    writeln!(handle)?;
+
+    let max_lines = graphic.rows.max(info_lines.len());
+    let art_start = (max_lines - graphic.rows) / 2;
+    let info_start = (max_lines - info_lines.len()) / 2;
+
+    // Reserve the rows, then return to the first one
+    if max_lines > 0 {
+        write!(handle, "{}\x1b[{}A", "\n".repeat(max_lines), max_lines)?;
+    }
+
+    for i in 0..max_lines {
+        if i == art_start {
+            write!(handle, "\x1b7{}\x1b8", graphic.escape)?;
+        }
+
+        if let Some(info_line) = i.checked_sub(info_start).and_then(|idx| info_lines.get(idx)) {
+            write!(handle, "\r\x1b[{}C  {}", graphic.columns.max(1), info_line)?;
+        }
+
+        writeln!(handle)?;
+    }
+
+    // Print empty line for spacing
+    writeln!(handle)?;
+
+    Ok(())
+}
+
+/// Print GPU info under a picture drawn with an image protocol
+///
+/// As beside the info, the rows are scrolled into view before the picture
+/// is drawn, then skipped over with cursor movement.
+fn print_graphic_above_info(graphic: &Graphic, info_lines: &[String]) -> Result<()> {
+    let stdout = io::stdout();
+    let mut handle = stdout.lock();
+
+    writeln!(handle)?;
+    if graphic.rows > 0 {
+        write!(handle, "{}\x1b[{}A", "\n".repeat(graphic.rows), graphic.rows)?;
+        write!(handle, "\x1b7{}\x1b8\x1b[{}B", graphic.escape, graphic.rows)?;
+    }
+    writeln!(handle)?;
+    for line in info_lines {
+        writeln!(handle, "{}", line)?;
+    }
+    writeln!(handle)?;
+
+    Ok(())
+}
+
+/// Print lines of the card between blank lines for spacing
+fn print_lines(lines: &[String]) -> Result<()> {
+    let stdout = io::stdout();
+    let mut handle = stdout.lock();
+    
+    writeln!(handle)?;
+    for line in lines {
+        writeln!(handle, "{}", line)?;
+    }
+    writeln!(handle)?;
+    
+    Ok(())
+}
+
+/// Create info lines for the selected fields, with labels and values in
+/// the theme's colors, under the system header when there is one
+///
+/// Framed panels carry the GPU name in their border, so the name field is
+/// only drawn as a heading without a frame. Lines wider than `width` are
+/// wrapped, with the continuation in the value color, after degrading them
+/// to the charset so the width is what the terminal shows.
+fn create_info_lines(gpu: &GpuInfo, options: &DisplayOptions, theme: &Theme, width: Option<usize>) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(ref system) = options.system {
+        if let Some(ref hostname) = system.hostname {
+            push_heading(&mut lines, hostname, options, theme, width);
+        }
+        for line in system_lines(system, options.units) {
+            push_info_line(&mut lines, &line, options, theme, width);
+        }
+        lines.push(String::new());
+    }
+
+    for field in &options.fields {
+        if *field == Field::Name {
+            if !options.frame {
+                push_heading(&mut lines, &gpu.name, options, theme, width);
+            }
+            continue;
+        }
+        let usage = if *field == Field::Memory && options.memory_bar { fields::memory_usage(gpu, options) } else { Vec::new() };
+        for line in field.lines(gpu, options) {
+            push_info_line(&mut lines, &line, options, theme, width);
+            if let Some(&(_, fraction)) = usage.iter().find(|(usage_line, _)| *usage_line == line) {
+                lines.push(usage_bar(fraction, options.charset, theme, width));
+            }
+        }
+    }
+    lines
+}
+
+/// Widest memory usage bar, so it stays a glance on wide terminals
+const USAGE_BAR_MAX_WIDTH: usize = 40;
+
+/// Width of the memory usage bar when stdout is not a terminal
+const USAGE_BAR_DEFAULT_WIDTH: usize = 30;
+
+/// A bar of the memory used, indented under its usage line and as wide as
+/// the panel allows up to `USAGE_BAR_MAX_WIDTH`
+fn usage_bar(fraction: f64, charset: Charset, theme: &Theme, width: Option<usize>) -> String {
+    let columns = width.map_or(USAGE_BAR_DEFAULT_WIDTH, |width| width.saturating_sub(2)).clamp(1, USAGE_BAR_MAX_WIDTH);
+    let used = ((fraction * columns as f64).round() as usize).min(columns);
+    let (used_cell, free_cell) = match charset {
+        Charset::Utf8 => ("█", "░"),
+        Charset::Ascii => ("#", "-"),
+    };
+    // Nearly full memory is what the bar is there to catch
+    let used_color = if fraction >= 0.9 { colored::Color::Red } else { theme.accent_color() };
+    format!(
+        "  {}{}",
+        theme.paint(&used_cell.repeat(used), used_color),
+        theme.paint(&free_cell.repeat(columns - used), theme.key_color())
+    )
+}
+
+/// Operating system, CPU and memory lines of the system header
+fn system_lines(system: &SystemInfo, units: Units) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(ref distro) = system.distro {
+        lines.push(format!("OS: {}", distro));
+    }
+    if let Some(ref kernel) = system.kernel {
+        lines.push(format!("Kernel: {}", kernel));
+    }
+    if let Some(ref cpu) = system.cpu {
+        lines.push(format!("CPU: {}", cpu));
+    }
+    if let Some(memory_bytes) = system.memory_bytes {
+        lines.push(format!("RAM: {}", units.memory(memory_bytes)));
+    }
+    lines
+}
+
+/// Add an underlined heading, cut to `width`
+fn push_heading(lines: &mut Vec<String>, heading: &str, options: &DisplayOptions, theme: &Theme, width: Option<usize>) {
+    let heading = options.charset.render(heading);
+    let heading = width.map_or_else(|| heading.to_string(), |width| layout::truncate(&heading, width));
+    let underline = "-".repeat(visible_width(&heading));
+    lines.push(theme.paint(&heading, theme.value_color()));
+    lines.push(theme.paint(&underline, theme.key_color()));
+}
+
+/// Add an info line, wrapped to `width`
+fn push_info_line(lines: &mut Vec<String>, line: &str, options: &DisplayOptions, theme: &Theme, width: Option<usize>) {
+    // Measured as the terminal will show it
+    let line = options.charset.render(line);
+    let Some(width) = width else {
+        lines.push(theme.paint_info_line(&line));
+        return;
+    };
+    for (idx, part) in layout::wrap(&line, width).iter().enumerate() {
+        lines.push(if idx == 0 { theme.paint_info_line(part) } else { theme.paint(part, theme.value_color()) });
+    }
+}
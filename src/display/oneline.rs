@@ -0,0 +1,122 @@
+//! `--oneline`: the selected GPU on a single short line, for shell prompt
+//! segments such as starship custom modules or a `PS1` command substitution.
+//!
+//! Prompts run it on every command, so the line leaves out anything unknown
+//! rather than printing placeholders, and carries no color or labels.
+
+use clap::ValueEnum;
+use std::io::{self, Write};
+
+use super::terminal::Charset;
+use crate::gpu::common::GpuInfo;
+use crate::units::Units;
+
+/// A value that can go on the line
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OnelineField {
+    /// Model name without the vendor, e.g. "RTX 3080"
+    Name,
+    /// Dedicated memory, e.g. "10GB"; follows the name when right after it
+    Memory,
+    /// Current core clock
+    Clock,
+    Temperature,
+    /// Power draw
+    Power,
+    /// Fan speed
+    Fan,
+}
+
+impl OnelineField {
+    /// Fields used without `--oneline-fields`
+    pub const DEFAULT: [OnelineField; 4] = [OnelineField::Name, OnelineField::Memory, OnelineField::Clock, OnelineField::Temperature];
+}
+
+/// Vendor and brand words that only take up room in a prompt
+const NAME_PREFIXES: [&str; 6] = ["NVIDIA ", "AMD ", "ATI ", "Intel(R) ", "Intel ", "GeForce "];
+
+/// Print `fields` of each GPU, GPUs separated by " | "
+pub fn print_oneline(gpus: &[GpuInfo], fields: &[OnelineField], units: Units, charset: Charset, newline: bool) -> io::Result<()> {
+    let separator = match charset {
+        Charset::Utf8 => " · ",
+        Charset::Ascii => " / ",
+    };
+    let line: Vec<String> = gpus.iter().map(|gpu| segment(gpu, fields, units, separator)).collect();
+    let line = line.join(" | ");
+
+    let mut stdout = io::stdout().lock();
+    write!(stdout, "{}", charset.render(&line))?;
+    if newline {
+        writeln!(stdout)?;
+    }
+    stdout.flush()
+}
+
+/// The values of one GPU, e.g. "RTX 3080 10GB · 1710MHz · 62°C"
+fn segment(gpu: &GpuInfo, fields: &[OnelineField], units: Units, separator: &str) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    let mut previous = None;
+    for &field in fields {
+        let Some(value) = value(gpu, field, units) else {
+            continue;
+        };
+        match parts.last_mut() {
+            Some(name) if field == OnelineField::Memory && previous == Some(OnelineField::Name) => {
+                name.push(' ');
+                name.push_str(&value);
+            }
+            _ => parts.push(value),
+        }
+        previous = Some(field);
+    }
+    parts.join(separator)
+}
+
+fn value(gpu: &GpuInfo, field: OnelineField, units: Units) -> Option<String> {
+    match field {
+        OnelineField::Name => Some(short_name(&gpu.name).to_string()),
+        // Integrated GPUs borrow system memory
+        OnelineField::Memory => gpu
+            .memory
+            .as_ref()
+            .filter(|memory| !gpu.is_integrated && memory.size_bytes > 0)
+            .map(|memory| memory_size(memory.size_bytes, units)),
+        OnelineField::Clock => (gpu.freq_mhz > 0).then(|| units.frequency(gpu.freq_mhz).replace(' ', "")),
+        OnelineField::Temperature => gpu.temperature_c.map(|celsius| units.temperature(celsius)),
+        OnelineField::Power => gpu.power_draw_w.map(|watts| format!("{:.0}W", watts)),
+        OnelineField::Fan => gpu.fan.and_then(|fan| match (fan.percent, fan.rpm) {
+            (Some(percent), _) => Some(format!("{}%", percent)),
+            (None, Some(rpm)) => Some(format!("{}RPM", rpm)),
+            (None, None) => None,
+        }),
+    }
+}
+
+/// "RTX 3080" from "NVIDIA GeForce RTX 3080", and "GTX 1060 6GB" from
+/// pci.ids names such as "NVIDIA Corporation GP106 [GeForce GTX 1060 6GB]"
+fn short_name(name: &str) -> &str {
+    let mut name = match (name.find('['), name.strip_suffix(']')) {
+        (Some(start), Some(_)) => &name[start + 1..name.len() - 1],
+        _ => name,
+    };
+    // Unnamed GPUs, "AMD GPU (Device ID: 73DF)", need their vendor
+    while let Some(rest) = NAME_PREFIXES.iter().find_map(|prefix| name.strip_prefix(prefix)).filter(|rest| !rest.starts_with("GPU ")) {
+        name = rest;
+    }
+    // "RX 7900 XTX" is as recognizable as "Radeon RX 7900 XTX"
+    name.strip_prefix("Radeon ").filter(|rest| rest.starts_with("RX ")).unwrap_or(name)
+}
+
+/// "10GB" or "512MiB": the `Units` size without the space, and without a
+/// decimal for whole sizes
+fn memory_size(bytes: u64, units: Units) -> String {
+    let gigabytes = units.scaled(bytes, 3);
+    if gigabytes < 1.0 {
+        return format!("{:.0}{}", units.scaled(bytes, 2).floor(), units.size_label(2));
+    }
+    if (gigabytes - gigabytes.round()).abs() < 0.05 {
+        format!("{:.0}{}", gigabytes, units.size_label(3))
+    } else {
+        format!("{:.1}{}", gigabytes, units.size_label(3))
+    }
+}
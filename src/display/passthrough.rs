@@ -0,0 +1,58 @@
+use colored::Colorize;
+
+use crate::gpu::common::GpuInfo;
+use crate::gpu::iommu::{self, Readiness};
+use crate::gpu::pci;
+
+/// Print IOMMU groups and VFIO readiness for every GPU
+pub fn print_passthrough_report(gpus: &[GpuInfo]) {
+    let group_count = iommu::group_count();
+    let iommu_enabled = group_count > 0;
+
+    if iommu_enabled {
+        println!("IOMMU: enabled ({} groups)", group_count);
+    } else {
+        println!(
+            "IOMMU: disabled (enable VT-d/AMD-Vi in firmware and boot with intel_iommu=on or amd_iommu=on)"
+        );
+    }
+
+    for (idx, gpu) in gpus.iter().enumerate() {
+        println!();
+
+        let Some(ref pci_info) = gpu.pci_info else {
+            println!("{}: {}", idx, gpu.name.bold());
+            println!("   PCI address unknown, cannot check passthrough");
+            continue;
+        };
+        let address = pci_info.address();
+        println!("{}: {} [{}]", idx, gpu.name.bold(), address);
+
+        let driver = pci::bound_driver(&pci::device_path(&address));
+        println!("   Driver: {}", driver.as_deref().unwrap_or("none"));
+
+        let group = iommu::read_group(&address);
+        if let Some(ref group) = group {
+            println!("   IOMMU group {}:", group.id);
+            for device in &group.devices {
+                println!(
+                    "     {}  {} ({})",
+                    device.address,
+                    device.name,
+                    device.driver.as_deref().unwrap_or("no driver")
+                );
+            }
+        }
+
+        let status = match iommu::readiness(&address, group.as_ref(), iommu_enabled) {
+            Readiness::Bound => "bound to vfio-pci".green(),
+            Readiness::Ready => "ready, bind every device in the group to vfio-pci".green(),
+            Readiness::IommuDisabled => "not ready, IOMMU is disabled".red(),
+            Readiness::NoGroup => "not ready, device has no IOMMU group".red(),
+            Readiness::SharedGroup(count) => {
+                format!("not ready, group is shared with {} device(s) from other slots", count).yellow()
+            }
+        };
+        println!("   Status: {}", status);
+    }
+}
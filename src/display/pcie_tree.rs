@@ -0,0 +1,82 @@
+use colored::Colorize;
+
+use crate::display::terminal::Charset;
+use crate::gpu::common::{GpuInfo, PcieLink};
+use crate::gpu::pcie_tree::{self, bandwidth, Node};
+
+/// Print the PCIe tree from each root complex down to the GPUs
+pub fn print_pcie_tree(gpus: &[GpuInfo], charset: Charset) {
+    let roots = pcie_tree::build(gpus);
+    if roots.is_empty() {
+        println!("No GPU with a PCI address in sysfs");
+    }
+
+    for (idx, root) in roots.iter().enumerate() {
+        if idx > 0 {
+            println!();
+        }
+        println!("{}", root.name.bold());
+        for (child_idx, child) in root.children.iter().enumerate() {
+            print_node(child, gpus, charset, "", child_idx + 1 == root.children.len(), &[]);
+        }
+    }
+
+    let placed: Vec<usize> = roots.iter().flat_map(|root| root.children.iter().flat_map(Node::gpus)).collect();
+    for (idx, gpu) in gpus.iter().enumerate().filter(|(idx, _)| !placed.contains(idx)) {
+        println!();
+        match gpu.pci_info {
+            Some(ref pci_info) => println!("GPU {}: {} [{}] is not in sysfs", idx, gpu.name.bold(), pci_info.address()),
+            None => println!("GPU {}: {} has no PCI address", idx, gpu.name.bold()),
+        }
+    }
+}
+
+/// Print a device and the ones below it
+///
+/// `limited` holds the GPUs a bridge above already slows down, with the
+/// link it limits them to, so a chain of equally slow ports is flagged once.
+fn print_node(node: &Node, gpus: &[GpuInfo], charset: Charset, prefix: &str, last: bool, limited: &[(usize, PcieLink)]) {
+    let branch = if last { "└─ " } else { "├─ " };
+    let label = match node.gpu {
+        Some(idx) => format!("GPU {}: {}", idx, gpus[idx].name).bold().to_string(),
+        None => node.name.clone(),
+    };
+    let mut line = format!("{} {}", node.address, label);
+    if let Some(max_link) = node.max_link {
+        line.push_str(&format!(" [{}]", link(max_link)));
+        if let Some(current) = node.link.filter(|current| current.generation != max_link.generation || current.width != max_link.width) {
+            line.push_str(&format!(" running at {}", link(current)));
+        }
+    }
+    println!("{}", charset.render(&format!("{}{}{}", prefix, branch, line)));
+
+    let notes_prefix = format!("{}{}", prefix, if last { "   " } else { "│  " });
+    let mut limited = limited.to_vec();
+    if node.is_bridge {
+        let below = node.gpus();
+        if below.len() > 1 {
+            let names: Vec<String> = below.iter().map(|idx| format!("GPU {}", idx)).collect();
+            let note = format!("shared by {}", names.join(", "));
+            println!("{}", charset.render(&format!("{}   {}", notes_prefix, note.yellow())));
+        }
+        if let Some(own) = node.max_link {
+            for (idx, gpu_link) in node.limited_gpus() {
+                let flagged = limited.iter().any(|&(flagged, above)| flagged == idx && bandwidth(above) <= bandwidth(own));
+                if flagged {
+                    continue;
+                }
+                limited.push((idx, own));
+                let note = format!("limits GPU {} ({}) to {}", idx, link(gpu_link), link(own));
+                println!("{}", charset.render(&format!("{}   {}", notes_prefix, note.red())));
+            }
+        }
+    }
+
+    for (idx, child) in node.children.iter().enumerate() {
+        print_node(child, gpus, charset, &notes_prefix, idx + 1 == node.children.len(), &limited);
+    }
+}
+
+fn link(link: PcieLink) -> String {
+    format!("Gen {} x{}", link.generation, link.width)
+}
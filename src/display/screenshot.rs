@@ -0,0 +1,307 @@
+//! `--screenshot`: the fetch card saved as a PNG or SVG picture.
+//!
+//! The card is rendered to text with color escapes exactly as for a
+//! terminal, then the escapes are interpreted into a grid of colored cells
+//! that is drawn with a built-in 8x8 bitmap font (PNG) or as text in a
+//! monospace font (SVG). Braille logo characters are drawn dot for dot.
+
+use anyhow::{bail, Context, Result};
+#[cfg(feature = "image")]
+use font8x8::legacy::{BASIC_LEGACY, BLOCK_LEGACY, BOX_LEGACY, LATIN_LEGACY};
+#[cfg(feature = "image")]
+use image::{Rgba, RgbaImage};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+#[cfg(feature = "image")]
+use super::graphics::{braille_dots, BRAILLE_DOTS};
+use super::theme::Rgb;
+
+/// Terminal-like colors behind and of uncolored text
+const BACKGROUND: Rgb = Rgb::new(0x1e, 0x1e, 0x2e);
+const FOREGROUND: Rgb = Rgb::new(0xcd, 0xd6, 0xf4);
+
+/// Cells of blank space around the card
+const MARGIN: usize = 2;
+
+#[cfg(feature = "image")]
+/// Pixels per font pixel; glyph rows are doubled on top of this so cells
+/// have the 1:2 shape of terminal cells
+const SCALE: u32 = 2;
+#[cfg(feature = "image")]
+const CELL_WIDTH: u32 = 8 * SCALE;
+#[cfg(feature = "image")]
+const CELL_HEIGHT: u32 = 16 * SCALE;
+
+/// A character and its colors
+#[derive(Clone, Copy)]
+struct Cell {
+    c: char,
+    fg: Rgb,
+    bg: Option<Rgb>,
+}
+
+/// Save `lines`, which may contain SGR color escapes, as a picture; the
+/// format follows the file extension
+pub fn save(lines: &[String], path: &Path) -> Result<()> {
+    let grid: Vec<Vec<Cell>> = lines.iter().map(|line| parse_line(line)).collect();
+    let extension = path.extension().and_then(|extension| extension.to_str()).map(str::to_lowercase);
+
+    match extension.as_deref() {
+        #[cfg(feature = "image")]
+        Some("png") => render_png(&grid)
+            .save(path)
+            .with_context(|| format!("Failed to write {}", path.display())),
+        #[cfg(not(feature = "image"))]
+        Some("png") => bail!("gpufetch was built without the \"image\" feature, which PNG screenshots need; save as .svg"),
+        Some("svg") => fs::write(path, render_svg(&grid)).with_context(|| format!("Failed to write {}", path.display())),
+        _ => bail!("Screenshots are saved as .png or .svg, not {}", path.display()),
+    }
+}
+
+/// Interpret the color escapes of a line into cells
+fn parse_line(line: &str) -> Vec<Cell> {
+    // The font has no glyph for the trademark sign
+    let line = line.replace('™', "(TM)");
+
+    let mut cells = Vec::new();
+    let mut fg = FOREGROUND;
+    let mut bg = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            cells.push(Cell { c, fg, bg });
+            continue;
+        }
+        if chars.next() != Some('[') {
+            continue;
+        }
+        let sequence: String = chars.by_ref().take_while(|&c| c != 'm').collect();
+        apply_sgr(&sequence, &mut fg, &mut bg);
+    }
+    cells
+}
+
+/// Apply the parameters of one `ESC [ ... m` sequence
+fn apply_sgr(sequence: &str, fg: &mut Rgb, bg: &mut Option<Rgb>) {
+    let params: Vec<u8> = sequence.split(';').map(|param| param.parse().unwrap_or(0)).collect();
+    let mut i = 0;
+    while i < params.len() {
+        match params[i] {
+            0 => {
+                *fg = FOREGROUND;
+                *bg = None;
+            }
+            code @ 30..=37 => *fg = ansi_color(code - 30),
+            code @ 90..=97 => *fg = ansi_color(code - 90 + 8),
+            code @ 40..=47 => *bg = Some(ansi_color(code - 40)),
+            code @ 100..=107 => *bg = Some(ansi_color(code - 100 + 8)),
+            39 => *fg = FOREGROUND,
+            49 => *bg = None,
+            code @ (38 | 48) => {
+                let color = match params.get(i + 1) {
+                    Some(2) if i + 4 < params.len() => {
+                        let rgb = Rgb::new(params[i + 2], params[i + 3], params[i + 4]);
+                        i += 4;
+                        Some(rgb)
+                    }
+                    Some(5) if i + 2 < params.len() => {
+                        let rgb = xterm_color(params[i + 2]);
+                        i += 2;
+                        Some(rgb)
+                    }
+                    _ => None,
+                };
+                if let Some(color) = color {
+                    if code == 38 {
+                        *fg = color;
+                    } else {
+                        *bg = Some(color);
+                    }
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+/// The 16 standard colors, as xterm shows them
+fn ansi_color(index: u8) -> Rgb {
+    const PALETTE: [Rgb; 16] = [
+        Rgb::new(0x00, 0x00, 0x00),
+        Rgb::new(0xcd, 0x00, 0x00),
+        Rgb::new(0x00, 0xcd, 0x00),
+        Rgb::new(0xcd, 0xcd, 0x00),
+        Rgb::new(0x00, 0x00, 0xee),
+        Rgb::new(0xcd, 0x00, 0xcd),
+        Rgb::new(0x00, 0xcd, 0xcd),
+        Rgb::new(0xe5, 0xe5, 0xe5),
+        Rgb::new(0x7f, 0x7f, 0x7f),
+        Rgb::new(0xff, 0x00, 0x00),
+        Rgb::new(0x00, 0xff, 0x00),
+        Rgb::new(0xff, 0xff, 0x00),
+        Rgb::new(0x5c, 0x5c, 0xff),
+        Rgb::new(0xff, 0x00, 0xff),
+        Rgb::new(0x00, 0xff, 0xff),
+        Rgb::new(0xff, 0xff, 0xff),
+    ];
+    PALETTE[usize::from(index) % PALETTE.len()]
+}
+
+/// A color of the xterm 256-color palette
+fn xterm_color(index: u8) -> Rgb {
+    match index {
+        0..=15 => ansi_color(index),
+        16..=231 => {
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            let cube = index - 16;
+            Rgb::new(level(cube / 36), level(cube / 6 % 6), level(cube % 6))
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            Rgb::new(gray, gray, gray)
+        }
+    }
+}
+
+#[cfg(feature = "image")]
+/// Rows of an 8x8 glyph, lowest bit leftmost
+fn glyph(c: char) -> Option<[u8; 8]> {
+    let code = c as usize;
+    match code {
+        0x20..=0x7f => Some(BASIC_LEGACY[code]),
+        0xa0..=0xff => Some(LATIN_LEGACY[code - 0xa0]),
+        0x2500..=0x257f => Some(BOX_LEGACY[code - 0x2500]),
+        0x2580..=0x259f => Some(BLOCK_LEGACY[code - 0x2580]),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "image")]
+fn render_png(grid: &[Vec<Cell>]) -> RgbaImage {
+    let columns = grid.iter().map(Vec::len).max().unwrap_or(0) + 2 * MARGIN;
+    let rows = grid.len() + 2 * MARGIN;
+    let background = Rgba([BACKGROUND.r, BACKGROUND.g, BACKGROUND.b, 255]);
+    let mut image = RgbaImage::from_pixel(columns as u32 * CELL_WIDTH, rows as u32 * CELL_HEIGHT, background);
+
+    for (row, line) in grid.iter().enumerate() {
+        for (column, cell) in line.iter().enumerate() {
+            let x0 = (column + MARGIN) as u32 * CELL_WIDTH;
+            let y0 = (row + MARGIN) as u32 * CELL_HEIGHT;
+            let fg = Rgba([cell.fg.r, cell.fg.g, cell.fg.b, 255]);
+
+            if let Some(bg) = cell.bg {
+                fill(&mut image, x0, y0, CELL_WIDTH, CELL_HEIGHT, Rgba([bg.r, bg.g, bg.b, 255]));
+            }
+
+            if let '\u{2800}'..='\u{28ff}' = cell.c {
+                // Square dots on a 2x4 grid, with a gap between them
+                let dots = braille_dots(cell.c);
+                let (dot_width, dot_height) = (CELL_WIDTH / 2, CELL_HEIGHT / 4);
+                for (bit, (dx, dy)) in BRAILLE_DOTS.iter().enumerate() {
+                    if dots & (1 << bit) != 0 {
+                        let x = x0 + dx * dot_width + SCALE;
+                        let y = y0 + dy * dot_height + SCALE;
+                        fill(&mut image, x, y, dot_width - 2 * SCALE, dot_height - 2 * SCALE, fg);
+                    }
+                }
+            } else if let Some(rows) = glyph(cell.c) {
+                for (gy, bits) in rows.iter().enumerate() {
+                    for gx in 0..8 {
+                        if bits & (1 << gx) != 0 {
+                            let x = x0 + gx * SCALE;
+                            let y = y0 + gy as u32 * 2 * SCALE;
+                            fill(&mut image, x, y, SCALE, 2 * SCALE, fg);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    image
+}
+
+#[cfg(feature = "image")]
+fn fill(image: &mut RgbaImage, x0: u32, y0: u32, width: u32, height: u32, color: Rgba<u8>) {
+    for y in y0..y0 + height {
+        for x in x0..x0 + width {
+            image.put_pixel(x, y, color);
+        }
+    }
+}
+
+fn render_svg(grid: &[Vec<Cell>]) -> String {
+    // Cells of a typical monospace font at 16px: 0.6em wide, 1.2em tall
+    const WIDTH: f64 = 9.6;
+    const HEIGHT: f64 = 19.2;
+
+    let columns = grid.iter().map(Vec::len).max().unwrap_or(0) + 2 * MARGIN;
+    let rows = grid.len() + 2 * MARGIN;
+    let (width, height) = (columns as f64 * WIDTH, rows as f64 * HEIGHT);
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}" viewBox="0 0 {:.1} {:.1}">"#,
+        width.ceil(),
+        height.ceil(),
+        width,
+        height
+    );
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="{}"/>"#, hex(BACKGROUND));
+    let _ = writeln!(
+        svg,
+        r#"<g font-family="DejaVu Sans Mono, Menlo, Consolas, monospace" font-size="16" xml:space="preserve">"#
+    );
+
+    for (row, line) in grid.iter().enumerate() {
+        let y = (row + MARGIN) as f64 * HEIGHT;
+
+        for (column, cell) in line.iter().enumerate() {
+            if let Some(bg) = cell.bg {
+                let x = (column + MARGIN) as f64 * WIDTH;
+                let _ = writeln!(svg, r#"<rect x="{:.1}" y="{:.1}" width="{}" height="{}" fill="{}"/>"#, x, y, WIDTH, HEIGHT, hex(bg));
+            }
+        }
+
+        // One text element per line, split into runs of the same color;
+        // textLength keeps glyphs on the cell grid whatever font is used
+        let _ = write!(
+            svg,
+            r#"<text x="{:.1}" y="{:.1}" textLength="{:.1}">"#,
+            MARGIN as f64 * WIDTH,
+            y + HEIGHT * 0.8,
+            line.len() as f64 * WIDTH
+        );
+        let mut cells = line.iter().peekable();
+        while let Some(first) = cells.next() {
+            let mut text = escape_xml(first.c);
+            while let Some(next) = cells.next_if(|next| next.fg == first.fg) {
+                text.push_str(&escape_xml(next.c));
+            }
+            let _ = write!(svg, r#"<tspan fill="{}">{}</tspan>"#, hex(first.fg), text);
+        }
+        let _ = writeln!(svg, "</text>");
+    }
+
+    svg.push_str("</g>\n</svg>\n");
+    svg
+}
+
+fn hex(color: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+fn escape_xml(c: char) -> String {
+    match c {
+        '&' => "&amp;".to_string(),
+        '<' => "&lt;".to_string(),
+        '>' => "&gt;".to_string(),
+        // Blank braille cells would otherwise show as boxes in some fonts
+        '\u{2800}' => " ".to_string(),
+        c => c.to_string(),
+    }
+}
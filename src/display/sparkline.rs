@@ -0,0 +1,56 @@
+use crate::gpu::common::{GpuInfo, Topology};
+
+/// Braille dot bits in fill order: down the left column, then the right
+const BRAILLE_DOTS: [u32; 8] = [0x01, 0x02, 0x04, 0x40, 0x08, 0x10, 0x20, 0x80];
+
+/// Render the GPU's cluster layout as a compact braille line
+///
+/// Each group of glyphs is one cluster (GPC, Shader Engine or slice) and each
+/// dot is one unit inside it (SM, CU or EU). Units are spread evenly across
+/// clusters since the exact per-cluster harvesting is not exposed.
+pub fn topology_sparkline(gpu: &GpuInfo) -> Option<String> {
+    let (clusters, units, cluster_name, unit_name) = cluster_layout(gpu)?;
+    if clusters == 0 || units == 0 {
+        return None;
+    }
+
+    let groups: Vec<String> = (0..clusters)
+        .map(|idx| {
+            // Hand the remainder to the first clusters
+            let count = units / clusters + u32::from(idx < units % clusters);
+            braille_cells(count)
+        })
+        .collect();
+
+    Some(format!(
+        "{} ({} {} / {} {})",
+        groups.join(" "),
+        clusters,
+        cluster_name,
+        units,
+        unit_name
+    ))
+}
+
+/// Number of clusters and units, with their vendor-specific names
+fn cluster_layout(gpu: &GpuInfo) -> Option<(u32, u32, &'static str, &'static str)> {
+    match gpu.topology.as_ref()? {
+        Topology::Nvidia(nvidia) => Some((nvidia.gpc_count?, nvidia.sm_count, "GPCs", "SMs")),
+        Topology::Amd(amd) => Some((amd.shader_engines?, amd.compute_units, "SEs", "CUs")),
+        Topology::Intel(intel) => Some((intel.slices?, intel.execution_units, "slices", "EUs")),
+        Topology::Generic { .. } => None,
+    }
+}
+
+/// Draw `count` filled dots, eight per braille glyph
+fn braille_cells(count: u32) -> String {
+    let full = count / 8;
+    let rest = count % 8;
+
+    let mut cells = "⣿".repeat(full as usize);
+    if rest > 0 {
+        let bits = BRAILLE_DOTS[..rest as usize].iter().sum::<u32>();
+        cells.extend(char::from_u32(0x2800 + bits));
+    }
+    cells
+}
@@ -0,0 +1,64 @@
+//! Totals across all GPUs, for `--summary`.
+
+use colored::Colorize;
+
+use super::fields::format_flops;
+use super::DisplayOptions;
+use crate::gpu::common::GpuInfo;
+
+/// Print the GPUs by name, their combined dedicated memory and peak
+/// performance, and how they are connected
+///
+/// Integrated GPUs borrow system memory, so their memory is left out of the
+/// total.
+pub fn print_summary(gpus: &[GpuInfo], options: &DisplayOptions) {
+    let mut models: Vec<(&str, usize)> = Vec::new();
+    for gpu in gpus {
+        match models.iter_mut().find(|(name, _)| *name == gpu.name) {
+            Some((_, count)) => *count += 1,
+            None => models.push((&gpu.name, 1)),
+        }
+    }
+    let models: Vec<String> = models
+        .into_iter()
+        .map(|(name, count)| if count > 1 { format!("{}x {}", count, name) } else { name.to_string() })
+        .collect();
+    let heading = format!("{} GPU{}: {}", gpus.len(), if gpus.len() == 1 { "" } else { "s" }, models.join(", "));
+    println!("{}", options.charset.render(&heading).bold());
+
+    let vram: u64 = gpus
+        .iter()
+        .filter(|gpu| !gpu.is_integrated)
+        .filter_map(|gpu| gpu.memory.as_ref())
+        .map(|memory| memory.size_bytes)
+        .sum();
+    if vram > 0 {
+        println!("Total VRAM: {}", options.units.memory(vram));
+    }
+
+    let gflops: f64 = gpus.iter().filter_map(|gpu| gpu.peak_performance_gflops).sum();
+    if gflops > 0.0 {
+        println!("Total Peak Performance: {}", format_flops(gflops));
+    }
+
+    if let Some(interconnect) = interconnect(gpus) {
+        println!("Interconnect: {}", interconnect);
+    }
+}
+
+/// NVLink when any GPU has active links, with their combined count and
+/// speed, otherwise PCIe for PCI GPUs
+fn interconnect(gpus: &[GpuInfo]) -> Option<String> {
+    let links: Vec<_> = gpus
+        .iter()
+        .filter_map(|gpu| gpu.nvlink.as_ref())
+        .filter(|nvlink| nvlink.active_links > 0)
+        .collect();
+    if !links.is_empty() {
+        let active_links: u32 = links.iter().map(|nvlink| nvlink.active_links).sum();
+        let bandwidth_gbs: f64 = links.iter().map(|nvlink| nvlink.bandwidth_gbs).sum();
+        return Some(format!("NVLink ({} links, {:.1} GB/s)", active_links, bandwidth_gbs));
+    }
+
+    gpus.iter().any(|gpu| gpu.pci_info.is_some()).then(|| "PCIe".to_string())
+}
@@ -0,0 +1,130 @@
+//! What the terminal on the other end can render.
+//!
+//! Serial consoles and old SSH clients often run without a UTF-8 locale or
+//! with a monochrome `TERM`. Both are read from the environment once:
+//!
+//! - The charset comes from the first non-empty of `LC_ALL`, `LC_CTYPE` and
+//!   `LANG`; only UTF-8 locales get braille logos and box drawing. The Linux
+//!   console and VT-series terminals lack those glyphs in any locale.
+//! - The color depth comes from `COLORTERM` and `TERM`.
+//! - Inline image support comes from the variables kitty, iTerm2, WezTerm and
+//!   Sixel terminals set. Inside tmux or screen nothing is assumed, since they
+//!   do not pass the images through.
+
+use std::borrow::Cow;
+use std::env;
+
+use super::graphics::GraphicsProtocol;
+
+/// Characters the terminal can display
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Charset {
+    Utf8,
+    /// Plain 7-bit ASCII
+    Ascii,
+}
+
+/// Colors the terminal can display
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorDepth {
+    Monochrome,
+    /// The 8/16 standard ANSI colors
+    Basic,
+    Ansi256,
+    TrueColor,
+}
+
+/// Terminals known to show nothing but text
+const MONOCHROME_TERMS: [&str; 5] = ["dumb", "vt52", "vt100", "vt102", "vt220"];
+
+/// Terminals whose fonts lack braille and box-drawing glyphs
+const ASCII_ONLY_TERMS: [&str; 6] = ["dumb", "linux", "vt52", "vt100", "vt102", "vt220"];
+
+/// `TERM` prefixes of terminals that draw Sixel graphics
+const SIXEL_TERMS: [&str; 4] = ["foot", "mlterm", "yaft", "contour"];
+
+/// Resolved capabilities of the output terminal
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TerminalCaps {
+    pub charset: Charset,
+    pub color_depth: ColorDepth,
+    /// Image protocol the terminal is known to support
+    pub graphics: Option<GraphicsProtocol>,
+}
+
+impl TerminalCaps {
+    /// Capabilities described by the current environment
+    pub fn detect() -> Self {
+        Self::resolve(|key| env::var(key).ok().filter(|value| !value.is_empty()))
+    }
+
+    fn resolve(var: impl Fn(&str) -> Option<String>) -> Self {
+        let term = var("TERM").unwrap_or_default();
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"].iter().find_map(|key| var(key)).unwrap_or_default();
+
+        let utf8_locale = {
+            let locale = locale.to_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        };
+        let charset = if utf8_locale && !ASCII_ONLY_TERMS.contains(&term.as_str()) {
+            Charset::Utf8
+        } else {
+            Charset::Ascii
+        };
+
+        let color_depth = match var("COLORTERM").as_deref() {
+            Some("truecolor") | Some("24bit") => ColorDepth::TrueColor,
+            _ if MONOCHROME_TERMS.contains(&term.as_str()) => ColorDepth::Monochrome,
+            _ if term.contains("256color") => ColorDepth::Ansi256,
+            _ => ColorDepth::Basic,
+        };
+
+        let term_program = var("TERM_PROGRAM").unwrap_or_default();
+        let graphics = if var("TMUX").is_some() || term.starts_with("screen") || term.starts_with("tmux") {
+            None
+        } else if var("KITTY_WINDOW_ID").is_some() || term == "xterm-kitty" || term == "xterm-ghostty" {
+            Some(GraphicsProtocol::Kitty)
+        } else if term_program == "iTerm.app" || term_program == "WezTerm" || var("LC_TERMINAL").as_deref() == Some("iTerm2") {
+            Some(GraphicsProtocol::Iterm2)
+        } else if term.contains("sixel") || SIXEL_TERMS.iter().any(|prefix| term.starts_with(prefix)) {
+            Some(GraphicsProtocol::Sixel)
+        } else {
+            None
+        };
+
+        TerminalCaps {
+            charset,
+            color_depth,
+            graphics,
+        }
+    }
+}
+
+impl Charset {
+    /// Rewrite `text` so this charset can display it
+    pub fn render<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if *self == Charset::Utf8 || text.is_ascii() {
+            return Cow::Borrowed(text);
+        }
+
+        let mut out = String::with_capacity(text.len());
+        for c in text.chars() {
+            match c {
+                c if c.is_ascii() => out.push(c),
+                // Blank braille cells pad the logos
+                '\u{2800}' => out.push(' '),
+                '\u{2801}'..='\u{28ff}' => out.push('#'),
+                '─' | '━' => out.push('-'),
+                '│' | '┃' => out.push('|'),
+                '╭' | '╮' | '╰' | '╯' | '┌' | '┐' | '└' | '┘' | '├' => out.push('+'),
+                '™' => out.push_str("(TM)"),
+                '®' => out.push_str("(R)"),
+                '°' => out.push_str(" deg"),
+                '²' => out.push_str("^2"),
+                '…' => out.push('~'),
+                _ => out.push('?'),
+            }
+        }
+        Cow::Owned(out)
+    }
+}
@@ -0,0 +1,229 @@
+//! Colors of the fetch card.
+//!
+//! Themes are written in 24-bit RGB, with the logo colored by a gradient
+//! running from its first line to its last. Terminals showing truecolor get
+//! exactly that; the rest get the named ANSI color each theme falls back to,
+//! which is what gpufetch has always printed there.
+
+use anyhow::{bail, Result};
+use colored::{Color, Colorize};
+
+use super::terminal::ColorDepth;
+use crate::gpu::common::GpuVendor;
+
+/// A 24-bit color
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Rgb { r, g, b }
+    }
+
+    /// Blend towards `other`; `t` runs from 0 (self) to 1 (other)
+    fn mix(self, other: Rgb, t: f64) -> Rgb {
+        let channel = |from: u8, to: u8| (f64::from(from) + (f64::from(to) - f64::from(from)) * t).round() as u8;
+        Rgb::new(channel(self.r, other.r), channel(self.g, other.g), channel(self.b, other.b))
+    }
+}
+
+impl From<Rgb> for Color {
+    fn from(rgb: Rgb) -> Self {
+        Color::TrueColor { r: rgb.r, g: rgb.g, b: rgb.b }
+    }
+}
+
+/// Colors spaced evenly from the top of the logo to the bottom
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Gradient {
+    stops: Vec<Rgb>,
+}
+
+impl Gradient {
+    /// A gradient through `stops`; with one stop it is a solid color
+    pub fn new(stops: Vec<Rgb>) -> Self {
+        assert!(!stops.is_empty(), "a gradient needs at least one color");
+        Gradient { stops }
+    }
+
+    /// Color at position `t` between 0 (first stop) and 1 (last stop)
+    pub fn at(&self, t: f64) -> Rgb {
+        let last = self.stops.len() - 1;
+        let position = t.clamp(0.0, 1.0) * last as f64;
+        let index = (position.floor() as usize).min(last.saturating_sub(1));
+        match self.stops.get(index + 1) {
+            Some(&next) => self.stops[index].mix(next, position - index as f64),
+            None => self.stops[index],
+        }
+    }
+}
+
+/// Logo and text colors for the fetch card
+#[derive(Clone, Debug)]
+pub struct Theme {
+    logo: Gradient,
+    /// Info labels such as "Memory:"
+    key: Rgb,
+    /// Info values and the GPU name
+    value: Rgb,
+    /// Named (logo, key, value) colors for terminals without truecolor;
+    /// without them the RGB colors are always used
+    fallback: Option<(Color, Color, Color)>,
+    truecolor: bool,
+    /// Whether 24-bit colors reach the output as they are, on a truecolor
+    /// terminal or in a picture
+    full_color: bool,
+}
+
+impl Theme {
+    /// The vendor's brand colors, light at the top of the logo and deep at the bottom
+    pub fn vendor(vendor: &GpuVendor) -> Self {
+        let white = Rgb::new(229, 229, 229);
+        let (stops, fallback) = match vendor {
+            GpuVendor::Nvidia => (
+                vec![Rgb::new(140, 210, 20), Rgb::new(118, 185, 0), Rgb::new(62, 110, 0)],
+                Color::Green,
+            ),
+            GpuVendor::Amd => (
+                vec![Rgb::new(255, 70, 60), Rgb::new(237, 28, 36), Rgb::new(150, 12, 20)],
+                Color::Red,
+            ),
+            GpuVendor::Intel => (
+                vec![Rgb::new(0, 199, 253), Rgb::new(0, 113, 197), Rgb::new(0, 70, 140)],
+                Color::Cyan,
+            ),
+            _ => (vec![white, Rgb::new(150, 150, 150)], Color::White),
+        };
+
+        Theme {
+            logo: Gradient::new(stops),
+            key: white,
+            value: white,
+            fallback: Some((fallback, Color::White, Color::White)),
+            truecolor: false,
+            full_color: false,
+        }
+    }
+
+    /// Parse `--custom-colors`: "r,g,b:r,g,b:r,g,b:r,g,b" for the logo's
+    /// primary and secondary colors, then the info keys and values
+    ///
+    /// The logo fades from the primary to the secondary color. Custom colors
+    /// have no named fallback; on terminals without truecolor `colored`
+    /// picks the closest ANSI color.
+    pub fn parse_custom(spec: &str) -> Result<Self> {
+        let colors = spec
+            .split(':')
+            .map(parse_rgb)
+            .collect::<Result<Vec<Rgb>>>()?;
+        let [logo_primary, logo_secondary, key, value] = colors[..] else {
+            bail!(
+                "expected 4 colors separated by ':' (logo primary, logo secondary, text primary, text secondary), got {}",
+                colors.len()
+            );
+        };
+
+        Ok(Theme {
+            logo: Gradient::new(vec![logo_primary, logo_secondary]),
+            key,
+            value,
+            fallback: None,
+            truecolor: true,
+            full_color: false,
+        })
+    }
+
+    /// Use the RGB colors only where the terminal can show them
+    pub fn for_depth(mut self, depth: ColorDepth) -> Self {
+        self.truecolor = self.fallback.is_none() || depth == ColorDepth::TrueColor;
+        self.full_color = depth == ColorDepth::TrueColor;
+        self
+    }
+
+    /// 24-bit color of logo line `line` out of `lines`, regardless of the terminal
+    pub fn logo_rgb(&self, line: usize, lines: usize) -> Rgb {
+        let t = if lines > 1 { line as f64 / (lines - 1) as f64 } else { 0.0 };
+        self.logo.at(t)
+    }
+
+    /// Color to print logo line `line` out of `lines` in
+    pub fn logo_color(&self, line: usize, lines: usize) -> Color {
+        match self.fallback {
+            Some((logo, _, _)) if !self.truecolor => logo,
+            _ => self.logo_rgb(line, lines).into(),
+        }
+    }
+
+    /// Color of borders and other accents, the top of the logo gradient
+    pub fn accent_color(&self) -> Color {
+        self.logo_color(0, 1)
+    }
+
+    /// Color of info labels
+    pub fn key_color(&self) -> Color {
+        match self.fallback {
+            Some((_, key, _)) if !self.truecolor => key,
+            _ => self.key.into(),
+        }
+    }
+
+    /// Color of info values and the GPU name
+    pub fn value_color(&self) -> Color {
+        match self.fallback {
+            Some((_, _, value)) if !self.truecolor => value,
+            _ => self.value.into(),
+        }
+    }
+
+    /// Color an info line, its label up to the first ": " in the key color
+    /// and the rest in the value color
+    pub fn paint_info_line(&self, line: &str) -> String {
+        match line.split_once(": ") {
+            Some((key, value)) => format!(
+                "{} {}",
+                self.paint(&format!("{}:", key), self.key_color()),
+                self.paint(value, self.value_color())
+            ),
+            None => self.paint(line, self.value_color()),
+        }
+    }
+
+    /// `text` in `color`
+    pub fn paint(&self, text: &str, color: Color) -> String {
+        self.paint_styled(text, color, false)
+    }
+
+    /// `text` in bold and `color`
+    pub fn paint_bold(&self, text: &str, color: Color) -> String {
+        self.paint_styled(text, color, true)
+    }
+
+    /// `colored` keeps 24-bit colors only when `COLORTERM` asks for them,
+    /// which a screenshot can't rely on, so full-color themes write those
+    /// escapes themselves
+    fn paint_styled(&self, text: &str, color: Color, bold: bool) -> String {
+        match color {
+            Color::TrueColor { r, g, b } if self.full_color && colored::control::SHOULD_COLORIZE.should_colorize() => {
+                format!("\x1b[{}38;2;{};{};{}m{}\x1b[0m", if bold { "1;" } else { "" }, r, g, b, text)
+            }
+            _ if bold => text.color(color).bold().to_string(),
+            _ => text.color(color).to_string(),
+        }
+    }
+}
+
+/// One "r,g,b" color with components from 0 to 255
+fn parse_rgb(text: &str) -> Result<Rgb> {
+    let components = text
+        .split(',')
+        .map(|component| component.trim().parse::<u8>())
+        .collect::<Result<Vec<u8>, _>>();
+    match components.as_deref() {
+        Ok(&[r, g, b]) => Ok(Rgb::new(r, g, b)),
+        _ => bail!("invalid color \"{}\": expected r,g,b with each value from 0 to 255", text),
+    }
+}
@@ -0,0 +1,156 @@
+//! Failures scripts can tell apart.
+//!
+//! Each kind has its own exit status, and with `--error-format json` the
+//! failure is printed as a JSON object together with the reasons backends
+//! gave for finding nothing.
+
+use anyhow::Error;
+use serde::Serialize;
+use std::io;
+use std::process::ExitCode;
+use thiserror::Error;
+
+use crate::args::ErrorFormat;
+use crate::gpu::backend::Backend;
+
+/// Exit statuses; other errors exit with 1, and command-line mistakes with
+/// 2 like invalid arguments
+pub const EXIT_INVALID_ARGUMENT: u8 = 2;
+pub const EXIT_NO_GPUS: u8 = 3;
+pub const EXIT_DETECTION_FAILED: u8 = 4;
+pub const EXIT_REQUIREMENT_UNMET: u8 = 5;
+pub const EXIT_PERMISSION_DENIED: u8 = 6;
+pub const EXIT_PARSE_FAILURE: u8 = 7;
+pub const EXIT_NOT_FOUND: u8 = 8;
+
+#[derive(Debug, Clone, Error, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GpufetchError {
+    /// A detection source is missing or not working on this system
+    #[error("{backend} backend unavailable: {reason}")]
+    BackendUnavailable { backend: Backend, reason: String },
+
+    /// gpufetch may not read something it needs
+    #[error("permission denied: {resource}")]
+    PermissionDenied { resource: String },
+
+    /// A tool or file produced output gpufetch does not understand
+    #[error("failed to parse {input}: {reason}")]
+    ParseFailure { input: String, reason: String },
+
+    /// A GPU or resource asked for by index or path does not exist
+    #[error("not found: {resource}")]
+    NotFound { resource: String },
+
+    /// A well-formed request asks for something gpufetch does not support
+    #[error("invalid {argument}: {reason}")]
+    InvalidArgument { argument: String, reason: String },
+
+    /// Nothing was detected, or nothing is left after `--vendor` and `--only`
+    #[error("{}", if *filtered { "No GPUs match the --vendor and --only filters" } else { "No GPUs detected on the system" })]
+    NoGpus { filtered: bool },
+
+    /// No detected GPU meets every `--require` condition
+    #[error("Requirement not met: no detected GPU satisfies {}", requirements.join(", "))]
+    RequirementUnmet { requirements: Vec<String> },
+}
+
+impl GpufetchError {
+    pub fn unavailable(backend: Backend, reason: impl Into<String>) -> Self {
+        GpufetchError::BackendUnavailable { backend, reason: reason.into() }
+    }
+
+    pub fn parse_failure(input: impl Into<String>, reason: impl Into<String>) -> Self {
+        GpufetchError::ParseFailure { input: input.into(), reason: reason.into() }
+    }
+
+    pub fn not_found(resource: impl Into<String>) -> Self {
+        GpufetchError::NotFound { resource: resource.into() }
+    }
+
+    pub fn invalid_argument(argument: impl Into<String>, reason: impl Into<String>) -> Self {
+        GpufetchError::InvalidArgument { argument: argument.into(), reason: reason.into() }
+    }
+
+    /// Sort a backend's error into a kind
+    ///
+    /// Errors raised as a `GpufetchError` keep their kind; denied file access
+    /// anywhere in the chain counts as a permission problem, and anything
+    /// else as the backend being unavailable.
+    pub fn classify(backend: Backend, error: &Error) -> Self {
+        if let Some(known) = error.downcast_ref::<GpufetchError>() {
+            return known.clone();
+        }
+
+        let denied = error
+            .chain()
+            .filter_map(|cause| cause.downcast_ref::<io::Error>())
+            .any(|cause| cause.kind() == io::ErrorKind::PermissionDenied);
+        if denied {
+            GpufetchError::PermissionDenied { resource: error.to_string() }
+        } else {
+            GpufetchError::unavailable(backend, format!("{:#}", error))
+        }
+    }
+
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            GpufetchError::BackendUnavailable { .. } => EXIT_DETECTION_FAILED,
+            GpufetchError::PermissionDenied { .. } => EXIT_PERMISSION_DENIED,
+            GpufetchError::ParseFailure { .. } => EXIT_PARSE_FAILURE,
+            GpufetchError::NotFound { .. } => EXIT_NOT_FOUND,
+            GpufetchError::InvalidArgument { .. } => EXIT_INVALID_ARGUMENT,
+            GpufetchError::NoGpus { .. } => EXIT_NO_GPUS,
+            GpufetchError::RequirementUnmet { .. } => EXIT_REQUIREMENT_UNMET,
+        }
+    }
+
+    /// Print the failure and return the exit status for it
+    ///
+    /// `diagnostics` are the errors of backends that found nothing, which
+    /// explain an empty result. Only the JSON report includes them, since
+    /// `-v` already logs them.
+    pub fn report(&self, diagnostics: &[GpufetchError], format: ErrorFormat) -> ExitCode {
+        match format {
+            ErrorFormat::Human => match self {
+                GpufetchError::NoGpus { .. } => println!("{}", self),
+                GpufetchError::RequirementUnmet { .. } => eprintln!("{}", self),
+                _ => eprintln!("Error: Failed to detect GPUs: {}", self),
+            },
+            ErrorFormat::Json => {
+                let report = Report {
+                    error: Entry::new(self),
+                    exit_code: self.exit_code(),
+                    diagnostics: diagnostics.iter().map(Entry::new).collect(),
+                };
+                match serde_json::to_string(&report) {
+                    Ok(json) => eprintln!("{}", json),
+                    Err(e) => eprintln!("Error: {}", e),
+                }
+            }
+        }
+        ExitCode::from(self.exit_code())
+    }
+}
+
+/// `--error-format json` output
+#[derive(Serialize)]
+struct Report<'a> {
+    error: Entry<'a>,
+    exit_code: u8,
+    diagnostics: Vec<Entry<'a>>,
+}
+
+/// An error's kind and fields, with its message
+#[derive(Serialize)]
+pub struct Entry<'a> {
+    message: String,
+    #[serde(flatten)]
+    error: &'a GpufetchError,
+}
+
+impl<'a> Entry<'a> {
+    pub fn new(error: &'a GpufetchError) -> Self {
+        Entry { message: error.to_string(), error }
+    }
+}
@@ -1,24 +1,51 @@
 use anyhow::{anyhow, Context, Result};
-use regex::Regex;
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 
-use crate::gpu::common::{Cache, GpuInfo, GpuVendor, Memory, MemoryType, Topology};
+use crate::gpu::common::{
+    pcie_generation, EccStatus, FanSpeed, GpuInfo, GpuVendor, Memory, MemoryType, PcieLink, PerformanceState, Process,
+};
+use crate::gpu::{icd, parse, pci, perf, snapshot, specs, sysfs_layout, sysroot, tools};
+use crate::system;
+
+/// Where AMDGPU-PRO installs its userspace drivers
+const AMDGPU_PRO_DIR: &str = "/opt/amdgpu-pro";
+
+/// Vulkan manifest name prefix of AMDGPU-PRO, e.g. "amd_pro_icd64.json"
+const AMDGPU_PRO_ICD: &str = "amd_pro_icd";
+
+/// Bus width assumed for models missing from the spec database
+const DEFAULT_BUS_WIDTH: u32 = 256;
+
+/// PCI vendor ID of AMD and ATI
+const PCI_VENDOR_ID_AMD: u16 = 0x1002;
+
+/// APUs of handheld PCs, which have no product name in sysfs: GPU device
+/// ID, the CPU model that tells them apart from the laptop chips sharing
+/// the ID ("" for IDs only the handheld uses), and the name to show
+const HANDHELD_APUS: [(&str, &str, &str); 5] = [
+    ("163f", "", "Steam Deck GPU"),
+    ("1435", "", "Steam Deck OLED GPU"),
+    ("15bf", "Ryzen Z1 Extreme", "Radeon Graphics (Ryzen Z1 Extreme)"),
+    ("15c8", "Ryzen Z1", "Radeon Graphics (Ryzen Z1)"),
+    ("150e", "Ryzen Z2 Extreme", "Radeon Graphics (Ryzen Z2 Extreme)"),
+];
 
 /// Detect AMD GPUs
 pub fn detect_amd_gpus() -> Result<Vec<GpuInfo>> {
     let mut gpus = Vec::new();
+    // Workstation cards often run the proprietary stack
+    let pro_driver = detect_amd_pro_driver();
     
     // Check for AMD GPUs in the system
     if let Ok(amd_gpu_paths) = find_amd_gpus_in_sysfs() {
         for path in amd_gpu_paths {
             if let Ok(mut gpu_info) = get_amd_gpu_info_from_sysfs(&path) {
-                // Try to enhance info using rocm-smi if available
-                if is_rocm_smi_available() {
-                    if let Ok(()) = enhance_with_rocm_smi(&mut gpu_info) {
-                        // Additional info added from rocm-smi
-                    }
+                // Only cards bound to amdgpu run it, not those on radeon or vfio-pci
+                let amdgpu = pci::bound_driver(&path).as_deref() == Some("amdgpu");
+                if let Some(pro_driver) = pro_driver.as_ref().filter(|_| amdgpu) {
+                    gpu_info.driver_version = Some(pro_driver.clone());
                 }
                 
                 gpus.push(gpu_info);
@@ -34,9 +61,9 @@ fn find_amd_gpus_in_sysfs() -> Result<Vec<PathBuf>> {
     let mut gpu_paths = Vec::new();
     
     // Try AMD card directory in /sys/class/drm
-    let drm_path = Path::new("/sys/class/drm");
+    let drm_path = sysroot::path("/sys/class/drm");
     if drm_path.exists() {
-        for entry in fs::read_dir(drm_path).context("Failed to read DRM directory")? {
+        for entry in fs::read_dir(&drm_path).context("Failed to read DRM directory")? {
             let entry = entry.context("Failed to read directory entry")?;
             let path = entry.path();
             
@@ -71,141 +98,214 @@ fn get_amd_gpu_info_from_sysfs(device_path: &Path) -> Result<GpuInfo> {
     
     // Read subsystem name (typically contains the full GPU model name)
     let product_name_path = device_path.join("product_name");
-    let name = if product_name_path.exists() {
+    let handheld = handheld_apu(&device_id);
+    let name = if let Some(name) = handheld {
+        name.to_string()
+    } else if product_name_path.exists() {
         fs::read_to_string(product_name_path)
             .map(|name| name.trim().to_string())
             .unwrap_or_else(|_| format!("AMD GPU (Device ID: {})", device_id))
     } else {
-        // Try to get name from modalias
-        let modalias_path = device_path.join("modalias");
-        if modalias_path.exists() {
-            if let Ok(modalias) = fs::read_to_string(modalias_path) {
-                let re = Regex::new(r"pci:v00001002d0000([0-9A-Fa-f]{4})").ok();
-                if let Some(re) = re {
-                    if let Some(caps) = re.captures(&modalias) {
-                        format!("AMD GPU (Device ID: {})", &caps[1])
-                    } else {
-                        format!("AMD GPU (Device ID: {})", device_id)
-                    }
-                } else {
-                    format!("AMD GPU (Device ID: {})", device_id)
-                }
-            } else {
-                format!("AMD GPU (Device ID: {})", device_id)
-            }
-        } else {
-            format!("AMD GPU (Device ID: {})", device_id)
+        // The modalias names the device as the kernel matched it
+        let modalias = fs::read_to_string(device_path.join("modalias")).ok();
+        match modalias.as_deref().and_then(parse::parse_modalias) {
+            Some((PCI_VENDOR_ID_AMD, device)) => format!("AMD GPU (Device ID: {:04X})", device),
+            _ => format!("AMD GPU (Device ID: {})", device_id),
         }
     };
     
     // Create basic GPU info
     let mut gpu_info = GpuInfo::new(&name, GpuVendor::Amd);
+    gpu_info.pci_info = pci::read_pci_info(device_path).ok();
+    gpu_info.is_integrated = handheld.is_some();
+    gpu_info.uuid = read_sysfs_string(&device_path.join("unique_id"));
+    gpu_info.serial = read_sysfs_string(&device_path.join("serial_number"));
     
     // Read frequencies
-    if let Some(pp_dpm_sclk_path) = find_file_in_dir(device_path, "pp_dpm_sclk") {
-        if let Ok(content) = fs::read_to_string(pp_dpm_sclk_path) {
-            // Try to extract max clock from pp_dpm_sclk
-            // The format is typically "3: 1860Mhz *"
-            let re = Regex::new(r"(\d+): (\d+)Mhz").ok();
-            if let Some(re) = re {
-                let mut max_freq = 0;
-                for cap in re.captures_iter(&content) {
-                    if let Ok(freq) = cap[2].parse::<u32>() {
-                        max_freq = std::cmp::max(max_freq, freq);
-                    }
-                }
-                if max_freq > 0 {
-                    gpu_info.max_freq_mhz = max_freq;
-                    
-                    // Also look for the current frequency (marked with *)
-                    let re_current = Regex::new(r"(\d+): (\d+)Mhz \*").ok();
-                    if let Some(re_current) = re_current {
-                        if let Some(cap) = re_current.captures(&content) {
-                            if let Ok(freq) = cap[2].parse::<u32>() {
-                                gpu_info.freq_mhz = freq;
-                            }
-                        }
-                    } else {
-                        gpu_info.freq_mhz = max_freq; // Fall back to max freq
-                    }
-                }
-            }
+    if let Some((current, max)) = read_dpm_clocks(device_path, "pp_dpm_sclk") {
+        gpu_info.max_freq_mhz = max;
+        if let Some(current) = current {
+            gpu_info.freq_mhz = current;
         }
     }
+    gpu_info.fabric_clock_mhz = read_dpm_clocks(device_path, "pp_dpm_fclk").map(|(_, max)| max);
     
     // Determine architecture
-    let (architecture, chip, process_nm) = get_amd_architecture(&name, &device_id);
+    let (architecture, chip, process) = get_amd_architecture(&name, &device_id);
     gpu_info.architecture = architecture;
     gpu_info.chip = chip;
-    gpu_info.process_nm = process_nm;
+    gpu_info.mark_heuristic(&["architecture", "chip"]);
+    if process.is_some() {
+        gpu_info.process = process;
+        gpu_info.mark_heuristic(&["process_nm"]);
+    }
     
     // Try to get memory info
-    if let Some(memory_info_path) = find_file_in_dir(device_path, "mem_info_vram_total") {
+    if let Some(memory_info_path) = sysfs_layout::find(device_path, "mem_info_vram_total") {
         if let Ok(content) = fs::read_to_string(memory_info_path) {
             if let Ok(bytes) = content.trim().parse::<u64>() {
                 let memory = Memory {
                     size_bytes: bytes,
                     memory_type: get_amd_memory_type(&name),
-                    bus_width: get_amd_bus_width(&name),
-                    clock_mhz: 0, // To be populated later
+                    bus_width: DEFAULT_BUS_WIDTH,
+                    clock_mhz: read_dpm_clocks(device_path, "pp_dpm_mclk").map_or(0, |(_, max)| max),
                 };
                 gpu_info.memory = Some(memory);
+                gpu_info.mark_heuristic(&["memory_type", "bus_width"]);
             }
         }
     }
+    // APUs only reserve a little VRAM and map the rest of what they use from system memory
+    gpu_info.shared_memory_bytes = read_sysfs_string(&device_path.join("mem_info_gtt_total")).and_then(|bytes| bytes.parse().ok());
+    gpu_info.memory_used_bytes = read_sysfs_string(&device_path.join("mem_info_vram_used")).and_then(|bytes| bytes.parse().ok());
+    gpu_info.shared_memory_used_bytes = read_sysfs_string(&device_path.join("mem_info_gtt_used")).and_then(|bytes| bytes.parse().ok());
+    // The VRAM manufacturer, as the VBIOS names it, since Linux 5.12
+    gpu_info.memory_vendor = read_sysfs_string(&device_path.join("mem_info_vram_vendor")).map(|vendor| memory_vendor_name(&vendor));
     
-    // Try to get topology information
-    gpu_info.topology = get_amd_topology(&name);
+    // ECC is reported through the RAS interface on cards that support it
+    gpu_info.ecc = read_amd_ecc(device_path);
+    gpu_info.performance_state = read_amd_power_profile(device_path)
+        .map(|power_profile| PerformanceState { power_profile: Some(power_profile), ..Default::default() });
     
-    // Try to get cache information
-    gpu_info.cache = get_amd_cache(&name);
+    specs::apply_specs(&mut gpu_info);
+    gpu_info.clock_offset_mhz = read_amd_clock_offset(device_path);
     
-    // Calculate peak performance
-    if let Some(ref topology) = gpu_info.topology {
-        if let Some(stream_processors) = topology.stream_processors {
-            // Peak FLOPS = 2 * stream_processors * clock
-            let peak_gflops = 2.0 * stream_processors as f64 * gpu_info.max_freq_mhz as f64 / 1000.0;
-            gpu_info.peak_performance_gflops = Some(peak_gflops);
-        }
-    }
+    gpu_info.peak_performance_gflops = perf::peak_gflops(&gpu_info);
     
     Ok(gpu_info)
 }
 
-/// Find a file with the given name in a directory, including subdirectories
-fn find_file_in_dir(dir: &Path, filename: &str) -> Option<PathBuf> {
-    if let Ok(entries) = fs::read_dir(dir) {
-        for entry in entries {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                if path.is_file() && path.file_name().and_then(|n| n.to_str()) == Some(filename) {
-                    return Some(path);
-                } else if path.is_dir() {
-                    if let Some(found) = find_file_in_dir(&path, filename) {
-                        return Some(found);
-                    }
+/// The current and highest level of a DPM clock table such as `pp_dpm_sclk`
+fn read_dpm_clocks(device_path: &Path, table: &str) -> Option<(Option<u32>, u32)> {
+    let content = fs::read_to_string(sysfs_layout::find(device_path, table)?).ok()?;
+    parse::parse_dpm_clocks(&content)
+}
+
+/// The name of a handheld APU, if the device is one
+fn handheld_apu(device_id: &str) -> Option<&'static str> {
+    let cpu = system::cpu_name().unwrap_or_default();
+    HANDHELD_APUS
+        .iter()
+        .find(|(id, cpu_model, _)| *id == device_id && cpu.contains(cpu_model))
+        .map(|&(_, _, name)| name)
+}
+
+/// Read a trimmed, non-empty string from a sysfs file
+fn read_sysfs_string(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Read ECC state from the amdgpu RAS interface
+///
+/// `ras/features` holds a bitmask of RAS-enabled blocks; bit 0 is the memory
+/// controller (UMC), which is what ECC protects.
+fn read_amd_ecc(device_path: &Path) -> Option<EccStatus> {
+    let ras_path = device_path.join("ras");
+    let features = read_sysfs_string(&ras_path.join("features"))?;
+    let mask = features.rsplit(':').next()?.trim().trim_start_matches("0x");
+    let mask = u64::from_str_radix(mask, 16).ok()?;
+    
+    let mut ecc = EccStatus {
+        enabled: mask & 1 != 0,
+        corrected_errors: None,
+        uncorrected_errors: None,
+    };
+    
+    // Formatted as "ue: 0" and "ce: 0" lines
+    if let Some(counts) = read_sysfs_string(&ras_path.join("umc_err_count")) {
+        for line in counts.lines() {
+            if let Some((key, value)) = line.split_once(':') {
+                let value = value.trim().parse().ok();
+                match key.trim() {
+                    "ce" => ecc.corrected_errors = value,
+                    "ue" => ecc.uncorrected_errors = value,
+                    _ => {}
                 }
             }
         }
     }
-    None
+    
+    Some(ecc)
+}
+
+/// The active power profile and the DPM performance level, e.g.
+/// "3D_FULL_SCREEN (auto)"
+///
+/// `pp_power_profile_mode` lists the profiles with the active one marked
+/// by a `*`, as "1 3D_FULL_SCREEN*:" or "1 3D_FULL_SCREEN *:" depending on
+/// the GPU generation. amdgpu reports throttling only in `gpu_metrics`.
+fn read_amd_power_profile(device_path: &Path) -> Option<String> {
+    let profile = read_sysfs_string(&device_path.join("pp_power_profile_mode")).and_then(|modes| {
+        modes
+            .lines()
+            .find(|line| line.contains('*'))
+            .and_then(|line| line.split_whitespace().nth(1))
+            .map(|name| name.trim_end_matches([':', '*']).to_string())
+            .filter(|name| !name.is_empty())
+    });
+    let level = read_sysfs_string(&device_path.join("power_dpm_force_performance_level"));
+
+    match (profile, level) {
+        (Some(profile), Some(level)) => Some(format!("{} ({})", profile, level)),
+        (profile, level) => profile.or(level),
+    }
 }
 
-/// Check if rocm-smi is available
-fn is_rocm_smi_available() -> bool {
-    Command::new("which")
-        .arg("rocm-smi")
-        .output()
-        .map(|output| output.status.success())
-        .unwrap_or(false)
+/// The core clock offset set through OverDrive on RDNA 3 and later
+///
+/// `pp_od_clk_voltage` has it as an "OD_SCLK_OFFSET:" section holding e.g.
+/// "150Mhz". Older generations set absolute clocks instead, which show up
+/// in `pp_dpm_sclk`.
+fn read_amd_clock_offset(device_path: &Path) -> Option<i32> {
+    let od = read_sysfs_string(&device_path.join("pp_od_clk_voltage"))?;
+    let mut lines = od.lines().map(str::trim).skip_while(|line| *line != "OD_SCLK_OFFSET:");
+    lines.next()?;
+    lines.next()?.strip_suffix("Mhz")?.parse().ok()
 }
 
-/// Enhance GPU info using rocm-smi
-fn enhance_with_rocm_smi(gpu_info: &mut GpuInfo) -> Result<()> {
-    let output = Command::new("rocm-smi")
-        .args(["--showdriverversion", "--showmemvendor", "--showvbios", "-a"])
-        .output()
-        .context("Failed to execute rocm-smi")?;
+/// Detect the AMDGPU-PRO userspace driver, with the DKMS module version when present
+///
+/// Removing the packages can leave `/opt/amdgpu-pro` behind, so the stack
+/// only counts as installed while its Vulkan driver is.
+fn detect_amd_pro_driver() -> Option<String> {
+    let vulkan = icd::vulkan_manifest_names().iter().any(|name| name.starts_with(AMDGPU_PRO_ICD));
+    if !vulkan || !sysroot::path(AMDGPU_PRO_DIR).exists() {
+        return None;
+    }
+    
+    match read_sysfs_string(&sysroot::path("/sys/module/amdgpu/version")) {
+        Some(version) => Some(format!("AMDGPU-PRO {}", version)),
+        None => Some("AMDGPU-PRO".to_string()),
+    }
+}
+
+/// What rocm-smi is asked to report
+const ROCM_SMI_ARGS: [&str; 10] = [
+    "--showdriverversion",
+    "--showmemvendor",
+    "--showvbios",
+    "--showtemp",
+    "--showpower",
+    "--showfan",
+    "--showbus",
+    "--showserial",
+    "--showclocks",
+    "--json",
+];
+
+/// Enhance an AMD GPU's info using rocm-smi, if it is installed
+///
+/// Values found in sysfs are kept; rocm-smi fills in the rest. The card is
+/// matched by PCI address.
+pub fn enhance_with_rocm_smi(gpu_info: &mut GpuInfo) -> Result<()> {
+    if gpu_info.vendor != GpuVendor::Amd || !tools::is_installed("rocm-smi") {
+        return Ok(());
+    }
+    
+    let output = snapshot::run("rocm-smi", &ROCM_SMI_ARGS).context("Failed to execute rocm-smi")?;
     
     if !output.status.success() {
         return Err(anyhow!("rocm-smi command failed"));
@@ -213,65 +313,215 @@ fn enhance_with_rocm_smi(gpu_info: &mut GpuInfo) -> Result<()> {
     
     let output_str = String::from_utf8(output.stdout)
         .context("rocm-smi output is not valid UTF-8")?;
+    let report = parse_rocm_smi_json(&output_str)?;
     
-    // Extract driver version
-    let re_driver = Regex::new(r"Driver Version: (.+)").ok();
-    if let Some(re) = re_driver {
-        if let Some(cap) = re.captures(&output_str) {
-            gpu_info.driver_version = Some(cap[1].trim().to_string());
-        }
+    if let Some(driver) = report.system.get("Driver version") {
+        // A proprietary driver found in sysfs is more specific
+        gpu_info.driver_version.get_or_insert_with(|| driver.clone());
     }
     
-    // Could extract more info if needed...
+    let address = gpu_info.pci_info.as_ref().map(|pci_info| pci_info.address());
+    let Some(card) = report.card(address.as_deref()) else {
+        return Ok(());
+    };
+    let number = |key: &str| card.get(key).and_then(|value| value.parse::<f64>().ok());
+    
+    if gpu_info.memory_vendor.is_none() {
+        gpu_info.memory_vendor = card.get("GPU memory vendor").map(|vendor| memory_vendor_name(vendor));
+    }
+    gpu_info.vbios_version = card.get("VBIOS version").cloned();
+    if gpu_info.serial.is_none() {
+        gpu_info.serial = card.get("Serial Number").cloned();
+    }
+    
+    if gpu_info.temperature_c.is_none() {
+        gpu_info.temperature_c = number("Temperature (Sensor edge) (C)");
+    }
+    gpu_info.junction_temperature_c = number("Temperature (Sensor junction) (C)");
+    gpu_info.memory_temperature_c = number("Temperature (Sensor memory) (C)");
+    // Newer releases report the socket power, older ones the average
+    gpu_info.power_draw_w = number("Current Socket Graphics Package Power (W)")
+        .or_else(|| number("Average Graphics Package Power (W)"));
+    
+    let fan = FanSpeed {
+        percent: number("Fan speed (%)").map(|percent| percent.round() as u32),
+        rpm: number("Fan RPM").map(|rpm| rpm.round() as u32),
+    };
+    if fan.percent.is_some() || fan.rpm.is_some() {
+        gpu_info.fan = Some(fan);
+    }
+    
+    gpu_info.pcie_link = card.get("pcie clock level").and_then(|level| parse_pcie_level(level));
     
     Ok(())
 }
 
+/// The cards and system values of `rocm-smi --json`, as strings
+struct RocmSmiReport {
+    /// "card0", "card1", ... with their values
+    cards: Vec<(String, HashMap<String, String>)>,
+    system: HashMap<String, String>,
+}
+
+impl RocmSmiReport {
+    /// The card at `address`, or the only card when it cannot be told apart
+    fn card(&self, address: Option<&str>) -> Option<&HashMap<String, String>> {
+        let by_address = address.and_then(|address| {
+            self.cards
+                .iter()
+                .find(|(_, values)| values.get("PCI Bus").is_some_and(|bus| bus.eq_ignore_ascii_case(address)))
+        });
+        match (by_address, self.cards.as_slice()) {
+            (Some((_, values)), _) => Some(values),
+            (None, [(_, values)]) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+/// Parse `rocm-smi --json`, skipping warnings printed before the JSON
+///
+/// Every value is a string; "N/A" and other placeholders are dropped.
+fn parse_rocm_smi_json(output: &str) -> Result<RocmSmiReport> {
+    let start = output.find('{').ok_or_else(|| anyhow!("rocm-smi printed no JSON"))?;
+    let json: HashMap<String, HashMap<String, serde_json::Value>> =
+        serde_json::from_str(&output[start..]).context("Failed to parse rocm-smi JSON")?;
+    
+    let strings = |values: HashMap<String, serde_json::Value>| -> HashMap<String, String> {
+        values
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let value = match value {
+                    serde_json::Value::String(value) => value.trim().to_string(),
+                    serde_json::Value::Number(value) => value.to_string(),
+                    _ => return None,
+                };
+                (!value.is_empty() && value != "N/A").then_some((key, value))
+            })
+            .collect()
+    };
+    
+    let mut report = RocmSmiReport { cards: Vec::new(), system: HashMap::new() };
+    for (key, values) in json {
+        if key == "system" {
+            report.system = strings(values);
+        } else if key.starts_with("card") {
+            report.cards.push((key, strings(values)));
+        }
+    }
+    report.cards.sort_by_key(|(key, _)| key.trim_start_matches("card").parse::<u32>().unwrap_or(u32::MAX));
+    Ok(report)
+}
+
+/// Memory vendors as amdgpu and rocm-smi spell them, e.g. "samsung" or "hynix"
+fn memory_vendor_name(vendor: &str) -> String {
+    match vendor.to_lowercase().as_str() {
+        "samsung" => "Samsung".to_string(),
+        "hynix" | "sk hynix" => "SK Hynix".to_string(),
+        "micron" => "Micron".to_string(),
+        "elpida" => "Elpida".to_string(),
+        "nanya" => "Nanya".to_string(),
+        "qimonda" => "Qimonda".to_string(),
+        "infineon" => "Infineon".to_string(),
+        "etron" => "Etron".to_string(),
+        "mosel" => "Mosel Vitelic".to_string(),
+        "winbond" => "Winbond".to_string(),
+        "esmt" => "ESMT".to_string(),
+        _ => vendor.to_string(),
+    }
+}
+
+/// The link of a "pcie clock level" such as "1 (16.0GT/s x16)"
+fn parse_pcie_level(level: &str) -> Option<PcieLink> {
+    let link = level.split_once('(')?.1.trim_end_matches(')');
+    let (speed, width) = link.split_once(' ')?;
+    let width = width.trim().trim_start_matches('x').parse().ok()?;
+    let speed: f64 = speed.trim_end_matches("GT/s").parse().ok()?;
+    Some(PcieLink { generation: pcie_generation(speed)?, width })
+}
+
 /// Determine AMD architecture, chip, and manufacturing process based on device ID and name
-fn get_amd_architecture(name: &str, device_id: &str) -> (String, String, Option<u32>) {
+fn get_amd_architecture(name: &str, device_id: &str) -> (String, String, Option<Process>) {
     let name_lower = name.to_lowercase();
     
+    // Handheld APUs, by device ID since they share names with laptop chips
+    if device_id == "163f" {
+        ("RDNA 2".to_string(), "Van Gogh".to_string(), Some(Process::new("TSMC", Some("N7"), 7)))
+    }
+    else if device_id == "1435" {
+        ("RDNA 2".to_string(), "Sephiroth".to_string(), Some(Process::new("TSMC", Some("N6"), 6)))
+    }
+    else if device_id == "15bf" {
+        ("RDNA 3".to_string(), "Phoenix".to_string(), Some(Process::new("TSMC", Some("N4"), 4)))
+    }
+    else if device_id == "15c8" {
+        ("RDNA 3".to_string(), "Phoenix 2".to_string(), Some(Process::new("TSMC", Some("N4"), 4)))
+    }
+    else if device_id == "150e" {
+        ("RDNA 3.5".to_string(), "Strix Point".to_string(), Some(Process::new("TSMC", Some("N4P"), 4)))
+    }
     // RDNA 3 (RX 7000)
-    if name_lower.contains("rx 7900") || device_id.starts_with("744c") || device_id.starts_with("7440") {
-        ("RDNA 3".to_string(), "Navi 31".to_string(), Some(5))
+    else if name_lower.contains("rx 7900") || device_id.starts_with("744c") || device_id.starts_with("7440") {
+        ("RDNA 3".to_string(), "Navi 31".to_string(), Some(Process::new("TSMC", Some("N5"), 5)))
     }
     // RDNA 2 (RX 6000)
     else if name_lower.contains("rx 6950") || name_lower.contains("rx 6900") || device_id.starts_with("73a") {
-        ("RDNA 2".to_string(), "Navi 21".to_string(), Some(7))
+        ("RDNA 2".to_string(), "Navi 21".to_string(), Some(Process::new("TSMC", Some("N7"), 7)))
     }
     else if name_lower.contains("rx 6800") || device_id.starts_with("73b") {
-        ("RDNA 2".to_string(), "Navi 21".to_string(), Some(7))
+        ("RDNA 2".to_string(), "Navi 21".to_string(), Some(Process::new("TSMC", Some("N7"), 7)))
     }
     else if name_lower.contains("rx 6700") || device_id.starts_with("73d") {
-        ("RDNA 2".to_string(), "Navi 22".to_string(), Some(7))
+        ("RDNA 2".to_string(), "Navi 22".to_string(), Some(Process::new("TSMC", Some("N7"), 7)))
     }
     else if name_lower.contains("rx 6600") || device_id.starts_with("73e") || device_id.starts_with("73f") {
-        ("RDNA 2".to_string(), "Navi 23".to_string(), Some(7))
+        ("RDNA 2".to_string(), "Navi 23".to_string(), Some(Process::new("TSMC", Some("N7"), 7)))
     }
     else if name_lower.contains("rx 6500") || name_lower.contains("rx 6400") || device_id.starts_with("743") {
-        ("RDNA 2".to_string(), "Navi 24".to_string(), Some(6))
+        ("RDNA 2".to_string(), "Navi 24".to_string(), Some(Process::new("TSMC", Some("N6"), 6)))
     }
-    // RDNA (RX 5000)
-    else if name_lower.contains("rx 5700") || device_id.starts_with("731") {
-        ("RDNA".to_string(), "Navi 10".to_string(), Some(7))
-    }
-    else if name_lower.contains("rx 5600") || device_id.starts_with("731") {
-        ("RDNA".to_string(), "Navi 10".to_string(), Some(7))
+    // RDNA (RX 5000); the RX 5600 XT is a cut-down Navi 10 sharing the RX 5700's 731f ID
+    else if name_lower.contains("rx 5700") || name_lower.contains("rx 5600") || device_id.starts_with("731") {
+        ("RDNA".to_string(), "Navi 10".to_string(), Some(Process::new("TSMC", Some("N7"), 7)))
     }
     else if name_lower.contains("rx 5500") || device_id.starts_with("7340") {
-        ("RDNA".to_string(), "Navi 14".to_string(), Some(7))
+        ("RDNA".to_string(), "Navi 14".to_string(), Some(Process::new("TSMC", Some("N7"), 7)))
+    }
+    // Radeon Pro workstation cards; the chip depends on the model, see the spec database
+    else if name_lower.contains("pro w7") {
+        ("RDNA 3".to_string(), "Unknown".to_string(), None)
+    }
+    else if name_lower.contains("pro w6") {
+        ("RDNA 2".to_string(), "Unknown".to_string(), None)
+    }
+    else if name_lower.contains("pro w5") {
+        ("RDNA".to_string(), "Unknown".to_string(), None)
+    }
+    // Instinct compute accelerators
+    else if name_lower.contains("mi100") {
+        ("CDNA".to_string(), "Arcturus".to_string(), Some(Process::new("TSMC", Some("N7"), 7)))
+    }
+    else if name_lower.contains("mi210") || name_lower.contains("mi250") {
+        ("CDNA 2".to_string(), "Aldebaran".to_string(), Some(Process::new("TSMC", Some("N6"), 6)))
     }
     // Vega
-    else if name_lower.contains("vega") || name_lower.contains("radeon vii") || device_id.starts_with("66") {
-        ("Vega".to_string(), "Vega 10/20".to_string(), Some(7))
+    else if name_lower.contains("vega") || name_lower.contains("radeon vii") || name_lower.contains("radeon pro vii") ||
+            name_lower.contains("wx 9100") || name_lower.contains("wx 8200") || device_id.starts_with("66") {
+        // Vega 20 moved to 7 nm; Vega 10 stayed on 14 nm
+        let process = if name_lower.contains("vii") || device_id.starts_with("66a") {
+            Process::new("TSMC", Some("N7"), 7)
+        } else {
+            Process::new("GlobalFoundries", Some("14LPP"), 14)
+        };
+        ("Vega".to_string(), "Vega 10/20".to_string(), Some(process))
     }
     // Polaris (RX 400/500)
-    else if name_lower.contains("rx 5") || name_lower.contains("rx 4") || device_id.starts_with("67") {
-        ("Polaris".to_string(), "Polaris".to_string(), Some(14))
+    else if name_lower.contains("rx 5") || name_lower.contains("rx 4") || name_lower.contains("pro wx") || device_id.starts_with("67") {
+        ("Polaris".to_string(), "Polaris".to_string(), Some(Process::new("GlobalFoundries", Some("14LPP"), 14)))
     }
     // Older GCN
-    else if name_lower.contains("rx 3") || name_lower.contains("r9") || device_id.starts_with("6") {
-        ("GCN".to_string(), "GCN".to_string(), Some(28))
+    else if name_lower.contains("rx 3") || name_lower.contains("r9") || name_lower.contains("firepro") || device_id.starts_with("6") {
+        ("GCN".to_string(), "GCN".to_string(), Some(Process::new("TSMC", None, 28)))
     }
     else {
         ("Unknown".to_string(), "Unknown".to_string(), None)
@@ -288,10 +538,15 @@ fn get_amd_memory_type(name: &str) -> MemoryType {
         MemoryType::Gddr6
     } else if name_lower.contains("rx 5") {
         MemoryType::Gddr6
-    } else if name_lower.contains("radeon vii") {
+    } else if name_lower.contains("pro w7") || name_lower.contains("pro w6") || name_lower.contains("pro w5") {
+        MemoryType::Gddr6
+    } else if name_lower.contains("radeon vii") || name_lower.contains("radeon pro vii") || name_lower.contains("wx 9100") ||
+              name_lower.contains("instinct") {
         MemoryType::Hbm2
     } else if name_lower.contains("vega") {
         MemoryType::Hbm2
+    } else if name_lower.contains("steam deck") || name_lower.contains("ryzen z") {
+        MemoryType::Lpddr5
     } else if name_lower.contains("rx 580") || name_lower.contains("rx 570") || 
               name_lower.contains("rx 480") || name_lower.contains("rx 470") {
         MemoryType::Gddr5
@@ -299,169 +554,3 @@ fn get_amd_memory_type(name: &str) -> MemoryType {
         MemoryType::Unknown
     }
 }
-
-/// Determine AMD memory bus width
-fn get_amd_bus_width(name: &str) -> u32 {
-    let name_lower = name.to_lowercase();
-    
-    if name_lower.contains("rx 7900") {
-        384
-    } else if name_lower.contains("rx 6900") || name_lower.contains("rx 6800") {
-        256
-    } else if name_lower.contains("rx 6700") {
-        192
-    } else if name_lower.contains("rx 6600") {
-        128
-    } else if name_lower.contains("rx 6500") || name_lower.contains("rx 6400") {
-        64
-    } else if name_lower.contains("rx 5700") {
-        256
-    } else if name_lower.contains("rx 5600") {
-        192
-    } else if name_lower.contains("rx 5500") {
-        128
-    } else if name_lower.contains("radeon vii") {
-        4096  // 4096-bit for HBM2
-    } else if name_lower.contains("vega") {
-        2048  // 2048-bit for HBM2
-    } else if name_lower.contains("rx 580") || name_lower.contains("rx 480") {
-        256
-    } else if name_lower.contains("rx 570") || name_lower.contains("rx 470") {
-        256
-    } else if name_lower.contains("rx 560") || name_lower.contains("rx 460") {
-        128
-    } else {
-        256  // Default value
-    }
-}
-
-/// Get topology information for AMD GPUs
-fn get_amd_topology(name: &str) -> Option<Topology> {
-    let name_lower = name.to_lowercase();
-    
-    let stream_processors = if name_lower.contains("rx 7900 xtx") {
-        Some(12288)  // RDNA 3, 96 CUs
-    } else if name_lower.contains("rx 7900 xt") {
-        Some(10752)  // RDNA 3, 84 CUs
-    } else if name_lower.contains("rx 6950 xt") || name_lower.contains("rx 6900 xt") {
-        Some(5120)   // RDNA 2, 80 CUs
-    } else if name_lower.contains("rx 6800 xt") {
-        Some(4608)   // RDNA 2, 72 CUs
-    } else if name_lower.contains("rx 6800") {
-        Some(3840)   // RDNA 2, 60 CUs
-    } else if name_lower.contains("rx 6700 xt") {
-        Some(2560)   // RDNA 2, 40 CUs
-    } else if name_lower.contains("rx 6600 xt") {
-        Some(2048)   // RDNA 2, 32 CUs
-    } else if name_lower.contains("rx 6600") {
-        Some(1792)   // RDNA 2, 28 CUs
-    } else if name_lower.contains("rx 6500 xt") {
-        Some(1024)   // RDNA 2, 16 CUs
-    } else if name_lower.contains("rx 5700 xt") {
-        Some(2560)   // RDNA, 40 CUs
-    } else if name_lower.contains("rx 5700") {
-        Some(2304)   // RDNA, 36 CUs
-    } else if name_lower.contains("rx 5600 xt") {
-        Some(2048)   // RDNA, 32 CUs
-    } else if name_lower.contains("rx 5500 xt") {
-        Some(1408)   // RDNA, 22 CUs
-    } else if name_lower.contains("radeon vii") {
-        Some(3840)   // Vega 20, 60 CUs
-    } else if name_lower.contains("vega 64") {
-        Some(4096)   // Vega 10, 64 CUs
-    } else if name_lower.contains("vega 56") {
-        Some(3584)   // Vega 10, 56 CUs
-    } else if name_lower.contains("rx 580") {
-        Some(2304)   // Polaris 20, 36 CUs
-    } else if name_lower.contains("rx 570") {
-        Some(2048)   // Polaris 20, 32 CUs
-    } else {
-        None
-    };
-    
-    // Compute units = stream_processors / 64 for most AMD GPUs
-    let compute_units = if let Some(sp) = stream_processors {
-        if name_lower.contains("rx 7") {
-            sp / 128  // RDNA 3 has 128 stream processors per CU
-        } else {
-            sp / 64   // RDNA 1/2 and older have 64 stream processors per CU
-        }
-    } else {
-        0
-    };
-    
-    // ROPs (Render Output Units) and TMUs (Texture Mapping Units)
-    let (rops, tmus) = if name_lower.contains("rx 7900 xtx") {
-        (Some(192), Some(384))
-    } else if name_lower.contains("rx 7900 xt") {
-        (Some(176), Some(336))
-    } else if name_lower.contains("rx 6950 xt") || name_lower.contains("rx 6900 xt") {
-        (Some(128), Some(160))
-    } else if name_lower.contains("rx 6800 xt") {
-        (Some(128), Some(144))
-    } else if name_lower.contains("rx 6800") {
-        (Some(96), Some(120))
-    } else if name_lower.contains("rx 6700 xt") {
-        (Some(64), Some(160))
-    } else if name_lower.contains("rx 6600 xt") {
-        (Some(64), Some(128))
-    } else if name_lower.contains("rx 5700 xt") {
-        (Some(64), Some(160))
-    } else if name_lower.contains("rx 580") {
-        (Some(32), Some(144))
-    } else {
-        (None, None)
-    };
-    
-    stream_processors.map(|sp| Topology {
-        compute_units: compute_units as u32,
-        cuda_cores: None,
-        tensor_cores: None,
-        rt_cores: None,
-        sm_count: None,
-        stream_processors: Some(sp),
-        rops,
-        tmus,
-        execution_units: None,
-        slices: None,
-        subslices: None,
-    })
-}
-
-/// Get cache information for AMD GPUs
-fn get_amd_cache(name: &str) -> Option<Cache> {
-    let name_lower = name.to_lowercase();
-    
-    // L2 and L3 cache sizes
-    let (l2_size, l3_size) = if name_lower.contains("rx 7900") {
-        (Some(6 * 1024 * 1024), Some(96 * 1024 * 1024))  // 6 MB L2, 96 MB Infinity Cache
-    } else if name_lower.contains("rx 6950") || name_lower.contains("rx 6900") || name_lower.contains("rx 6800") {
-        (Some(512 * 1024), Some(128 * 1024 * 1024))  // 512 KB L2, 128 MB Infinity Cache
-    } else if name_lower.contains("rx 6700") {
-        (Some(384 * 1024), Some(96 * 1024 * 1024))   // 384 KB L2, 96 MB Infinity Cache
-    } else if name_lower.contains("rx 6600") {
-        (Some(256 * 1024), Some(32 * 1024 * 1024))   // 256 KB L2, 32 MB Infinity Cache
-    } else if name_lower.contains("rx 6500") || name_lower.contains("rx 6400") {
-        (Some(128 * 1024), Some(16 * 1024 * 1024))   // 128 KB L2, 16 MB Infinity Cache
-    } else if name_lower.contains("rx 5700") {
-        (Some(4 * 1024 * 1024), None)                // 4 MB L2, no L3
-    } else if name_lower.contains("rx 5600") {
-        (Some(4 * 1024 * 1024), None)                // 4 MB L2, no L3
-    } else if name_lower.contains("rx 5500") {
-        (Some(4 * 1024 * 1024), None)                // 4 MB L2, no L3
-    } else if name_lower.contains("radeon vii") {
-        (Some(4 * 1024 * 1024), None)                // 4 MB L2, no L3
-    } else if name_lower.contains("vega") {
-        (Some(4 * 1024 * 1024), None)                // 4 MB L2, no L3
-    } else if name_lower.contains("rx 580") || name_lower.contains("rx 570") {
-        (Some(2 * 1024 * 1024), None)                // 2 MB L2, no L3
-    } else {
-        (None, None)
-    };
-    
-    Some(Cache {
-        l1_size: None, // AMD doesn't typically publish L1 cache sizes
-        l2_size,
-        l3_size,
-    })
-}
@@ -0,0 +1,157 @@
+//! The sources GPUs are detected from.
+//!
+//! Each backend contributes detectors, which find GPUs, and enrichers, which
+//! add details to GPUs found by others, to a registry. GpuManager runs the
+//! detectors of the selected backends in priority order and merges the
+//! records of each physical GPU into the first one.
+
+use anyhow::{anyhow, bail, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::gpu::common::GpuInfo;
+use crate::gpu::{amd, intel, lspci, nvidia};
+#[cfg(feature = "nvml")]
+use crate::gpu::nvml;
+#[cfg(feature = "udev")]
+use crate::gpu::udev;
+#[cfg(feature = "vulkan")]
+use crate::gpu::vulkan;
+
+/// A source of GPU information
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// amdgpu and i915 sysfs attributes, with a plain PCI scan as fallback
+    Sysfs,
+    /// Vendor command-line tools: nvidia-smi and rocm-smi
+    Smi,
+    /// NVIDIA's management library, libnvidia-ml
+    Nvml,
+    /// Physical devices reported by vulkaninfo
+    Vulkan,
+    /// DRM cards enumerated through libudev, with their hardware database names
+    Udev,
+    /// Display adapters listed by lspci, for when /sys/bus/pci is restricted
+    Lspci,
+}
+
+impl Backend {
+    /// Backends used without `--backend`, highest priority first
+    ///
+    /// Vulkan is opt-in: it only finds GPUs the others miss, such as SoC GPUs
+    /// outside the PCI bus, and running vulkaninfo is slow.
+    pub const DEFAULT: [Backend; 3] = [Backend::Smi, Backend::Nvml, Backend::Sysfs];
+
+    /// Whether this build has the backend; nvml, vulkan and udev are cargo features
+    pub fn is_built(&self) -> bool {
+        match self {
+            Backend::Nvml => cfg!(feature = "nvml"),
+            Backend::Vulkan => cfg!(feature = "vulkan"),
+            Backend::Udev => cfg!(feature = "udev"),
+            Backend::Sysfs | Backend::Smi | Backend::Lspci => true,
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_possible_value() {
+            Some(value) => write!(f, "{}", value.get_name()),
+            None => write!(f, "{:?}", self),
+        }
+    }
+}
+
+/// The backends to use, parsed from `--backend`
+///
+/// Plain names replace the default list and set the priority order;
+/// `+name` adds a backend after the defaults and `-name` excludes one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BackendSelection(pub Vec<Backend>);
+
+impl FromStr for BackendSelection {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parse = |name: &str| {
+            Backend::from_str(name.trim(), true).map_err(|_| {
+                anyhow!("unknown backend \"{}\" (expected sysfs, smi, nvml, vulkan, udev or lspci)", name.trim())
+            })
+        };
+        let parse_built = |name: &str| {
+            let backend = parse(name)?;
+            if !backend.is_built() {
+                bail!("gpufetch was built without the \"{}\" feature, which --backend {} needs", backend, backend);
+            }
+            Ok(backend)
+        };
+
+        let (mut forced, mut added, mut excluded) = (Vec::new(), Vec::new(), Vec::new());
+        for item in s.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            if let Some(name) = item.strip_prefix('-') {
+                excluded.push(parse(name)?);
+            } else if let Some(name) = item.strip_prefix('+') {
+                added.push(parse_built(name)?);
+            } else {
+                forced.push(parse_built(item)?);
+            }
+        }
+
+        let mut backends = if forced.is_empty() { Backend::DEFAULT.to_vec() } else { forced };
+        backends.extend(added);
+        backends.retain(|backend| !excluded.contains(backend));
+        let mut unique = Vec::new();
+        for backend in backends {
+            if !unique.contains(&backend) {
+                unique.push(backend);
+            }
+        }
+
+        if unique.is_empty() {
+            bail!("no backends left to detect GPUs with");
+        }
+        Ok(BackendSelection(unique))
+    }
+}
+
+/// One detection routine in the registry
+pub struct Detector {
+    pub backend: Backend,
+    /// What the detector looks for, for verbose messages
+    pub name: &'static str,
+    pub detect: fn() -> Result<Vec<GpuInfo>>,
+}
+
+/// Every detector, in priority order within its backend
+pub const DETECTORS: &[Detector] = &[
+    Detector { backend: Backend::Smi, name: "NVIDIA GPUs via nvidia-smi", detect: nvidia::detect_nvidia_gpus },
+    #[cfg(feature = "nvml")]
+    Detector { backend: Backend::Nvml, name: "NVIDIA GPUs via NVML", detect: nvml::detect_nvml_gpus },
+    Detector { backend: Backend::Sysfs, name: "AMD GPUs", detect: amd::detect_amd_gpus },
+    Detector { backend: Backend::Sysfs, name: "Intel GPUs", detect: intel::detect_intel_gpus },
+    #[cfg(feature = "vulkan")]
+    Detector { backend: Backend::Vulkan, name: "Vulkan devices", detect: vulkan::detect_vulkan_gpus },
+    #[cfg(feature = "udev")]
+    Detector { backend: Backend::Udev, name: "DRM devices via udev", detect: udev::detect_udev_gpus },
+    Detector { backend: Backend::Lspci, name: "PCI devices via lspci", detect: lspci::detect_lspci_gpus },
+];
+
+/// A routine adding a backend's details to GPUs already detected
+pub struct Enricher {
+    pub backend: Backend,
+    pub name: &'static str,
+    pub enrich: fn(&mut GpuInfo) -> Result<()>,
+}
+
+/// Every enricher, run on each detected GPU in this order
+pub const ENRICHERS: &[Enricher] = &[
+    Enricher { backend: Backend::Smi, name: "rocm-smi", enrich: amd::enhance_with_rocm_smi },
+];
+
+/// The registered detectors of a backend
+pub fn detectors(backend: Backend) -> impl Iterator<Item = &'static Detector> {
+    DETECTORS.iter().filter(move |detector| detector.backend == backend)
+}
@@ -1,6 +1,13 @@
+use anyhow::anyhow;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
 use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+use crate::gpu::edid::Edid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum GpuVendor {
     Nvidia,
     Amd,
@@ -21,10 +28,13 @@ impl fmt::Display for GpuVendor {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
 pub enum MemoryType {
     Ddr3,
     Ddr4,
+    /// System memory of handheld and thin-and-light APUs
+    Lpddr5,
     Gddr5,
     Gddr5X,
     Gddr6,
@@ -39,6 +49,7 @@ impl fmt::Display for MemoryType {
         match self {
             MemoryType::Ddr3 => write!(f, "DDR3"),
             MemoryType::Ddr4 => write!(f, "DDR4"),
+            MemoryType::Lpddr5 => write!(f, "LPDDR5"),
             MemoryType::Gddr5 => write!(f, "GDDR5"),
             MemoryType::Gddr5X => write!(f, "GDDR5X"),
             MemoryType::Gddr6 => write!(f, "GDDR6"),
@@ -50,47 +61,223 @@ impl fmt::Display for MemoryType {
     }
 }
 
-#[derive(Debug, Clone)]
+impl FromStr for MemoryType {
+    type Err = anyhow::Error;
+
+    /// Parse the names produced by `Display`, ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "DDR3" => Ok(MemoryType::Ddr3),
+            "DDR4" => Ok(MemoryType::Ddr4),
+            "LPDDR5" => Ok(MemoryType::Lpddr5),
+            "GDDR5" => Ok(MemoryType::Gddr5),
+            "GDDR5X" => Ok(MemoryType::Gddr5X),
+            "GDDR6" => Ok(MemoryType::Gddr6),
+            "GDDR6X" => Ok(MemoryType::Gddr6X),
+            "HBM" => Ok(MemoryType::Hbm),
+            "HBM2" => Ok(MemoryType::Hbm2),
+            "UNKNOWN" => Ok(MemoryType::Unknown),
+            _ => Err(anyhow!("Unknown memory type \"{}\"", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Memory {
     pub size_bytes: u64,
     pub memory_type: MemoryType,
     pub bus_width: u32,
+    /// Highest memory clock in MHz, 0 when unknown
     pub clock_mhz: u32,
 }
 
-#[derive(Debug, Clone)]
-pub struct Topology {
-    // Common fields
-    pub compute_units: u32,
-    
-    // NVIDIA specific 
+/// Whether memory error correction is on, with error counts when exposed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EccStatus {
+    pub enabled: bool,
+    pub corrected_errors: Option<u64>,
+    pub uncorrected_errors: Option<u64>,
+}
+
+/// Performance state, and what is holding clocks back
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PerformanceState {
+    /// NVIDIA P-state, from "P0" (fastest) down to "P12"
+    pub pstate: Option<String>,
+    /// amdgpu power profile and performance level, e.g. "3D_FULL_SCREEN (auto)"
+    pub power_profile: Option<String>,
+    /// Active throttle reasons, e.g. "thermal" or "power cap"
+    #[serde(deserialize_with = "static_strs")]
+    pub throttle_reasons: Vec<&'static str>,
+}
+
+/// Fan speed, in whichever of the two units the driver reports
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FanSpeed {
+    pub percent: Option<u32>,
+    pub rpm: Option<u32>,
+}
+
+/// The negotiated PCIe link
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PcieLink {
+    pub generation: u32,
+    /// Lanes, e.g. 16 for x16
+    pub width: u32,
+}
+
+/// PCIe generation of a link transferring `gts` GT/s per lane
+pub fn pcie_generation(gts: f64) -> Option<u32> {
+    [2.5, 5.0, 8.0, 16.0, 32.0, 64.0]
+        .iter()
+        .position(|&speed| (gts - speed).abs() < 0.1)
+        .map(|idx| idx as u32 + 1)
+}
+
+/// How busy one engine of the GPU is, e.g. "Render/3D" or "Video"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineLoad {
+    pub name: String,
+    pub busy_percent: f64,
+}
+
+/// Active NVLink connections of an NVIDIA GPU
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NvLinkStatus {
+    pub active_links: u32,
+    /// Combined speed of the active links in GB/s
+    pub bandwidth_gbs: f64,
+}
+
+/// Execution units of a GPU, counted the way its vendor counts them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "vendor", rename_all = "snake_case")]
+pub enum Topology {
+    Nvidia(NvidiaTopology),
+    Amd(AmdTopology),
+    Intel(IntelTopology),
+    /// Other vendors, of which only a compute unit count is known
+    Generic { compute_units: u32 },
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NvidiaTopology {
+    pub sm_count: u32,
+    pub gpc_count: Option<u32>,
     pub cuda_cores: Option<u32>,
     pub tensor_cores: Option<u32>,
     pub rt_cores: Option<u32>,
-    pub sm_count: Option<u32>,
-    
-    // AMD specific
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AmdTopology {
+    pub compute_units: u32,
     pub stream_processors: Option<u32>,
+    pub shader_engines: Option<u32>,
     pub rops: Option<u32>,
     pub tmus: Option<u32>,
-    
-    // Intel specific
-    pub execution_units: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IntelTopology {
+    pub execution_units: u32,
     pub slices: Option<u32>,
     pub subslices: Option<u32>,
 }
 
-#[derive(Debug, Clone)]
+impl Topology {
+    /// Spec field names that give the compute unit count: SMs, CUs or EUs
+    pub const COMPUTE_UNITS: [&'static str; 3] = ["sm_count", "compute_units", "execution_units"];
+
+    /// Spec field names of the other unit counts
+    pub const COUNTS: [&'static str; 10] = [
+        "cuda_cores",
+        "tensor_cores",
+        "rt_cores",
+        "gpc_count",
+        "stream_processors",
+        "rops",
+        "tmus",
+        "shader_engines",
+        "slices",
+        "subslices",
+    ];
+
+    /// A topology of `vendor`'s kind that only knows its compute unit count
+    pub fn new(vendor: &GpuVendor, compute_units: u32) -> Self {
+        match vendor {
+            GpuVendor::Nvidia => Topology::Nvidia(NvidiaTopology { sm_count: compute_units, ..Default::default() }),
+            GpuVendor::Amd => Topology::Amd(AmdTopology { compute_units, ..Default::default() }),
+            GpuVendor::Intel => Topology::Intel(IntelTopology { execution_units: compute_units, ..Default::default() }),
+            _ => Topology::Generic { compute_units },
+        }
+    }
+
+    /// SMs, CUs or EUs
+    pub fn compute_units(&self) -> u32 {
+        match self {
+            Topology::Nvidia(nvidia) => nvidia.sm_count,
+            Topology::Amd(amd) => amd.compute_units,
+            Topology::Intel(intel) => intel.execution_units,
+            Topology::Generic { compute_units } => *compute_units,
+        }
+    }
+
+    pub fn set_compute_units(&mut self, count: u32) {
+        match self {
+            Topology::Nvidia(nvidia) => nvidia.sm_count = count,
+            Topology::Amd(amd) => amd.compute_units = count,
+            Topology::Intel(intel) => intel.execution_units = count,
+            Topology::Generic { compute_units } => *compute_units = count,
+        }
+    }
+
+    /// CUDA cores, stream processors or EUs, the units vendors quote
+    /// shader throughput in
+    pub fn shader_cores(&self) -> Option<u32> {
+        match self {
+            Topology::Nvidia(nvidia) => nvidia.cuda_cores,
+            Topology::Amd(amd) => amd.stream_processors,
+            Topology::Intel(intel) => Some(intel.execution_units),
+            Topology::Generic { .. } => None,
+        }
+    }
+
+    /// The unit count named by its spec field, e.g. "cuda_cores", if this
+    /// vendor's GPUs have such units
+    pub fn count_mut(&mut self, field: &str) -> Option<&mut Option<u32>> {
+        let slot = match (self, field) {
+            (Topology::Nvidia(nvidia), "cuda_cores") => &mut nvidia.cuda_cores,
+            (Topology::Nvidia(nvidia), "tensor_cores") => &mut nvidia.tensor_cores,
+            (Topology::Nvidia(nvidia), "rt_cores") => &mut nvidia.rt_cores,
+            (Topology::Nvidia(nvidia), "gpc_count") => &mut nvidia.gpc_count,
+            (Topology::Amd(amd), "stream_processors") => &mut amd.stream_processors,
+            (Topology::Amd(amd), "rops") => &mut amd.rops,
+            (Topology::Amd(amd), "tmus") => &mut amd.tmus,
+            (Topology::Amd(amd), "shader_engines") => &mut amd.shader_engines,
+            (Topology::Intel(intel), "slices") => &mut intel.slices,
+            (Topology::Intel(intel), "subslices") => &mut intel.subslices,
+            _ => return None,
+        };
+        Some(slot)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Cache {
+    /// L1 of each SM or CU
     pub l1_size: Option<u64>,
     pub l2_size: Option<u64>,
     pub l3_size: Option<u64>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PciInfo {
     pub vendor_id: u16,
     pub device_id: u16,
+    /// Tells apart models sold under the same device ID, e.g. the RX 7900 XTX
+    /// (c8) and XT (cc)
+    pub revision: Option<u8>,
     pub class_id: u16,
     pub domain: u16,
     pub bus: u8,
@@ -98,26 +285,332 @@ pub struct PciInfo {
     pub function: u8,
 }
 
-#[derive(Debug, Clone)]
+impl PciInfo {
+    /// PCI address in the canonical `dddd:bb:dd.f` form
+    pub fn address(&self) -> String {
+        format!("{:04x}:{:02x}:{:02x}.{:x}", self.domain, self.bus, self.device, self.function)
+    }
+}
+
+/// Where a value that was not read from the hardware came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    /// The embedded spec database
+    SpecDb,
+    /// The user's overrides.toml
+    Override,
+    /// gpufetch's own tables of model names and device IDs
+    Heuristic,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::SpecDb => write!(f, "spec database"),
+            Source::Override => write!(f, "overrides.toml"),
+            Source::Heuristic => write!(f, "model name"),
+        }
+    }
+}
+
+/// A monitor connected to one of the GPU's outputs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisplayInfo {
+    /// Connector name such as "DP-1" or "eDP-1"
+    pub connector: String,
+    pub edid: Option<Edid>,
+}
+
+/// Video acceleration profiles reported by VA-API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaapiInfo {
+    pub driver: Option<String>,
+    /// Profile names without the `VAProfile` prefix, e.g. "HEVCMain10"
+    pub decode: Vec<String>,
+    pub encode: Vec<String>,
+}
+
+/// Ray tracing and upscaler support
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Features {
+    pub ray_tracing: bool,
+    /// Upscalers the GPU can run, newest generation of each, e.g. "DLSS 3"
+    #[serde(deserialize_with = "static_strs")]
+    pub upscalers: Vec<&'static str>,
+    /// Vulkan drivers are installed, but none for this GPU
+    pub missing_vulkan_driver: bool,
+}
+
+/// The installed Vulkan and OpenCL drivers as seen from one GPU
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Runtime {
+    pub vulkan: Vec<Icd>,
+    pub opencl: Vec<Icd>,
+    /// Missing, broken or conflicting drivers for this GPU
+    pub warnings: Vec<String>,
+}
+
+/// An installable client driver, by manifest name, e.g. "radeon_icd"
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Icd {
+    pub name: String,
+    /// Whether the driver can run on this GPU
+    pub serves_gpu: bool,
+}
+
+/// Which part a GPU plays in a hybrid (PRIME/Optimus) laptop setup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HybridRole {
+    /// Drives the displays
+    Primary,
+    /// Renders on request and hands frames to the primary GPU
+    Offload,
+}
+
+/// How displays are switched between the GPUs of a hybrid setup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HybridMode {
+    /// A hardware mux managed by vga_switcheroo
+    Mux,
+    /// Muxless PRIME render offload
+    PrimeOffload,
+}
+
+impl fmt::Display for HybridMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HybridMode::Mux => write!(f, "vga_switcheroo mux"),
+            HybridMode::PrimeOffload => write!(f, "PRIME render offload"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HybridInfo {
+    pub role: HybridRole,
+    /// The GPU is runtime-suspended to save power
+    pub suspended: bool,
+    pub mode: HybridMode,
+    /// Whether the mux routes the built-in panel to this GPU, where
+    /// vga_switcheroo is readable (it needs root)
+    pub drives_panel: Option<bool>,
+    /// Whether the firmware can cut the GPU's power completely (D3cold)
+    /// while idle, as muxless laptops do with the dGPU
+    pub d3cold: Option<bool>,
+}
+
+impl HybridInfo {
+    /// Short label such as "Primary" or "Offload / suspended"
+    pub fn label(&self) -> &'static str {
+        match (self.role, self.suspended) {
+            (HybridRole::Primary, _) => "Primary",
+            (HybridRole::Offload, true) => "Offload / suspended",
+            (HybridRole::Offload, false) => "Offload",
+        }
+    }
+}
+
+/// SR-IOV capability of a physical function
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SriovInfo {
+    pub total_vfs: u32,
+    pub enabled_vfs: u32,
+    /// PCI addresses of the enabled virtual functions
+    pub vf_addresses: Vec<String>,
+}
+
+/// Manufacturing process, e.g. TSMC N4 or Samsung 8N
+///
+/// Node names stopped matching feature sizes long ago, so the foundry's
+/// own name is kept next to the nominal size.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Process {
+    /// e.g. "TSMC", "Samsung", "GlobalFoundries" or "Intel"
+    pub foundry: Option<String>,
+    /// The foundry's name for the node, e.g. "N4", "8N" or "Intel 7"
+    pub node: Option<String>,
+    /// Nominal feature size in nanometres
+    pub nm: u32,
+}
+
+impl Process {
+    /// A node known only by its size
+    pub fn nm(nm: u32) -> Self {
+        Process { foundry: None, node: None, nm }
+    }
+
+    /// A foundry's node, e.g. `Process::new("TSMC", Some("N7"), 7)`
+    pub fn new(foundry: &str, node: Option<&str>, nm: u32) -> Self {
+        Process { foundry: Some(foundry.to_string()), node: node.map(String::from), nm }
+    }
+}
+
+/// "TSMC N4 (4 nm)", "Intel 7 (10 nm)", "Intel 14 nm" or "28 nm"
+impl fmt::Display for Process {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.foundry, &self.node) {
+            // Intel names its nodes after itself
+            (Some(foundry), Some(node)) if node.starts_with(foundry.as_str()) => write!(f, "{} ({} nm)", node, self.nm),
+            (Some(foundry), Some(node)) => write!(f, "{} {} ({} nm)", foundry, node, self.nm),
+            (Some(foundry), None) => write!(f, "{} {} nm", foundry, self.nm),
+            (None, _) => write!(f, "{} nm", self.nm),
+        }
+    }
+}
+
+/// Where a GPU sits on a multi-socket machine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NumaAffinity {
+    /// NUMA node of the GPU's PCIe root
+    pub node: Option<u32>,
+    /// CPUs closest to the GPU, as a list such as "0-31,64-95"
+    pub cpus: Option<String>,
+}
+
+/// Runtime power management of a PCI device, from its `power` directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimePm {
+    /// "active", "suspended", "suspending", "resuming", "error" or "unsupported"
+    pub status: String,
+    /// "auto" lets the kernel suspend the idle device, "on" keeps it powered
+    pub control: String,
+    /// PCI power state, e.g. "D0" or "D3cold"; kernels before 5.17 lack it
+    pub d_state: Option<String>,
+}
+
+/// Temperatures at which the GPU protects itself, in degrees Celsius
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ThermalLimits {
+    /// Clocks are lowered from here on
+    pub slowdown_c: Option<f64>,
+    /// The GPU switches off
+    pub shutdown_c: Option<f64>,
+}
+
+impl ThermalLimits {
+    /// The limits, or `None` when neither is known
+    pub fn new(slowdown_c: Option<f64>, shutdown_c: Option<f64>) -> Option<Self> {
+        (slowdown_c.is_some() || shutdown_c.is_some()).then_some(ThermalLimits { slowdown_c, shutdown_c })
+    }
+}
+
+/// A firmware image running on one of the GPU's microcontrollers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Firmware {
+    /// Block or microcontroller, e.g. "GuC", "HuC" or "SMC"
+    pub name: String,
+    /// Loaded version, as the driver formats it
+    pub version: Option<String>,
+    /// Load state where the driver reports one, e.g. "running"
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GpuInfo {
     pub name: String,
     pub vendor: GpuVendor,
     pub architecture: String,
     pub chip: String,
-    pub process_nm: Option<u32>,
+    pub process: Option<Process>,
+    pub die_size_mm2: Option<u32>,
+    pub transistors_million: Option<u32>,
+    /// Year the first product on the chip launched
+    pub release_year: Option<u32>,
     pub freq_mhz: u32,
     pub max_freq_mhz: u32,
+    /// Boost clock of the reference design, from the spec database
+    pub reference_boost_mhz: Option<u32>,
+    /// Core clock offset set through the driver's overclocking interface
+    pub clock_offset_mhz: Option<i32>,
     pub memory: Option<Memory>,
+    /// Highest Infinity Fabric clock of AMD GPUs in MHz
+    pub fabric_clock_mhz: Option<u32>,
+    pub ecc: Option<EccStatus>,
     pub topology: Option<Topology>,
     pub cache: Option<Cache>,
     pub pci_info: Option<PciInfo>,
     pub driver_version: Option<String>,
+    /// Kernel driver bound to the PCI device, e.g. "amdgpu" or "vfio-pci"
+    pub kernel_driver: Option<String>,
+    /// Userspace Mesa version for GPUs on Mesa-backed kernel drivers
+    pub mesa_version: Option<String>,
     pub compute_capability: Option<String>, // For NVIDIA
     pub opengl_version: Option<String>,
-    pub vulkan_version: Option<String>,
-    pub opencl_version: Option<String>,
     pub peak_performance_gflops: Option<f64>,
+    /// FP32 to FP64 throughput ratio, e.g. 2 for half-rate FP64
+    pub fp64_ratio: Option<u32>,
+    pub nvlink: Option<NvLinkStatus>,
+    /// Current GPU temperature in degrees Celsius; the edge sensor on AMD GPUs
+    pub temperature_c: Option<f64>,
+    /// Hottest spot on the die, reported by AMD GPUs
+    pub junction_temperature_c: Option<f64>,
+    pub memory_temperature_c: Option<f64>,
+    pub thermal_limits: Option<ThermalLimits>,
+    /// Current core voltage in millivolts
+    pub core_voltage_mv: Option<u32>,
+    pub performance_state: Option<PerformanceState>,
+    /// Current board power draw in watts
+    pub power_draw_w: Option<f64>,
+    /// Rated board power in watts, from the spec database
+    pub rated_power_w: Option<u32>,
+    /// Energy the GPU has used since the driver loaded, in joules
+    pub energy_j: Option<f64>,
+    /// Average power since the previous refresh of `--watch` or the daemon,
+    /// from the energy counter
+    pub average_power_w: Option<f64>,
+    /// How busy the video encoder is
+    pub encoder_utilization_percent: Option<u32>,
+    /// Per-engine load, only sampled in detailed mode
+    pub engine_load: Vec<EngineLoad>,
+    pub fan: Option<FanSpeed>,
+    pub pcie_link: Option<PcieLink>,
+    /// The fastest link the GPU supports
+    pub pcie_max_link: Option<PcieLink>,
+    /// Label of the physical slot the GPU sits in, e.g. "1" or "PCIE2"
+    pub pci_slot: Option<String>,
     pub is_integrated: bool,
+    /// System memory an integrated GPU can map beyond its own carve-out
+    /// (GTT on amdgpu), in bytes
+    pub shared_memory_bytes: Option<u64>,
+    /// VRAM in use when detected, in bytes
+    pub memory_used_bytes: Option<u64>,
+    /// Shared system memory in use by an integrated GPU, in bytes
+    pub shared_memory_used_bytes: Option<u64>,
+    /// Headless datacenter card (Tesla, Instinct) without display outputs
+    pub compute_only: bool,
+    pub uuid: Option<String>,
+    pub serial: Option<String>,
+    pub vbios_version: Option<String>,
+    /// VRAM manufacturer, e.g. "Samsung"
+    pub memory_vendor: Option<String>,
+    pub sriov: Option<SriovInfo>,
+    pub runtime_pm: Option<RuntimePm>,
+    pub numa: Option<NumaAffinity>,
+    /// PCI address of the parent device when this GPU is an SR-IOV virtual function
+    pub physical_function: Option<String>,
+    /// Role in a hybrid graphics setup, if the system has one
+    pub hybrid: Option<HybridInfo>,
+    pub displays: Vec<DisplayInfo>,
+    /// Only queried when asked for with `--vaapi`
+    pub vaapi: Option<VaapiInfo>,
+    pub features: Option<Features>,
+    pub runtime: Option<Runtime>,
+    /// Firmware the kernel driver loaded, e.g. GuC or SMC
+    pub firmware: Vec<Firmware>,
+    /// Kernel command-line parameters concerning the GPU, e.g. "nomodeset"
+    pub kernel_parameters: Vec<String>,
+    /// modprobe.d options of the GPU's driver modules, e.g.
+    /// "nvidia_drm.modeset=Y", and the blacklisted ones
+    pub module_options: Vec<String>,
+    /// Driver problems found by the health check
+    pub warnings: Vec<String>,
+    /// Fields filled in from the spec database, user overrides or name
+    /// heuristics, by spec field name
+    #[serde(deserialize_with = "static_keys")]
+    pub provenance: BTreeMap<&'static str, Source>,
 }
 
 impl GpuInfo {
@@ -127,71 +620,213 @@ impl GpuInfo {
             vendor,
             architecture: String::from("Unknown"),
             chip: String::from("Unknown"),
-            process_nm: None,
+            process: None,
+            die_size_mm2: None,
+            transistors_million: None,
+            release_year: None,
             freq_mhz: 0,
             max_freq_mhz: 0,
+            reference_boost_mhz: None,
+            clock_offset_mhz: None,
             memory: None,
+            fabric_clock_mhz: None,
+            ecc: None,
             topology: None,
             cache: None,
             pci_info: None,
             driver_version: None,
+            kernel_driver: None,
+            mesa_version: None,
             compute_capability: None,
             opengl_version: None,
-            vulkan_version: None,
-            opencl_version: None,
             peak_performance_gflops: None,
+            fp64_ratio: None,
+            nvlink: None,
+            temperature_c: None,
+            junction_temperature_c: None,
+            memory_temperature_c: None,
+            thermal_limits: None,
+            core_voltage_mv: None,
+            performance_state: None,
+            power_draw_w: None,
+            rated_power_w: None,
+            energy_j: None,
+            average_power_w: None,
+            encoder_utilization_percent: None,
+            engine_load: Vec::new(),
+            fan: None,
+            pcie_link: None,
+            pcie_max_link: None,
+            pci_slot: None,
             is_integrated: false,
+            shared_memory_bytes: None,
+            memory_used_bytes: None,
+            shared_memory_used_bytes: None,
+            compute_only: false,
+            uuid: None,
+            serial: None,
+            vbios_version: None,
+            memory_vendor: None,
+            sriov: None,
+            runtime_pm: None,
+            numa: None,
+            physical_function: None,
+            hybrid: None,
+            displays: Vec::new(),
+            vaapi: None,
+            features: None,
+            runtime: None,
+            firmware: Vec::new(),
+            kernel_parameters: Vec::new(),
+            module_options: Vec::new(),
+            warnings: Vec::new(),
+            provenance: BTreeMap::new(),
         }
     }
     
-    pub fn get_memory_size_readable(&self) -> String {
-        if let Some(ref memory) = self.memory {
-            let size_mb = memory.size_bytes / 1024 / 1024;
-            if size_mb >= 1024 {
-                format!("{:.1} GB", size_mb as f64 / 1024.0)
-            } else {
-                format!("{} MB", size_mb)
-            }
-        } else {
-            String::from("Unknown")
-        }
+    /// Effective memory data rate per pin in Gbps
+    ///
+    /// Drivers report different clocks of the same memory: nvidia-smi
+    /// reports half the data rate for every type, amdgpu the command clock,
+    /// which GDDR6 transfers 16 times and GDDR5 4 times per cycle.
+    pub fn memory_data_rate_gbps(&self) -> Option<f64> {
+        let memory = self.memory.as_ref().filter(|memory| memory.clock_mhz > 0)?;
+        let transfers_per_clock = match (&self.vendor, &memory.memory_type) {
+            (_, MemoryType::Unknown | MemoryType::Ddr3 | MemoryType::Ddr4 | MemoryType::Lpddr5) => return None,
+            (GpuVendor::Nvidia, _) => 2,
+            (GpuVendor::Amd, MemoryType::Gddr6 | MemoryType::Gddr6X) => 16,
+            (GpuVendor::Amd, MemoryType::Gddr5 | MemoryType::Gddr5X) => 4,
+            (GpuVendor::Amd, MemoryType::Hbm | MemoryType::Hbm2) => 2,
+            _ => return None,
+        };
+        Some(f64::from(memory.clock_mhz) * f64::from(transfers_per_clock) / 1000.0)
     }
     
-    pub fn get_process_readable(&self) -> String {
-        match self.process_nm {
-            Some(nm) => format!("{} nm", nm),
-            None => String::from("Unknown"),
+    /// Record that these fields were guessed from the model name
+    pub fn mark_heuristic(&mut self, fields: &[&'static str]) {
+        for &field in fields {
+            self.provenance.insert(field, Source::Heuristic);
         }
     }
     
-    pub fn get_compute_units_readable(&self) -> String {
-        if let Some(ref topology) = self.topology {
-            match self.vendor {
-                GpuVendor::Nvidia => {
-                    if let Some(cores) = topology.cuda_cores {
-                        format!("{} CUDA Cores", cores)
-                    } else {
-                        String::from("Unknown CUDA Cores")
+    /// Forget every value guessed from the model name or looked up in the
+    /// spec database, keeping what was read from the system and the user's
+    /// overrides
+    ///
+    /// Peak performance goes too when the core count it was worked out from
+    /// was guessed.
+    pub fn retain_measured(&mut self) {
+        let guessed: Vec<&'static str> = self
+            .provenance
+            .iter()
+            .filter(|(_, source)| matches!(source, Source::SpecDb | Source::Heuristic))
+            .map(|(field, _)| *field)
+            .collect();
+
+        for field in guessed {
+            match field {
+                "architecture" => self.architecture = String::from("Unknown"),
+                "chip" => self.chip = String::from("Unknown"),
+                "process_nm" => self.process = None,
+                "die" => {
+                    self.die_size_mm2 = None;
+                    self.transistors_million = None;
+                    self.release_year = None;
+                }
+                "boost_clock_mhz" => self.reference_boost_mhz = None,
+                "board_power_w" => self.rated_power_w = None,
+                "max_freq_mhz" => self.max_freq_mhz = 0,
+                "freq_mhz" => self.freq_mhz = 0,
+                "compute_capability" => self.compute_capability = None,
+                "fp64_ratio" => self.fp64_ratio = None,
+                "memory" => self.memory = None,
+                "memory_type" | "bus_width" => {
+                    if let Some(ref mut memory) = self.memory {
+                        if field == "memory_type" {
+                            memory.memory_type = MemoryType::Unknown;
+                        } else {
+                            memory.bus_width = 0;
+                        }
                     }
                 }
-                GpuVendor::Amd => {
-                    if let Some(sps) = topology.stream_processors {
-                        format!("{} Stream Processors", sps)
-                    } else {
-                        String::from("Unknown Stream Processors")
+                "l1_kb" => {
+                    if let Some(ref mut cache) = self.cache {
+                        cache.l1_size = None;
                     }
                 }
-                GpuVendor::Intel => {
-                    if let Some(eus) = topology.execution_units {
-                        format!("{} Execution Units", eus)
-                    } else {
-                        String::from("Unknown Execution Units")
+                "l2_kb" => {
+                    if let Some(ref mut cache) = self.cache {
+                        cache.l2_size = None;
+                    }
+                }
+                "l3_kb" => {
+                    if let Some(ref mut cache) = self.cache {
+                        cache.l3_size = None;
+                    }
+                }
+                // The remaining fields are unit counts; a guessed compute unit
+                // count takes the whole topology with it
+                field if Topology::COMPUTE_UNITS.contains(&field) => self.topology = None,
+                _ => {
+                    if let Some(ref mut topology) = self.topology {
+                        if let Some(slot) = topology.count_mut(field) {
+                            *slot = None;
+                        }
+                    }
+                    if matches!(field, "cuda_cores" | "stream_processors") {
+                        self.peak_performance_gflops = None;
                     }
                 }
-                _ => format!("{} Compute Units", topology.compute_units),
             }
-        } else {
-            String::from("Unknown Compute Units")
+            self.provenance.remove(field);
+        }
+
+        if self.topology.is_none() || self.max_freq_mhz == 0 {
+            self.peak_performance_gflops = None;
+        }
+        if self.cache.as_ref().is_some_and(|cache| cache.l2_size.is_none() && cache.l3_size.is_none()) {
+            self.cache = None;
         }
     }
+    
+    /// Peak FP64 throughput, when the FP64 rate of the model is known
+    pub fn peak_fp64_gflops(&self) -> Option<f64> {
+        match (self.peak_performance_gflops, self.fp64_ratio) {
+            (Some(peak), Some(ratio)) if ratio > 0 => Some(peak / ratio as f64),
+            _ => None,
+        }
+    }
+    
+    pub fn get_compute_units_readable(&self) -> String {
+        match self.topology {
+            Some(Topology::Nvidia(ref nvidia)) => match nvidia.cuda_cores {
+                Some(cores) => format!("{} CUDA Cores", cores),
+                None => String::from("Unknown CUDA Cores"),
+            },
+            Some(Topology::Amd(ref amd)) => match amd.stream_processors {
+                Some(sps) => format!("{} Stream Processors", sps),
+                None => String::from("Unknown Stream Processors"),
+            },
+            Some(Topology::Intel(ref intel)) => format!("{} Execution Units", intel.execution_units),
+            Some(Topology::Generic { compute_units }) => format!("{} Compute Units", compute_units),
+            None => String::from("Unknown Compute Units"),
+        }
+    }
+}
+
+/// Names read back for a `&'static str` field live for the rest of the
+/// process; a replayed report holds only a handful of them
+fn leak(name: String) -> &'static str {
+    Box::leak(name.into_boxed_str())
+}
+
+/// Read a list of names such as throttle reasons
+fn static_strs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<&'static str>, D::Error> {
+    Ok(Vec::<String>::deserialize(deserializer)?.into_iter().map(leak).collect())
+}
+
+/// Read the provenance map, keyed by spec field name
+fn static_keys<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BTreeMap<&'static str, Source>, D::Error> {
+    let provenance = BTreeMap::<String, Source>::deserialize(deserializer)?;
+    Ok(provenance.into_iter().map(|(field, source)| (leak(field), source)).collect())
 }
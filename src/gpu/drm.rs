@@ -0,0 +1,117 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::gpu::common::{DisplayInfo, GpuInfo};
+use crate::gpu::{edid, pci, sysroot};
+
+/// Where the kernel lists DRM cards and their connectors
+const DRM_CLASS_PATH: &str = "/sys/class/drm";
+
+/// A display connector on a DRM card, such as `eDP-1` or `HDMI-A-1`
+#[derive(Debug, Clone)]
+pub struct Connector {
+    pub name: String,
+    pub path: PathBuf,
+    pub connected: bool,
+}
+
+/// DRM card directories (`/sys/class/drm/cardN`) backed by the PCI device at `address`
+pub fn cards_for_device(address: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(sysroot::path(DRM_CLASS_PATH)) else {
+        return Vec::new();
+    };
+
+    let mut cards: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| is_card_dir(path))
+        .filter(|path| {
+            pci::read_pci_info(&path.join("device")).is_ok_and(|pci_info| pci_info.address() == address)
+        })
+        .collect();
+    cards.sort();
+    cards
+}
+
+/// Connectors of a DRM card, in name order
+pub fn connectors(card: &Path) -> Vec<Connector> {
+    let Some(card_name) = card.file_name().and_then(|name| name.to_str()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}-", card_name);
+    let Ok(entries) = fs::read_dir(card) else {
+        return Vec::new();
+    };
+
+    let mut connectors: Vec<Connector> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let name = file_name.strip_prefix(&prefix)?.to_string();
+            let path = entry.path();
+            let connected = fs::read_to_string(path.join("status")).is_ok_and(|status| status.trim() == "connected");
+            Some(Connector { name, path, connected })
+        })
+        .collect();
+    connectors.sort_by(|a, b| a.name.cmp(&b.name));
+    connectors
+}
+
+/// Whether the PCI device at `address` has any display connectors
+///
+/// Returns `None` when no DRM card is bound to it, e.g. with the NVIDIA
+/// driver loaded without modesetting.
+pub fn has_outputs(address: &str) -> Option<bool> {
+    let cards = cards_for_device(address);
+    if cards.is_empty() {
+        return None;
+    }
+    Some(cards.iter().any(|card| !connectors(card).is_empty()))
+}
+
+/// PCI address of a DRM node such as `card0` or `renderD128`
+pub fn node_address(node: &str) -> Option<String> {
+    let device = sysroot::path(DRM_CLASS_PATH).join(node).join("device");
+    pci::read_pci_info(&device).ok().map(|pci_info| pci_info.address())
+}
+
+/// Render node (`/dev/dri/renderDN`) of the PCI device at `address`
+pub fn render_node(address: &str) -> Option<PathBuf> {
+    let entries = fs::read_dir(sysroot::path(DRM_CLASS_PATH)).ok()?;
+
+    let mut nodes: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("renderD"))
+        .filter(|entry| {
+            pci::read_pci_info(&entry.path().join("device")).is_ok_and(|pci_info| pci_info.address() == address)
+        })
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    nodes.sort();
+    nodes.first().map(|node| Path::new("/dev/dri").join(node))
+}
+
+/// Fill in the monitors connected to a GPU
+pub fn read_displays(gpu: &mut GpuInfo) {
+    let Some(ref pci_info) = gpu.pci_info else {
+        return;
+    };
+
+    gpu.displays = cards_for_device(&pci_info.address())
+        .iter()
+        .flat_map(|card| connectors(card))
+        .filter(|connector| connector.connected)
+        .map(|connector| DisplayInfo {
+            edid: fs::read(connector.path.join("edid")).ok().and_then(|data| edid::parse(&data)),
+            connector: connector.name,
+        })
+        .collect();
+}
+
+/// Whether `path` is a `cardN` directory rather than a connector or render node
+fn is_card_dir(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_prefix("card"))
+        .is_some_and(|rest| !rest.is_empty() && rest.chars().all(|c| c.is_ascii_digit()))
+}
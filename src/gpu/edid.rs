@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+
+/// Fixed pattern every EDID base block starts with
+const EDID_HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+
+/// Size of the EDID base block
+const EDID_BLOCK_LEN: usize = 128;
+
+/// Offsets of the four 18-byte descriptors in the base block
+const DESCRIPTOR_OFFSETS: [usize; 4] = [54, 72, 90, 108];
+
+/// Display descriptor tag holding the monitor name
+const DESCRIPTOR_TAG_NAME: u8 = 0xfc;
+
+/// The parts of an EDID blob gpufetch shows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Edid {
+    /// Three-letter PNP manufacturer code, e.g. "DEL"
+    pub manufacturer: String,
+    pub product_code: u16,
+    pub name: Option<String>,
+    /// Preferred (native) mode from the first detailed timing descriptor
+    pub native_mode: Option<Mode>,
+    /// Physical size in centimetres, if the monitor reports it
+    pub size_cm: Option<(u8, u8)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mode {
+    pub width: u32,
+    pub height: u32,
+    pub refresh_hz: u32,
+}
+
+/// Parse the base block of an EDID blob
+pub fn parse(data: &[u8]) -> Option<Edid> {
+    if data.len() < EDID_BLOCK_LEN || data[..8] != EDID_HEADER {
+        return None;
+    }
+
+    let name = DESCRIPTOR_OFFSETS
+        .iter()
+        .map(|&offset| &data[offset..offset + 18])
+        .find(|descriptor| is_display_descriptor(descriptor) && descriptor[3] == DESCRIPTOR_TAG_NAME)
+        .map(descriptor_text)
+        .filter(|name| !name.is_empty());
+
+    let native_mode = DESCRIPTOR_OFFSETS
+        .iter()
+        .map(|&offset| &data[offset..offset + 18])
+        .find(|descriptor| !is_display_descriptor(descriptor))
+        .and_then(parse_detailed_timing);
+
+    let size_cm = match (data[21], data[22]) {
+        (0, _) | (_, 0) => None,
+        (width, height) => Some((width, height)),
+    };
+
+    Some(Edid {
+        manufacturer: manufacturer_code(u16::from_be_bytes([data[8], data[9]])),
+        product_code: u16::from_le_bytes([data[10], data[11]]),
+        name,
+        native_mode,
+        size_cm,
+    })
+}
+
+/// Display descriptors have a zero pixel clock, timing descriptors do not
+fn is_display_descriptor(descriptor: &[u8]) -> bool {
+    descriptor[0] == 0 && descriptor[1] == 0
+}
+
+/// Text payload of a display descriptor, which ends at a newline
+fn descriptor_text(descriptor: &[u8]) -> String {
+    let text = &descriptor[5..18];
+    let end = text.iter().position(|&b| b == b'\n').unwrap_or(text.len());
+    String::from_utf8_lossy(&text[..end]).trim().to_string()
+}
+
+/// Resolution and refresh rate of a detailed timing descriptor
+fn parse_detailed_timing(descriptor: &[u8]) -> Option<Mode> {
+    // Pixel clock in units of 10 kHz
+    let pixel_clock_hz = u64::from(u16::from_le_bytes([descriptor[0], descriptor[1]])) * 10_000;
+
+    let width = u32::from(descriptor[2]) | (u32::from(descriptor[4] & 0xf0) << 4);
+    let h_blank = u32::from(descriptor[3]) | (u32::from(descriptor[4] & 0x0f) << 8);
+    let height = u32::from(descriptor[5]) | (u32::from(descriptor[7] & 0xf0) << 4);
+    let v_blank = u32::from(descriptor[6]) | (u32::from(descriptor[7] & 0x0f) << 8);
+
+    let total_pixels = u64::from(width + h_blank) * u64::from(height + v_blank);
+    if width == 0 || height == 0 || total_pixels == 0 {
+        return None;
+    }
+
+    Some(Mode {
+        width,
+        height,
+        refresh_hz: ((pixel_clock_hz + total_pixels / 2) / total_pixels) as u32,
+    })
+}
+
+/// Decode the packed 5-bit letters of a PNP manufacturer ID
+fn manufacturer_code(id: u16) -> String {
+    [10, 5, 0]
+        .iter()
+        .map(|shift| char::from(b'A' - 1 + ((id >> shift) & 0x1f) as u8))
+        .collect()
+}
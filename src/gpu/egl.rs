@@ -0,0 +1,241 @@
+//! OpenGL version of each GPU from an offscreen EGL context.
+//!
+//! EGL_EXT_platform_device opens a GPU by its DRM node, so this works on
+//! Wayland, on X11 without `DISPLAY` and on headless machines alike. The
+//! context is never drawn to; it only has to be current for glGetString.
+//!
+//! libEGL is loaded at runtime like NVML. It is not closed again: Mesa and
+//! the NVIDIA driver register exit handlers that must still be mapped when
+//! the process ends.
+
+use anyhow::{anyhow, bail, Result};
+use std::ffi::{c_char, c_uint, c_void, CStr};
+use std::ptr;
+
+use crate::gpu::common::GpuInfo;
+use crate::gpu::{drm, snapshot};
+
+const LIBRARY: &CStr = c"libEGL.so.1";
+
+type EglInt = i32;
+type EglBoolean = c_uint;
+type Handle = *mut c_void;
+
+const EGL_NONE: EglInt = 0x3038;
+const EGL_EXTENSIONS: EglInt = 0x3055;
+const EGL_SURFACE_TYPE: EglInt = 0x3033;
+const EGL_PBUFFER_BIT: EglInt = 0x0001;
+const EGL_RENDERABLE_TYPE: EglInt = 0x3040;
+const EGL_OPENGL_BIT: EglInt = 0x0008;
+const EGL_OPENGL_API: c_uint = 0x30A2;
+const EGL_PLATFORM_DEVICE_EXT: c_uint = 0x313F;
+const EGL_DRM_DEVICE_FILE_EXT: EglInt = 0x3233;
+const GL_VERSION: c_uint = 0x1F02;
+
+/// Most devices asked of eglQueryDevicesEXT
+const MAX_DEVICES: usize = 16;
+
+/// The EGL entry points the query uses
+struct Egl {
+    query_string: unsafe extern "C" fn(Handle, EglInt) -> *const c_char,
+    query_devices: unsafe extern "C" fn(EglInt, *mut Handle, *mut EglInt) -> EglBoolean,
+    query_device_string: unsafe extern "C" fn(Handle, EglInt) -> *const c_char,
+    platform_display: unsafe extern "C" fn(c_uint, Handle, *const EglInt) -> Handle,
+    initialize: unsafe extern "C" fn(Handle, *mut EglInt, *mut EglInt) -> EglBoolean,
+    terminate: unsafe extern "C" fn(Handle) -> EglBoolean,
+    bind_api: unsafe extern "C" fn(c_uint) -> EglBoolean,
+    choose_config: unsafe extern "C" fn(Handle, *const EglInt, *mut Handle, EglInt, *mut EglInt) -> EglBoolean,
+    create_context: unsafe extern "C" fn(Handle, Handle, Handle, *const EglInt) -> Handle,
+    destroy_context: unsafe extern "C" fn(Handle, Handle) -> EglBoolean,
+    make_current: unsafe extern "C" fn(Handle, Handle, Handle, Handle) -> EglBoolean,
+    get_string: unsafe extern "C" fn(c_uint) -> *const u8,
+}
+
+/// Fill in the OpenGL version of each GPU with a DRM node, and the Mesa
+/// version when the libraries did not give it away
+pub fn query(gpus: &mut [GpuInfo]) {
+    // The drivers answer for this machine, not the one a snapshot came from
+    if snapshot::is_replaying() || gpus.iter().all(|gpu| gpu.pci_info.is_none()) {
+        return;
+    }
+    let egl = match Egl::open() {
+        Ok(egl) => egl,
+        Err(e) => {
+            tracing::info!("no OpenGL versions: {:#}", e);
+            return;
+        }
+    };
+
+    for device in egl.devices() {
+        let Some(address) = egl.device_address(device) else {
+            continue;
+        };
+        let Some(gpu) = gpus.iter_mut().find(|gpu| gpu.pci_info.as_ref().is_some_and(|pci_info| pci_info.address() == address)) else {
+            continue;
+        };
+        match egl.opengl_version(device) {
+            Ok(version) => {
+                if gpu.mesa_version.is_none() {
+                    gpu.mesa_version = version.split_once("Mesa ").map(|(_, mesa)| mesa.trim().to_string());
+                }
+                gpu.opengl_version = Some(version);
+            }
+            Err(e) => tracing::info!("no OpenGL version for {}: {:#}", gpu.name, e),
+        }
+    }
+}
+
+impl Egl {
+    /// Load libEGL and check that it can open devices without a display
+    fn open() -> Result<Self> {
+        // SAFETY: dlopen with a constant, NUL-terminated library name
+        let library = unsafe { libc::dlopen(LIBRARY.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        if library.is_null() {
+            bail!("{} is not installed", LIBRARY.to_string_lossy());
+        }
+
+        // SAFETY: the signatures match egl.h and eglext.h for each symbol
+        let egl = unsafe { Self::resolve(library)? };
+
+        // SAFETY: EGL_NO_DISPLAY asks for the client extensions
+        let extensions = unsafe { (egl.query_string)(ptr::null_mut(), EGL_EXTENSIONS) };
+        let extensions = if extensions.is_null() {
+            String::new()
+        } else {
+            // SAFETY: EGL returns a NUL-terminated string it owns
+            unsafe { CStr::from_ptr(extensions) }.to_string_lossy().into_owned()
+        };
+        for extension in ["EGL_EXT_device_enumeration", "EGL_EXT_platform_device"] {
+            if !extensions.split(' ').any(|name| name == extension) {
+                bail!("{} lacks {}", LIBRARY.to_string_lossy(), extension);
+            }
+        }
+        Ok(egl)
+    }
+
+    /// Look up the entry points; extension functions and GL come through
+    /// eglGetProcAddress
+    ///
+    /// # Safety
+    /// `library` must be a handle returned by dlopen for libEGL.
+    unsafe fn resolve(library: *mut c_void) -> Result<Self> {
+        let proc_address: unsafe extern "C" fn(*const c_char) -> *mut c_void = symbol(library, c"eglGetProcAddress")?;
+        let extension = |name: &CStr| {
+            let address = proc_address(name.as_ptr());
+            if address.is_null() {
+                Err(anyhow!("{} lacks {}", LIBRARY.to_string_lossy(), name.to_string_lossy()))
+            } else {
+                Ok(address)
+            }
+        };
+
+        Ok(Egl {
+            query_string: symbol(library, c"eglQueryString")?,
+            query_devices: std::mem::transmute_copy(&extension(c"eglQueryDevicesEXT")?),
+            query_device_string: std::mem::transmute_copy(&extension(c"eglQueryDeviceStringEXT")?),
+            platform_display: std::mem::transmute_copy(&extension(c"eglGetPlatformDisplayEXT")?),
+            initialize: symbol(library, c"eglInitialize")?,
+            terminate: symbol(library, c"eglTerminate")?,
+            bind_api: symbol(library, c"eglBindAPI")?,
+            choose_config: symbol(library, c"eglChooseConfig")?,
+            create_context: symbol(library, c"eglCreateContext")?,
+            destroy_context: symbol(library, c"eglDestroyContext")?,
+            make_current: symbol(library, c"eglMakeCurrent")?,
+            get_string: std::mem::transmute_copy(&extension(c"glGetString")?),
+        })
+    }
+
+    /// Every device EGL can render on, software rasterizers included
+    fn devices(&self) -> Vec<Handle> {
+        let mut devices = [ptr::null_mut(); MAX_DEVICES];
+        let mut count: EglInt = 0;
+        // SAFETY: EGL writes at most MAX_DEVICES handles into the array
+        let ok = unsafe { (self.query_devices)(MAX_DEVICES as EglInt, devices.as_mut_ptr(), &mut count) };
+        if ok == 0 {
+            return Vec::new();
+        }
+        devices[..count.clamp(0, MAX_DEVICES as EglInt) as usize].to_vec()
+    }
+
+    /// PCI address of a device, through the DRM node EGL names for it
+    ///
+    /// Software devices have no node and are skipped.
+    fn device_address(&self, device: Handle) -> Option<String> {
+        // SAFETY: the device came from eglQueryDevicesEXT
+        let file = unsafe { (self.query_device_string)(device, EGL_DRM_DEVICE_FILE_EXT) };
+        if file.is_null() {
+            return None;
+        }
+        // SAFETY: EGL returns a NUL-terminated string it owns
+        let file = unsafe { CStr::from_ptr(file) }.to_string_lossy().into_owned();
+        drm::node_address(file.rsplit('/').next()?)
+    }
+
+    /// GL_VERSION of a context on the device, e.g.
+    /// "4.6 (Compatibility Profile) Mesa 24.0.5"
+    fn opengl_version(&self, device: Handle) -> Result<String> {
+        // SAFETY: the device came from eglQueryDevicesEXT, and the display
+        // is terminated before returning, which frees the context with it
+        unsafe {
+            let display = (self.platform_display)(EGL_PLATFORM_DEVICE_EXT, device, ptr::null());
+            if display.is_null() {
+                bail!("eglGetPlatformDisplayEXT failed");
+            }
+            let (mut major, mut minor) = (0, 0);
+            if (self.initialize)(display, &mut major, &mut minor) == 0 {
+                bail!("eglInitialize failed");
+            }
+            let version = self.current_version(display);
+            (self.terminate)(display);
+            version
+        }
+    }
+
+    /// Make a desktop OpenGL context current on an initialized display
+    /// and ask it for its version
+    ///
+    /// # Safety
+    /// `display` must be an initialized EGL display.
+    unsafe fn current_version(&self, display: Handle) -> Result<String> {
+        if (self.bind_api)(EGL_OPENGL_API) == 0 {
+            bail!("the driver has no desktop OpenGL");
+        }
+        let attributes = [EGL_SURFACE_TYPE, EGL_PBUFFER_BIT, EGL_RENDERABLE_TYPE, EGL_OPENGL_BIT, EGL_NONE];
+        let mut config: Handle = ptr::null_mut();
+        let mut configs: EglInt = 0;
+        if (self.choose_config)(display, attributes.as_ptr(), &mut config, 1, &mut configs) == 0 || configs == 0 {
+            bail!("no OpenGL config");
+        }
+        let context = (self.create_context)(display, config, ptr::null_mut(), [EGL_NONE].as_ptr());
+        if context.is_null() {
+            bail!("eglCreateContext failed");
+        }
+
+        // Surfaceless: no surface is drawn to, so none is created
+        let version = if (self.make_current)(display, ptr::null_mut(), ptr::null_mut(), context) == 0 {
+            Err(anyhow!("eglMakeCurrent failed"))
+        } else {
+            let version = (self.get_string)(GL_VERSION);
+            if version.is_null() {
+                Err(anyhow!("glGetString failed"))
+            } else {
+                Ok(CStr::from_ptr(version.cast()).to_string_lossy().into_owned())
+            }
+        };
+        (self.make_current)(display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+        (self.destroy_context)(display, context);
+        version
+    }
+}
+
+/// Look up a function in the library
+///
+/// # Safety
+/// `T` must be a function pointer type matching the symbol's C signature.
+unsafe fn symbol<T: Copy>(library: *mut c_void, name: &CStr) -> Result<T> {
+    let address = libc::dlsym(library, name.as_ptr());
+    if address.is_null() {
+        bail!("{} lacks {}", LIBRARY.to_string_lossy(), name.to_string_lossy());
+    }
+    Ok(std::mem::transmute_copy(&address))
+}
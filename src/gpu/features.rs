@@ -0,0 +1,105 @@
+//! Hardware ray tracing and the upscalers a GPU can run.
+//!
+//! Support follows from the architecture, since drivers do not report it
+//! outside a graphics API, and is narrowed down by the driver the GPU is
+//! bound to: DLSS needs NVIDIA's proprietary driver, and nothing works on a
+//! GPU handed to a virtual machine.
+
+use crate::gpu::common::{Features, GpuInfo, GpuVendor, NvidiaTopology, Topology};
+use crate::gpu::icd;
+
+/// Kernel drivers that only hold a device for passthrough to a VM
+pub const PASSTHROUGH_DRIVERS: [&str; 2] = ["vfio-pci", "pci-stub"];
+
+/// What the GPU supports, or `None` for GPUs without a graphics stack:
+/// headless compute cards and those bound to a passthrough driver
+///
+/// `vulkan_icds` are the installed ICD manifests; when there are some but
+/// none for this GPU, the features are flagged as unusable until a Vulkan
+/// driver is installed.
+pub fn detect(gpu: &GpuInfo, vulkan_icds: &[String]) -> Option<Features> {
+    if gpu.compute_only || gpu.kernel_driver.as_deref().is_some_and(|driver| PASSTHROUGH_DRIVERS.contains(&driver)) {
+        return None;
+    }
+
+    let (ray_tracing, upscalers) = match gpu.vendor {
+        GpuVendor::Nvidia => nvidia(gpu),
+        GpuVendor::Amd => amd(gpu),
+        GpuVendor::Intel => intel(gpu),
+        _ => (false, Vec::new()),
+    };
+    if !ray_tracing && upscalers.is_empty() {
+        return None;
+    }
+
+    Some(Features {
+        ray_tracing,
+        upscalers,
+        missing_vulkan_driver: !vulkan_icds.is_empty() && !icd::has_vulkan_driver(gpu, vulkan_icds),
+    })
+}
+
+/// RTX cards have RT and tensor cores from Turing on; the GTX 16 series
+/// shares the architecture without them
+fn nvidia(gpu: &GpuInfo) -> (bool, Vec<&'static str>) {
+    let rtx = gpu.name.to_lowercase().contains("rtx")
+        || matches!(gpu.topology, Some(Topology::Nvidia(NvidiaTopology { rt_cores: Some(cores), .. })) if cores > 0);
+    let dlss = match gpu.architecture.as_str() {
+        "Turing" | "Ampere" => Some("DLSS 2"),
+        "Ada Lovelace" => Some("DLSS 3"),
+        "Blackwell" => Some("DLSS 4"),
+        _ => None,
+    };
+    // Nouveau exposes neither ray tracing nor the DLSS runtime
+    let proprietary = gpu.kernel_driver.as_deref().is_none_or(|driver| driver == "nvidia");
+
+    let mut upscalers = Vec::new();
+    if let Some(dlss) = dlss.filter(|_| rtx && proprietary) {
+        upscalers.push(dlss);
+    }
+    upscalers.push(match gpu.architecture.as_str() {
+        "Turing" | "Ampere" | "Ada Lovelace" | "Blackwell" => "FSR 3",
+        "Pascal" | "Volta" => "FSR 2",
+        _ => "FSR 1",
+    });
+    // XeSS runs on DP4a, which arrived with Pascal
+    if !matches!(gpu.architecture.as_str(), "Maxwell" | "Kepler" | "Unknown") {
+        upscalers.push("XeSS");
+    }
+
+    (dlss.is_some() && rtx && proprietary, upscalers)
+}
+
+/// Ray accelerators and DP4a arrived with RDNA 2, FSR 4 is RDNA 4 only
+fn amd(gpu: &GpuInfo) -> (bool, Vec<&'static str>) {
+    let (ray_tracing, fsr) = match gpu.architecture.as_str() {
+        "RDNA 4" => (true, "FSR 4"),
+        "RDNA 2" | "RDNA 3" | "RDNA 3.5" => (true, "FSR 3"),
+        "RDNA" => (false, "FSR 3"),
+        "Vega" | "Polaris" => (false, "FSR 2"),
+        "Unknown" => return (false, Vec::new()),
+        _ => (false, "FSR 1"),
+    };
+
+    let mut upscalers = vec![fsr];
+    if ray_tracing {
+        upscalers.push("XeSS");
+    }
+    (ray_tracing, upscalers)
+}
+
+/// Arc cards have RT units and XMX engines; Xe integrated graphics run
+/// XeSS on DP4a
+fn intel(gpu: &GpuInfo) -> (bool, Vec<&'static str>) {
+    let arc = gpu.name.contains("Arc") || gpu.architecture.starts_with("Xe-HPG") || gpu.architecture.starts_with("Xe2");
+    if arc {
+        return (true, vec!["XeSS (XMX)", "FSR 3"]);
+    }
+
+    match gpu.architecture.as_str() {
+        "Gen12 (Xe)" => (false, vec!["XeSS", "FSR 2"]),
+        "Gen11" | "Gen9.5" | "Gen9" => (false, vec!["FSR 2"]),
+        "Unknown" => (false, Vec::new()),
+        _ => (false, vec!["FSR 1"]),
+    }
+}
@@ -0,0 +1,99 @@
+//! Versions of the firmware the kernel driver loaded onto the GPU.
+//!
+//! amdgpu lists one file per firmware block under the PCI device's
+//! `fw_version` directory. i915 and xe only report GuC and HuC in debugfs,
+//! under `/sys/kernel/debug/dri/<card or PCI address>`, which is readable
+//! by root alone; without it Intel GPUs just have no firmware listed.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::gpu::common::{Firmware, GpuInfo};
+use crate::gpu::{drm, pci, sysroot};
+
+/// Where debugfs keeps a directory per DRM device
+const DRI_DEBUGFS_PATH: &str = "/sys/kernel/debug/dri";
+
+/// Intel microcontrollers with an info file, and the file's name
+const INTEL_UC_FIRMWARE: [(&str, &str); 3] = [("GuC", "guc_info"), ("HuC", "huc_info"), ("GSC", "gsc_info")];
+
+/// Fill in the firmware the GPU's driver loaded
+pub fn read_firmware(gpu: &mut GpuInfo) {
+    let Some(ref pci_info) = gpu.pci_info else {
+        return;
+    };
+    let address = pci_info.address();
+
+    gpu.firmware = match gpu.kernel_driver.as_deref() {
+        Some("amdgpu") => amd_firmware(&pci::device_path(&address)),
+        Some("i915" | "xe") => intel_firmware(&address),
+        _ => Vec::new(),
+    };
+}
+
+/// Every nonzero `<block>_fw_version`, e.g. "SMC" and "0x004c4900"
+///
+/// Blocks the GPU does not have read as zero.
+fn amd_firmware(device_path: &Path) -> Vec<Firmware> {
+    let mut firmware: Vec<Firmware> = fs::read_dir(device_path.join("fw_version"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name();
+            let block = file_name.to_str()?.strip_suffix("_fw_version")?;
+            let version = fs::read_to_string(entry.path()).ok()?.trim().to_string();
+            let nonzero = u64::from_str_radix(version.trim_start_matches("0x"), 16).is_ok_and(|value| value > 0);
+            nonzero.then(|| Firmware { name: block.to_uppercase(), version: Some(version), status: None })
+        })
+        .collect();
+    firmware.sort_by(|a, b| a.name.cmp(&b.name));
+    firmware
+}
+
+/// GuC, HuC and GSC from the first debugfs directory of the device that
+/// has their info files
+fn intel_firmware(address: &str) -> Vec<Firmware> {
+    let root = sysroot::path(DRI_DEBUGFS_PATH);
+    // xe names the directory after the PCI address, i915 after the card minor
+    let mut dirs = vec![root.join(address)];
+    dirs.extend(
+        drm::cards_for_device(address)
+            .iter()
+            .filter_map(|card| card.file_name()?.to_str()?.strip_prefix("card").map(|minor| root.join(minor))),
+    );
+
+    let uc_dirs: Vec<PathBuf> = dirs.iter().flat_map(|dir| [dir.join("gt0/uc"), dir.join("gt/uc")]).collect();
+    let Some(uc_dir) = uc_dirs.iter().find(|dir| dir.join("guc_info").is_file()) else {
+        return Vec::new();
+    };
+
+    INTEL_UC_FIRMWARE
+        .iter()
+        .filter_map(|&(name, file)| {
+            let info = fs::read_to_string(uc_dir.join(file)).ok()?;
+            Some(parse_uc_info(name, &info))
+        })
+        .collect()
+}
+
+/// Parse a `guc_info`-style file:
+///
+/// ```text
+/// GuC firmware: i915/adlp_guc_70.bin
+///     status: RUNNING
+///     version: wanted 70.5, found 70.5.1
+/// ```
+fn parse_uc_info(name: &str, info: &str) -> Firmware {
+    let field = |key: &str| info.lines().find_map(|line| line.trim().strip_prefix(key).map(str::trim));
+    // "wanted" is what the driver asked for, "found" what it loaded
+    let version = field("version:")
+        .and_then(|version| version.split(", ").find_map(|part| part.strip_prefix("found ")))
+        .map(str::to_string);
+
+    Firmware {
+        name: name.to_string(),
+        version,
+        status: field("status:").map(|status| status.to_lowercase().replace('_', " ")),
+    }
+}
@@ -0,0 +1,108 @@
+//! Sensors of AMD GPUs from the amdgpu `gpu_metrics` table.
+//!
+//! The driver exposes one binary snapshot of the SMU's metrics per read,
+//! so temperatures, power and clocks come from the same instant. The table
+//! starts with a header naming its layout: format 1 is the discrete GPU
+//! table (revisions 1–3 share the fields read here), format 2 the APU one.
+//! Unsupported sensors read as 0xffff.
+
+use std::fs;
+
+use crate::gpu::common::{EngineLoad, GpuInfo, GpuVendor};
+use crate::gpu::pci;
+
+/// Field value for sensors the GPU does not have
+const UNSUPPORTED: u16 = 0xffff;
+
+/// What one table holds, in the units the rest of gpufetch uses
+#[derive(Debug, Default)]
+struct Metrics {
+    edge_c: Option<f64>,
+    junction_c: Option<f64>,
+    memory_c: Option<f64>,
+    power_w: Option<f64>,
+    energy_j: Option<f64>,
+    gfxclk_mhz: Option<u32>,
+    /// Busy percentages of the graphics, memory controller and media engines
+    activity: Vec<(&'static str, f64)>,
+}
+
+/// Fill in sensors of an amdgpu GPU from its metrics table
+///
+/// Values from the table replace the clock read from the DPM levels, which
+/// only names the level in use; other values are only filled in where the
+/// backends left gaps, which hwmon then fills further.
+pub fn read(gpu: &mut GpuInfo) {
+    if gpu.vendor != GpuVendor::Amd {
+        return;
+    }
+    let Some(ref pci_info) = gpu.pci_info else {
+        return;
+    };
+    let Ok(table) = fs::read(pci::device_path(&pci_info.address()).join("gpu_metrics")) else {
+        return;
+    };
+    let Some(metrics) = parse(&table) else {
+        tracing::debug!("unsupported gpu_metrics table of {} bytes", table.len());
+        return;
+    };
+
+    gpu.temperature_c = gpu.temperature_c.or(metrics.edge_c);
+    gpu.junction_temperature_c = gpu.junction_temperature_c.or(metrics.junction_c);
+    gpu.memory_temperature_c = gpu.memory_temperature_c.or(metrics.memory_c);
+    gpu.power_draw_w = gpu.power_draw_w.or(metrics.power_w);
+    gpu.energy_j = gpu.energy_j.or(metrics.energy_j);
+    if let Some(mhz) = metrics.gfxclk_mhz {
+        gpu.freq_mhz = mhz;
+    }
+    if gpu.engine_load.is_empty() {
+        gpu.engine_load = metrics
+            .activity
+            .into_iter()
+            .map(|(name, busy_percent)| EngineLoad { name: name.to_string(), busy_percent })
+            .collect();
+    }
+}
+
+/// Decode a table of a layout we know
+fn parse(table: &[u8]) -> Option<Metrics> {
+    let u16_at = |offset: usize| {
+        let bytes = table.get(offset..offset + 2)?;
+        Some(u16::from_le_bytes([bytes[0], bytes[1]])).filter(|&value| value != UNSUPPORTED)
+    };
+    let u64_at = |offset: usize| Some(u64::from_le_bytes(table.get(offset..offset + 8)?.try_into().ok()?));
+    let structure_size = usize::from(u16_at(0)?);
+    let (format, content) = (*table.get(2)?, *table.get(3)?);
+    if structure_size > table.len() {
+        return None;
+    }
+
+    let percent = |offset: usize| u16_at(offset).map(f64::from).filter(|&percent| percent <= 100.0);
+    let activity = |fields: &[(&'static str, usize)]| {
+        fields.iter().filter_map(|&(name, offset)| Some((name, percent(offset)?))).collect()
+    };
+    match (format, content) {
+        // Degrees Celsius and watts
+        (1, 1..=3) => Some(Metrics {
+            edge_c: u16_at(4).map(f64::from),
+            junction_c: u16_at(6).map(f64::from),
+            memory_c: u16_at(8).map(f64::from),
+            power_w: u16_at(22).map(f64::from),
+            // The SMU counts in steps of 15.259 microjoules
+            energy_j: u64_at(24).filter(|&steps| steps > 0).map(|steps| steps as f64 * 15.259e-6),
+            gfxclk_mhz: u16_at(40).map(u32::from),
+            activity: activity(&[("Graphics", 16), ("Memory Controller", 18), ("Media", 20)]),
+        }),
+        // Hundredths of a degree and milliwatts
+        (2, _) => Some(Metrics {
+            edge_c: u16_at(16).map(|centidegrees| f64::from(centidegrees) / 100.0),
+            junction_c: None,
+            memory_c: None,
+            power_w: u16_at(44).map(|milliwatts| f64::from(milliwatts) / 1000.0),
+            energy_j: None,
+            gfxclk_mhz: u16_at(68).map(u32::from),
+            activity: activity(&[("Graphics", 40), ("Media", 42)]),
+        }),
+        _ => None,
+    }
+}
@@ -0,0 +1,155 @@
+//! Driver problems that leave a GPU half working or not working at all.
+//!
+//! These are the usual reasons for a GPU showing up without a driver
+//! version or clocks: two drivers fighting over an NVIDIA card, a card
+//! reserved for a virtual machine, an unsigned module rejected by Secure
+//! Boot, or no driver installed in the first place.
+
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use crate::gpu::common::{GpuInfo, GpuVendor};
+use crate::gpu::sysroot;
+
+/// EFI variable holding the Secure Boot state, in the global variable GUID
+pub const SECURE_BOOT_VARIABLE: &str = "/sys/firmware/efi/efivars/SecureBoot-8be4df61-93ca-11d2-aa0d-e0f1256cadb8";
+
+/// Lockdown modes with the active one in brackets, e.g. "none [integrity] confidentiality"
+pub const LOCKDOWN_PATH: &str = "/sys/kernel/security/lockdown";
+
+/// "Y" when the kernel was booted with `module.sig_enforce=1` or built to enforce signatures
+pub const SIG_ENFORCE_PATH: &str = "/sys/module/module/parameters/sig_enforce";
+
+/// Release of the running kernel, naming its directory under `/lib/modules`
+pub const OSRELEASE_PATH: &str = "/proc/sys/kernel/osrelease";
+
+/// What ends a module file with an appended signature
+const SIGNATURE_MARKER: &[u8] = b"~Module signature appended~\n";
+
+/// Whether a kernel module is loaded, or built into the kernel
+pub fn module_loaded(name: &str) -> bool {
+    sysroot::path("/sys/module").join(name).is_dir()
+}
+
+/// Whether the firmware enforces Secure Boot, or `None` on BIOS systems
+///
+/// The variable starts with four bytes of attributes, followed by the
+/// one-byte value.
+pub fn secure_boot_enabled() -> Option<bool> {
+    let variable = fs::read(sysroot::path(SECURE_BOOT_VARIABLE)).ok()?;
+    variable.get(4).map(|&value| value == 1)
+}
+
+/// Why the kernel only loads signed modules, if it does
+///
+/// Distribution kernels lock themselves down under Secure Boot, which is
+/// what makes them refuse unsigned modules; a kernel can also enforce
+/// signatures on its own.
+pub fn signature_enforcement() -> Option<&'static str> {
+    let read = |path: &str| fs::read_to_string(sysroot::path(path)).ok();
+    let locked_down = read(LOCKDOWN_PATH).is_some_and(|modes| !modes.contains("[none]") && modes.contains('['));
+    if secure_boot_enabled() == Some(true) {
+        Some("Secure Boot is enabled")
+    } else if read(SIG_ENFORCE_PATH).is_some_and(|value| value.trim() == "Y") {
+        Some("the kernel only loads signed modules")
+    } else if locked_down {
+        Some("the kernel is locked down")
+    } else {
+        None
+    }
+}
+
+/// Where the running kernel's module index puts a module
+enum Installed {
+    At(PathBuf),
+    Missing,
+    /// The index could not be read
+    Unknown,
+}
+
+fn installed_module(name: &str) -> Installed {
+    let Ok(release) = fs::read_to_string(sysroot::path(OSRELEASE_PATH)) else {
+        return Installed::Unknown;
+    };
+    let modules_dir = sysroot::path("/lib/modules").join(release.trim());
+    let Ok(index) = fs::read_to_string(modules_dir.join("modules.dep")) else {
+        return Installed::Unknown;
+    };
+    // Lines are "path/nvidia.ko.zst: dependencies", compressed or not
+    let file_name = format!("{}.ko", name);
+    index
+        .lines()
+        .filter_map(|line| line.split_once(':').map(|(path, _)| Path::new(path)))
+        .find(|path| {
+            let found = path.file_name().unwrap_or_default().to_string_lossy();
+            found == file_name || found.strip_prefix(&file_name).is_some_and(|rest| rest.starts_with('.'))
+        })
+        .map_or(Installed::Missing, |path| Installed::At(modules_dir.join(path)))
+}
+
+/// Whether a module file carries a signature, or `None` for compressed
+/// files, which are signed before compression
+fn is_signed(path: &Path) -> Option<bool> {
+    if path.extension()? != "ko" {
+        return None;
+    }
+    let mut file = File::open(path).ok()?;
+    let mut tail = [0; SIGNATURE_MARKER.len()];
+    file.seek(SeekFrom::End(-(SIGNATURE_MARKER.len() as i64))).ok()?;
+    file.read_exact(&mut tail).ok()?;
+    Some(tail == SIGNATURE_MARKER)
+}
+
+/// Why the installed nvidia module is not loaded, when the kernel only
+/// takes signed modules
+///
+/// Without it NVIDIA GPUs have no driver for nvidia-smi or NVML to reach
+/// them through, so they would otherwise just go missing.
+pub fn blocked_nvidia_module() -> Option<String> {
+    let enforcement = signature_enforcement()?;
+    if module_loaded("nvidia") {
+        return None;
+    }
+    let signed = match installed_module("nvidia") {
+        Installed::At(path) => is_signed(&path),
+        Installed::Missing => return None,
+        Installed::Unknown => None,
+    };
+    let reason = match signed {
+        Some(false) => "the nvidia module is not signed, so the kernel refuses it: sign it with a key enrolled through mokutil",
+        Some(true) => "the nvidia module is signed, but the kernel refuses it: enroll its signing key with mokutil --import",
+        None => "the nvidia module is not loaded, as an unsigned module is refused: sign it or enroll its key with mokutil",
+    };
+    Some(format!("{}; {}", enforcement, reason))
+}
+
+/// Fill in the warnings of every GPU, `blocked_nvidia` being why the
+/// kernel refused the nvidia module, if it did
+pub fn check(gpus: &mut [GpuInfo], blocked_nvidia: Option<&str>) {
+    let nvidia_loaded = module_loaded("nvidia");
+    let nouveau_loaded = module_loaded("nouveau");
+
+    for gpu in gpus {
+        // Only PCI devices have a driver binding to check
+        if gpu.pci_info.is_none() {
+            continue;
+        }
+
+        let nvidia = gpu.vendor == GpuVendor::Nvidia;
+        if nvidia && nvidia_loaded && nouveau_loaded {
+            gpu.warnings
+                .push("both nouveau and nvidia are loaded; blacklist nouveau to let nvidia drive this GPU".to_string());
+        }
+
+        match (gpu.kernel_driver.as_deref(), blocked_nvidia.filter(|_| nvidia)) {
+            (Some("vfio-pci"), _) => {
+                gpu.warnings.push("bound to vfio-pci, reserved for passthrough to a virtual machine".to_string())
+            }
+            (Some("nouveau"), Some(blocked)) => gpu.warnings.push(format!("driven by nouveau instead of nvidia: {}", blocked)),
+            (Some(_), _) => {}
+            (None, Some(blocked)) => gpu.warnings.push(format!("no driver bound: {}", blocked)),
+            (None, None) => gpu.warnings.push("no kernel driver bound; install or load the driver for this GPU".to_string()),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! Sensors the kernel driver exposes through hwmon.
+//!
+//! amdgpu, i915/xe and nouveau register a hwmon device under the GPU's PCI
+//! device, e.g. `/sys/bus/pci/devices/0000:03:00.0/hwmon/hwmon3`.
+//!
+//! The proprietary NVIDIA driver has no hwmon device; its core voltage
+//! comes from the nvidia-smi XML report instead, as NVML has no public
+//! voltage query.
+
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+use crate::gpu::common::{GpuInfo, ThermalLimits};
+use crate::gpu::{pci, sysfs_layout};
+
+/// Core voltages a running GPU can have, in millivolts; amdgpu reads a few
+/// millivolts while the graphics core is powered off (GFXOFF)
+const CORE_VOLTAGE_MV: RangeInclusive<u32> = 300..=2000;
+
+/// Read a sensor file of the device's hwmon devices
+fn read_sensor(device_path: &Path, name: &str) -> Option<i64> {
+    fs::read_to_string(sysfs_layout::find(device_path, name)?).ok()?.trim().parse().ok()
+}
+
+/// Fill in the GPU temperature, its limits, the core voltage and the energy
+/// used where a backend did not report them
+pub fn read_sensors(gpu: &mut GpuInfo) {
+    let Some(ref pci_info) = gpu.pci_info else {
+        return;
+    };
+    let device_path = pci::device_path(&pci_info.address());
+
+    // Readings are in millidegrees Celsius; temp1 is the edge or package sensor
+    let celsius = |name| read_sensor(&device_path, name).map(|millidegrees| millidegrees as f64 / 1000.0);
+    if gpu.temperature_c.is_none() {
+        gpu.temperature_c = celsius("temp1_input");
+    }
+    // crit is where amdgpu and nouveau start throttling, emergency where
+    // the GPU shuts down
+    if gpu.thermal_limits.is_none() {
+        gpu.thermal_limits = ThermalLimits::new(celsius("temp1_crit"), celsius("temp1_emergency"));
+    }
+    // in0 is vddgfx on amdgpu and the GT rail on i915, in millivolts
+    if gpu.core_voltage_mv.is_none() {
+        gpu.core_voltage_mv = read_sensor(&device_path, "in0_input")
+            .and_then(|millivolts| u32::try_from(millivolts).ok())
+            .filter(|millivolts| CORE_VOLTAGE_MV.contains(millivolts));
+    }
+    // i915 and xe count the energy of the card or package in microjoules
+    if gpu.energy_j.is_none() {
+        gpu.energy_j = read_sensor(&device_path, "energy1_input").map(|microjoules| microjoules as f64 / 1e6);
+    }
+}
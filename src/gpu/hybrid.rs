@@ -0,0 +1,161 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+use tracing::info;
+
+use crate::gpu::common::{GpuInfo, HybridInfo, HybridMode, HybridRole};
+use crate::gpu::{drm, pci, sysroot};
+
+/// Present when the kernel manages a hardware display mux
+const VGA_SWITCHEROO_PATH: &str = "/sys/kernel/debug/vgaswitcheroo";
+
+/// What vga_switcheroo says about the GPUs it switches between
+enum Switcheroo {
+    /// No handler registered, or debugfs is not mounted
+    Absent,
+    /// Present, but debugfs is only readable by root
+    Hidden,
+    Clients(Vec<SwitcherooClient>),
+}
+
+/// A GPU line of the `switch` file, e.g. "1:DIS: :DynOff:0000:01:00.0"
+struct SwitcherooClient {
+    address: String,
+    /// The mux routes the panel to it ("+")
+    active: bool,
+    /// Powered through runtime PM ("DynOff", "DynPwr"), which only muxless
+    /// machines use
+    dynamic: bool,
+}
+
+impl Switcheroo {
+    fn read() -> Self {
+        let path = sysroot::path(VGA_SWITCHEROO_PATH).join("switch");
+        match fs::read_to_string(&path) {
+            Ok(content) => Switcheroo::Clients(content.lines().filter_map(SwitcherooClient::parse).collect()),
+            // Without root even the debugfs mount point cannot be looked into
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                info!("cannot read {}; run as root to see which GPU the mux selects", path.display());
+                Switcheroo::Hidden
+            }
+            Err(_) if sysroot::path(VGA_SWITCHEROO_PATH).exists() => Switcheroo::Hidden,
+            Err(_) => Switcheroo::Absent,
+        }
+    }
+
+    fn mode(&self) -> HybridMode {
+        match self {
+            Switcheroo::Clients(clients) if !clients.is_empty() && !clients.iter().any(|client| client.dynamic) => HybridMode::Mux,
+            Switcheroo::Clients(_) | Switcheroo::Absent => HybridMode::PrimeOffload,
+            // A registered handler is most often a mux
+            Switcheroo::Hidden => HybridMode::Mux,
+        }
+    }
+
+    /// Whether the mux routes the panel to the GPU at `address`, when known
+    fn drives_panel(&self, address: &str) -> Option<bool> {
+        match self {
+            Switcheroo::Clients(clients) if self.mode() == HybridMode::Mux => {
+                clients.iter().find(|client| client.address == address).map(|client| client.active)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl SwitcherooClient {
+    fn parse(line: &str) -> Option<Self> {
+        // The address has colons of its own, so it is the rest after four fields
+        let mut fields = line.splitn(5, ':');
+        let (_id, client, active, power, address) = (fields.next()?, fields.next()?, fields.next()?, fields.next()?, fields.next()?);
+        // HDMI audio functions are switched along with their GPU
+        if client.contains("Audio") {
+            return None;
+        }
+        Some(SwitcherooClient { address: address.trim().to_string(), active: active == "+", dynamic: power.starts_with("Dyn") })
+    }
+}
+
+/// Mark GPUs in laptop-style hybrid setups as primary or offload
+///
+/// A setup counts as hybrid when there are several GPUs and at least one is
+/// integrated. The primary GPU is the one a readable display mux routes the
+/// panel to, else the one with a connected display, falling back to the
+/// firmware's boot VGA device and then to the integrated GPU, which drives
+/// the panel on muxless laptops.
+pub fn annotate(gpus: &mut [GpuInfo]) {
+    if gpus.len() < 2 || !gpus.iter().any(is_integrated) {
+        return;
+    }
+
+    let switcheroo = Switcheroo::read();
+    let mode = switcheroo.mode();
+    let addresses: Vec<Option<String>> = gpus.iter().map(|gpu| gpu.pci_info.as_ref().map(|pci_info| pci_info.address())).collect();
+    let panel: Vec<Option<bool>> = addresses.iter().map(|address| address.as_deref().and_then(|address| switcheroo.drives_panel(address))).collect();
+
+    let states: Vec<(bool, bool, bool)> = addresses
+        .iter()
+        .map(|address| match address {
+            Some(address) => {
+                let device_path = pci::device_path(address);
+                (has_connected_display(address), is_boot_vga(&device_path), is_runtime_suspended(&device_path))
+            }
+            None => (false, false, false),
+        })
+        .collect();
+
+    let primary = panel
+        .iter()
+        .position(|drives_panel| *drives_panel == Some(true))
+        .or_else(|| states.iter().position(|(displays, _, _)| *displays))
+        .or_else(|| states.iter().position(|(_, boot_vga, _)| *boot_vga))
+        .or_else(|| gpus.iter().position(is_integrated));
+
+    for (idx, (gpu, (_, _, suspended))) in gpus.iter_mut().zip(states).enumerate() {
+        let role = if Some(idx) == primary {
+            HybridRole::Primary
+        } else {
+            HybridRole::Offload
+        };
+        let drives_panel = panel[idx];
+        let d3cold = addresses[idx].as_deref().map(|address| supports_d3cold(&pci::device_path(address)));
+        gpu.hybrid = Some(HybridInfo { role, suspended, mode, drives_panel, d3cold });
+    }
+}
+
+/// The render offload requested through the environment, e.g. "DRI_PRIME=1"
+pub fn requested_offload() -> Option<String> {
+    ["DRI_PRIME", "__NV_PRIME_RENDER_OFFLOAD"].iter().find_map(|key| {
+        env::var(key)
+            .ok()
+            .filter(|value| !value.is_empty() && value != "0")
+            .map(|value| format!("{}={}", key, value))
+    })
+}
+
+/// Integrated GPUs sit on the root bus; Intel ones are flagged during detection
+pub fn is_integrated(gpu: &GpuInfo) -> bool {
+    gpu.is_integrated || gpu.pci_info.as_ref().is_some_and(|pci_info| pci_info.bus == 0)
+}
+
+fn has_connected_display(address: &str) -> bool {
+    drm::cards_for_device(address)
+        .iter()
+        .any(|card| drm::connectors(card).iter().any(|connector| connector.connected))
+}
+
+fn is_boot_vga(device_path: &Path) -> bool {
+    fs::read_to_string(device_path.join("boot_vga")).is_ok_and(|value| value.trim() == "1")
+}
+
+/// Whether the platform can power the device off, through ACPI power
+/// resources for D3, and the kernel lets it
+fn supports_d3cold(device_path: &Path) -> bool {
+    let allowed = fs::read_to_string(device_path.join("d3cold_allowed")).is_ok_and(|value| value.trim() == "1");
+    allowed && device_path.join("firmware_node").join("power_resources_D3hot").is_dir()
+}
+
+fn is_runtime_suspended(device_path: &Path) -> bool {
+    fs::read_to_string(device_path.join("power").join("runtime_status")).is_ok_and(|value| value.trim() == "suspended")
+}
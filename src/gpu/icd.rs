@@ -0,0 +1,232 @@
+//! Installed Vulkan and OpenCL drivers, for the Runtime section.
+//!
+//! Both APIs reach the vendor drivers (installable client drivers, ICDs)
+//! through a loader reading a directory of manifests: JSON files naming a
+//! library for Vulkan, and `.icd` files holding just the library name for
+//! OpenCL. Which drivers can serve a GPU follows from its kernel driver.
+
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::gpu::common::{GpuInfo, GpuVendor, Icd, Runtime};
+use crate::gpu::{features, snapshot};
+
+/// Where the Vulkan loader looks for ICD manifests
+const VULKAN_DIRS: [&str; 3] = ["/etc/vulkan/icd.d", "/usr/local/share/vulkan/icd.d", "/usr/share/vulkan/icd.d"];
+
+/// Where the OpenCL ICD loader looks for vendor files
+const OPENCL_DIR: &str = "/etc/OpenCL/vendors";
+
+/// Drivers that claim the same GPU and get in each other's way, with what
+/// happens when both are installed
+const CONFLICTS: [(&str, &str, &str); 2] = [
+    ("radeon_icd", "amd_icd", "RADV and AMDVLK are both installed; AMDVLK wins unless AMD_VULKAN_ICD=RADV is set"),
+    ("libamdocl", "libMesaOpenCL", "ROCm and Clover both provide OpenCL; applications may list this GPU twice"),
+];
+
+/// An ICD manifest and the library it loads
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    /// File name, e.g. "radeon_icd.x86_64.json" or "nvidia.icd"
+    pub file_name: String,
+    pub library: Option<String>,
+}
+
+/// Every manifest the loaders would read
+#[derive(Debug, Clone, Default)]
+pub struct Installed {
+    pub vulkan: Vec<Manifest>,
+    pub opencl: Vec<Manifest>,
+}
+
+#[derive(Deserialize)]
+struct VulkanManifest {
+    #[serde(rename = "ICD")]
+    icd: VulkanIcd,
+}
+
+#[derive(Deserialize)]
+struct VulkanIcd {
+    library_path: String,
+}
+
+impl Installed {
+    /// Read the Vulkan and OpenCL manifests
+    pub fn read() -> Self {
+        let opencl = manifests(OPENCL_DIR, ".icd")
+            .map(|(file_name, content)| Manifest {
+                library: Some(content.trim().to_string()).filter(|library| !library.is_empty()),
+                file_name,
+            })
+            .collect();
+        Installed { vulkan: vulkan_manifests(), opencl }
+    }
+
+    /// File names of the Vulkan manifests
+    pub fn vulkan_names(&self) -> Vec<String> {
+        self.vulkan.iter().map(|manifest| manifest.file_name.clone()).collect()
+    }
+
+    /// The drivers as seen from `gpu`, or `None` for GPUs without a
+    /// graphics stack or systems without any ICDs
+    pub fn runtime(&self, gpu: &GpuInfo) -> Option<Runtime> {
+        let passthrough = gpu.kernel_driver.as_deref().is_some_and(|driver| features::PASSTHROUGH_DRIVERS.contains(&driver));
+        if passthrough || (self.vulkan.is_empty() && self.opencl.is_empty()) {
+            return None;
+        }
+
+        let vulkan_prefixes = vulkan_prefixes(gpu);
+        let opencl_prefixes = opencl_prefixes(gpu);
+        let vulkan_serving: Vec<&Manifest> = self
+            .vulkan
+            .iter()
+            .filter(|manifest| vulkan_prefixes.iter().any(|prefix| manifest.file_name.starts_with(prefix)))
+            .collect();
+        let opencl_serving: Vec<&Manifest> = self
+            .opencl
+            .iter()
+            .filter(|manifest| {
+                let library = manifest.library.as_deref().map(library_name).unwrap_or_default();
+                opencl_prefixes.iter().any(|prefix| library.starts_with(prefix))
+            })
+            .collect();
+
+        let mut warnings = Vec::new();
+        if vulkan_serving.is_empty() && !gpu.is_integrated && !gpu.compute_only && gpu.kernel_driver.is_some() {
+            warnings.push("no Vulkan driver for this GPU is installed".to_string());
+        }
+        for manifest in vulkan_serving.iter().chain(&opencl_serving) {
+            match manifest.library.as_deref() {
+                // Bare names are found through the library search path
+                Some(library) if library.starts_with('/') && !Path::new(library).exists() => {
+                    warnings.push(format!("{} points to {}, which is missing", manifest.file_name, library));
+                }
+                Some(_) => {}
+                None => warnings.push(format!("{} names no driver library", manifest.file_name)),
+            }
+        }
+        for (first, second, problem) in CONFLICTS {
+            let serves = |prefix: &str| {
+                vulkan_serving.iter().any(|manifest| manifest.file_name.starts_with(prefix))
+                    || opencl_serving
+                        .iter()
+                        .any(|manifest| manifest.library.as_deref().is_some_and(|library| library_name(library).starts_with(prefix)))
+            };
+            if serves(first) && serves(second) {
+                warnings.push(problem.to_string());
+            }
+        }
+
+        let list = |manifests: &[Manifest], serving: &[&Manifest]| {
+            manifests
+                .iter()
+                .map(|manifest| Icd {
+                    name: icd_name(&manifest.file_name),
+                    serves_gpu: serving.iter().any(|serving| serving.file_name == manifest.file_name),
+                })
+                .collect()
+        };
+        Some(Runtime {
+            vulkan: list(&self.vulkan, &vulkan_serving),
+            opencl: list(&self.opencl, &opencl_serving),
+            warnings,
+        })
+    }
+}
+
+/// The Vulkan manifests, in the order the loader reads them
+pub fn vulkan_manifests() -> Vec<Manifest> {
+    VULKAN_DIRS
+        .iter()
+        .flat_map(|dir| manifests(dir, ".json"))
+        .map(|(file_name, content)| Manifest {
+            library: serde_json::from_str::<VulkanManifest>(&content).ok().map(|manifest| manifest.icd.library_path),
+            file_name,
+        })
+        .collect()
+}
+
+/// File names of the installed Vulkan manifests, e.g. "radeon_icd.x86_64.json"
+pub fn vulkan_manifest_names() -> Vec<String> {
+    vulkan_manifests().into_iter().map(|manifest| manifest.file_name).collect()
+}
+
+/// Manifest name prefixes of the Vulkan drivers that can drive a GPU
+///
+/// The kernel driver decides between e.g. nouveau and the proprietary
+/// NVIDIA driver; without one the vendor's usual driver is assumed.
+pub fn vulkan_prefixes(gpu: &GpuInfo) -> &'static [&'static str] {
+    match gpu.kernel_driver.as_deref() {
+        Some("nvidia") => &["nvidia_icd"],
+        Some("nouveau") => &["nouveau_icd"],
+        Some("amdgpu") => &["radeon_icd", "amd_icd", "amd_pro_icd"],
+        Some("i915") | Some("xe") => &["intel_icd", "intel_hasvk_icd"],
+        Some("virtio-pci") | Some("virtio_gpu") => &["virtio_icd"],
+        Some(_) => &[],
+        None => match gpu.vendor {
+            GpuVendor::Nvidia => &["nvidia_icd", "nouveau_icd"],
+            GpuVendor::Amd => &["radeon_icd", "amd_icd"],
+            GpuVendor::Intel => &["intel_icd", "intel_hasvk_icd"],
+            _ => &[],
+        },
+    }
+}
+
+/// Whether a Vulkan driver for the GPU is installed
+pub fn has_vulkan_driver(gpu: &GpuInfo, manifests: &[String]) -> bool {
+    let prefixes = vulkan_prefixes(gpu);
+    manifests
+        .iter()
+        .any(|manifest| prefixes.iter().any(|prefix| manifest.starts_with(prefix)))
+}
+
+/// File names ending in `extension` in a manifest directory, with their
+/// contents, sorted by name as the loaders read them
+fn manifests(dir: &str, extension: &str) -> impl Iterator<Item = (String, String)> {
+    // The drivers installed here say nothing about the machine a snapshot came from
+    let entries = if snapshot::is_replaying() { None } else { fs::read_dir(dir).ok() };
+    let mut found: Vec<(String, String)> = entries
+        .into_iter()
+        .flat_map(|entries| entries.flatten())
+        .map(|entry| (entry.file_name().to_string_lossy().into_owned(), entry.path()))
+        .filter(|(name, _)| name.ends_with(extension))
+        .filter_map(|(name, path)| Some((name, fs::read_to_string(path).ok()?)))
+        .collect();
+    found.sort();
+    found.into_iter()
+}
+
+/// Library file name prefixes of the OpenCL drivers that can run on a GPU
+///
+/// Mesa's Rusticl and Clover cover the open drivers next to the vendor
+/// runtimes: ROCm for AMD and NEO (libigdrcl) for Intel.
+fn opencl_prefixes(gpu: &GpuInfo) -> &'static [&'static str] {
+    match gpu.kernel_driver.as_deref() {
+        Some("nvidia") => &["libnvidia-opencl"],
+        Some("nouveau") | Some("radeon") => &["libRusticlOpenCL", "libMesaOpenCL"],
+        Some("amdgpu") => &["libamdocl", "libRusticlOpenCL", "libMesaOpenCL"],
+        Some("i915") | Some("xe") => &["libigdrcl", "libRusticlOpenCL"],
+        Some(_) => &[],
+        None => match gpu.vendor {
+            GpuVendor::Nvidia => &["libnvidia-opencl"],
+            GpuVendor::Amd => &["libamdocl", "libRusticlOpenCL", "libMesaOpenCL"],
+            GpuVendor::Intel => &["libigdrcl", "libRusticlOpenCL"],
+            _ => &[],
+        },
+    }
+}
+
+/// Last component of a library path
+fn library_name(library: &str) -> &str {
+    library.rsplit('/').next().unwrap_or(library)
+}
+
+/// Manifest name without extension and architecture, e.g. "radeon_icd"
+fn icd_name(file_name: &str) -> String {
+    let stem = file_name.trim_end_matches(".json").trim_end_matches(".icd");
+    match stem.rsplit_once('.') {
+        Some((name, arch)) if ["x86_64", "i686", "aarch64", "armv7l"].contains(&arch) => name.to_string(),
+        _ => stem.to_string(),
+    }
+}
@@ -1,10 +1,9 @@
 use anyhow::{Context, Result};
-use regex::Regex;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 
-use crate::gpu::common::{Cache, GpuInfo, GpuVendor, Memory, MemoryType, Topology};
+use crate::gpu::common::{Cache, GpuInfo, GpuVendor, IntelTopology, Memory, MemoryType, Process, Topology};
+use crate::gpu::{pci, perf, specs, sysfs_layout, sysroot};
 
 /// Detect Intel GPUs
 pub fn detect_intel_gpus() -> Result<Vec<GpuInfo>> {
@@ -27,9 +26,9 @@ fn find_intel_gpus_in_sysfs() -> Result<Vec<PathBuf>> {
     let mut gpu_paths = Vec::new();
     
     // Try Intel card directory in /sys/class/drm
-    let drm_path = Path::new("/sys/class/drm");
+    let drm_path = sysroot::path("/sys/class/drm");
     if drm_path.exists() {
-        for entry in fs::read_dir(drm_path).context("Failed to read DRM directory")? {
+        for entry in fs::read_dir(&drm_path).context("Failed to read DRM directory")? {
             let entry = entry.context("Failed to read directory entry")?;
             let path = entry.path();
             
@@ -67,40 +66,47 @@ fn get_intel_gpu_info_from_sysfs(device_path: &Path) -> Result<GpuInfo> {
     
     // Create basic GPU info
     let mut gpu_info = GpuInfo::new(&name, GpuVendor::Intel);
-    gpu_info.is_integrated = true;  // Most Intel GPUs are integrated
+    let discrete = u16::from_str_radix(&device_id, 16).is_ok_and(is_arc);
+    gpu_info.is_integrated = !discrete;
+    gpu_info.pci_info = pci::read_pci_info(device_path).ok();
     
     // Read frequencies
     read_intel_frequencies(device_path, &mut gpu_info);
     
     // Determine architecture and other info
-    let (architecture, chip, generation, process_nm) = get_intel_architecture(&name, &device_id);
+    let (architecture, chip, generation, process) = get_intel_architecture(&name, &device_id);
     gpu_info.architecture = architecture;
     gpu_info.chip = chip;
-    gpu_info.process_nm = process_nm;
+    gpu_info.mark_heuristic(&["architecture", "chip"]);
+    if process.is_some() {
+        gpu_info.process = process;
+        gpu_info.mark_heuristic(&["process_nm"]);
+    }
     
     // Try to get memory info (integrated GPUs usually use system memory)
-    gpu_info.memory = get_intel_memory(&name);
-    
-    // Try to get topology information
-    gpu_info.topology = get_intel_topology(&name, generation);
-    
-    // Try to get cache information
-    gpu_info.cache = get_intel_cache(&name, generation);
+    gpu_info.memory = if discrete { Some(get_arc_memory()) } else { get_intel_memory(&name) };
+    gpu_info.mark_heuristic(&["memory"]);
     
-    // Calculate peak performance
-    if let Some(ref topology) = gpu_info.topology {
-        if let Some(execution_units) = topology.execution_units {
-            // Peak FLOPS = 2 * 8 * execution_units * clock (Intel GPUs have 8 ALUs per EU)
-            let peak_gflops = 2.0 * 8.0 * execution_units as f64 * gpu_info.max_freq_mhz as f64 / 1000.0;
-            gpu_info.peak_performance_gflops = Some(peak_gflops);
+    // The guesses below are for integrated GPUs; Arc cards get theirs from the spec database
+    if !discrete {
+        gpu_info.topology = get_intel_topology(&name, generation);
+        gpu_info.cache = get_intel_cache(&name, generation);
+    }
+    if gpu_info.topology.is_some() {
+        gpu_info.mark_heuristic(&["compute_units"]);
+    }
+    if let Some((l2_known, l3_known)) = gpu_info.cache.as_ref().map(|cache| (cache.l2_size.is_some(), cache.l3_size.is_some())) {
+        if l2_known {
+            gpu_info.mark_heuristic(&["l2_kb"]);
+        }
+        if l3_known {
+            gpu_info.mark_heuristic(&["l3_kb"]);
         }
     }
     
-    // Get driver info
-    if is_intel_gpu_tool_available() {
-        let driver_version = get_intel_driver_version();
-        gpu_info.driver_version = driver_version;
-    }
+    specs::apply_specs(&mut gpu_info);
+    
+    gpu_info.peak_performance_gflops = perf::peak_gflops(&gpu_info);
     
     Ok(gpu_info)
 }
@@ -120,6 +126,20 @@ fn get_intel_gpu_name(device_id: &str, device_path: &Path) -> String {
     
     // Map known device IDs to names
     match device_id {
+        // Battlemage (Xe2-HPG)
+        "e20b" => "Intel Arc B580 Graphics".to_string(),
+        "e20c" => "Intel Arc B570 Graphics".to_string(),
+        // Alchemist (Xe-HPG)
+        "56a0" => "Intel Arc A770 Graphics".to_string(),
+        "56a1" => "Intel Arc A750 Graphics".to_string(),
+        "56a2" => "Intel Arc A580 Graphics".to_string(),
+        "56a5" => "Intel Arc A380 Graphics".to_string(),
+        "56a6" => "Intel Arc A310 Graphics".to_string(),
+        "5690" => "Intel Arc A770M Graphics".to_string(),
+        "5691" => "Intel Arc A730M Graphics".to_string(),
+        "5692" => "Intel Arc A550M Graphics".to_string(),
+        "5693" => "Intel Arc A370M Graphics".to_string(),
+        "5694" => "Intel Arc A350M Graphics".to_string(),
         // Tiger Lake (Gen12)
         "9a49" => "Intel Iris Xe Graphics (96 EUs)".to_string(),
         "9a40" => "Intel Iris Xe Graphics (80 EUs)".to_string(),
@@ -221,8 +241,7 @@ fn get_intel_gpu_name(device_id: &str, device_path: &Path) -> String {
 /// Read Intel GPU frequencies from sysfs
 fn read_intel_frequencies(device_path: &Path, gpu_info: &mut GpuInfo) {
     // Try to read max frequency
-    let max_freq_path = device_path.join("gt_max_freq_mhz");
-    if max_freq_path.exists() {
+    if let Some(max_freq_path) = sysfs_layout::find(device_path, "gt_max_freq_mhz") {
         if let Ok(content) = fs::read_to_string(max_freq_path) {
             if let Ok(freq) = content.trim().parse::<u32>() {
                 gpu_info.max_freq_mhz = freq;
@@ -231,8 +250,7 @@ fn read_intel_frequencies(device_path: &Path, gpu_info: &mut GpuInfo) {
     }
     
     // Try to read min frequency
-    let min_freq_path = device_path.join("gt_min_freq_mhz");
-    if min_freq_path.exists() {
+    if let Some(min_freq_path) = sysfs_layout::find(device_path, "gt_min_freq_mhz") {
         if let Ok(content) = fs::read_to_string(min_freq_path) {
             if let Ok(freq) = content.trim().parse::<u32>() {
                 gpu_info.freq_mhz = freq;
@@ -265,107 +283,91 @@ fn read_intel_frequencies(device_path: &Path, gpu_info: &mut GpuInfo) {
             gpu_info.max_freq_mhz = 1000;  // Default
         }
         
+        gpu_info.mark_heuristic(&["max_freq_mhz"]);
+        
         if gpu_info.freq_mhz == 0 {
             gpu_info.freq_mhz = gpu_info.max_freq_mhz;
+            gpu_info.mark_heuristic(&["freq_mhz"]);
         }
     }
 }
 
-/// Check if intel-gpu-tools is available
-fn is_intel_gpu_tool_available() -> bool {
-    Command::new("which")
-        .arg("intel_gpu_top")
-        .output()
-        .map(|output| output.status.success())
-        .unwrap_or(false)
-}
-
-/// Get Intel driver version
-fn get_intel_driver_version() -> Option<String> {
-    // Try reading from X server output
-    if let Ok(output) = Command::new("sh")
-        .args(["-c", "DISPLAY=:0 glxinfo | grep 'OpenGL version string'"])
-        .output() {
-        if output.status.success() {
-            let output_str = String::from_utf8_lossy(&output.stdout);
-            let re = Regex::new(r"OpenGL version string: .* Mesa (.+)").ok()?;
-            if let Some(cap) = re.captures(&output_str) {
-                return Some(cap[1].trim().to_string());
-            }
-        }
-    }
-    
-    // Try reading from direct rendering info
-    if let Ok(output) = Command::new("sh")
-        .args(["-c", "DISPLAY=:0 glxinfo | grep 'direct rendering'"])
-        .output() {
-        if output.status.success() {
-            let output_str = String::from_utf8_lossy(&output.stdout);
-            if output_str.contains("direct rendering: Yes") {
-                return Some("Mesa DRI Intel".to_string());
-            }
-        }
-    }
-    
-    // Try finding kernel driver version
-    if let Ok(output) = Command::new("sh")
-        .args(["-c", "modinfo -F version i915"])
-        .output() {
-        if output.status.success() {
-            let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            if !version.is_empty() {
-                return Some(format!("i915 {}", version));
-            }
-        }
-    }
-    
-    None
-}
-
 /// Determine Intel architecture and generation
-fn get_intel_architecture(name: &str, device_id: &str) -> (String, String, u32, Option<u32>) {
+fn get_intel_architecture(name: &str, device_id: &str) -> (String, String, u32, Option<Process>) {
     let name_lower = name.to_lowercase();
     
-    if name_lower.contains("iris xe") || device_id.starts_with("9a") || 
+    if device_id.starts_with("e20") {
+        ("Xe2-HPG".to_string(), "BMG".to_string(), 20, Some(Process::new("TSMC", Some("N5"), 5)))
+    }
+    else if device_id.starts_with("56") {
+        ("Xe-HPG".to_string(), "DG2".to_string(), 12, Some(Process::new("TSMC", Some("N6"), 6)))
+    }
+    else if name_lower.contains("iris xe") || device_id.starts_with("9a") || 
        device_id.starts_with("4c8") || device_id.starts_with("468") || 
        device_id.starts_with("469") {
-        ("Gen12 (Xe)".to_string(), "Gen12".to_string(), 12, Some(10))
+        // Alder Lake's Intel 7 is a refined 10nm SuperFin; Rocket Lake
+        // backported Xe to 14 nm
+        let process = if device_id.starts_with("46") {
+            Process::new("Intel", Some("Intel 7"), 10)
+        } else if device_id.starts_with("4c8") {
+            Process::new("Intel", None, 14)
+        } else {
+            Process::new("Intel", Some("10nm SuperFin"), 10)
+        };
+        ("Gen12 (Xe)".to_string(), "Gen12".to_string(), 12, Some(process))
     }
     else if name_lower.contains("iris plus") || device_id.starts_with("8a") {
-        ("Gen11".to_string(), "Gen11".to_string(), 11, Some(10))
+        ("Gen11".to_string(), "Gen11".to_string(), 11, Some(Process::new("Intel", None, 10)))
     }
     else if name_lower.contains("uhd graphics") || name_lower.contains("hd graphics 6") || 
              device_id.starts_with("3e") || device_id.starts_with("3184") || 
              device_id.starts_with("3185") || device_id.starts_with("9bc") {
-        ("Gen9.5".to_string(), "Gen9.5".to_string(), 10, Some(14))
+        ("Gen9.5".to_string(), "Gen9.5".to_string(), 10, Some(Process::new("Intel", None, 14)))
     }
     else if name_lower.contains("hd graphics 5") || device_id.starts_with("19") {
-        ("Gen9".to_string(), "Gen9".to_string(), 9, Some(14))
+        ("Gen9".to_string(), "Gen9".to_string(), 9, Some(Process::new("Intel", None, 14)))
     }
     else if name_lower.contains("hd graphics") && (name_lower.contains("6000") || 
              name_lower.contains("5500") || name_lower.contains("5300") || 
              device_id.starts_with("16")) {
-        ("Gen8 (Broadwell)".to_string(), "Gen8".to_string(), 8, Some(14))
+        ("Gen8 (Broadwell)".to_string(), "Gen8".to_string(), 8, Some(Process::new("Intel", None, 14)))
     }
     else if name_lower.contains("hd graphics 4") || device_id.starts_with("04") || 
              device_id.starts_with("0a") || device_id.starts_with("0d2") {
-        ("Gen7.5 (Haswell)".to_string(), "Gen7.5".to_string(), 8, Some(22))
+        ("Gen7.5 (Haswell)".to_string(), "Gen7.5".to_string(), 8, Some(Process::new("Intel", None, 22)))
     }
     else if name_lower.contains("hd graphics 2500") || name_lower.contains("hd graphics 4000") || 
              device_id.starts_with("015") || device_id.starts_with("016") || 
              device_id.starts_with("0f3") {
-        ("Gen7 (Ivy Bridge)".to_string(), "Gen7".to_string(), 7, Some(22))
+        ("Gen7 (Ivy Bridge)".to_string(), "Gen7".to_string(), 7, Some(Process::new("Intel", None, 22)))
     }
     else if name_lower.contains("hd graphics 2000") || name_lower.contains("hd graphics 3000") || 
              device_id.starts_with("010") || device_id.starts_with("011") || 
              device_id.starts_with("012") {
-        ("Gen6 (Sandy Bridge)".to_string(), "Gen6".to_string(), 6, Some(32))
+        ("Gen6 (Sandy Bridge)".to_string(), "Gen6".to_string(), 6, Some(Process::new("Intel", None, 32)))
     }
     else {
         ("Unknown".to_string(), "Unknown".to_string(), 0, None)
     }
 }
 
+/// Whether the PCI device ID is an Arc card, DG2 (Alchemist, 56xx) or
+/// Battlemage (e20x), rather than a GPU built into the CPU
+pub fn is_arc(device_id: u16) -> bool {
+    device_id >> 8 == 0x56 || device_id >> 4 == 0xe20
+}
+
+/// Memory of an Arc card: always GDDR6, sized by the board; the bus width
+/// comes from the spec database
+fn get_arc_memory() -> Memory {
+    Memory {
+        size_bytes: 0,
+        memory_type: MemoryType::Gddr6,
+        bus_width: 0,
+        clock_mhz: 0,
+    }
+}
+
 /// Create a memory object for Intel GPUs
 fn get_intel_memory(name: &str) -> Option<Memory> {
     let name_lower = name.to_lowercase();
@@ -529,19 +531,7 @@ fn get_intel_topology(name: &str, generation: u32) -> Option<Topology> {
         _ => (None, None),
     };
     
-    execution_units.map(|eus| Topology {
-        compute_units: eus,
-        cuda_cores: None,
-        tensor_cores: None,
-        rt_cores: None,
-        sm_count: None,
-        stream_processors: None,
-        rops: None,
-        tmus: None,
-        execution_units: Some(eus),
-        slices,
-        subslices,
-    })
+    execution_units.map(|execution_units| Topology::Intel(IntelTopology { execution_units, slices, subslices }))
 }
 
 /// Get cache information for Intel GPUs
@@ -553,7 +543,7 @@ fn get_intel_cache(name: &str, generation: u32) -> Option<Cache> {
     } else if name_lower.contains("iris xe") {
         Some(8 * 1024 * 1024)   // 8 MB for other Xe Graphics
     } else if name_lower.contains("iris plus g7") {
-        Some(1 * 1024 * 1024)   // ~1 MB for Iris Plus G7
+        Some(1024 * 1024)       // ~1 MB for Iris Plus G7
     } else if name_lower.contains("iris plus") {
         Some(768 * 1024)        // ~768 KB for other Iris Plus
     } else if name_lower.contains("iris pro") {
@@ -566,7 +556,7 @@ fn get_intel_cache(name: &str, generation: u32) -> Option<Cache> {
     
     let l2_size = match generation {
         12 => Some(2 * 1024 * 1024),  // 2 MB for Gen12
-        11 => Some(1 * 1024 * 1024),  // 1 MB for Gen11
+        11 => Some(1024 * 1024),      // 1 MB for Gen11
         9 | 10 => Some(768 * 1024),   // 768 KB for Gen9/Gen9.5
         8 => Some(512 * 1024),        // 512 KB for Gen8
         7 => Some(256 * 1024),        // 256 KB for Gen7/Gen7.5
@@ -0,0 +1,103 @@
+//! Live engine load of Intel GPUs from `intel_gpu_top`, part of
+//! intel-gpu-tools.
+//!
+//! intel_gpu_top samples until it is stopped, so it runs under `timeout`
+//! long enough for two samples; the first covers the tool's startup. It
+//! needs perf access to the i915 PMU, which usually means root, and finds
+//! nothing otherwise.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use crate::gpu::common::{EngineLoad, GpuInfo, GpuVendor};
+use crate::gpu::{snapshot, tools};
+
+/// Milliseconds between samples
+const SAMPLE_PERIOD_MS: &str = "500";
+
+/// How long intel_gpu_top runs before `timeout` stops it
+const RUN_TIME: &str = "1.2";
+
+/// One sample of `intel_gpu_top -J`
+#[derive(Deserialize)]
+struct Sample {
+    frequency: Option<Frequency>,
+    #[serde(default)]
+    engines: BTreeMap<String, Engine>,
+}
+
+#[derive(Deserialize)]
+struct Frequency {
+    /// What the GPU ran at over the sample, in MHz
+    actual: f64,
+}
+
+#[derive(Deserialize)]
+struct Engine {
+    /// Percentage of the sample the engine was busy
+    busy: f64,
+}
+
+/// Fill in the engine load and actual clock of an Intel GPU
+pub fn query(gpu: &mut GpuInfo) {
+    if gpu.vendor != GpuVendor::Intel {
+        return;
+    }
+    let Some(ref pci_info) = gpu.pci_info else {
+        return;
+    };
+    if !tools::is_installed("intel_gpu_top") {
+        return;
+    }
+
+    let device = format!("pci:slot={}", pci_info.address());
+    let args = [RUN_TIME, "intel_gpu_top", "-J", "-s", SAMPLE_PERIOD_MS, "-d", &device];
+    let Ok(output) = snapshot::run("timeout", &args) else {
+        return;
+    };
+    let Some(sample) = last_sample(&String::from_utf8_lossy(&output.stdout)) else {
+        return;
+    };
+
+    // Engines are numbered per class, e.g. "Render/3D/0" or "Video/1"
+    gpu.engine_load = sample
+        .engines
+        .into_iter()
+        .map(|(name, engine)| EngineLoad {
+            name: name.strip_suffix("/0").unwrap_or(&name).to_string(),
+            busy_percent: engine.busy,
+        })
+        .collect();
+    if let Some(frequency) = sample.frequency.filter(|frequency| frequency.actual > 0.0) {
+        gpu.freq_mhz = frequency.actual.round() as u32;
+    }
+}
+
+/// The last complete sample of a JSON array that was cut off mid-sample
+/// when the tool was stopped
+fn last_sample(output: &str) -> Option<Sample> {
+    let mut samples = Vec::new();
+    let (mut depth, mut start, mut in_string, mut escaped) = (0, 0, false, false);
+    for (idx, c) in output.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            _ if in_string => {}
+            '{' => {
+                if depth == 0 {
+                    start = idx;
+                }
+                depth += 1;
+            }
+            '}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    samples.push(&output[start..=idx]);
+                }
+            }
+            _ => {}
+        }
+    }
+    samples.iter().rev().find_map(|sample| serde_json::from_str(sample).ok())
+}
@@ -0,0 +1,106 @@
+use std::fs;
+use std::path::Path;
+
+use crate::gpu::{pci, sysroot};
+
+/// Where the kernel lists IOMMU groups
+const IOMMU_GROUPS_PATH: &str = "/sys/kernel/iommu_groups";
+
+/// PCI class code of PCI-to-PCI bridges
+const PCI_CLASS_BRIDGE_PCI: u16 = 0x0604;
+
+/// A device that shares an IOMMU group with a GPU
+#[derive(Debug, Clone)]
+pub struct GroupDevice {
+    pub address: String,
+    pub name: String,
+    pub class_id: u16,
+    pub driver: Option<String>,
+}
+
+/// An IOMMU group and every device in it
+#[derive(Debug, Clone)]
+pub struct IommuGroup {
+    pub id: u32,
+    pub devices: Vec<GroupDevice>,
+}
+
+/// Outcome of the passthrough readiness check for one GPU
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Readiness {
+    /// The whole group is already bound to vfio-pci
+    Bound,
+    /// The group is isolated and can be handed to vfio-pci
+    Ready,
+    /// The kernel has no IOMMU enabled
+    IommuDisabled,
+    /// The device is not in any IOMMU group
+    NoGroup,
+    /// The group also holds devices from other slots
+    SharedGroup(usize),
+}
+
+/// Number of IOMMU groups, or 0 when the kernel runs without an IOMMU
+pub fn group_count() -> usize {
+    fs::read_dir(sysroot::path(IOMMU_GROUPS_PATH)).map(|entries| entries.count()).unwrap_or(0)
+}
+
+/// The IOMMU group of the PCI device at `address`
+pub fn read_group(address: &str) -> Option<IommuGroup> {
+    let group_path = fs::canonicalize(pci::device_path(address).join("iommu_group")).ok()?;
+    let id = group_path.file_name()?.to_str()?.parse().ok()?;
+
+    let mut devices: Vec<GroupDevice> = fs::read_dir(group_path.join("devices"))
+        .ok()?
+        .flatten()
+        .filter_map(|entry| read_group_device(&entry.path()))
+        .collect();
+    devices.sort_by(|a, b| a.address.cmp(&b.address));
+
+    Some(IommuGroup { id, devices })
+}
+
+/// Describe one member of an IOMMU group
+fn read_group_device(path: &Path) -> Option<GroupDevice> {
+    let pci_info = pci::read_pci_info(path).ok()?;
+    Some(GroupDevice {
+        address: pci_info.address(),
+        name: pci::device_name(pci_info.vendor_id, pci_info.device_id),
+        class_id: pci_info.class_id,
+        driver: pci::bound_driver(path),
+    })
+}
+
+/// Decide whether the GPU at `address` can be passed through as-is
+///
+/// Other functions of the same slot (such as the HDMI audio controller) and
+/// bridges are expected in the group; anything else must move with the GPU.
+pub fn readiness(address: &str, group: Option<&IommuGroup>, iommu_enabled: bool) -> Readiness {
+    if !iommu_enabled {
+        return Readiness::IommuDisabled;
+    }
+    let Some(group) = group else {
+        return Readiness::NoGroup;
+    };
+
+    let slot = slot_of(address);
+    let endpoints: Vec<&GroupDevice> = group
+        .devices
+        .iter()
+        .filter(|device| device.class_id != PCI_CLASS_BRIDGE_PCI)
+        .collect();
+
+    let unrelated = endpoints.iter().filter(|device| slot_of(&device.address) != slot).count();
+    if unrelated > 0 {
+        Readiness::SharedGroup(unrelated)
+    } else if endpoints.iter().all(|device| device.driver.as_deref() == Some("vfio-pci")) {
+        Readiness::Bound
+    } else {
+        Readiness::Ready
+    }
+}
+
+/// The `domain:bus:device` part of a PCI address
+fn slot_of(address: &str) -> &str {
+    address.rsplit_once('.').map_or(address, |(slot, _)| slot)
+}
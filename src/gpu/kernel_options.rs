@@ -0,0 +1,194 @@
+//! Kernel command-line parameters and module options that change how GPU
+//! drivers behave, for detailed mode.
+//!
+//! These are often why a GPU misbehaves: `nomodeset` leaves it without a
+//! display driver, `nvidia-drm.modeset=0` breaks Wayland, and an IOMMU in
+//! passthrough or strict mode changes DMA. Module options come from the
+//! `options` and `blacklist` lines of modprobe.d, with the values in effect
+//! read back from `/sys/module` where the module exposes them.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::gpu::common::{GpuInfo, GpuVendor};
+use crate::gpu::sysroot;
+
+/// The parameters the kernel booted with
+pub const CMDLINE_PATH: &str = "/proc/cmdline";
+
+/// Where modprobe reads its configuration, a file in an earlier directory
+/// hiding one of the same name in a later one
+pub const MODPROBE_DIRS: [&str; 4] = ["/etc/modprobe.d", "/run/modprobe.d", "/usr/lib/modprobe.d", "/lib/modprobe.d"];
+
+/// Command-line parameters that concern every GPU, by name before any "="
+const GLOBAL_PARAMETERS: [&str; 12] = [
+    "nomodeset",
+    "iommu",
+    "intel_iommu",
+    "amd_iommu",
+    "iommu.passthrough",
+    "iommu.strict",
+    "pcie_aspm",
+    "pci",
+    "modprobe.blacklist",
+    "module_blacklist",
+    "rd.driver.blacklist",
+    "vfio_pci.ids",
+];
+
+/// The driver modules a GPU of each vendor may use, with underscores
+const NVIDIA_MODULES: [&str; 5] = ["nvidia", "nvidia_drm", "nvidia_modeset", "nvidia_uvm", "nouveau"];
+const AMD_MODULES: [&str; 2] = ["amdgpu", "radeon"];
+const INTEL_MODULES: [&str; 2] = ["i915", "xe"];
+
+/// Parameters worth showing even when left at their default, since the
+/// default differs between driver versions
+const WATCHED_PARAMETERS: [(&str, &str); 2] = [("nvidia_drm", "modeset"), ("nvidia_drm", "fbdev")];
+
+/// The kernel command line and modprobe configuration of this machine
+#[derive(Debug, Clone, Default)]
+pub struct KernelOptions {
+    /// Command-line words, module names spelled with underscores
+    cmdline: Vec<String>,
+    /// `options` lines by module, in the order modprobe applies them
+    modprobe: BTreeMap<String, Vec<String>>,
+    blacklisted: Vec<String>,
+}
+
+impl KernelOptions {
+    /// Read `/proc/cmdline` and the modprobe.d files
+    pub fn read() -> Self {
+        let cmdline = fs::read_to_string(sysroot::path(CMDLINE_PATH)).map(|cmdline| split_words(&cmdline)).unwrap_or_default();
+        let mut options = KernelOptions { cmdline: cmdline.iter().map(|word| normalize(word)).collect(), ..Default::default() };
+        for content in modprobe_files().into_values().filter_map(|path| fs::read_to_string(path).ok()) {
+            options.add_modprobe(&content);
+        }
+        options
+    }
+
+    fn add_modprobe(&mut self, content: &str) {
+        // A trailing backslash continues the line
+        let content = content.replace("\\\n", " ");
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some("options"), Some(module)) => {
+                    let module = module.replace('-', "_");
+                    self.modprobe.entry(module.clone()).or_default().extend(words.map(|option| format!("{}.{}", module, option)));
+                }
+                (Some("blacklist"), Some(module)) => self.blacklisted.push(module.replace('-', "_")),
+                _ => {}
+            }
+        }
+    }
+
+    /// Command-line parameters that concern `gpu`, e.g. "nomodeset" or
+    /// "amdgpu.ppfeaturemask=0xffffffff"
+    pub fn kernel_parameters(&self, gpu: &GpuInfo) -> Vec<String> {
+        let modules = modules(gpu);
+        self.cmdline
+            .iter()
+            .filter(|word| {
+                let name = word.split('=').next().unwrap_or_default();
+                let module = name.split_once('.').map(|(module, _)| module);
+                GLOBAL_PARAMETERS.contains(&name) || module.is_some_and(|module| modules.contains(&module))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// modprobe.d options of the modules of `gpu`, with the values in
+    /// effect where the loaded module shows them, and the blacklisted ones
+    pub fn module_options(&self, gpu: &GpuInfo) -> Vec<String> {
+        let mut options: Vec<String> = Vec::new();
+        for &module in modules(gpu) {
+            for option in self.modprobe.get(module).into_iter().flatten() {
+                let name = option.split('=').next().unwrap_or_default();
+                options.retain(|existing| existing.split('=').next() != Some(name));
+                options.push(loaded_value(name).map_or_else(|| option.clone(), |value| format!("{}={}", name, value)));
+            }
+            for &(_, parameter) in WATCHED_PARAMETERS.iter().filter(|(watched, _)| *watched == module) {
+                let name = format!("{}.{}", module, parameter);
+                if let Some(value) = loaded_value(&name).filter(|_| !options.iter().any(|existing| existing.split('=').next() == Some(&name))) {
+                    options.push(format!("{}={}", name, value));
+                }
+            }
+            if self.blacklisted.iter().any(|blacklisted| blacklisted == module) {
+                options.push(format!("blacklist {}", module));
+            }
+        }
+        options
+    }
+}
+
+fn modules(gpu: &GpuInfo) -> &'static [&'static str] {
+    match gpu.vendor {
+        GpuVendor::Nvidia => &NVIDIA_MODULES,
+        GpuVendor::Amd => &AMD_MODULES,
+        GpuVendor::Intel => &INTEL_MODULES,
+        GpuVendor::Arm | GpuVendor::Other(_) => &[],
+    }
+}
+
+/// Whether `module`, as named in `/sys/module`, is a GPU driver
+pub fn is_gpu_module(module: &str) -> bool {
+    [&NVIDIA_MODULES[..], &AMD_MODULES, &INTEL_MODULES].iter().any(|modules| modules.contains(&module))
+}
+
+/// The current value of "module.parameter" in `/sys/module`, where the
+/// module is loaded and lets it be read
+fn loaded_value(name: &str) -> Option<String> {
+    let (module, parameter) = name.split_once('.')?;
+    let path = sysroot::path("/sys/module").join(module).join("parameters").join(parameter);
+    fs::read_to_string(path).ok().map(|value| value.trim().to_string())
+}
+
+/// The `.conf` files modprobe reads, by file name
+pub fn modprobe_files() -> BTreeMap<String, PathBuf> {
+    let mut files = BTreeMap::new();
+    for dir in MODPROBE_DIRS {
+        for entry in fs::read_dir(sysroot::path(dir)).into_iter().flatten().flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.ends_with(".conf") {
+                files.entry(name).or_insert_with(|| entry.path());
+            }
+        }
+    }
+    files
+}
+
+/// Words of the command line, keeping double-quoted values with spaces
+/// together and dropping the quotes
+fn split_words(cmdline: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    for c in cmdline.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => words.extend(Some(std::mem::take(&mut word)).filter(|word| !word.is_empty())),
+            c => word.push(c),
+        }
+    }
+    words.extend(Some(word).filter(|word| !word.is_empty()));
+    words
+}
+
+/// "nvidia_drm.modeset=1" from "nvidia-drm.modeset=1": the kernel takes
+/// either spelling of module names, and values as they are
+fn normalize(word: &str) -> String {
+    let (name, value) = match word.split_once('=') {
+        Some((name, value)) => (name, Some(value)),
+        None => (word, None),
+    };
+    let name = match name.split_once('.') {
+        Some((module, parameter)) => format!("{}.{}", module.replace('-', "_"), parameter),
+        None => name.to_string(),
+    };
+    match value {
+        Some(value) => format!("{}={}", name, value),
+        None => name,
+    }
+}
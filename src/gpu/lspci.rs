@@ -0,0 +1,69 @@
+//! GPUs listed by `lspci -vmmnn`, for systems where `/sys/bus/pci` is
+//! restricted, such as some containers and hardened kernels.
+//!
+//! lspci falls back to `/proc/bus/pci` when sysfs is unreadable, and names
+//! devices from the system's own pci.ids, so the records match what the
+//! sysfs PCI scan would have made.
+
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::error::GpufetchError;
+use crate::gpu::backend::Backend;
+use crate::gpu::common::{GpuInfo, PciInfo};
+use crate::gpu::{pci, snapshot};
+
+/// Detect GPUs from the display adapters lspci lists
+pub fn detect_lspci_gpus() -> Result<Vec<GpuInfo>> {
+    // -D prints the PCI domain, which lspci leaves out when it is 0000
+    let output = snapshot::run("lspci", &["-vmmnn", "-D"])
+        .map_err(|_| GpufetchError::unavailable(Backend::Lspci, "lspci is not installed"))?;
+    if !output.status.success() {
+        let reason = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(GpufetchError::unavailable(Backend::Lspci, format!("lspci failed: {}", reason)).into());
+    }
+
+    Ok(parse_lspci(&String::from_utf8_lossy(&output.stdout)).iter().filter_map(gpu_from_record).collect())
+}
+
+/// Split lspci's machine-readable output into one map of tags per device
+fn parse_lspci(output: &str) -> Vec<HashMap<&str, &str>> {
+    output
+        .split("\n\n")
+        .map(|record| record.lines().filter_map(|line| line.split_once(":\t")).collect::<HashMap<_, _>>())
+        .filter(|record| !record.is_empty())
+        .collect()
+}
+
+/// A GPU from one device record, if it is a display adapter
+///
+/// With `-nn`, names end in their ID in brackets, e.g.
+/// "GA102 [GeForce RTX 3080] [2206]".
+fn gpu_from_record(record: &HashMap<&str, &str>) -> Option<GpuInfo> {
+    let (_, class) = split_id(record.get("Class")?)?;
+    if !pci::is_display_adapter(class) {
+        return None;
+    }
+    let (vendor_name, vendor_id) = split_id(record.get("Vendor")?)?;
+    let (device_name, device_id) = split_id(record.get("Device")?)?;
+    let (domain, bus, device, function) = pci::parse_pci_address(record.get("Slot")?)?;
+
+    let mut gpu_info = GpuInfo::new(&format!("{} {}", vendor_name, device_name), pci::vendor_from_id(vendor_id));
+    gpu_info.pci_info = Some(PciInfo {
+        vendor_id,
+        device_id,
+        revision: record.get("Rev").and_then(|revision| u8::from_str_radix(revision.trim(), 16).ok()),
+        class_id: class,
+        domain,
+        bus,
+        device,
+        function,
+    });
+    Some(gpu_info)
+}
+
+/// Split "NVIDIA Corporation [10de]" into its name and hex ID
+fn split_id(value: &str) -> Option<(&str, u16)> {
+    let (name, id) = value.trim().strip_suffix(']')?.rsplit_once(" [")?;
+    Some((name, u16::from_str_radix(id, 16).ok()?))
+}
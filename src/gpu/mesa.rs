@@ -0,0 +1,76 @@
+use once_cell::sync::Lazy;
+use regex::bytes::Regex;
+use std::fs;
+use std::path::Path;
+
+use crate::gpu::snapshot;
+
+/// Where distributions install Mesa's libraries
+const LIB_DIRS: [&str; 5] = [
+    "/usr/lib64",
+    "/usr/lib/x86_64-linux-gnu",
+    "/usr/lib/aarch64-linux-gnu",
+    "/usr/lib",
+    "/usr/local/lib",
+];
+
+static MESA_VERSION_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"Mesa (\d+\.\d+\.\d+(?:-[0-9A-Za-z.~+]+)?)").expect("valid regex"));
+static GALLIUM_VERSION: Lazy<Option<String>> = Lazy::new(gallium_version);
+
+/// Whether userspace graphics for this kernel driver come from Mesa
+fn is_mesa_driver(kernel_driver: &str) -> bool {
+    dri_drivers(kernel_driver).next().is_some()
+}
+
+/// Version of the Mesa stack serving a GPU bound to `kernel_driver`
+///
+/// Mesa 24.1 and later name their shared library after the release, e.g.
+/// `libgallium-24.1.2.so`. Older releases embed the version in each DRI
+/// driver instead, so the driver matching the kernel driver is searched for
+/// the "Mesa X.Y.Z" string it reports as part of the OpenGL version.
+pub fn version(kernel_driver: &str) -> Option<String> {
+    // The libraries are this machine's, not the one a snapshot came from
+    if snapshot::is_replaying() || !is_mesa_driver(kernel_driver) {
+        return None;
+    }
+    if let Some(ref version) = *GALLIUM_VERSION {
+        return Some(version.clone());
+    }
+
+    dri_drivers(kernel_driver)
+        .flat_map(|driver| LIB_DIRS.iter().map(move |dir| Path::new(dir).join("dri").join(driver)))
+        .find_map(|path| embedded_version(&path))
+}
+
+/// Mesa DRI drivers for a kernel driver, most likely first
+fn dri_drivers(kernel_driver: &str) -> impl Iterator<Item = &'static str> {
+    let drivers: &[&str] = match kernel_driver {
+        "amdgpu" => &["radeonsi_dri.so"],
+        "radeon" => &["r600_dri.so", "radeonsi_dri.so", "r300_dri.so"],
+        "i915" | "xe" => &["iris_dri.so", "crocus_dri.so", "i965_dri.so"],
+        "nouveau" => &["nouveau_dri.so"],
+        "virtio-pci" | "virtio_gpu" => &["virtio_gpu_dri.so"],
+        _ => &[],
+    };
+    drivers.iter().copied()
+}
+
+/// Version from a `libgallium-X.Y.Z.so` file name
+fn gallium_version() -> Option<String> {
+    LIB_DIRS.iter().find_map(|dir| {
+        let entries = fs::read_dir(dir).ok()?;
+        entries.flatten().find_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let version = name.strip_prefix("libgallium-")?.strip_suffix(".so")?;
+            version.starts_with(|c: char| c.is_ascii_digit()).then(|| version.to_string())
+        })
+    })
+}
+
+/// The "Mesa X.Y.Z" version string compiled into a driver binary
+fn embedded_version(path: &Path) -> Option<String> {
+    let data = fs::read(path).ok()?;
+    let captures = MESA_VERSION_RE.captures(&data)?;
+    Some(String::from_utf8_lossy(&captures[1]).into_owned())
+}
@@ -1,69 +1,216 @@
+pub mod backend;
 pub mod common;
+pub mod drm;
+pub mod edid;
+pub mod egl;
+pub mod features;
+pub mod firmware;
+pub mod gpu_metrics;
+pub mod health;
+pub mod hwmon;
+pub mod hybrid;
+pub mod icd;
+pub mod iommu;
+pub mod kernel_options;
+pub mod lspci;
+pub mod mesa;
+#[cfg(feature = "nvml")]
+pub mod nvml;
+pub mod parse;
 pub mod pci;
+pub mod pcie_tree;
+pub mod perf;
+pub mod reconcile;
+pub mod selector;
+pub mod snapshot;
+pub mod specs;
+pub mod sysfs_layout;
+pub mod sysroot;
+pub mod tools;
+#[cfg(feature = "udev")]
+pub mod udev;
+pub mod update;
+pub mod vaapi;
+#[cfg(feature = "vulkan")]
+pub mod vulkan;
 pub mod amd;
 pub mod intel;
+pub mod intel_gpu_top;
 pub mod nvidia;
 
-use anyhow::{Context, Result};
-use common::GpuInfo;
+use anyhow::Result;
+use backend::{Backend, ENRICHERS};
+use common::{GpuInfo, GpuVendor};
+
+use crate::error::GpufetchError;
+use tracing::{info, info_span, trace, warn};
+
+/// What detection found, and why the backends that found nothing did not
+pub struct Detection {
+    pub gpus: Vec<GpuInfo>,
+    pub diagnostics: Vec<GpufetchError>,
+}
 
 /// Manager for GPU detection and information gathering
 pub struct GpuManager {
-    pub verbose: bool,
+    /// Backends to detect GPUs with, highest priority first
+    pub backends: Vec<Backend>,
 }
 
 impl GpuManager {
     /// Create a new GPU manager instance
     pub fn new() -> Result<Self> {
-        Ok(GpuManager { verbose: false })
+        Ok(GpuManager { backends: Backend::DEFAULT.to_vec() })
     }
     
-    /// Set verbosity level
-    pub fn with_verbose(mut self, verbose: bool) -> Self {
-        self.verbose = verbose;
+    /// Choose the backends to detect GPUs with, in priority order
+    pub fn with_backends(mut self, backends: Vec<Backend>) -> Self {
+        self.backends = backends;
         self
     }
     
     /// Detect available GPUs on the system
-    pub fn detect_gpus(&self) -> Result<Vec<GpuInfo>> {
-        let mut gpus = Vec::new();
+    pub fn detect_gpus(&self) -> Result<Detection, GpufetchError> {
+        let mut gpus: Vec<GpuInfo> = Vec::new();
+        let mut diagnostics = Vec::new();
         
-        // Detect NVIDIA GPUs
-        match nvidia::detect_nvidia_gpus() {
-            Ok(mut nvidia_gpus) => gpus.append(&mut nvidia_gpus),
-            Err(e) => {
-                if self.verbose {
-                    eprintln!("Failed to detect NVIDIA GPUs: {}", e);
+        // A GPU found by several backends is merged into the record of the first one
+        for detector in self.backends.iter().flat_map(|&backend| backend::detectors(backend)) {
+            let _span = info_span!("detect", backend = %detector.backend, detector = detector.name).entered();
+            match (detector.detect)() {
+                Ok(found) => {
+                    info!("found {} GPU(s)", found.len());
+                    for gpu in found {
+                        trace!(?gpu);
+                        reconcile::add(&mut gpus, gpu);
+                    }
+                }
+                Err(e) => {
+                    let error = GpufetchError::classify(detector.backend, &e);
+                    info!("{}", error);
+                    diagnostics.push(error);
                 }
             }
         }
         
-        // Detect AMD GPUs
-        match amd::detect_amd_gpus() {
-            Ok(mut amd_gpus) => gpus.append(&mut amd_gpus),
-            Err(e) => {
-                if self.verbose {
-                    eprintln!("Failed to detect AMD GPUs: {}", e);
-                }
+        // Fallback to PCI detection if no GPUs found
+        if gpus.is_empty() && self.backends.contains(&Backend::Sysfs) {
+            info!("no GPUs found yet, scanning the PCI bus");
+            let pci_gpus = pci::detect_gpus_from_pci().or_else(|e| {
+                // Containers and hardened kernels may hide /sys/bus/pci from us
+                info!("{:#}; asking lspci instead", e);
+                lspci::detect_lspci_gpus().map_err(|_| GpufetchError::classify(Backend::Sysfs, &e))
+            })?;
+            gpus.extend(pci_gpus);
+        }
+        
+        // NVIDIA GPUs whose module the kernel refused have no driver for
+        // nvidia-smi or NVML to find them through, so they come off the PCI bus
+        let nvidia_on_bus = pci::list_pci_devices().iter().any(|pci_info| pci::vendor_from_id(pci_info.vendor_id) == GpuVendor::Nvidia);
+        let blocked_nvidia = health::blocked_nvidia_module().filter(|_| nvidia_on_bus);
+        if let Some(ref blocked) = blocked_nvidia {
+            warn!("{}", blocked);
+            if self.backends.contains(&Backend::Sysfs) {
+                let address = |gpu: &GpuInfo| gpu.pci_info.as_ref().map(|pci_info| pci_info.address());
+                let known: Vec<Option<String>> = gpus.iter().map(address).collect();
+                let missing = pci::detect_gpus_from_pci().unwrap_or_default().into_iter().filter(|gpu| gpu.vendor == GpuVendor::Nvidia && !known.contains(&address(gpu)));
+                gpus.extend(missing);
             }
         }
         
-        // Detect Intel GPUs
-        match intel::detect_intel_gpus() {
-            Ok(mut intel_gpus) => gpus.append(&mut intel_gpus),
-            Err(e) => {
-                if self.verbose {
-                    eprintln!("Failed to detect Intel GPUs: {}", e);
+        // Backends answer in their own order; PCI order keeps indices stable
+        // across runs and backends, with GPUs off the PCI bus last
+        gpus.sort_by_key(|gpu| (gpu.pci_info.is_none(), gpu.pci_info.as_ref().map(|pci_info| pci_info.address())));
+        
+        for enricher in ENRICHERS.iter().filter(|enricher| self.backends.contains(&enricher.backend)) {
+            let _span = info_span!("enrich", backend = %enricher.backend, enricher = enricher.name).entered();
+            for gpu in &mut gpus {
+                if let Err(e) = (enricher.enrich)(gpu) {
+                    info!("no details for {}: {:#}", gpu.name, e);
                 }
             }
         }
         
-        // Fallback to PCI detection if no GPUs found
-        if gpus.is_empty() {
-            let pci_gpus = pci::detect_gpus_from_pci().context("Failed to detect GPUs from PCI")?;
-            gpus.extend(pci_gpus);
+        // Tell SR-IOV physical functions apart from their virtual functions,
+        // read runtime power management, NUMA placement, the fastest PCIe link, the slot and sensors (the
+        // amdgpu metrics table before hwmon), and find the monitors each GPU
+        // drives and the driver stack and firmware serving it
+        for gpu in &mut gpus {
+            pci::read_sriov(gpu);
+            pci::read_runtime_pm(gpu);
+            pci::read_numa(gpu);
+            pci::read_pcie_max_link(gpu);
+            pci::read_slot(gpu);
+            gpu_metrics::read(gpu);
+            hwmon::read_sensors(gpu);
+            drm::read_displays(gpu);
+            if let Some(ref pci_info) = gpu.pci_info {
+                gpu.kernel_driver = pci::bound_driver(&pci::device_path(&pci_info.address()));
+            }
+            gpu.mesa_version = gpu.kernel_driver.as_deref().and_then(mesa::version);
+            firmware::read_firmware(gpu);
         }
+        health::check(&mut gpus, blocked_nvidia.as_deref());
         
-        Ok(gpus)
+        // Work out which GPU drives the displays on PRIME/Optimus laptops
+        hybrid::annotate(&mut gpus);
+        
+        // Headless datacenter cards get a compute-oriented layout, the
+        // others a list of the gaming features they support
+        let icds = icd::Installed::read();
+        let vulkan_icds = icds.vulkan_names();
+        let kernel_options = kernel_options::KernelOptions::read();
+        for gpu in &mut gpus {
+            gpu.compute_only = is_compute_only(gpu);
+            gpu.features = features::detect(gpu, &vulkan_icds);
+            gpu.runtime = icds.runtime(gpu);
+            gpu.kernel_parameters = kernel_options.kernel_parameters(gpu);
+            gpu.module_options = kernel_options.module_options(gpu);
+            let unreported = unreported(gpu);
+            if !unreported.is_empty() {
+                info!("{}: not reported by any backend: {}", gpu.name, unreported.join(", "));
+            }
+        }
+        
+        Ok(Detection { gpus, diagnostics })
+    }
+}
+
+/// Values on the card that detection found nowhere for `gpu`, for `-v`
+fn unreported(gpu: &GpuInfo) -> Vec<&'static str> {
+    [
+        ("memory size", gpu.memory.is_none()),
+        ("core clock", gpu.freq_mhz == 0),
+        ("compute units", gpu.topology.is_none()),
+        ("temperature", gpu.temperature_c.is_none()),
+        ("power draw", gpu.power_draw_w.is_none()),
+        ("fan speed", gpu.fan.is_none()),
+        ("PCIe link", gpu.pcie_link.is_none()),
+        ("driver", gpu.driver_version.is_none() && gpu.kernel_driver.is_none()),
+    ]
+    .into_iter()
+    .filter_map(|(value, missing)| missing.then_some(value))
+    .collect()
+}
+
+/// PCI class of 3D controllers, which have no VGA-compatible display engine
+const PCI_CLASS_3D_CONTROLLER: u16 = 0x0302;
+
+/// Whether a GPU is a datacenter compute card rather than a display adapter
+///
+/// Tesla and Instinct cards are recognised by name. Otherwise a 3D controller
+/// whose DRM card has no connectors counts, unless it is the offload GPU of a
+/// hybrid laptop, which is a 3D controller too.
+fn is_compute_only(gpu: &GpuInfo) -> bool {
+    let name_lower = gpu.name.to_lowercase();
+    if ["tesla", "instinct", "a100", "h100"].iter().any(|family| name_lower.contains(family)) {
+        return true;
+    }
+
+    match gpu.pci_info {
+        Some(ref pci_info) if pci_info.class_id == PCI_CLASS_3D_CONTROLLER && gpu.hybrid.is_none() => {
+            drm::has_outputs(&pci_info.address()) == Some(false)
+        }
+        _ => false,
     }
 }
@@ -1,146 +1,446 @@
 use anyhow::{anyhow, Context, Result};
+use once_cell::sync::Lazy;
 use regex::Regex;
-use std::process::Command;
+use std::collections::HashMap;
 
-use crate::gpu::common::{Cache, GpuInfo, GpuVendor, Memory, MemoryType, Topology};
+use crate::gpu::common::{
+    Cache, EccStatus, FanSpeed, GpuInfo, GpuVendor, Memory, MemoryType, NumaAffinity, NvLinkStatus, PciInfo, PcieLink,
+    PerformanceState, Process, Source, ThermalLimits,
+};
+use crate::error::GpufetchError;
+use crate::gpu::backend::Backend;
+use crate::gpu::parse::{self, parse_nvidia_optional};
+use crate::gpu::{pci, perf, reconcile, snapshot, specs, tools};
+
+/// Bus width assumed for models missing from the spec database
+const DEFAULT_BUS_WIDTH: u32 = 256;
+
+/// Color codes `nvidia-smi topo -m` underlines its headings with
+static ESCAPE_CODES: Lazy<Regex> = Lazy::new(|| Regex::new("\x1b\\[[0-9;]*m").expect("valid regex"));
+
+/// A link in `nvidia-smi nvlink --status`, e.g. "Link 0: 25.781 GB/s"
+static NVLINK_SPEED: Lazy<Regex> = Lazy::new(|| Regex::new(r"Link \d+: ([\d.]+) GB/s").expect("valid regex"));
 
 /// Detect NVIDIA GPUs using nvidia-smi
+///
+/// The CSV query is the main source. The XML report of `nvidia-smi -q -x`
+/// adds what the query cannot ask for, such as the VBIOS, the PCIe link and
+/// the encoder load, and stands in for the query when it fails or returns
+/// rows that do not parse, as with fields renamed by a driver update.
 pub fn detect_nvidia_gpus() -> Result<Vec<GpuInfo>> {
-    let mut gpus = Vec::new();
-    
     // Check if nvidia-smi is available
-    if !is_nvidia_smi_available() {
-        return Ok(vec![]);
+    if !tools::is_installed("nvidia-smi") {
+        return Err(GpufetchError::unavailable(Backend::Smi, "nvidia-smi is not installed").into());
+    }
+    
+    let from_xml = query_xml_report();
+    let mut gpus = match (query_csv(), from_xml) {
+        (Ok(mut gpus), from_xml) if !gpus.is_empty() => {
+            for (bus_id, report) in from_xml.unwrap_or_default() {
+                if let Some((_, gpu_info)) = gpus.iter_mut().find(|(known, _)| *known == bus_id) {
+                    reconcile::merge(gpu_info, report);
+                }
+            }
+            gpus
+        }
+        (_, Ok(from_xml)) => from_xml,
+        (Err(e), Err(_)) => return Err(e),
+        (Ok(gpus), Err(_)) => gpus,
+    };
+    
+    let compute_capabilities = query_compute_capabilities();
+    let affinities = query_cpu_affinity();
+    for (index, (bus_id, gpu_info)) in gpus.iter_mut().enumerate() {
+        gpu_info.compute_capability = compute_capabilities.get(bus_id.as_str()).cloned();
+        gpu_info.nvlink = get_nvidia_nvlink(bus_id);
+        gpu_info.numa = affinities.get(&format!("GPU{}", index)).cloned();
+        complete_from_name(gpu_info);
     }
     
+    Ok(gpus.into_iter().map(|(_, gpu_info)| gpu_info).collect())
+}
+
+/// GPUs from `nvidia-smi --query-gpu`, with their bus IDs
+fn query_csv() -> Result<Vec<(String, GpuInfo)>> {
+    let mut gpus = Vec::new();
+    
     // Run nvidia-smi to get GPU info
-    let output = Command::new("nvidia-smi")
-        .args(["--query-gpu=name,driver_version,memory.total,pci.bus_id,pstate,clocks.max.gr,clocks.current.gr", "--format=csv,noheader"])
-        .output()
+    let output = snapshot::run("nvidia-smi", &["--query-gpu=name,driver_version,memory.total,pci.bus_id,pstate,clocks.max.gr,clocks.current.gr,uuid,serial,ecc.mode.current,ecc.errors.corrected.volatile.total,ecc.errors.uncorrected.volatile.total,temperature.gpu,clocks_throttle_reasons.active,clocks.max.mem", "--format=csv,noheader"])
         .context("Failed to execute nvidia-smi")?;
     
     if !output.status.success() {
-        return Err(anyhow!("nvidia-smi command failed"));
+        let reason = format!("nvidia-smi failed: {}", String::from_utf8_lossy(&output.stdout).trim());
+        return Err(GpufetchError::unavailable(Backend::Smi, reason).into());
     }
     
     let output_str = String::from_utf8(output.stdout)
-        .context("nvidia-smi output is not valid UTF-8")?;
+        .map_err(|_| GpufetchError::parse_failure("nvidia-smi output", "not valid UTF-8"))?;
     
-    // Parse each GPU line
-    for line in output_str.lines() {
-        let fields: Vec<&str> = line.split(',').map(|s| s.trim()).collect();
-        if fields.len() < 7 {
-            continue;
-        }
-        
-        let name = fields[0];
-        let driver_version = fields[1];
-        let memory_total = fields[2];
-        let _pci_bus_id = fields[3]; // Unused but kept for clarity
-        let _performance_state = fields[4]; // Unused but kept for clarity
-        let max_clock = fields[5];
-        let current_clock = fields[6];
+    // Rows without a name and a bus ID are garbled
+    for row in output_str.lines().filter_map(parse::parse_nvidia_smi_row) {
+        let mut gpu_info = GpuInfo::new(row.name, GpuVendor::Nvidia);
+        gpu_info.driver_version = Some(row.driver_version.to_string());
+        gpu_info.pci_info = get_nvidia_pci_info(row.bus_id);
+        gpu_info.uuid = row.uuid.map(str::to_string);
+        gpu_info.serial = row.serial.map(str::to_string);
+        gpu_info.ecc = row.ecc_mode.map(|mode| EccStatus {
+            enabled: mode == "Enabled",
+            corrected_errors: row.corrected_errors,
+            uncorrected_errors: row.uncorrected_errors,
+        });
+        gpu_info.temperature_c = row.temperature_c;
+        gpu_info.performance_state = row.pstate.map(|pstate| PerformanceState {
+            pstate: Some(pstate.to_string()),
+            throttle_reasons: row.throttle_mask.map(throttle_reasons).unwrap_or_default(),
+            ..Default::default()
+        });
         
-        // Create GPU info
-        let mut gpu_info = GpuInfo::new(name, GpuVendor::Nvidia);
-        gpu_info.driver_version = Some(driver_version.to_string());
-        
-        // Parse memory
-        if let Some(memory_mb) = parse_nvidia_memory(memory_total) {
-            let memory = Memory {
-                size_bytes: memory_mb * 1024 * 1024,
-                memory_type: get_nvidia_memory_type(name),
-                bus_width: get_nvidia_bus_width(name),
-                clock_mhz: 0, // To be populated later
-            };
+        if let Some(size_bytes) = row.memory_bytes {
+            let mut memory = nvidia_memory(row.name, size_bytes);
+            memory.clock_mhz = row.max_memory_clock_mhz.unwrap_or(0);
             gpu_info.memory = Some(memory);
         }
         
-        // Parse clocks
-        if let Ok(current_mhz) = parse_nvidia_clock(current_clock) {
+        if let Some(current_mhz) = row.clock_mhz {
             gpu_info.freq_mhz = current_mhz;
         }
-        
-        if let Ok(max_mhz) = parse_nvidia_clock(max_clock) {
+        if let Some(max_mhz) = row.max_clock_mhz {
             gpu_info.max_freq_mhz = max_mhz;
         }
         
-        // Try to get architecture and compute capability
-        if let Some((arch, compute_cap)) = get_nvidia_architecture(name) {
-            gpu_info.architecture = arch;
-            gpu_info.compute_capability = Some(compute_cap);
-        }
+        gpus.push((row.bus_id.to_string(), gpu_info));
+    }
+    
+    Ok(gpus)
+}
+
+/// GPUs from the XML report of `nvidia-smi -q -x`, with their bus IDs
+///
+/// Values are read by element name, so reordered or added elements do not
+/// matter:
+///
+/// ```text
+/// <gpu id="00000000:01:00.0">
+///     <product_name>NVIDIA GeForce RTX 3080</product_name>
+///     <vbios_version>94.02.42.00.A9</vbios_version>
+///     <pci>
+///         <pci_gpu_link_info>
+///             <pcie_gen><current_link_gen>4</current_link_gen></pcie_gen>
+///             <link_widths><current_link_width>16x</current_link_width></link_widths>
+/// ```
+fn query_xml_report() -> Result<Vec<(String, GpuInfo)>> {
+    let output = snapshot::run("nvidia-smi", &["-q", "-x"]).context("Failed to execute nvidia-smi")?;
+    if !output.status.success() {
+        return Err(anyhow!("nvidia-smi -q -x failed"));
+    }
+    parse_nvidia_xml(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse the XML report; see `query_xml_report`
+fn parse_nvidia_xml(xml: &str) -> Result<Vec<(String, GpuInfo)>> {
+    // The report names its DTD, which is not needed to read it
+    let options = roxmltree::ParsingOptions { allow_dtd: true, ..Default::default() };
+    let document = roxmltree::Document::parse_with_options(xml, options)
+        .map_err(|e| GpufetchError::parse_failure("nvidia-smi XML report", e.to_string()))?;
+    let root = document.root_element();
+    let driver_version = xml_text(root, &["driver_version"]);
+    
+    let mut gpus = Vec::new();
+    for gpu in root.children().filter(|node| node.has_tag_name("gpu")) {
+        let (Some(bus_id), Some(name)) = (gpu.attribute("id"), xml_text(gpu, &["product_name"])) else {
+            continue;
+        };
+        // Values come with units, e.g. "41 C", "2100 MHz" or "16x"
+        let number = |path: &[&str]| -> Option<f64> {
+            xml_text(gpu, path)?.split_whitespace().next()?.trim_end_matches(['x', '%']).parse().ok()
+        };
+        let whole = |path: &[&str]| number(path).map(|value| value.round() as u32);
         
-        // Try to get chip info
-        if let Some(chip) = get_nvidia_chip(name) {
-            gpu_info.chip = chip;
-        }
+        let mut gpu_info = GpuInfo::new(&name, GpuVendor::Nvidia);
+        gpu_info.driver_version = driver_version.clone();
+        gpu_info.pci_info = get_nvidia_pci_info(bus_id);
+        gpu_info.uuid = xml_text(gpu, &["uuid"]);
+        gpu_info.serial = xml_text(gpu, &["serial"]);
+        gpu_info.vbios_version = xml_text(gpu, &["vbios_version"]);
+        gpu_info.ecc = xml_text(gpu, &["ecc_mode", "current_ecc"]).map(|mode| EccStatus {
+            enabled: mode == "Enabled",
+            corrected_errors: None,
+            uncorrected_errors: None,
+        });
+        gpu_info.performance_state = xml_text(gpu, &["performance_state"])
+            .map(|pstate| PerformanceState { pstate: Some(pstate), ..Default::default() });
         
-        // Try to get manufacturing process
-        if let Some(process) = get_nvidia_process_nm(name) {
-            gpu_info.process_nm = Some(process);
+        if let Some(size_bytes) = number(&["fb_memory_usage", "total"]).and_then(|mib| (mib as u64).checked_mul(1024 * 1024)) {
+            let mut memory = nvidia_memory(&name, size_bytes);
+            memory.clock_mhz = whole(&["max_clocks", "mem_clock"]).unwrap_or(0);
+            gpu_info.memory = Some(memory);
         }
+        gpu_info.memory_used_bytes = number(&["fb_memory_usage", "used"]).and_then(|mib| (mib as u64).checked_mul(1024 * 1024));
+        gpu_info.freq_mhz = whole(&["clocks", "graphics_clock"]).unwrap_or(0);
+        gpu_info.max_freq_mhz = whole(&["max_clocks", "graphics_clock"]).unwrap_or(0);
         
-        // Try to get topology information
-        if let Some(topology) = get_nvidia_topology(name) {
-            gpu_info.topology = Some(topology);
-        }
+        gpu_info.temperature_c = number(&["temperature", "gpu_temp"]);
+        gpu_info.thermal_limits = ThermalLimits::new(
+            number(&["temperature", "gpu_temp_slow_threshold"]),
+            number(&["temperature", "gpu_temp_max_threshold"]),
+        );
+        // Drivers before 530 call the section power_readings
+        gpu_info.power_draw_w =
+            number(&["gpu_power_readings", "power_draw"]).or_else(|| number(&["power_readings", "power_draw"]));
+        // Drivers from 530 on report the core rail, in millivolts
+        gpu_info.core_voltage_mv = whole(&["voltage", "graphics_volt"]);
+        gpu_info.fan = whole(&["fan_speed"]).map(|percent| FanSpeed { percent: Some(percent), rpm: None });
+        gpu_info.encoder_utilization_percent = whole(&["utilization", "encoder_util"]);
         
-        // Try to get cache information
-        if let Some(cache) = get_nvidia_cache(name) {
-            gpu_info.cache = Some(cache);
+        let link = ["pci", "pci_gpu_link_info"];
+        if let (Some(generation), Some(width)) = (
+            whole(&[link[0], link[1], "pcie_gen", "current_link_gen"]),
+            whole(&[link[0], link[1], "link_widths", "current_link_width"]),
+        ) {
+            gpu_info.pcie_link = Some(PcieLink { generation, width });
         }
-        
-        // Calculate peak performance
-        if let Some(ref topology) = gpu_info.topology {
-            if let Some(cuda_cores) = topology.cuda_cores {
-                // Peak FLOPS = 2 * cores * clock
-                let peak_gflops = 2.0 * cuda_cores as f64 * gpu_info.max_freq_mhz as f64 / 1000.0;
-                gpu_info.peak_performance_gflops = Some(peak_gflops);
-            }
+        if let (Some(generation), Some(width)) = (
+            whole(&[link[0], link[1], "pcie_gen", "max_link_gen"]),
+            whole(&[link[0], link[1], "link_widths", "max_link_width"]),
+        ) {
+            gpu_info.pcie_max_link = Some(PcieLink { generation, width });
         }
         
-        gpus.push(gpu_info);
+        gpus.push((bus_id.to_string(), gpu_info));
     }
     
     Ok(gpus)
 }
 
-/// Check if nvidia-smi is available
-fn is_nvidia_smi_available() -> bool {
-    Command::new("which")
-        .arg("nvidia-smi")
-        .output()
-        .map(|output| output.status.success())
-        .unwrap_or(false)
+/// Text of the element at `path` below `node`, unless it is "N/A" or empty
+fn xml_text(node: roxmltree::Node, path: &[&str]) -> Option<String> {
+    let mut node = node;
+    for name in path {
+        node = node.children().find(|child| child.has_tag_name(*name))?;
+    }
+    let text = node.text()?.trim();
+    (!text.is_empty() && text != "N/A").then(|| text.to_string())
 }
 
-/// Parse memory total from nvidia-smi output
-fn parse_nvidia_memory(memory_str: &str) -> Option<u64> {
-    let re = Regex::new(r"(\d+) MiB").ok()?;
-    re.captures(memory_str)
-        .and_then(|cap| cap[1].parse::<u64>().ok())
+/// Memory of the size the driver reports, with its type guessed from the model name
+pub fn nvidia_memory(name: &str, size_bytes: u64) -> Memory {
+    Memory {
+        size_bytes,
+        memory_type: get_nvidia_memory_type(name),
+        bus_width: DEFAULT_BUS_WIDTH,
+        clock_mhz: 0,
+    }
 }
 
-/// Parse clock value from nvidia-smi output
-fn parse_nvidia_clock(clock_str: &str) -> Result<u32> {
-    let re = Regex::new(r"(\d+) MHz").context("Invalid regex")?;
-    let cap = re.captures(clock_str)
-        .ok_or_else(|| anyhow!("Invalid clock format"))?;
+/// Fill in what the driver does not report from the model name and the
+/// spec database, then work out peak performance
+pub fn complete_from_name(gpu_info: &mut GpuInfo) {
+    // A compute capability from the driver names the architecture exactly;
+    // the model name is only a guess for cards it knows
+    let from_driver = gpu_info.compute_capability.as_deref().and_then(architecture_from_compute_capability);
+    if let Some(arch) = from_driver {
+        gpu_info.architecture = arch.to_string();
+    } else if let Some((arch, compute_cap)) = get_nvidia_architecture(&gpu_info.name) {
+        gpu_info.architecture = arch;
+        gpu_info.mark_heuristic(&["architecture"]);
+        if gpu_info.compute_capability.is_none() {
+            gpu_info.compute_capability = Some(compute_cap);
+            gpu_info.mark_heuristic(&["compute_capability"]);
+        }
+    }
+    
+    // Try to get manufacturing process
+    if let Some(process) = get_nvidia_process(&gpu_info.name) {
+        gpu_info.process = Some(process);
+        gpu_info.mark_heuristic(&["process_nm"]);
+    }
+    
+    if let Some(kb) = gpu_info.compute_capability.as_deref().and_then(l1_kb_per_sm) {
+        let cache = gpu_info.cache.get_or_insert(Cache { l1_size: None, l2_size: None, l3_size: None });
+        cache.l1_size = Some(kb * 1024);
+        gpu_info.mark_heuristic(&["l1_kb"]);
+    }
+    
+    // The driver reports the memory size; its type and bus width were guessed
+    if let Some(type_known) = gpu_info.memory.as_ref().map(|memory| !matches!(memory.memory_type, MemoryType::Unknown)) {
+        gpu_info.mark_heuristic(&["bus_width"]);
+        if type_known {
+            gpu_info.mark_heuristic(&["memory_type"]);
+        }
+    }
+    
+    specs::apply_specs(gpu_info);
+    // A spec database agreeing with the driver does not make the architecture a guess
+    if from_driver.is_some_and(|arch| gpu_info.architecture == arch)
+        && gpu_info.provenance.get("architecture") == Some(&Source::SpecDb)
+    {
+        gpu_info.provenance.remove("architecture");
+    }
     
-    cap[1].parse::<u32>()
-        .context("Invalid clock value")
+    gpu_info.peak_performance_gflops = perf::peak_gflops(gpu_info);
+}
+
+/// Names of the bits set in an NVML clocks throttle reason mask
+pub fn throttle_reasons(mask: u64) -> Vec<&'static str> {
+    const REASONS: [(u64, &str); 9] = [
+        (0x1, "idle"),
+        (0x2, "application clocks"),
+        (0x4, "power cap"),
+        (0x8, "hardware slowdown"),
+        (0x10, "sync boost"),
+        (0x20, "thermal"),
+        (0x40, "hardware thermal"),
+        (0x80, "power brake"),
+        (0x100, "display clock"),
+    ];
+    REASONS.iter().filter(|(bit, _)| mask & bit != 0).map(|&(_, reason)| reason).collect()
+}
+
+/// Compute capabilities by PCI bus ID, as the driver reports them
+///
+/// nvidia-smi only knows `compute_cap` from driver 510 on, and an unknown
+/// field fails the whole query, so it is asked for separately.
+fn query_compute_capabilities() -> HashMap<String, String> {
+    let Ok(output) = snapshot::run("nvidia-smi", &["--query-gpu=pci.bus_id,compute_cap", "--format=csv,noheader"]) else {
+        return HashMap::new();
+    };
+    if !output.status.success() {
+        return HashMap::new();
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_once(','))
+        .filter_map(|(bus_id, compute_cap)| {
+            parse_nvidia_optional(compute_cap.trim()).map(|compute_cap| (bus_id.trim().to_string(), compute_cap.to_string()))
+        })
+        .collect()
+}
+
+/// NUMA and CPU affinity of each GPU by its row label ("GPU0"), from the
+/// `nvidia-smi topo -m` matrix
+fn query_cpu_affinity() -> HashMap<String, NumaAffinity> {
+    let Ok(output) = snapshot::run("nvidia-smi", &["topo", "-m"]) else {
+        return HashMap::new();
+    };
+    if !output.status.success() {
+        return HashMap::new();
+    }
+    parse_nvidia_topology(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse the tab-separated matrix of `nvidia-smi topo -m`:
+///
+/// ```text
+///         GPU0    GPU1    CPU Affinity    NUMA Affinity   GPU NUMA ID
+/// GPU0     X      NV4     0-31,64-95      0               N/A
+/// ```
+///
+/// The legend after the matrix has no tabs and is skipped.
+fn parse_nvidia_topology(output: &str) -> HashMap<String, NumaAffinity> {
+    // Headings are underlined with escape codes even when piped
+    let output = ESCAPE_CODES.replace_all(output, "");
+    let clean = |cell: &str| cell.trim().to_string();
+    let mut rows = output.lines().filter(|line| line.contains('\t'));
+    let Some(header) = rows.next() else {
+        return HashMap::new();
+    };
+    let header: Vec<String> = header.split('\t').map(clean).collect();
+    let column = |name: &str| header.iter().position(|cell| cell.starts_with(name));
+    let (cpu_column, numa_column) = (column("CPU Affinity"), column("NUMA Affinity"));
+
+    rows.filter_map(|row| {
+        let cells: Vec<String> = row.split('\t').map(clean).collect();
+        let label = cells.first().filter(|label| label.starts_with("GPU"))?.clone();
+        let cell = |column: Option<usize>| column.and_then(|column| cells.get(column)).filter(|cell| !cell.is_empty() && *cell != "N/A");
+        let affinity = NumaAffinity {
+            node: cell(numa_column).and_then(|node| node.parse().ok()),
+            cpus: cell(cpu_column).cloned(),
+        };
+        (affinity.node.is_some() || affinity.cpus.is_some()).then_some((label, affinity))
+    })
+    .collect()
+}
+
+/// Architecture of a compute capability such as "8.9"
+fn architecture_from_compute_capability(compute_capability: &str) -> Option<&'static str> {
+    let (major, minor) = compute_capability.split_once('.')?;
+    let architecture = match (major.parse::<u32>().ok()?, minor.parse::<u32>().ok()?) {
+        (3, _) => "Kepler",
+        (5, _) => "Maxwell",
+        (6, _) => "Pascal",
+        (7, 0) | (7, 2) => "Volta",
+        (7, 5) => "Turing",
+        (8, 9) => "Ada Lovelace",
+        (8, _) => "Ampere",
+        (9, _) => "Hopper",
+        (10, _) | (12, _) => "Blackwell",
+        _ => return None,
+    };
+    Some(architecture)
+}
+
+/// Combined L1 and shared memory of each SM in KiB, by compute capability,
+/// as NVIDIA's tuning guides give it from Volta on
+fn l1_kb_per_sm(compute_capability: &str) -> Option<u64> {
+    let (major, minor) = compute_capability.split_once('.')?;
+    let kb = match (major.parse::<u32>().ok()?, minor.parse::<u32>().ok()?) {
+        (7, 0) | (7, 2) => 128,
+        (7, 5) => 96,
+        (8, 0) => 192,
+        (8, 6) | (8, 7) | (8, 9) => 128,
+        (9, 0) | (10, _) => 256,
+        (12, _) => 128,
+        _ => return None,
+    };
+    Some(kb)
+}
+
+/// Look up the sysfs PCI entry for an NVIDIA bus ID (e.g. "00000000:01:00.0")
+pub fn get_nvidia_pci_info(bus_id: &str) -> Option<PciInfo> {
+    let (domain, bus, device, function) = pci::parse_pci_address(bus_id)?;
+    let address = format!("{:04x}:{:02x}:{:02x}.{:x}", domain, bus, device, function);
+    pci::read_pci_info(&pci::device_path(&address)).ok()
+}
+
+/// Query the active NVLink links of a GPU
+fn get_nvidia_nvlink(bus_id: &str) -> Option<NvLinkStatus> {
+    let output = snapshot::run("nvidia-smi", &["nvlink", "--status", "-i", bus_id]).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    
+    parse_nvidia_nvlink(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse `nvidia-smi nvlink --status` output, e.g. "Link 0: 25.781 GB/s"
+fn parse_nvidia_nvlink(output: &str) -> Option<NvLinkStatus> {
+    let speeds: Vec<f64> = NVLINK_SPEED
+        .captures_iter(output)
+        .filter_map(|cap| cap[1].parse::<f64>().ok())
+        .collect();
+    if speeds.is_empty() {
+        return None;
+    }
+    
+    Some(NvLinkStatus {
+        active_links: speeds.len() as u32,
+        bandwidth_gbs: speeds.iter().sum(),
+    })
 }
 
 /// Determine memory type based on GPU name
 fn get_nvidia_memory_type(name: &str) -> MemoryType {
     let name_lower = name.to_lowercase();
     
-    if name_lower.contains("rtx 30") || name_lower.contains("rtx 40") {
+    // Workstation cards use plain GDDR6 even where their GeForce siblings use GDDR6X
+    if name_lower.contains("ada generation") || name_lower.contains("rtx a") || name_lower.contains("quadro rtx") {
+        MemoryType::Gddr6
+    } else if name_lower.contains("rtx 30") || name_lower.contains("rtx 40") {
         MemoryType::Gddr6X
-    } else if name_lower.contains("rtx 20") || name_lower.contains("gtx 16") || name_lower.contains("rtx a") {
+    } else if name_lower.contains("rtx 20") || name_lower.contains("gtx 16") {
         MemoryType::Gddr6
-    } else if name_lower.contains("titan v") || name_lower.contains("tesla v") {
+    } else if name_lower.contains("titan v") || name_lower.contains("tesla v") || name_lower.contains("quadro gv") {
         MemoryType::Hbm2
     } else if name_lower.contains("titan x") || name_lower.contains("gtx 1080") {
         MemoryType::Gddr5X
@@ -151,50 +451,22 @@ fn get_nvidia_memory_type(name: &str) -> MemoryType {
     }
 }
 
-/// Estimate memory bus width based on GPU name
-fn get_nvidia_bus_width(name: &str) -> u32 {
-    let name_lower = name.to_lowercase();
-    
-    if name_lower.contains("rtx 3090") || name_lower.contains("rtx 3080") || 
-       name_lower.contains("rtx 4090") || name_lower.contains("rtx 4080") {
-        384
-    } else if name_lower.contains("rtx 3070") || name_lower.contains("rtx 3060 ti") ||
-              name_lower.contains("rtx 4070") {
-        256
-    } else if name_lower.contains("rtx 3060") || name_lower.contains("rtx 4060") {
-        192
-    } else if name_lower.contains("rtx 3050") || name_lower.contains("rtx 4050") {
-        128
-    } else if name_lower.contains("rtx 2080") {
-        256
-    } else if name_lower.contains("rtx 2070") || name_lower.contains("rtx 2060") {
-        192
-    } else if name_lower.contains("gtx 1080") || name_lower.contains("gtx 1070") {
-        256
-    } else if name_lower.contains("gtx 1060") {
-        192
-    } else if name_lower.contains("gtx 1050") {
-        128
-    } else {
-        256  // Default value
-    }
-}
-
 /// Determine NVIDIA architecture and compute capability
 fn get_nvidia_architecture(name: &str) -> Option<(String, String)> {
     let name_lower = name.to_lowercase();
     
-    if name_lower.contains("rtx 40") {
+    if name_lower.contains("rtx 40") || name_lower.contains("ada generation") {
         Some(("Ada Lovelace".to_string(), "8.9".to_string()))
-    } else if name_lower.contains("rtx 30") {
+    } else if name_lower.contains("rtx 30") || name_lower.contains("rtx a") {
         Some(("Ampere".to_string(), "8.6".to_string()))
     } else if name_lower.contains("a100") || name_lower.contains("a30") {
         Some(("Ampere".to_string(), "8.0".to_string()))
-    } else if name_lower.contains("rtx 20") || name_lower.contains("gtx 16") {
+    } else if name_lower.contains("rtx 20") || name_lower.contains("gtx 16") || name_lower.contains("quadro rtx") ||
+              name_lower.contains("tesla t4") {
         Some(("Turing".to_string(), "7.5".to_string()))
-    } else if name_lower.contains("tesla v") || name_lower.contains("titan v") {
+    } else if name_lower.contains("tesla v") || name_lower.contains("titan v") || name_lower.contains("quadro gv") {
         Some(("Volta".to_string(), "7.0".to_string()))
-    } else if name_lower.contains("gtx 10") || name_lower.contains("tesla p") {
+    } else if name_lower.contains("gtx 10") || name_lower.contains("tesla p") || name_lower.contains("quadro p") {
         Some(("Pascal".to_string(), "6.1".to_string()))
     } else if name_lower.contains("tesla p100") {
         Some(("Pascal".to_string(), "6.0".to_string()))
@@ -209,181 +481,30 @@ fn get_nvidia_architecture(name: &str) -> Option<(String, String)> {
     }
 }
 
-/// Determine NVIDIA chip name
-fn get_nvidia_chip(name: &str) -> Option<String> {
-    let name_lower = name.to_lowercase();
-    
-    if name_lower.contains("rtx 4090") || name_lower.contains("rtx 4080") {
-        Some("AD102".to_string())
-    } else if name_lower.contains("rtx 4070") {
-        Some("AD104".to_string())
-    } else if name_lower.contains("rtx 4060") {
-        Some("AD106".to_string())
-    } else if name_lower.contains("rtx 4050") {
-        Some("AD107".to_string())
-    } else if name_lower.contains("rtx 3090") || name_lower.contains("rtx 3080") {
-        Some("GA102".to_string())
-    } else if name_lower.contains("rtx 3070") || name_lower.contains("rtx 3060 ti") {
-        Some("GA104".to_string())
-    } else if name_lower.contains("rtx 3060") {
-        Some("GA106".to_string())
-    } else if name_lower.contains("rtx 3050") {
-        Some("GA107".to_string())
-    } else if name_lower.contains("a100") {
-        Some("GA100".to_string())
-    } else if name_lower.contains("rtx 2080") {
-        Some("TU102".to_string())
-    } else if name_lower.contains("rtx 2070") || name_lower.contains("rtx 2060") {
-        Some("TU106".to_string())
-    } else if name_lower.contains("gtx 1660") || name_lower.contains("gtx 1650") {
-        Some("TU116".to_string())
-    } else {
-        None
-    }
-}
-
 /// Determine manufacturing process based on architecture
-fn get_nvidia_process_nm(name: &str) -> Option<u32> {
+fn get_nvidia_process(name: &str) -> Option<Process> {
     let name_lower = name.to_lowercase();
     
-    if name_lower.contains("rtx 40") {
-        Some(4)  // 4nm for Ada Lovelace
-    } else if name_lower.contains("rtx 30") || name_lower.contains("a100") {
-        Some(8)  // 8nm for Ampere consumer GPUs
-    } else if name_lower.contains("rtx 20") || name_lower.contains("gtx 16") {
-        Some(12) // 12nm for Turing
-    } else if name_lower.contains("titan v") || name_lower.contains("tesla v") {
-        Some(12) // 12nm for Volta
-    } else if name_lower.contains("gtx 10") || name_lower.contains("tesla p") {
-        Some(16) // 16nm for Pascal
+    if name_lower.contains("rtx 40") || name_lower.contains("ada generation") {
+        Some(Process::new("TSMC", Some("4N"), 4))  // Ada Lovelace
+    } else if name_lower.contains("a100") {
+        Some(Process::new("TSMC", Some("N7"), 7))  // Ampere datacenter GPUs
+    } else if name_lower.contains("rtx 30") || name_lower.contains("rtx a") {
+        Some(Process::new("Samsung", Some("8N"), 8))  // Ampere consumer and workstation GPUs
+    } else if name_lower.contains("rtx 20") || name_lower.contains("gtx 16") || name_lower.contains("quadro rtx") ||
+              name_lower.contains("tesla t4") {
+        Some(Process::new("TSMC", Some("12FFN"), 12)) // Turing
+    } else if name_lower.contains("titan v") || name_lower.contains("tesla v") || name_lower.contains("quadro gv") {
+        Some(Process::new("TSMC", Some("12FFN"), 12)) // Volta
+    } else if name_lower.contains("gtx 1050") || name_lower.contains("gt 1030") {
+        Some(Process::new("Samsung", Some("14LPP"), 14)) // Pascal GP107 and GP108
+    } else if name_lower.contains("gtx 10") || name_lower.contains("tesla p") || name_lower.contains("quadro p") {
+        Some(Process::new("TSMC", Some("16FF"), 16)) // Pascal
     } else if name_lower.contains("gtx 9") {
-        Some(28) // 28nm for Maxwell Gen 2
+        Some(Process::new("TSMC", None, 28)) // Maxwell Gen 2
     } else if name_lower.contains("gtx 750") || name_lower.contains("gtx 860m") {
-        Some(28) // 28nm for Maxwell Gen 1
+        Some(Process::new("TSMC", None, 28)) // Maxwell Gen 1
     } else {
         None
     }
 }
-
-/// Get topology information for NVIDIA GPUs
-fn get_nvidia_topology(name: &str) -> Option<Topology> {
-    let name_lower = name.to_lowercase();
-    
-    let (sm_count, cores_per_sm) = if name_lower.contains("rtx 4090") {
-        (128, 128)  // Ada Lovelace, 16,384 CUDA cores
-    } else if name_lower.contains("rtx 4080") {
-        (76, 128)   // Ada Lovelace, 9,728 CUDA cores
-    } else if name_lower.contains("rtx 4070 ti") {
-        (60, 128)   // Ada Lovelace, 7,680 CUDA cores
-    } else if name_lower.contains("rtx 4070") {
-        (46, 128)   // Ada Lovelace, 5,888 CUDA cores
-    } else if name_lower.contains("rtx 4060 ti") {
-        (34, 128)   // Ada Lovelace, 4,352 CUDA cores
-    } else if name_lower.contains("rtx 3090") {
-        (82, 128)   // Ampere, 10,496 CUDA cores
-    } else if name_lower.contains("rtx 3080") {
-        (68, 128)   // Ampere, 8,704 CUDA cores
-    } else if name_lower.contains("rtx 3070") {
-        (46, 128)   // Ampere, 5,888 CUDA cores
-    } else if name_lower.contains("rtx 3060 ti") {
-        (38, 128)   // Ampere, 4,864 CUDA cores
-    } else if name_lower.contains("rtx 2080 ti") {
-        (68, 64)    // Turing, 4,352 CUDA cores
-    } else if name_lower.contains("rtx 2080") {
-        (46, 64)    // Turing, 2,944 CUDA cores
-    } else if name_lower.contains("rtx 2070") {
-        (36, 64)    // Turing, 2,304 CUDA cores
-    } else if name_lower.contains("rtx 2060") {
-        (30, 64)    // Turing, 1,920 CUDA cores
-    } else if name_lower.contains("gtx 1080 ti") {
-        (28, 128)   // Pascal, 3,584 CUDA cores
-    } else if name_lower.contains("gtx 1080") {
-        (20, 128)   // Pascal, 2,560 CUDA cores
-    } else if name_lower.contains("gtx 1070") {
-        (15, 128)   // Pascal, 1,920 CUDA cores
-    } else if name_lower.contains("gtx 1060") {
-        (10, 128)   // Pascal, 1,280 CUDA cores
-    } else {
-        return None;
-    };
-    
-    let cuda_cores = sm_count * cores_per_sm;
-    
-    // Tensor cores: 0 for Pascal and earlier, 8 per SM for Turing, 4 per SM for Ampere
-    let tensor_cores = if name_lower.contains("rtx 40") || name_lower.contains("rtx 30") {
-        Some(sm_count * 4)
-    } else if name_lower.contains("rtx 20") {
-        Some(sm_count * 8)
-    } else {
-        None
-    };
-    
-    // RT cores: 0 for Pascal and earlier, 1 per SM for Turing and Ampere
-    let rt_cores = if name_lower.contains("rtx") {
-        Some(sm_count)
-    } else {
-        None
-    };
-    
-    Some(Topology {
-        compute_units: sm_count,
-        cuda_cores: Some(cuda_cores),
-        tensor_cores,
-        rt_cores,
-        sm_count: Some(sm_count),
-        stream_processors: None,
-        rops: None,
-        tmus: None,
-        execution_units: None,
-        slices: None,
-        subslices: None,
-    })
-}
-
-/// Get cache information for NVIDIA GPUs
-fn get_nvidia_cache(name: &str) -> Option<Cache> {
-    let name_lower = name.to_lowercase();
-    
-    // L2 cache sizes vary widely by GPU model
-    let l2_size = if name_lower.contains("rtx 4090") {
-        Some(72 * 1024 * 1024)  // 72 MB for RTX 4090
-    } else if name_lower.contains("rtx 4080") {
-        Some(64 * 1024 * 1024)  // 64 MB for RTX 4080
-    } else if name_lower.contains("rtx 4070") {
-        Some(48 * 1024 * 1024)  // 48 MB for RTX 4070
-    } else if name_lower.contains("rtx 4060") {
-        Some(32 * 1024 * 1024)  // 32 MB for RTX 4060
-    } else if name_lower.contains("rtx 3090") {
-        Some(6 * 1024 * 1024)   // 6 MB for RTX 3090
-    } else if name_lower.contains("rtx 3080") {
-        Some(5 * 1024 * 1024)   // 5 MB for RTX 3080
-    } else if name_lower.contains("rtx 3070") {
-        Some(4 * 1024 * 1024)   // 4 MB for RTX 3070
-    } else if name_lower.contains("rtx 3060") {
-        Some(3 * 1024 * 1024)   // 3 MB for RTX 3060
-    } else if name_lower.contains("rtx 2080 ti") {
-        Some(6 * 1024 * 1024)   // 6 MB for RTX 2080 Ti
-    } else if name_lower.contains("rtx 2080") {
-        Some(4 * 1024 * 1024)   // 4 MB for RTX 2080
-    } else if name_lower.contains("rtx 2070") {
-        Some(4 * 1024 * 1024)   // 4 MB for RTX 2070
-    } else if name_lower.contains("rtx 2060") {
-        Some(3 * 1024 * 1024)   // 3 MB for RTX 2060
-    } else if name_lower.contains("gtx 1080 ti") {
-        Some(3 * 1024 * 1024)   // 3 MB for GTX 1080 Ti
-    } else if name_lower.contains("gtx 1080") {
-        Some(2 * 1024 * 1024)   // 2 MB for GTX 1080
-    } else if name_lower.contains("gtx 1070") {
-        Some(2 * 1024 * 1024)   // 2 MB for GTX 1070
-    } else if name_lower.contains("gtx 1060") {
-        Some(1536 * 1024)       // 1.5 MB for GTX 1060
-    } else {
-        None
-    };
-    
-    Some(Cache {
-        l1_size: None, // NVIDIA doesn't typically publish L1 cache sizes
-        l2_size,
-        l3_size: None, // No L3 cache on most NVIDIA GPUs
-    })
-}
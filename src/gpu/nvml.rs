@@ -0,0 +1,307 @@
+//! GPU detection through NVML, the library nvidia-smi is built on.
+//!
+//! libnvidia-ml ships with the NVIDIA driver, so it is loaded at runtime
+//! rather than linked: gpufetch keeps running on machines without it, and
+//! containers that mount the driver libraries but not nvidia-smi still see
+//! their GPUs.
+//!
+//! Neither NVML nor nvidia-smi says who made the VRAM, so NVIDIA GPUs show
+//! no memory vendor.
+
+use anyhow::{anyhow, bail, Result};
+use std::ffi::{c_char, c_int, c_uint, c_void, CStr};
+
+use crate::error::GpufetchError;
+use crate::gpu::backend::Backend;
+use crate::gpu::common::{GpuInfo, GpuVendor, PerformanceState, ThermalLimits};
+use crate::gpu::{nvidia, snapshot};
+
+const LIBRARY: &CStr = c"libnvidia-ml.so.1";
+
+const NVML_SUCCESS: c_int = 0;
+const NVML_ERROR_NO_PERMISSION: c_int = 4;
+const NVML_CLOCK_GRAPHICS: c_uint = 0;
+const NVML_CLOCK_MEM: c_uint = 2;
+const NVML_TEMPERATURE_GPU: c_uint = 0;
+const NVML_TEMPERATURE_THRESHOLD_SHUTDOWN: c_uint = 0;
+const NVML_TEMPERATURE_THRESHOLD_SLOWDOWN: c_uint = 1;
+/// `nvmlPstates_t` value for an unknown P-state
+const NVML_PSTATE_UNKNOWN: c_uint = 32;
+
+// Buffer sizes from nvml.h
+const NAME_BUFFER_SIZE: usize = 96;
+const UUID_BUFFER_SIZE: usize = 80;
+const SERIAL_BUFFER_SIZE: usize = 30;
+const VERSION_BUFFER_SIZE: usize = 80;
+
+type Device = *mut c_void;
+
+/// `nvmlPciInfo_t`
+#[repr(C)]
+struct PciInfo {
+    bus_id_legacy: [c_char; 16],
+    domain: c_uint,
+    bus: c_uint,
+    device: c_uint,
+    /// Device ID in the high 16 bits, vendor ID in the low 16
+    pci_device_id: c_uint,
+    pci_sub_system_id: c_uint,
+    bus_id: [c_char; 32],
+}
+
+/// `nvmlMemory_t`
+#[repr(C)]
+struct MemoryInfo {
+    total: u64,
+    free: u64,
+    used: u64,
+}
+
+/// The NVML entry points gpufetch uses
+struct Nvml {
+    library: *mut c_void,
+    shutdown: unsafe extern "C" fn() -> c_int,
+    device_count: unsafe extern "C" fn(*mut c_uint) -> c_int,
+    device_handle: unsafe extern "C" fn(c_uint, *mut Device) -> c_int,
+    device_name: unsafe extern "C" fn(Device, *mut c_char, c_uint) -> c_int,
+    device_uuid: unsafe extern "C" fn(Device, *mut c_char, c_uint) -> c_int,
+    device_serial: unsafe extern "C" fn(Device, *mut c_char, c_uint) -> c_int,
+    device_pci_info: unsafe extern "C" fn(Device, *mut PciInfo) -> c_int,
+    device_memory: unsafe extern "C" fn(Device, *mut MemoryInfo) -> c_int,
+    device_clock: unsafe extern "C" fn(Device, c_uint, *mut c_uint) -> c_int,
+    device_max_clock: unsafe extern "C" fn(Device, c_uint, *mut c_uint) -> c_int,
+    device_temperature: unsafe extern "C" fn(Device, c_uint, *mut c_uint) -> c_int,
+    device_temperature_threshold: unsafe extern "C" fn(Device, c_uint, *mut c_uint) -> c_int,
+    device_performance_state: unsafe extern "C" fn(Device, *mut c_uint) -> c_int,
+    device_throttle_reasons: unsafe extern "C" fn(Device, *mut u64) -> c_int,
+    /// Missing from NVML releases older than CUDA 9
+    device_compute_capability: Option<unsafe extern "C" fn(Device, *mut c_int, *mut c_int) -> c_int>,
+    /// Missing from drivers older than R510
+    device_clock_offset: Option<unsafe extern "C" fn(Device, *mut c_int) -> c_int>,
+    /// Missing from drivers older than R410
+    device_total_energy: Option<unsafe extern "C" fn(Device, *mut u64) -> c_int>,
+    driver_version: unsafe extern "C" fn(*mut c_char, c_uint) -> c_int,
+}
+
+impl Nvml {
+    /// Load libnvidia-ml and initialize it
+    fn open() -> Result<Self> {
+        // The library answers for this machine, not the one a snapshot came from
+        if snapshot::is_replaying() {
+            return Err(GpufetchError::unavailable(Backend::Nvml, "not available when replaying a snapshot").into());
+        }
+
+        // SAFETY: dlopen with a constant, NUL-terminated library name
+        let library = unsafe { libc::dlopen(LIBRARY.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        if library.is_null() {
+            let reason = format!("{} is not installed", LIBRARY.to_string_lossy());
+            return Err(GpufetchError::unavailable(Backend::Nvml, reason).into());
+        }
+
+        // SAFETY: the signatures match nvml.h for each symbol
+        let resolved = unsafe { Self::resolve(library) };
+        let (init, nvml) = match resolved {
+            Ok(resolved) => resolved,
+            Err(e) => {
+                // SAFETY: the handle came from dlopen and nothing from it is kept
+                unsafe { libc::dlclose(library) };
+                return Err(e);
+            }
+        };
+
+        // From here on dropping `nvml` closes the library again
+        // SAFETY: nvmlInit takes no arguments
+        match unsafe { init() } {
+            NVML_SUCCESS => Ok(nvml),
+            NVML_ERROR_NO_PERMISSION => {
+                Err(GpufetchError::PermissionDenied { resource: "the NVIDIA driver (NVML)".to_string() }.into())
+            }
+            code => Err(GpufetchError::unavailable(Backend::Nvml, format!("nvmlInit failed with error {}", code)).into()),
+        }
+    }
+
+    /// Look up nvmlInit and the entry points used after it
+    ///
+    /// # Safety
+    /// `library` must be a handle returned by dlopen for libnvidia-ml.
+    unsafe fn resolve(library: *mut c_void) -> Result<(unsafe extern "C" fn() -> c_int, Self)> {
+        let init = symbol(library, c"nvmlInit_v2")?;
+        let nvml = Nvml {
+            library,
+            shutdown: symbol(library, c"nvmlShutdown")?,
+            device_count: symbol(library, c"nvmlDeviceGetCount_v2")?,
+            device_handle: symbol(library, c"nvmlDeviceGetHandleByIndex_v2")?,
+            device_name: symbol(library, c"nvmlDeviceGetName")?,
+            device_uuid: symbol(library, c"nvmlDeviceGetUUID")?,
+            device_serial: symbol(library, c"nvmlDeviceGetSerial")?,
+            device_pci_info: symbol(library, c"nvmlDeviceGetPciInfo_v3")?,
+            device_memory: symbol(library, c"nvmlDeviceGetMemoryInfo")?,
+            device_clock: symbol(library, c"nvmlDeviceGetClockInfo")?,
+            device_max_clock: symbol(library, c"nvmlDeviceGetMaxClockInfo")?,
+            device_temperature: symbol(library, c"nvmlDeviceGetTemperature")?,
+            device_temperature_threshold: symbol(library, c"nvmlDeviceGetTemperatureThreshold")?,
+            device_performance_state: symbol(library, c"nvmlDeviceGetPerformanceState")?,
+            device_throttle_reasons: symbol(library, c"nvmlDeviceGetCurrentClocksThrottleReasons")?,
+            device_compute_capability: symbol(library, c"nvmlDeviceGetCudaComputeCapability").ok(),
+            device_clock_offset: symbol(library, c"nvmlDeviceGetGpcClkVfOffset").ok(),
+            device_total_energy: symbol(library, c"nvmlDeviceGetTotalEnergyConsumption").ok(),
+            driver_version: symbol(library, c"nvmlSystemGetDriverVersion")?,
+        };
+        Ok((init, nvml))
+    }
+
+    /// Read one GPU, leaving out whatever the device does not support
+    fn gpu(&self, index: c_uint, driver_version: Option<&str>) -> Result<GpuInfo> {
+        let mut device: Device = std::ptr::null_mut();
+        // SAFETY: NVML writes the handle through the pointer we pass
+        check(unsafe { (self.device_handle)(index, &mut device) }, "nvmlDeviceGetHandleByIndex")?;
+
+        // SAFETY: each call gets a buffer of the size nvml.h asks for, or a
+        // pointer to the struct it fills in
+        unsafe {
+            let name = read_string(NAME_BUFFER_SIZE, |buffer, size| (self.device_name)(device, buffer, size))
+                .ok_or_else(|| anyhow!("nvmlDeviceGetName failed"))?;
+            let mut gpu_info = GpuInfo::new(&name, GpuVendor::Nvidia);
+            gpu_info.driver_version = driver_version.map(String::from);
+            gpu_info.uuid = read_string(UUID_BUFFER_SIZE, |buffer, size| (self.device_uuid)(device, buffer, size));
+            gpu_info.serial = read_string(SERIAL_BUFFER_SIZE, |buffer, size| (self.device_serial)(device, buffer, size));
+
+            let mut pci: PciInfo = std::mem::zeroed();
+            if (self.device_pci_info)(device, &mut pci) == NVML_SUCCESS {
+                let bus_id = CStr::from_ptr(pci.bus_id.as_ptr()).to_string_lossy();
+                gpu_info.pci_info = nvidia::get_nvidia_pci_info(&bus_id);
+            }
+
+            let mut clock: c_uint = 0;
+            let mut memory = MemoryInfo { total: 0, free: 0, used: 0 };
+            if (self.device_memory)(device, &mut memory) == NVML_SUCCESS {
+                gpu_info.memory_used_bytes = Some(memory.used);
+                let mut memory = nvidia::nvidia_memory(&name, memory.total);
+                if (self.device_max_clock)(device, NVML_CLOCK_MEM, &mut clock) == NVML_SUCCESS {
+                    memory.clock_mhz = clock;
+                }
+                gpu_info.memory = Some(memory);
+            }
+
+            if (self.device_clock)(device, NVML_CLOCK_GRAPHICS, &mut clock) == NVML_SUCCESS {
+                gpu_info.freq_mhz = clock;
+            }
+            if (self.device_max_clock)(device, NVML_CLOCK_GRAPHICS, &mut clock) == NVML_SUCCESS {
+                gpu_info.max_freq_mhz = clock;
+            }
+
+            let mut temperature: c_uint = 0;
+            if (self.device_temperature)(device, NVML_TEMPERATURE_GPU, &mut temperature) == NVML_SUCCESS {
+                gpu_info.temperature_c = Some(f64::from(temperature));
+            }
+            let threshold = |kind| {
+                let mut celsius: c_uint = 0;
+                ((self.device_temperature_threshold)(device, kind, &mut celsius) == NVML_SUCCESS)
+                    .then(|| f64::from(celsius))
+            };
+            gpu_info.thermal_limits = ThermalLimits::new(
+                threshold(NVML_TEMPERATURE_THRESHOLD_SLOWDOWN),
+                threshold(NVML_TEMPERATURE_THRESHOLD_SHUTDOWN),
+            );
+
+            let mut pstate: c_uint = NVML_PSTATE_UNKNOWN;
+            if (self.device_performance_state)(device, &mut pstate) == NVML_SUCCESS && pstate != NVML_PSTATE_UNKNOWN {
+                let mut mask: u64 = 0;
+                let throttle_reasons = if (self.device_throttle_reasons)(device, &mut mask) == NVML_SUCCESS {
+                    nvidia::throttle_reasons(mask)
+                } else {
+                    Vec::new()
+                };
+                gpu_info.performance_state = Some(PerformanceState {
+                    pstate: Some(format!("P{}", pstate)),
+                    throttle_reasons,
+                    ..Default::default()
+                });
+            }
+
+            if let Some(device_clock_offset) = self.device_clock_offset {
+                let mut offset: c_int = 0;
+                if device_clock_offset(device, &mut offset) == NVML_SUCCESS {
+                    gpu_info.clock_offset_mhz = Some(offset);
+                }
+            }
+
+            // Volta and later count millijoules since the driver loaded
+            if let Some(device_total_energy) = self.device_total_energy {
+                let mut millijoules: u64 = 0;
+                if device_total_energy(device, &mut millijoules) == NVML_SUCCESS {
+                    gpu_info.energy_j = Some(millijoules as f64 / 1000.0);
+                }
+            }
+
+            if let Some(device_compute_capability) = self.device_compute_capability {
+                let (mut major, mut minor): (c_int, c_int) = (0, 0);
+                if device_compute_capability(device, &mut major, &mut minor) == NVML_SUCCESS {
+                    gpu_info.compute_capability = Some(format!("{}.{}", major, minor));
+                }
+            }
+
+            nvidia::complete_from_name(&mut gpu_info);
+            Ok(gpu_info)
+        }
+    }
+}
+
+impl Drop for Nvml {
+    fn drop(&mut self) {
+        // SAFETY: nvmlShutdown only reports an error if NVML was never
+        // initialized, and the library handle is not used after this
+        unsafe {
+            (self.shutdown)();
+            libc::dlclose(self.library);
+        }
+    }
+}
+
+/// Detect NVIDIA GPUs through NVML
+pub fn detect_nvml_gpus() -> Result<Vec<GpuInfo>> {
+    let nvml = Nvml::open()?;
+
+    let mut count: c_uint = 0;
+    // SAFETY: NVML writes the count through the pointer we pass
+    check(unsafe { (nvml.device_count)(&mut count) }, "nvmlDeviceGetCount")?;
+
+    // SAFETY: the buffer is the size nvml.h asks for
+    let driver_version =
+        unsafe { read_string(VERSION_BUFFER_SIZE, |buffer, size| (nvml.driver_version)(buffer, size)) };
+
+    (0..count).map(|index| nvml.gpu(index, driver_version.as_deref())).collect()
+}
+
+/// Look up a function in the library
+///
+/// # Safety
+/// `T` must be a function pointer type matching the symbol's C signature.
+unsafe fn symbol<T: Copy>(library: *mut c_void, name: &CStr) -> Result<T> {
+    let address = libc::dlsym(library, name.as_ptr());
+    if address.is_null() {
+        bail!("{} lacks {}", LIBRARY.to_string_lossy(), name.to_string_lossy());
+    }
+    Ok(std::mem::transmute_copy(&address))
+}
+
+/// Turn an NVML return code into an error naming the call
+fn check(code: c_int, call: &str) -> Result<()> {
+    match code {
+        NVML_SUCCESS => Ok(()),
+        code => Err(anyhow!("{} failed with error {}", call, code)),
+    }
+}
+
+/// Call an NVML function that fills in a string buffer
+///
+/// # Safety
+/// `call` must write at most `size` bytes, NUL-terminated, into the buffer.
+unsafe fn read_string(size: usize, call: impl FnOnce(*mut c_char, c_uint) -> c_int) -> Option<String> {
+    let mut buffer = vec![0 as c_char; size];
+    if call(buffer.as_mut_ptr(), size as c_uint) != NVML_SUCCESS {
+        return None;
+    }
+    let value = CStr::from_ptr(buffer.as_ptr()).to_string_lossy().trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
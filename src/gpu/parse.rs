@@ -0,0 +1,167 @@
+//! Parsers for text that tools and sysfs hand us.
+//!
+//! They do no I/O and use nothing but std, so the fuzz targets under
+//! `fuzz/` build this file on its own. Input that does not have the
+//! expected shape gives `None` rather than a partly filled-in value.
+
+/// Parse a PCI address in `domain:bus:device.function` form
+///
+/// The domain is optional and may be written with 4 or 8 hex digits, as
+/// printed by lspci and nvidia-smi respectively.
+pub fn parse_pci_address(address: &str) -> Option<(u16, u8, u8, u8)> {
+    let (location, function) = address.trim().rsplit_once('.')?;
+    let parts: Vec<&str> = location.split(':').collect();
+    let (domain, bus, device) = match parts.as_slice() {
+        [domain, bus, device] if domain.len() == 4 || domain.len() == 8 => (hex(domain)?, *bus, *device),
+        [bus, device] => (0, *bus, *device),
+        _ => return None,
+    };
+
+    if bus.len() != 2 || device.len() != 2 || function.len() != 1 {
+        return None;
+    }
+    // A bus has 32 device slots of 8 functions each
+    let (device, function) = (hex(device)?, hex(function)?);
+    if device > 0x1f || function > 7 {
+        return None;
+    }
+
+    Some((u16::try_from(domain).ok()?, u8::try_from(hex(bus)?).ok()?, device as u8, function as u8))
+}
+
+/// Vendor and device ID from a PCI `modalias`
+///
+/// e.g. "pci:v00001002d000073DFsv00001682sd00005701bc03sc00i00", with each
+/// ID as 8 hex digits and the class, subclass and interface as 2.
+pub fn parse_modalias(modalias: &str) -> Option<(u16, u16)> {
+    let mut rest = modalias.trim().strip_prefix("pci:")?;
+    let mut field = |tag: &str, digits: usize| -> Option<u32> {
+        let value = rest.strip_prefix(tag)?.get(..digits)?;
+        rest = &rest[tag.len() + digits..];
+        hex(value)
+    };
+    let vendor = u16::try_from(field("v", 8)?).ok()?;
+    let device = u16::try_from(field("d", 8)?).ok()?;
+    // The rest must be well formed too, or the IDs may be from a garbled line
+    for (tag, digits) in [("sv", 8), ("sd", 8), ("bc", 2), ("sc", 2), ("i", 2)] {
+        field(tag, digits)?;
+    }
+    rest.is_empty().then_some((vendor, device))
+}
+
+/// The current and highest level of a DPM clock table such as `pp_dpm_sclk`
+///
+/// Levels are listed as "1: 1860Mhz *", the current one marked with a `*`;
+/// APUs add a sleep level as "S: 19Mhz". Lines of another shape are skipped.
+pub fn parse_dpm_clocks(content: &str) -> Option<(Option<u32>, u32)> {
+    let levels: Vec<(u32, bool)> = content
+        .lines()
+        .filter_map(|line| {
+            let (index, level) = line.split_once(':')?;
+            let index = index.trim();
+            if index != "S" && (index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit())) {
+                return None;
+            }
+            let mut words = level.split_whitespace();
+            let clock = words.next()?;
+            let mhz = clock.strip_suffix("Mhz").or_else(|| clock.strip_suffix("MHz"))?;
+            if mhz.is_empty() || !mhz.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            let current = match words.next() {
+                None => false,
+                Some("*") => true,
+                Some(_) => return None,
+            };
+            Some((mhz.parse().ok()?, current))
+        })
+        .collect();
+
+    let max = levels.iter().map(|&(mhz, _)| mhz).max().filter(|&max| max > 0)?;
+    let current = levels.iter().find(|&&(_, current)| current).map(|&(mhz, _)| mhz);
+    Some((current, max))
+}
+
+/// One row of the `nvidia-smi --query-gpu` CSV, with "[N/A]" values as `None`
+#[derive(Debug)]
+pub struct NvidiaSmiRow<'a> {
+    pub name: &'a str,
+    pub driver_version: &'a str,
+    pub memory_bytes: Option<u64>,
+    pub bus_id: &'a str,
+    pub pstate: Option<&'a str>,
+    pub max_clock_mhz: Option<u32>,
+    pub clock_mhz: Option<u32>,
+    pub uuid: Option<&'a str>,
+    pub serial: Option<&'a str>,
+    pub ecc_mode: Option<&'a str>,
+    pub corrected_errors: Option<u64>,
+    pub uncorrected_errors: Option<u64>,
+    pub temperature_c: Option<f64>,
+    pub throttle_mask: Option<u64>,
+    pub max_memory_clock_mhz: Option<u32>,
+}
+
+/// Parse a row of the query in `nvidia::query_csv`, e.g.
+/// "NVIDIA GeForce RTX 3080, 550.54.14, 10240 MiB, 00000000:01:00.0, P8, 2100 MHz, 210 MHz, ..."
+///
+/// The first seven columns are required and the row must hold a valid bus
+/// ID; later columns are missing on old drivers.
+pub fn parse_nvidia_smi_row(line: &str) -> Option<NvidiaSmiRow<'_>> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    if fields.len() < 7 || fields[0].is_empty() || parse_pci_address(fields[3]).is_none() {
+        return None;
+    }
+    let optional = |index: usize| fields.get(index).copied().and_then(parse_nvidia_optional);
+    let count = |index: usize| optional(index).and_then(|count| count.parse().ok());
+
+    Some(NvidiaSmiRow {
+        name: fields[0],
+        driver_version: fields[1],
+        memory_bytes: with_unit(fields[2], "MiB").and_then(|mib| mib.checked_mul(1024 * 1024)),
+        bus_id: fields[3],
+        pstate: parse_nvidia_optional(fields[4]),
+        max_clock_mhz: with_unit(fields[5], "MHz").and_then(|mhz| u32::try_from(mhz).ok()),
+        clock_mhz: with_unit(fields[6], "MHz").and_then(|mhz| u32::try_from(mhz).ok()),
+        uuid: optional(7),
+        serial: optional(8),
+        ecc_mode: optional(9),
+        corrected_errors: count(10),
+        uncorrected_errors: count(11),
+        temperature_c: optional(12).and_then(|temperature| temperature.parse().ok()).filter(|t: &f64| t.is_finite()),
+        throttle_mask: optional(13).and_then(|mask| mask.strip_prefix("0x")).and_then(hex_u64),
+        max_memory_clock_mhz: optional(14)
+            .and_then(|clock| with_unit(clock, "MHz"))
+            .and_then(|mhz| u32::try_from(mhz).ok()),
+    })
+}
+
+/// An nvidia-smi value, unless it is missing: empty, or "[N/A]" and the like
+pub fn parse_nvidia_optional(value: &str) -> Option<&str> {
+    (!value.is_empty() && !value.starts_with('[')).then_some(value)
+}
+
+/// A whole number followed by a unit, e.g. "10240 MiB"
+fn with_unit(value: &str, unit: &str) -> Option<u64> {
+    let number = value.strip_suffix(unit)?.trim_end();
+    if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    number.parse().ok()
+}
+
+/// Up to 8 hex digits, without sign or prefix
+fn hex(digits: &str) -> Option<u32> {
+    if digits.len() > 8 {
+        return None;
+    }
+    hex_u64(digits).map(|value| value as u32)
+}
+
+/// Up to 16 hex digits, without sign or prefix
+fn hex_u64(digits: &str) -> Option<u64> {
+    if digits.is_empty() || digits.len() > 16 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    u64::from_str_radix(digits, 16).ok()
+}
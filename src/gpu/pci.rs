@@ -1,24 +1,31 @@
 use anyhow::{anyhow, Context, Result};
-use lazy_static::lazy_static;
-use pci_ids::{Device, FromId, Subclass, Vendor};
+use once_cell::sync::Lazy;
+use pci_ids::{FromId, Vendor};
+use std::collections::HashMap;
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 
-use crate::gpu::common::{GpuInfo, GpuVendor, PciInfo};
+use crate::error::GpufetchError;
+use crate::gpu::backend::Backend;
+use crate::gpu::common::{pcie_generation, GpuInfo, GpuVendor, NumaAffinity, PciInfo, PcieLink, RuntimePm, SriovInfo};
+use crate::gpu::{sysfs_layout, sysroot, update};
+
+pub use crate::gpu::parse::parse_pci_address;
 
 // PCI vendor IDs
 const PCI_VENDOR_ID_NVIDIA: u16 = 0x10de;
-const PCI_VENDOR_ID_AMD: u16 = 0x1002;
-const PCI_VENDOR_ID_ATI: u16 = 0x1002; // ATI was acquired by AMD
+const PCI_VENDOR_ID_AMD: u16 = 0x1002; // Also used by legacy ATI devices
 const PCI_VENDOR_ID_INTEL: u16 = 0x8086;
+const PCI_VENDOR_ID_ARM: u16 = 0x13b5; // Mali, as reported by Vulkan drivers
 
 // PCI device class codes for GPUs
 const PCI_CLASS_DISPLAY_VGA: u16 = 0x0300;
 const PCI_CLASS_DISPLAY_3D: u16 = 0x0302;
 
-lazy_static! {
-    static ref PCI_SYS_PATH: PathBuf = PathBuf::from("/sys/bus/pci/devices");
-}
+static PCI_SYS_PATH: Lazy<PathBuf> = Lazy::new(|| sysroot::path("/sys/bus/pci/devices"));
+pub static PCI_SLOTS_PATH: Lazy<PathBuf> = Lazy::new(|| sysroot::path("/sys/bus/pci/slots"));
+static DOWNLOADED_IDS: Lazy<Option<PciIds>> = Lazy::new(update::cached_pci_ids);
 
 /// Detect GPUs using the PCI subsystem
 pub fn detect_gpus_from_pci() -> Result<Vec<GpuInfo>> {
@@ -26,11 +33,16 @@ pub fn detect_gpus_from_pci() -> Result<Vec<GpuInfo>> {
     
     // Check if PCI path exists
     if !PCI_SYS_PATH.exists() {
-        return Err(anyhow!("PCI sysfs path not found"));
+        let reason = format!("{} not found", PCI_SYS_PATH.display());
+        return Err(GpufetchError::unavailable(Backend::Sysfs, reason).into());
     }
     
     // Iterate through PCI devices
-    for entry in fs::read_dir(&*PCI_SYS_PATH).context("Failed to read PCI devices directory")? {
+    let entries = fs::read_dir(&*PCI_SYS_PATH).map_err(|e| match e.kind() {
+        io::ErrorKind::PermissionDenied => GpufetchError::PermissionDenied { resource: PCI_SYS_PATH.display().to_string() },
+        _ => GpufetchError::unavailable(Backend::Sysfs, format!("Failed to read {}: {}", PCI_SYS_PATH.display(), e)),
+    })?;
+    for entry in entries {
         let entry = entry.context("Failed to read directory entry")?;
         let device_path = entry.path();
         
@@ -49,50 +61,56 @@ pub fn detect_gpus_from_pci() -> Result<Vec<GpuInfo>> {
     Ok(gpus)
 }
 
+/// Display adapters on the PCI bus, in address order
+pub fn list_pci_devices() -> Vec<PciInfo> {
+    let Ok(entries) = fs::read_dir(&*PCI_SYS_PATH) else {
+        return Vec::new();
+    };
+    let mut devices: Vec<PciInfo> = entries
+        .flatten()
+        .filter_map(|entry| read_pci_info(&entry.path()).ok())
+        .filter(|pci_info| is_display_adapter(pci_info.class_id))
+        .collect();
+    devices.sort_by_key(PciInfo::address);
+    devices
+}
+
+/// The vendor a PCI vendor ID belongs to
+pub fn vendor_from_id(vendor_id: u16) -> GpuVendor {
+    match vendor_id {
+        PCI_VENDOR_ID_NVIDIA => GpuVendor::Nvidia,
+        PCI_VENDOR_ID_AMD => GpuVendor::Amd,
+        PCI_VENDOR_ID_INTEL => GpuVendor::Intel,
+        PCI_VENDOR_ID_ARM => GpuVendor::Arm,
+        _ => GpuVendor::Other(format!("Unknown (0x{:04x})", vendor_id)),
+    }
+}
+
 /// Read PCI device information from sysfs
-fn read_pci_info(device_path: &Path) -> Result<PciInfo> {
-    // Parse PCI location from path name
+///
+/// `device_path` may be the PCI device directory itself or a symlink to it,
+/// such as `/sys/class/drm/card0/device`.
+pub fn read_pci_info(device_path: &Path) -> Result<PciInfo> {
+    let device_path = fs::canonicalize(device_path).context("Invalid device path")?;
+    
+    // Parse PCI location from path name (e.g., "0000:01:00.0")
     let device_name = device_path
         .file_name()
         .context("Invalid device path")?
         .to_string_lossy();
-    
-    // Parse PCI location (e.g., "0000:01:00.0")
-    let parts: Vec<&str> = device_name.split(':').collect();
-    if parts.len() != 2 {
-        return Err(anyhow!("Invalid PCI path format"));
-    }
-    
-    let domain = u16::from_str_radix(parts[0], 16).context("Invalid domain")?;
-    
-    let bus_dev_fn: Vec<&str> = parts[1].split('.').collect();
-    if bus_dev_fn.len() != 2 {
-        return Err(anyhow!("Invalid bus/device/function format"));
-    }
-    
-    let bus_dev: Vec<&str> = bus_dev_fn[0].split(':').collect();
-    let bus = if bus_dev.len() > 1 {
-        u8::from_str_radix(bus_dev[1], 16).context("Invalid bus")?
-    } else {
-        u8::from_str_radix(bus_dev[0], 16).context("Invalid bus")?
-    };
-    
-    let device = if bus_dev.len() > 1 {
-        u8::from_str_radix(bus_dev[1], 16).context("Invalid device")?
-    } else {
-        0
-    };
-    
-    let function = u8::from_str_radix(bus_dev_fn[1], 16).context("Invalid function")?;
+    let (domain, bus, device, function) = parse_pci_address(&device_name)
+        .ok_or_else(|| anyhow!("Invalid PCI path format"))?;
     
     // Read device vendor and device ID
-    let vendor_id = read_hex_file(&device_path.join("vendor"))?;
-    let device_id = read_hex_file(&device_path.join("device"))?;
+    let vendor_id = read_hex_file(&device_path.join("vendor"))? as u16;
+    let device_id = read_hex_file(&device_path.join("device"))? as u16;
     let class_id = read_hex_file(&device_path.join("class"))? >> 8; // Class is in the top 16 bits
+    let revision = read_hex_file(&device_path.join("revision")).ok().map(|revision| revision as u8);
     
     Ok(PciInfo {
         vendor_id,
         device_id,
+        revision,
         class_id: class_id as u16,
         domain,
         bus,
@@ -101,47 +119,252 @@ fn read_pci_info(device_path: &Path) -> Result<PciInfo> {
     })
 }
 
+/// Fill in SR-IOV details for a GPU with a known PCI address
+///
+/// Physical functions get their VF counts and addresses; virtual functions
+/// get the address of the physical function they belong to.
+pub fn read_sriov(gpu: &mut GpuInfo) {
+    let Some(ref pci_info) = gpu.pci_info else {
+        return;
+    };
+    let device_path = device_path(&pci_info.address());
+    
+    gpu.physical_function = link_name(&device_path.join("physfn"));
+    
+    let total_vfs = read_decimal_file(&device_path.join("sriov_totalvfs")).unwrap_or(0);
+    if total_vfs == 0 {
+        return;
+    }
+    
+    let enabled_vfs = read_decimal_file(&device_path.join("sriov_numvfs")).unwrap_or(0);
+    let vf_addresses = (0..enabled_vfs)
+        .filter_map(|idx| link_name(&device_path.join(format!("virtfn{}", idx))))
+        .collect();
+    
+    gpu.sriov = Some(SriovInfo {
+        total_vfs,
+        enabled_vfs,
+        vf_addresses,
+    });
+}
+
+/// Fill in whether the kernel may power a GPU down while it is idle, and
+/// whether it has
+///
+/// Reading these files does not wake a suspended device.
+pub fn read_runtime_pm(gpu: &mut GpuInfo) {
+    let Some(ref pci_info) = gpu.pci_info else {
+        return;
+    };
+    let device_path = device_path(&pci_info.address());
+    let read = |name: &str| {
+        fs::read_to_string(device_path.join(name))
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    let (Some(status), Some(control)) = (read("power/runtime_status"), read("power/control")) else {
+        return;
+    };
+    gpu.runtime_pm = Some(RuntimePm {
+        status,
+        control,
+        d_state: read("power_state"),
+    });
+}
+
+/// Read the NUMA node a GPU is attached to and the CPUs local to it
+///
+/// Machines with a single node report node -1, leaving what a backend
+/// found as it is.
+pub fn read_numa(gpu: &mut GpuInfo) {
+    let Some(ref pci_info) = gpu.pci_info else {
+        return;
+    };
+    let device_path = device_path(&pci_info.address());
+    let read = |name: &str| fs::read_to_string(device_path.join(name)).ok().map(|value| value.trim().to_string());
+
+    let Some(node) = read("numa_node").and_then(|node| node.parse::<u32>().ok()) else {
+        return;
+    };
+    gpu.numa = Some(NumaAffinity {
+        node: Some(node),
+        cpus: read("local_cpulist").filter(|cpus| !cpus.is_empty()),
+    });
+}
+
+/// Fill in the fastest PCIe link a GPU supports from sysfs, for GPUs whose
+/// backend read the current one
+///
+/// Radeon and Arc cards sit behind a PCIe switch on the card, so sysfs shows
+/// the GPU's link to that switch rather than to the slot. Backends read the
+/// current link past it, and without one the GPU's own is not worth showing.
+pub fn read_pcie_max_link(gpu: &mut GpuInfo) {
+    let Some(pci_info) = gpu.pci_info.as_ref().filter(|_| gpu.pcie_link.is_some() && gpu.pcie_max_link.is_none()) else {
+        return;
+    };
+    gpu.pcie_max_link = read_link(&device_path(&pci_info.address()), "max");
+}
+
+/// `current_link_*` or `max_link_*` of a device, e.g. "16.0 GT/s PCIe" and "16"
+pub fn read_link(path: &Path, which: &str) -> Option<PcieLink> {
+    let read = |name: String| fs::read_to_string(path.join(name)).ok();
+    let speed = read(format!("{}_link_speed", which))?;
+    let width = read(format!("{}_link_width", which))?;
+    let gts: f64 = speed.split_whitespace().next()?.parse().ok()?;
+    let width: u32 = width.trim().parse().ok()?;
+    // Devices without a link report 0 lanes or an "Unknown" speed
+    (width > 0).then_some(PcieLink { generation: pcie_generation(gts)?, width })
+}
+
+/// Find the physical slot a GPU is in
+///
+/// Each slot the firmware or hotplug driver knows of is a directory named
+/// by its label, holding the address of the device in it without the
+/// function, e.g. "0000:01:00". Desktop boards often describe none.
+pub fn read_slot(gpu: &mut GpuInfo) {
+    let Some(ref pci_info) = gpu.pci_info else {
+        return;
+    };
+    let address = format!("{:04x}:{:02x}:{:02x}", pci_info.domain, pci_info.bus, pci_info.device);
+    let Ok(slots) = fs::read_dir(&*PCI_SLOTS_PATH) else {
+        return;
+    };
+    gpu.pci_slot = slots.flatten().find_map(|slot| {
+        let slot_address = fs::read_to_string(slot.path().join("address")).ok()?;
+        (slot_address.trim() == address).then(|| slot.file_name().to_string_lossy().into_owned())
+    });
+}
+
+/// Sysfs directory of the PCI device at `address`
+pub fn device_path(address: &str) -> PathBuf {
+    PCI_SYS_PATH.join(address)
+}
+
+/// Name of the kernel driver bound to a PCI device, if any
+pub fn bound_driver(device_path: &Path) -> Option<String> {
+    link_name(&device_path.join("driver"))
+}
+
+/// Final path component of the target of a sysfs symlink
+fn link_name(path: &Path) -> Option<String> {
+    fs::canonicalize(path)
+        .ok()
+        .and_then(|target| target.file_name().map(|name| name.to_string_lossy().into_owned()))
+}
+
+/// Read a decimal value from a sysfs file
+fn read_decimal_file(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
 /// Read a hex value from a sysfs file
-fn read_hex_file(path: &Path) -> Result<u16> {
+fn read_hex_file(path: &Path) -> Result<u32> {
     let content = fs::read_to_string(path).context("Failed to read file")?;
     let hex_str = content.trim().trim_start_matches("0x");
-    u16::from_str_radix(hex_str, 16).context("Invalid hex value")
+    u32::from_str_radix(hex_str, 16).context("Invalid hex value")
 }
 
 /// Check if a PCI class ID is a display adapter
-fn is_display_adapter(class_id: u16) -> bool {
+pub fn is_display_adapter(class_id: u16) -> bool {
     class_id == PCI_CLASS_DISPLAY_VGA || class_id == PCI_CLASS_DISPLAY_3D
 }
 
-/// Create a GPU info structure from PCI information
-fn create_gpu_info_from_pci(pci_info: &PciInfo, device_path: &Path) -> Option<GpuInfo> {
-    let vendor = match pci_info.vendor_id {
-        PCI_VENDOR_ID_NVIDIA => GpuVendor::Nvidia,
-        PCI_VENDOR_ID_AMD | PCI_VENDOR_ID_ATI => GpuVendor::Amd,
-        PCI_VENDOR_ID_INTEL => GpuVendor::Intel,
-        _ => GpuVendor::Other(format!("Unknown (0x{:04x})", pci_info.vendor_id)),
-    };
-    
-    // Try to get device name from pci.ids database
-    let vendor_info = Vendor::from_id(pci_info.vendor_id);
-    
+/// Human-readable device name from the pci.ids database
+pub fn device_name(vendor_id: u16, device_id: u16) -> String {
+    // A pci.ids fetched with `gpufetch update-db` knows newer devices
+    if let Some(name) = DOWNLOADED_IDS.as_ref().and_then(|ids| ids.name(vendor_id, device_id)) {
+        return name;
+    }
+
     // Handle the different API for the pci-ids crate
-    let device_name = if let Some(v) = vendor_info {
-        if let Some(d) = v.devices().find(|d| d.id() == pci_info.device_id) {
+    if let Some(v) = Vendor::from_id(vendor_id) {
+        if let Some(d) = v.devices().find(|d| d.id() == device_id) {
             format!("{} {}", v.name(), d.name())
         } else {
-            format!("{} Device {:04x}", v.name(), pci_info.device_id)
+            format!("{} Device {:04x}", v.name(), device_id)
         }
     } else {
-        format!("Unknown Device {:04x}:{:04x}", pci_info.vendor_id, pci_info.device_id)
-    };
+        format!("Unknown Device {:04x}:{:04x}", vendor_id, device_id)
+    }
+}
+
+/// Vendor and device names parsed from a pci.ids file
+#[derive(Debug, Default)]
+pub struct PciIds {
+    vendors: HashMap<u16, (String, HashMap<u16, String>)>,
+}
+
+impl PciIds {
+    /// Parse the vendor and device sections, skipping subsystems and the
+    /// device classes that follow them
+    pub fn parse(content: &str) -> Self {
+        let mut ids = PciIds::default();
+        let mut vendor = None;
+
+        for line in content.lines() {
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+            if line.starts_with("C ") {
+                break;
+            }
+
+            match line.strip_prefix('\t') {
+                Some(subsystem) if subsystem.starts_with('\t') => {}
+                Some(device) => {
+                    if let (Some(vendor_id), Some((device_id, name))) = (vendor, parse_ids_entry(device)) {
+                        if let Some((_, devices)) = ids.vendors.get_mut(&vendor_id) {
+                            devices.insert(device_id, name);
+                        }
+                    }
+                }
+                None => {
+                    vendor = parse_ids_entry(line).map(|(vendor_id, name)| {
+                        ids.vendors.insert(vendor_id, (name, HashMap::new()));
+                        vendor_id
+                    });
+                }
+            }
+        }
+
+        ids
+    }
+
+    /// Number of devices across all vendors
+    pub fn device_count(&self) -> usize {
+        self.vendors.values().map(|(_, devices)| devices.len()).sum()
+    }
+
+    /// "Vendor Device" name, in the same form as `device_name`
+    fn name(&self, vendor_id: u16, device_id: u16) -> Option<String> {
+        let (vendor, devices) = self.vendors.get(&vendor_id)?;
+        Some(match devices.get(&device_id) {
+            Some(device) => format!("{} {}", vendor, device),
+            None => format!("{} Device {:04x}", vendor, device_id),
+        })
+    }
+}
+
+/// One "1002  Advanced Micro Devices" line of pci.ids
+fn parse_ids_entry(line: &str) -> Option<(u16, String)> {
+    let (id, name) = line.split_once("  ")?;
+    Some((u16::from_str_radix(id, 16).ok()?, name.trim().to_string()))
+}
+
+/// Create a GPU info structure from PCI information
+fn create_gpu_info_from_pci(pci_info: &PciInfo, device_path: &Path) -> Option<GpuInfo> {
+    let vendor = vendor_from_id(pci_info.vendor_id);
+    
+    let device_name = device_name(pci_info.vendor_id, pci_info.device_id);
     
     // Create basic GPU info
     let mut gpu_info = GpuInfo::new(&device_name, vendor);
     gpu_info.pci_info = Some(pci_info.clone());
     
     // Try to read some additional info from sysfs
-    if let Ok(freq) = fs::read_to_string(device_path.join("drm").join("card0").join("device").join("pp_dpm_sclk")) {
+    if let Some(freq) = sysfs_layout::find(device_path, "pp_dpm_sclk").and_then(|path| fs::read_to_string(path).ok()) {
         // AMD-style frequency info
         if let Some(max_freq) = freq.lines().last() {
             if let Some(mhz_str) = max_freq.split_whitespace().nth(1) {
@@ -151,7 +374,7 @@ fn create_gpu_info_from_pci(pci_info: &PciInfo, device_path: &Path) -> Option<Gp
                 }
             }
         }
-    } else if let Ok(freq) = fs::read_to_string(device_path.join("drm").join("card0").join("gt_max_freq_mhz")) {
+    } else if let Some(freq) = sysfs_layout::find(device_path, "gt_max_freq_mhz").and_then(|path| fs::read_to_string(path).ok()) {
         // Intel-style frequency info
         if let Ok(mhz) = freq.trim().parse::<u32>() {
             gpu_info.freq_mhz = mhz;
@@ -0,0 +1,167 @@
+//! The PCIe hierarchy above each GPU, for `--topology`.
+//!
+//! A PCI device's sysfs directory sits inside the one of the bridge it is
+//! behind, so the real path of `/sys/bus/pci/devices/0000:03:00.0` names
+//! every port on the way from the root complex, e.g.
+//! `/sys/devices/pci0000:00/0000:00:01.1/0000:01:00.0/0000:02:00.0/0000:03:00.0`
+//! for a GPU behind a root port and the two ports of a switch.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::gpu::common::{GpuInfo, PcieLink};
+use crate::gpu::pci;
+
+/// PCI class of PCI-to-PCI bridges: root ports and switch ports
+const PCI_CLASS_BRIDGE_PCI: u16 = 0x0604;
+
+/// Transfer rate per lane of each PCIe generation in GT/s
+const LANE_RATES: [f64; 6] = [2.5, 5.0, 8.0, 16.0, 32.0, 64.0];
+
+/// A root complex and the devices below it that lead to GPUs
+#[derive(Debug)]
+pub struct Root {
+    /// Name of the host bridge directory, e.g. "pci0000:00"
+    pub name: String,
+    pub children: Vec<Node>,
+}
+
+/// A bridge on the way to a GPU, or a GPU
+#[derive(Debug)]
+pub struct Node {
+    pub address: String,
+    pub name: String,
+    pub is_bridge: bool,
+    /// The link as negotiated now; GPUs drop to lower speeds when idle
+    pub link: Option<PcieLink>,
+    /// The fastest link the device supports
+    pub max_link: Option<PcieLink>,
+    /// Index of the GPU this device is
+    pub gpu: Option<usize>,
+    pub children: Vec<Node>,
+    path: PathBuf,
+}
+
+impl Node {
+    fn read(path: PathBuf) -> Self {
+        let address = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let (name, is_bridge) = match pci::read_pci_info(&path) {
+            Ok(pci_info) => {
+                (pci::device_name(pci_info.vendor_id, pci_info.device_id), pci_info.class_id == PCI_CLASS_BRIDGE_PCI)
+            }
+            Err(_) => (String::from("Unknown device"), false),
+        };
+        Node {
+            address,
+            name,
+            is_bridge,
+            link: pci::read_link(&path, "current"),
+            max_link: pci::read_link(&path, "max"),
+            gpu: None,
+            children: Vec::new(),
+            path,
+        }
+    }
+
+    /// Indices of the GPUs at or below this device
+    pub fn gpus(&self) -> Vec<usize> {
+        self.gpu.into_iter().chain(self.children.iter().flat_map(Node::gpus)).collect()
+    }
+
+    /// GPUs below whose fastest link is faster than this device's, with it
+    ///
+    /// A GPU can go no faster than the slowest link on its way to the CPU,
+    /// such as a Gen3 switch or a chipset slot wired as x4.
+    pub fn limited_gpus(&self) -> Vec<(usize, PcieLink)> {
+        let Some(own) = self.max_link.filter(|_| self.is_bridge) else {
+            return Vec::new();
+        };
+        self.children
+            .iter()
+            .flat_map(Node::gpu_links)
+            .filter(|&(_, link)| bandwidth(link) > bandwidth(own))
+            .collect()
+    }
+
+    /// Fastest link of each GPU at or below this device
+    fn gpu_links(&self) -> Vec<(usize, PcieLink)> {
+        let own = self.gpu.zip(self.max_link);
+        own.into_iter().chain(self.children.iter().flat_map(Node::gpu_links)).collect()
+    }
+
+    /// Add the chain of devices leading to a GPU below this one
+    fn insert(children: &mut Vec<Node>, chain: &[PathBuf], gpu: usize) {
+        let Some((first, rest)) = chain.split_first() else {
+            return;
+        };
+        let index = match children.iter().position(|child| child.path == *first) {
+            Some(index) => index,
+            None => {
+                children.push(Node::read(first.clone()));
+                children.len() - 1
+            }
+        };
+        let node = &mut children[index];
+        if rest.is_empty() {
+            node.gpu = Some(gpu);
+        } else {
+            Node::insert(&mut node.children, rest, gpu);
+        }
+    }
+}
+
+/// Rough data rate of a link, to compare links of different generations
+pub fn bandwidth(link: PcieLink) -> f64 {
+    let rate = LANE_RATES.get(link.generation.saturating_sub(1) as usize).copied().unwrap_or(0.0);
+    rate * f64::from(link.width)
+}
+
+/// The tree of root complexes, ports and GPUs
+///
+/// Only the devices on the way to a GPU are included, in PCI order. GPUs
+/// without a PCI address are left out.
+pub fn build(gpus: &[GpuInfo]) -> Vec<Root> {
+    let mut roots: Vec<Root> = Vec::new();
+    let mut chains: Vec<(usize, String, Vec<PathBuf>)> = gpus
+        .iter()
+        .enumerate()
+        .filter_map(|(index, gpu)| {
+            let address = gpu.pci_info.as_ref()?.address();
+            let (root, chain) = chain(&pci::device_path(&address))?;
+            Some((index, root, chain))
+        })
+        .collect();
+    chains.sort_by(|a, b| a.2.cmp(&b.2));
+
+    for (gpu, root_name, chain) in chains {
+        let root = match roots.iter().position(|root| root.name == root_name) {
+            Some(index) => &mut roots[index],
+            None => {
+                roots.push(Root { name: root_name, children: Vec::new() });
+                roots.last_mut().expect("just pushed")
+            }
+        };
+        Node::insert(&mut root.children, &chain, gpu);
+    }
+    roots.sort_by(|a, b| a.name.cmp(&b.name));
+    roots
+}
+
+/// Host bridge name and the sysfs directories from the first port down to
+/// the device itself
+fn chain(device_path: &Path) -> Option<(String, Vec<PathBuf>)> {
+    let real = fs::canonicalize(device_path).ok()?;
+    let mut chain = Vec::new();
+    let mut root = String::from("unknown root");
+    let mut path = PathBuf::new();
+    for component in real.components() {
+        path.push(component);
+        let name = component.as_os_str().to_string_lossy();
+        if pci::parse_pci_address(&name).is_some() {
+            chain.push(path.clone());
+        } else if name.starts_with("pci") && chain.is_empty() {
+            root = name.into_owned();
+        }
+    }
+    (!chain.is_empty()).then_some((root, chain))
+}
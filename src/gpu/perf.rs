@@ -0,0 +1,75 @@
+//! Peak FP32 throughput from the unit counts and the architecture.
+//!
+//! A unit does one fused multiply-add, two operations, per FP32 lane and
+//! clock, but how many lanes a unit has depends on the architecture:
+//! Ampere's consumer chips doubled the FP32 lanes of an SM, RDNA 3 issues
+//! two FP32 operations per lane, and Xe2 widened its vector engines to
+//! SIMD16. Vendors publish peaks at the reference boost clock, so that
+//! clock is used where the spec database knows it.
+
+use crate::gpu::common::{GpuInfo, Topology};
+
+/// Peak FP32 throughput in GFLOPS, when the unit counts and a clock are known
+pub fn peak_gflops(gpu: &GpuInfo) -> Option<f64> {
+    let clock_mhz = gpu.reference_boost_mhz.unwrap_or(gpu.max_freq_mhz);
+    if clock_mhz == 0 {
+        return None;
+    }
+    let lanes = fp32_lanes(gpu.topology.as_ref()?, &gpu.architecture, &gpu.chip)?;
+    Some(2.0 * f64::from(lanes) * f64::from(clock_mhz) / 1000.0)
+}
+
+/// FP32 lanes of the whole GPU
+///
+/// Counted from the SMs, CUs or EUs of a known architecture; the core
+/// counts the vendors give stand in for architectures not listed here.
+fn fp32_lanes(topology: &Topology, architecture: &str, chip: &str) -> Option<u32> {
+    match topology {
+        Topology::Nvidia(nvidia) => {
+            sm_lanes(architecture, chip).map(|lanes| nvidia.sm_count * lanes).filter(|&lanes| lanes > 0).or(nvidia.cuda_cores)
+        }
+        Topology::Amd(amd) => {
+            cu_lanes(architecture).map(|lanes| amd.compute_units * lanes).filter(|&lanes| lanes > 0).or(amd.stream_processors)
+        }
+        Topology::Intel(intel) => Some(intel.execution_units * eu_lanes(architecture)),
+        Topology::Generic { .. } => None,
+    }
+}
+
+/// FP32 lanes per SM
+///
+/// The compute flagships GP100 and GA100 kept half the lanes of their
+/// generation's other chips.
+fn sm_lanes(architecture: &str, chip: &str) -> Option<u32> {
+    match architecture {
+        "Kepler" => Some(192),
+        "Pascal" if chip.starts_with("GP100") => Some(64),
+        "Ampere" if chip.starts_with("GA100") => Some(64),
+        "Maxwell" | "Pascal" | "Ampere" | "Ada Lovelace" | "Hopper" | "Blackwell" => Some(128),
+        "Volta" | "Turing" => Some(64),
+        _ => None,
+    }
+}
+
+/// FP32 lanes per CU, counting RDNA 3's dual issue as twice the lanes
+fn cu_lanes(architecture: &str) -> Option<u32> {
+    match architecture {
+        "RDNA 3" | "RDNA 3.5" | "RDNA 4" | "CDNA 3" => Some(128),
+        "RDNA 2" | "RDNA" | "Vega" | "Polaris" | "GCN" | "CDNA" | "CDNA 2" => Some(64),
+        _ => None,
+    }
+}
+
+/// FP32 lanes per EU or vector engine
+///
+/// Gen6 EUs were SIMD4; from Gen7 to Xe-HPG they are SIMD8, and Xe-HPC and
+/// Xe2 vector engines SIMD16.
+fn eu_lanes(architecture: &str) -> u32 {
+    if architecture.starts_with("Gen6") {
+        4
+    } else if architecture.starts_with("Xe2") || architecture.starts_with("Xe-HPC") {
+        16
+    } else {
+        8
+    }
+}
@@ -0,0 +1,232 @@
+//! Merging the records several backends make of one GPU.
+//!
+//! nvidia-smi and NVML both see every NVIDIA GPU, and Vulkan sees most GPUs
+//! again; each knows things the others do not. Records of the same PCI
+//! device are merged into the one from the highest-priority backend: values
+//! it lacks are taken from the others, and values it only guessed from the
+//! model name or the spec database give way to ones another backend read
+//! from the hardware.
+
+use std::mem;
+
+use crate::gpu::common::{GpuInfo, MemoryType, Topology};
+use tracing::debug;
+
+/// Add a GPU found by a lower-priority backend, merging it into the record
+/// of the same GPU if there already is one
+pub fn add(gpus: &mut Vec<GpuInfo>, gpu: GpuInfo) {
+    match gpus.iter_mut().find(|known| is_same_gpu(known, &gpu)) {
+        Some(known) => {
+            debug!("merging {} into the record of a higher-priority backend", gpu.name);
+            merge(known, gpu);
+        }
+        None => {
+            debug!("adding {}", gpu.name);
+            gpus.push(gpu);
+        }
+    }
+}
+
+/// Whether two backends found the same physical GPU: the same PCI address,
+/// or the same vendor and name for GPUs off the PCI bus
+fn is_same_gpu(a: &GpuInfo, b: &GpuInfo) -> bool {
+    match (&a.pci_info, &b.pci_info) {
+        (Some(a), Some(b)) => a.address() == b.address(),
+        _ => a.vendor == b.vendor && a.name == b.name,
+    }
+}
+
+/// Fill in `base` from `other`
+///
+/// `base` keeps its name and vendor. Spec fields are replaced when `base`
+/// lacks them or only guessed them or took them from the spec database while
+/// `other` measured them; everything else only fills gaps.
+pub fn merge(base: &mut GpuInfo, mut other: GpuInfo) {
+    if should_replace(base, &other, "architecture", base.architecture != "Unknown", other.architecture != "Unknown") {
+        base.architecture = mem::take(&mut other.architecture);
+    }
+    if should_replace(base, &other, "chip", base.chip != "Unknown", other.chip != "Unknown") {
+        base.chip = mem::take(&mut other.chip);
+    }
+    if should_replace(base, &other, "process_nm", base.process.is_some(), other.process.is_some()) {
+        base.process = other.process.take();
+    }
+    if should_replace(
+        base,
+        &other,
+        "boost_clock_mhz",
+        base.reference_boost_mhz.is_some(),
+        other.reference_boost_mhz.is_some(),
+    ) {
+        base.reference_boost_mhz = other.reference_boost_mhz;
+    }
+    if should_replace(base, &other, "board_power_w", base.rated_power_w.is_some(), other.rated_power_w.is_some()) {
+        base.rated_power_w = other.rated_power_w;
+    }
+    if should_replace(base, &other, "fp64_ratio", base.fp64_ratio.is_some(), other.fp64_ratio.is_some()) {
+        base.fp64_ratio = other.fp64_ratio;
+    }
+    if should_replace(base, &other, "die", base.die_size_mm2.is_some(), other.die_size_mm2.is_some()) {
+        base.die_size_mm2 = other.die_size_mm2;
+        base.transistors_million = other.transistors_million;
+        base.release_year = other.release_year;
+    }
+    merge_memory(base, &mut other);
+
+    let took_freq = base.freq_mhz == 0 && other.freq_mhz > 0;
+    let took_max_freq = base.max_freq_mhz == 0 && other.max_freq_mhz > 0;
+    let took_compute_capability = base.compute_capability.is_none() && other.compute_capability.is_some();
+    let took_topology = base.topology.is_none() && other.topology.is_some();
+    let took_cache = base.cache.is_none() && other.cache.is_some();
+
+    if base.freq_mhz == 0 {
+        base.freq_mhz = other.freq_mhz;
+    }
+    if base.max_freq_mhz == 0 {
+        base.max_freq_mhz = other.max_freq_mhz;
+    }
+    base.is_integrated |= other.is_integrated;
+
+    fill(&mut base.clock_offset_mhz, other.clock_offset_mhz);
+    fill(&mut base.fabric_clock_mhz, other.fabric_clock_mhz);
+    fill(&mut base.ecc, other.ecc);
+    fill(&mut base.topology, other.topology);
+    fill(&mut base.cache, other.cache);
+    fill(&mut base.shared_memory_bytes, other.shared_memory_bytes);
+    fill(&mut base.memory_used_bytes, other.memory_used_bytes);
+    fill(&mut base.shared_memory_used_bytes, other.shared_memory_used_bytes);
+    fill(&mut base.pci_info, other.pci_info);
+    fill(&mut base.driver_version, other.driver_version);
+    fill(&mut base.kernel_driver, other.kernel_driver);
+    fill(&mut base.mesa_version, other.mesa_version);
+    fill(&mut base.compute_capability, other.compute_capability);
+    fill(&mut base.opengl_version, other.opengl_version);
+    fill(&mut base.peak_performance_gflops, other.peak_performance_gflops);
+    fill(&mut base.nvlink, other.nvlink);
+    fill(&mut base.temperature_c, other.temperature_c);
+    fill(&mut base.junction_temperature_c, other.junction_temperature_c);
+    fill(&mut base.memory_temperature_c, other.memory_temperature_c);
+    fill(&mut base.thermal_limits, other.thermal_limits);
+    fill(&mut base.core_voltage_mv, other.core_voltage_mv);
+    fill(&mut base.performance_state, other.performance_state);
+    fill(&mut base.power_draw_w, other.power_draw_w);
+    fill(&mut base.energy_j, other.energy_j);
+    fill(&mut base.average_power_w, other.average_power_w);
+    fill(&mut base.encoder_utilization_percent, other.encoder_utilization_percent);
+    fill(&mut base.fan, other.fan);
+    fill(&mut base.pcie_link, other.pcie_link);
+    fill(&mut base.pcie_max_link, other.pcie_max_link);
+    fill(&mut base.pci_slot, other.pci_slot);
+    fill(&mut base.uuid, other.uuid);
+    fill(&mut base.serial, other.serial);
+    fill(&mut base.vbios_version, other.vbios_version);
+    fill(&mut base.memory_vendor, other.memory_vendor);
+    fill(&mut base.sriov, other.sriov);
+    fill(&mut base.runtime_pm, other.runtime_pm);
+    fill(&mut base.numa, other.numa);
+    fill(&mut base.physical_function, other.physical_function);
+    fill(&mut base.hybrid, other.hybrid);
+    fill(&mut base.vaapi, other.vaapi);
+    fill(&mut base.features, other.features);
+    fill(&mut base.runtime, other.runtime);
+
+    if base.displays.is_empty() {
+        base.displays = other.displays;
+    }
+    if base.engine_load.is_empty() {
+        base.engine_load = other.engine_load;
+    }
+    if base.firmware.is_empty() {
+        base.firmware = other.firmware;
+    }
+    if base.kernel_parameters.is_empty() {
+        base.kernel_parameters = other.kernel_parameters;
+    }
+    if base.module_options.is_empty() {
+        base.module_options = other.module_options;
+    }
+    for warning in other.warnings {
+        if !base.warnings.contains(&warning) {
+            base.warnings.push(warning);
+        }
+    }
+
+    // Values taken whole from `other` bring their provenance along
+    for (field, source) in other.provenance {
+        let taken = match field {
+            "freq_mhz" => took_freq,
+            "max_freq_mhz" => took_max_freq,
+            "compute_capability" => took_compute_capability,
+            "l1_kb" | "l2_kb" | "l3_kb" => took_cache,
+            _ => took_topology && (Topology::COMPUTE_UNITS.contains(&field) || Topology::COUNTS.contains(&field)),
+        };
+        if taken {
+            base.provenance.insert(field, source);
+        }
+    }
+}
+
+/// Memory size and clock fill gaps; type and bus width are spec fields
+fn merge_memory(base: &mut GpuInfo, other: &mut GpuInfo) {
+    let Some(other_memory) = other.memory.take() else {
+        return;
+    };
+    let Some((type_known, bus_width_known)) = base
+        .memory
+        .as_ref()
+        .map(|memory| (!matches!(memory.memory_type, MemoryType::Unknown), memory.bus_width > 0))
+    else {
+        for field in ["memory", "memory_type", "bus_width"] {
+            adopt_source(base, other, field);
+        }
+        base.memory = Some(other_memory);
+        return;
+    };
+
+    let other_type_known = !matches!(other_memory.memory_type, MemoryType::Unknown);
+    let replace_type = should_replace(base, other, "memory_type", type_known, other_type_known);
+    let replace_bus_width = should_replace(base, other, "bus_width", bus_width_known, other_memory.bus_width > 0);
+
+    let Some(memory) = base.memory.as_mut() else {
+        return;
+    };
+    if replace_type {
+        memory.memory_type = other_memory.memory_type;
+    }
+    if replace_bus_width {
+        memory.bus_width = other_memory.bus_width;
+    }
+    if memory.size_bytes == 0 {
+        memory.size_bytes = other_memory.size_bytes;
+    }
+    if memory.clock_mhz == 0 {
+        memory.clock_mhz = other_memory.clock_mhz;
+    }
+}
+
+/// Whether `other`'s value of a spec field should replace `base`'s, moving
+/// its provenance over if so
+fn should_replace(base: &mut GpuInfo, other: &GpuInfo, field: &'static str, base_known: bool, other_known: bool) -> bool {
+    let base_measured = base_known && !base.provenance.contains_key(field);
+    let other_measured = other_known && !other.provenance.contains_key(field);
+    let replace = other_known && (!base_known || (!base_measured && other_measured));
+    if replace {
+        adopt_source(base, other, field);
+    }
+    replace
+}
+
+/// Record a field as coming from wherever `other` got it
+fn adopt_source(base: &mut GpuInfo, other: &GpuInfo, field: &'static str) {
+    match other.provenance.get(field) {
+        Some(&source) => base.provenance.insert(field, source),
+        None => base.provenance.remove(field),
+    };
+}
+
+/// Take `other` when `base` has no value
+fn fill<T>(base: &mut Option<T>, other: Option<T>) {
+    if base.is_none() {
+        *base = other;
+    }
+}
@@ -0,0 +1,123 @@
+use anyhow::{anyhow, Result};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::gpu::common::GpuInfo;
+use crate::gpu::pci;
+
+/// A user-supplied way of picking one GPU out of the detected list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GpuSelector {
+    /// Position in the detection order
+    Index(usize),
+    /// PCI address, normalized to `dddd:bb:dd.f`
+    PciAddress(String),
+    /// Case-insensitive substring of the GPU name
+    Name(String),
+}
+
+impl FromStr for GpuSelector {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(anyhow!("GPU selector cannot be empty"));
+        }
+
+        if let Ok(index) = s.parse::<usize>() {
+            return Ok(GpuSelector::Index(index));
+        }
+
+        if let Some((domain, bus, device, function)) = pci::parse_pci_address(s) {
+            let address = format!("{:04x}:{:02x}:{:02x}.{:x}", domain, bus, device, function);
+            return Ok(GpuSelector::PciAddress(address));
+        }
+
+        Ok(GpuSelector::Name(s.to_lowercase()))
+    }
+}
+
+impl fmt::Display for GpuSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GpuSelector::Index(index) => write!(f, "{}", index),
+            GpuSelector::PciAddress(address) => write!(f, "{}", address),
+            GpuSelector::Name(name) => write!(f, "\"{}\"", name),
+        }
+    }
+}
+
+impl GpuSelector {
+    /// Check whether a GPU matches this selector (indices never match here)
+    pub fn matches(&self, gpu: &GpuInfo) -> bool {
+        match self {
+            GpuSelector::Index(_) => false,
+            GpuSelector::PciAddress(address) => gpu
+                .pci_info
+                .as_ref()
+                .is_some_and(|pci| pci.address() == *address),
+            GpuSelector::Name(name) => gpu.name.to_lowercase().contains(name.as_str()),
+        }
+    }
+
+    /// Resolve the selector to an index into `gpus`
+    ///
+    /// Numbers too large to be an index are model numbers, so "3080" finds
+    /// the RTX 3080. Fails when nothing matches or when a name matches more
+    /// than one GPU, listing the candidates so the user can refine the
+    /// selector.
+    pub fn resolve(&self, gpus: &[GpuInfo]) -> Result<usize> {
+        if let GpuSelector::Index(index) = self {
+            if *index < gpus.len() {
+                return Ok(*index);
+            }
+            let by_name = GpuSelector::Name(index.to_string());
+            if gpus.iter().any(|gpu| by_name.matches(gpu)) {
+                return by_name.resolve(gpus);
+            }
+            return Err(anyhow!(
+                "GPU index {} out of range ({} GPUs detected)\n{}",
+                index,
+                gpus.len(),
+                list_candidates(gpus, 0..gpus.len())
+            ));
+        }
+
+        let matching: Vec<usize> = gpus
+            .iter()
+            .enumerate()
+            .filter(|(_, gpu)| self.matches(gpu))
+            .map(|(idx, _)| idx)
+            .collect();
+
+        match matching.as_slice() {
+            [idx] => Ok(*idx),
+            [] => Err(anyhow!(
+                "No GPU matches {}\n{}",
+                self,
+                list_candidates(gpus, 0..gpus.len())
+            )),
+            _ => Err(anyhow!(
+                "{} matches {} GPUs, use an index or PCI address instead:\n{}",
+                self,
+                matching.len(),
+                list_candidates(gpus, matching.iter().copied())
+            )),
+        }
+    }
+}
+
+/// Format a list of GPUs for selector error messages
+fn list_candidates(gpus: &[GpuInfo], indices: impl Iterator<Item = usize>) -> String {
+    indices
+        .map(|idx| {
+            let gpu = &gpus[idx];
+            match gpu.pci_info {
+                Some(ref pci) => format!("  {}: {} [{}]", idx, gpu.name, pci.address()),
+                None => format!("  {}: {}", idx, gpu.name),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,448 @@
+//! Diagnostic snapshots: `gpufetch dump` and `--replay`.
+//!
+//! A snapshot is a tarball holding a copy of the sysfs entries of every
+//! display adapter, the output of each tool detection ran, and what was
+//! detected. Replaying extracts it, reads sysfs from the copy through
+//! `sysroot`, and answers tool invocations from the recorded output, so a
+//! bug report renders on the developer's machine as it did for the reporter.
+//! An unpacked snapshot directory replays the same way; the golden tests
+//! keep theirs under `tests/fixtures`. Libraries, driver manifests and NVML
+//! of the replaying machine are left out, as the snapshot has none of them.
+
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::symlink;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, ExitStatus, Output};
+use std::sync::OnceLock;
+
+use crate::gpu::common::GpuInfo;
+use crate::gpu::{health, kernel_options, pci, sysroot};
+
+/// Directory in a snapshot with recorded tool output
+const TOOLS_DIR: &str = "tools";
+
+/// File in `TOOLS_DIR` listing which command each recorded output is of
+const INDEX_FILE: &str = "index.txt";
+
+/// Largest sysfs file copied; bigger ones are binary blobs such as the VBIOS
+const MAX_FILE_SIZE: u64 = 64 * 1024;
+
+/// How deep below a PCI device directory files are copied, enough for
+/// `drm/card0/card0-DP-1/edid` and `hwmon/hwmon3/temp1_input`
+const MAX_DEPTH: usize = 4;
+
+/// Device files that are large, slow or have side effects when read
+const SKIPPED_FILES: [&str; 5] = ["config", "rom", "remove", "rescan", "reset"];
+
+/// Where tool invocations go
+enum Mode {
+    /// Run tools normally
+    Live,
+    /// Run tools and save their output under the directory
+    Record(PathBuf),
+    /// Answer from output saved under the directory
+    Replay(PathBuf),
+}
+
+static MODE: OnceLock<Mode> = OnceLock::new();
+
+fn mode() -> &'static Mode {
+    MODE.get_or_init(|| Mode::Live)
+}
+
+/// Whether output comes from a snapshot rather than this machine
+pub fn is_replaying() -> bool {
+    matches!(mode(), Mode::Replay(_))
+}
+
+/// Run a detection tool, recording or replaying it as the mode asks
+///
+/// Replaying a command the snapshot has no output for fails as if the tool
+/// were not installed. Every run is logged at debug level with how it ended.
+pub fn run(program: &str, args: &[&str]) -> io::Result<Output> {
+    let output = run_or_replay(program, args);
+    match output {
+        Ok(ref output) => tracing::debug!("{} {}: {}", program, args.join(" "), output.status),
+        Err(ref e) => tracing::debug!("{} {}: {}", program, args.join(" "), e),
+    }
+    output
+}
+
+fn run_or_replay(program: &str, args: &[&str]) -> io::Result<Output> {
+    match mode() {
+        Mode::Live => Command::new(program).args(args).output(),
+        Mode::Record(dir) => {
+            let output = Command::new(program).args(args).output()?;
+            if let Err(e) = record(dir, program, args, &output) {
+                tracing::warn!("could not record {} output: {:#}", program, e);
+            }
+            Ok(output)
+        }
+        Mode::Replay(dir) => {
+            let base = dir.join(TOOLS_DIR).join(tool_key(program, args));
+            let status = fs::read_to_string(base.with_extension("status"))
+                .map_err(|_| io::Error::new(io::ErrorKind::NotFound, format!("{} is not in the snapshot", program)))?;
+            let code: i32 = status.trim().parse().unwrap_or(1);
+            Ok(Output {
+                status: ExitStatus::from_raw(code << 8),
+                stdout: fs::read(base.with_extension("stdout")).unwrap_or_default(),
+                stderr: fs::read(base.with_extension("stderr")).unwrap_or_default(),
+            })
+        }
+    }
+}
+
+/// Whether the snapshot being replayed recorded any run of `program`
+///
+/// Tools that were not installed fail to start and leave nothing behind.
+/// A tool run under `timeout` is recorded as a run of `timeout`, so the
+/// index of recorded commands is searched for it as well.
+pub fn has_output_of(program: &str) -> bool {
+    let Mode::Replay(dir) = mode() else {
+        return false;
+    };
+    let prefix = format!("{}-", program);
+    let recorded = fs::read_dir(dir.join(TOOLS_DIR)).is_ok_and(|entries| {
+        entries.flatten().any(|entry| {
+            // The rest of the name is the hash of the arguments
+            let name = entry.file_name().to_string_lossy().into_owned();
+            name.strip_prefix(&prefix)
+                .and_then(|rest| rest.strip_suffix(".status"))
+                .is_some_and(|hash| hash.chars().all(|c| c.is_ascii_hexdigit()))
+        })
+    });
+    recorded || fs::read_to_string(dir.join(TOOLS_DIR).join(INDEX_FILE)).is_ok_and(|index| {
+        index.lines().filter_map(|line| line.split_once('\t')).any(|(_, command)| {
+            let words: Vec<&str> = command.split_whitespace().collect();
+            matches!(words.as_slice(), ["timeout", _, wrapped, ..] if *wrapped == program)
+        })
+    })
+}
+
+/// File name stem for a command's recorded output
+///
+/// Command lines can be longer than a file name may be, so they are hashed.
+fn tool_key(program: &str, args: &[&str]) -> String {
+    let digest = Sha256::digest(command_line(program, args).as_bytes());
+    let hash: String = digest[..6].iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    });
+    format!("{}-{}", program, hash)
+}
+
+fn command_line(program: &str, args: &[&str]) -> String {
+    std::iter::once(program).chain(args.iter().copied()).collect::<Vec<_>>().join(" ")
+}
+
+fn record(dir: &Path, program: &str, args: &[&str], output: &Output) -> Result<()> {
+    let tools = dir.join(TOOLS_DIR);
+    fs::create_dir_all(&tools)?;
+    let key = tool_key(program, args);
+    let base = tools.join(&key);
+    fs::write(base.with_extension("stdout"), &output.stdout)?;
+    fs::write(base.with_extension("stderr"), &output.stderr)?;
+    fs::write(base.with_extension("status"), output.status.code().unwrap_or(1).to_string())?;
+
+    // Which file holds which command, for people reading the snapshot
+    let mut index = OpenOptions::new().create(true).append(true).open(tools.join(INDEX_FILE))?;
+    writeln!(index, "{}\t{}", key, command_line(program, args))?;
+    Ok(())
+}
+
+/// A temporary directory removed when dropped
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(purpose: &str) -> Result<Self> {
+        let path = std::env::temp_dir().join(format!("gpufetch-{}-{}", purpose, process::id()));
+        if path.exists() {
+            fs::remove_dir_all(&path).with_context(|| format!("Failed to clear {}", path.display()))?;
+        }
+        fs::create_dir_all(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+        Ok(TempDir(path))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Start recording tool output into a new snapshot directory
+///
+/// Must be called before detection runs.
+pub fn start_recording() -> Result<TempDir> {
+    let dir = TempDir::new("dump")?;
+    if MODE.set(Mode::Record(dir.path().to_path_buf())).is_err() {
+        bail!("tool output is already being recorded or replayed");
+    }
+    Ok(dir)
+}
+
+/// Finish a snapshot: copy sysfs, note what was detected and pack it all
+/// into a gzipped tarball at `archive`
+pub fn finish_recording(dir: &TempDir, gpus: &[GpuInfo], archive: &Path) -> Result<()> {
+    copy_sysfs(dir.path())?;
+
+    let mut detected = format!("gpufetch {}\n\n", env!("CARGO_PKG_VERSION"));
+    for gpu in gpus {
+        let _ = writeln!(detected, "{:#?}", gpu);
+    }
+    fs::write(dir.path().join("detected.txt"), detected).context("Failed to write detected.txt")?;
+
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(archive)
+        .arg("-C")
+        .arg(dir.path())
+        .arg(".")
+        .status()
+        .context("Failed to run tar")?;
+    if !status.success() {
+        bail!("tar failed to write {}", archive.display());
+    }
+    Ok(())
+}
+
+/// Read sysfs and tool output from a snapshot from now on
+///
+/// `snapshot` is the tarball, which is unpacked into a temporary directory,
+/// or a directory holding an unpacked one, such as the test fixtures. Must
+/// be called before anything reads sysfs. The returned directory holds the
+/// unpacked tarball and has to be kept until output is done.
+pub fn start_replay(snapshot: &Path) -> Result<Option<TempDir>> {
+    if snapshot.is_dir() {
+        replay_from(snapshot.to_path_buf())?;
+        return Ok(None);
+    }
+    if !snapshot.is_file() {
+        bail!("Snapshot {} does not exist", snapshot.display());
+    }
+    let dir = TempDir::new("replay")?;
+    let status = Command::new("tar")
+        .arg("-xzf")
+        .arg(snapshot)
+        .arg("-C")
+        .arg(dir.path())
+        .status()
+        .context("Failed to run tar")?;
+    if !status.success() {
+        bail!("{} is not a gpufetch snapshot", snapshot.display());
+    }
+
+    replay_from(dir.path().to_path_buf())?;
+    Ok(Some(dir))
+}
+
+fn replay_from(dir: PathBuf) -> Result<()> {
+    if MODE.set(Mode::Replay(dir.clone())).is_err() {
+        bail!("tool output is already being recorded or replayed");
+    }
+    sysroot::set_root(dir);
+    Ok(())
+}
+
+/// Copy the sysfs entries detection reads into `dir`, keeping the links
+/// between them working
+fn copy_sysfs(dir: &Path) -> Result<()> {
+    let devices = dir.join("sys/bus/pci/devices");
+    fs::create_dir_all(&devices)?;
+
+    for pci_info in pci::list_pci_devices() {
+        let address = pci_info.address();
+        let source = pci::device_path(&address);
+        let target = devices.join(&address);
+        copy_tree(&source, &source, &target, 0)?;
+        copy_device_links(dir, &source, &target)?;
+    }
+
+    // DRM class entries are links into the device directories, as on a real system
+    let class_drm = dir.join("sys/class/drm");
+    fs::create_dir_all(&class_drm)?;
+    for device in fs::read_dir(&devices)?.flatten() {
+        let Ok(nodes) = fs::read_dir(device.path().join("drm")) else {
+            continue;
+        };
+        for node in nodes.flatten() {
+            let link = format!("../../bus/pci/devices/{}/drm/{}", device.file_name().to_string_lossy(), node.file_name().to_string_lossy());
+            let _ = symlink(link, class_drm.join(node.file_name()));
+        }
+    }
+
+    // Slot directories are small; only the address in each is read
+    for slot in fs::read_dir(&*pci::PCI_SLOTS_PATH).into_iter().flatten().flatten() {
+        if let Ok(address) = fs::read(slot.path().join("address")) {
+            let target = dir.join("sys/bus/pci/slots").join(slot.file_name());
+            fs::create_dir_all(&target)?;
+            fs::write(target.join("address"), address)?;
+        }
+    }
+
+    let amdgpu_version = sysroot::path("/sys/module/amdgpu/version");
+    if let Ok(version) = fs::read(&amdgpu_version) {
+        let target = dir.join("sys/module/amdgpu");
+        fs::create_dir_all(&target)?;
+        fs::write(target.join("version"), version)?;
+    }
+    // Module parameters are only read for the options shown, so just those of GPU drivers
+    for module in fs::read_dir(sysroot::path("/sys/module")).into_iter().flatten().flatten() {
+        let name = module.file_name().to_string_lossy().into_owned();
+        if !kernel_options::is_gpu_module(&name) {
+            continue;
+        }
+        let target = dir.join("sys/module").join(&name).join("parameters");
+        copy_tree(&module.path().join("parameters"), &module.path().join("parameters"), &target, MAX_DEPTH)?;
+    }
+    // Whether the kernel would load an unsigned nvidia module; the module
+    // files are too big to copy, so only the index lines of GPU drivers are kept
+    for file in [health::SECURE_BOOT_VARIABLE, health::LOCKDOWN_PATH, health::SIG_ENFORCE_PATH, health::OSRELEASE_PATH] {
+        if let Ok(content) = fs::read(sysroot::path(file)) {
+            let target = dir.join(file.trim_start_matches('/'));
+            fs::create_dir_all(target.parent().expect("the file is in a directory"))?;
+            fs::write(target, content)?;
+        }
+    }
+    if let Ok(release) = fs::read_to_string(sysroot::path(health::OSRELEASE_PATH)) {
+        let modules_dep = Path::new("lib/modules").join(release.trim()).join("modules.dep");
+        if let Ok(index) = fs::read_to_string(sysroot::root().join(&modules_dep)) {
+            let gpu_lines: String = index.lines().filter(|line| is_gpu_module_line(line)).map(|line| format!("{}\n", line)).collect();
+            fs::create_dir_all(dir.join(modules_dep.parent().expect("modules.dep is in a directory")))?;
+            fs::write(dir.join(&modules_dep), gpu_lines)?;
+        }
+    }
+    if let Ok(cmdline) = fs::read(sysroot::path(kernel_options::CMDLINE_PATH)) {
+        fs::create_dir_all(dir.join("proc"))?;
+        fs::write(dir.join("proc/cmdline"), cmdline)?;
+    }
+    for (name, path) in kernel_options::modprobe_files() {
+        let target = dir.join("etc/modprobe.d");
+        fs::create_dir_all(&target)?;
+        if let Ok(content) = fs::read(path) {
+            fs::write(target.join(name), content)?;
+        }
+    }
+    if sysroot::path("/sys/kernel/debug/vgaswitcheroo").exists() {
+        let target = dir.join("sys/kernel/debug/vgaswitcheroo");
+        fs::create_dir_all(&target)?;
+        // Only root can read it; replays of other snapshots see the mux as hidden
+        if let Ok(switch) = fs::read(sysroot::path("/sys/kernel/debug/vgaswitcheroo/switch")) {
+            fs::write(target.join("switch"), switch)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether a modules.dep line is about a GPU driver, e.g.
+/// "updates/dkms/nvidia-drm.ko: updates/dkms/nvidia.ko"
+fn is_gpu_module_line(line: &str) -> bool {
+    let path = line.split(':').next().unwrap_or_default();
+    let file_name = Path::new(path).file_name().unwrap_or_default().to_string_lossy();
+    file_name.split(".ko").next().is_some_and(|module| kernel_options::is_gpu_module(&module.replace('-', "_")))
+}
+
+/// Copy small readable files and real subdirectories of the device at `root`
+///
+/// Links that stay inside the device, such as `drm/card0/device` pointing
+/// back at it, are recreated as they are; the others are left out.
+fn copy_tree(root: &Path, source: &Path, target: &Path, depth: usize) -> Result<()> {
+    fs::create_dir_all(target).with_context(|| format!("Failed to create {}", target.display()))?;
+    let Ok(entries) = fs::read_dir(source) else {
+        return Ok(());
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() && depth < MAX_DEPTH {
+            copy_tree(root, &entry.path(), &target.join(&name), depth + 1)?;
+        } else if file_type.is_symlink() && depth > 0 {
+            if let Some(link) = internal_link(root, &entry.path()) {
+                let _ = symlink(link, target.join(&name));
+            }
+        } else if file_type.is_file() {
+            let skipped = SKIPPED_FILES.iter().any(|skipped| name == *skipped)
+                || name.to_string_lossy().starts_with("resource")
+                || entry.metadata().is_ok_and(|metadata| metadata.len() > MAX_FILE_SIZE);
+            if skipped {
+                continue;
+            }
+            if let Ok(content) = fs::read(entry.path()) {
+                fs::write(target.join(&name), content)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A relative link's target, if it resolves to somewhere inside `root`
+fn internal_link(root: &Path, link: &Path) -> Option<PathBuf> {
+    let target = fs::read_link(link).ok()?;
+    let inside = target.is_relative()
+        && fs::canonicalize(link).ok()?.starts_with(fs::canonicalize(root).ok()?);
+    inside.then_some(target)
+}
+
+/// Recreate the `driver`, `iommu_group` and SR-IOV links of a PCI device,
+/// and the ACPI power resources its firmware node lists
+fn copy_device_links(dir: &Path, source: &Path, target: &Path) -> Result<()> {
+    let link_name = |name: &str| {
+        fs::read_link(source.join(name))
+            .ok()
+            .and_then(|link| link.file_name().map(|name| name.to_string_lossy().into_owned()))
+    };
+
+    if let Some(driver) = link_name("driver") {
+        fs::create_dir_all(dir.join("sys/bus/pci/drivers").join(&driver))?;
+        let _ = symlink(format!("../../drivers/{}", driver), target.join("driver"));
+    }
+
+    if let Some(group) = link_name("iommu_group") {
+        let group_dir = dir.join("sys/kernel/iommu_groups").join(&group);
+        let members = group_dir.join("devices");
+        fs::create_dir_all(&members)?;
+        let _ = symlink(format!("../../../../kernel/iommu_groups/{}", group), target.join("iommu_group"));
+
+        // Group members that are not GPUs are copied without their own links
+        for member in fs::read_dir(source.join("iommu_group/devices")).into_iter().flatten().flatten() {
+            let address = member.file_name();
+            let member_dir = dir.join("sys/bus/pci/devices").join(&address);
+            if !member_dir.exists() {
+                copy_tree(&member.path(), &member.path(), &member_dir, MAX_DEPTH)?;
+            }
+            let _ = symlink(format!("../../../../bus/pci/devices/{}", address.to_string_lossy()), members.join(&address));
+        }
+    }
+
+    // Only whether there are any is read, so the link targets are left out
+    let power_resources = Path::new("firmware_node/power_resources_D3hot");
+    for resource in fs::read_dir(source.join(power_resources)).into_iter().flatten().flatten() {
+        let resources = target.join(power_resources);
+        fs::create_dir_all(&resources)?;
+        fs::write(resources.join(resource.file_name()), "")?;
+    }
+
+    for entry in fs::read_dir(source).into_iter().flatten().flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name == "physfn" || name.starts_with("virtfn") {
+            if let Some(address) = link_name(&name) {
+                let _ = symlink(format!("../{}", address), target.join(&name));
+            }
+        }
+    }
+
+    Ok(())
+}
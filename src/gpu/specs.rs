@@ -0,0 +1,443 @@
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::gpu::common::{Cache, GpuInfo, GpuVendor, MemoryType, PciInfo, Process, Source, Topology};
+use crate::gpu::update;
+
+/// The database compiled into the binary
+const EMBEDDED_SPECS: &str = include_str!("../../data/specs.toml");
+
+static EMBEDDED: Lazy<SpecDb> = Lazy::new(|| SpecDb::parse(EMBEDDED_SPECS).expect("embedded spec database is valid"));
+static DOWNLOADED: Lazy<Option<SpecDb>> = Lazy::new(update::cached_specs);
+static USER_OVERRIDES: Lazy<Overrides> = Lazy::new(Overrides::load_or_warn);
+
+/// A collection of per-model GPU specifications
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpecDb {
+    #[serde(default)]
+    pub gpu: Vec<GpuSpec>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chip: Vec<ChipSpec>,
+}
+
+/// Published specifications for one GPU model
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GpuSpec {
+    pub vendor: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub device_ids: Vec<String>,
+    /// PCI revision IDs, e.g. "c8", for models that share their device IDs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub revisions: Vec<String>,
+    #[serde(flatten)]
+    pub fields: SpecFields,
+}
+
+/// Published facts about one die, shared by the models built on it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChipSpec {
+    pub vendor: String,
+    /// Chip name as the model entries give it, e.g. "AD102"
+    pub name: String,
+    pub die_size_mm2: Option<u32>,
+    pub transistors_million: Option<u32>,
+    /// Year the first product on the chip launched
+    pub release_year: Option<u32>,
+    pub foundry: Option<String>,
+    /// The foundry's name for the process, e.g. "N7" or "8N"
+    pub process_node: Option<String>,
+}
+
+/// Spec values that can be filled in on a detected GPU
+///
+/// Every field is optional; missing fields are left as detected or to the
+/// vendor heuristics.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpecFields {
+    pub architecture: Option<String>,
+    pub chip: Option<String>,
+    pub process_nm: Option<u32>,
+    /// Reference boost clock in MHz
+    pub boost_clock_mhz: Option<u32>,
+    /// Rated board power of the reference card in watts (TDP, TBP or TGP)
+    pub board_power_w: Option<u32>,
+    pub memory_type: Option<String>,
+    pub bus_width: Option<u32>,
+
+    // NVIDIA
+    pub sm_count: Option<u32>,
+    pub gpc_count: Option<u32>,
+    pub cuda_cores: Option<u32>,
+    pub tensor_cores: Option<u32>,
+    pub rt_cores: Option<u32>,
+
+    // AMD
+    pub compute_units: Option<u32>,
+    pub stream_processors: Option<u32>,
+    pub shader_engines: Option<u32>,
+    pub rops: Option<u32>,
+    pub tmus: Option<u32>,
+
+    // Intel
+    pub execution_units: Option<u32>,
+    pub slices: Option<u32>,
+    pub subslices: Option<u32>,
+
+    /// L1 of each SM or CU
+    pub l1_kb: Option<u64>,
+    pub l2_kb: Option<u64>,
+    pub l3_kb: Option<u64>,
+
+    /// FP32 to FP64 throughput ratio, e.g. 2 for half-rate FP64
+    pub fp64_ratio: Option<u32>,
+}
+
+/// User corrections from `~/.config/gpufetch/overrides.toml`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Overrides {
+    #[serde(default)]
+    pub device: Vec<DeviceOverride>,
+}
+
+/// Corrected values for one PCI device ID
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceOverride {
+    pub device_id: String,
+    /// Restrict the override to one vendor when device IDs collide
+    pub vendor: Option<String>,
+    #[serde(flatten)]
+    pub fields: SpecFields,
+}
+
+impl SpecDb {
+    /// The database shipped with gpufetch
+    pub fn embedded() -> &'static SpecDb {
+        &EMBEDDED
+    }
+
+    /// The database fetched with `gpufetch update-db`, else the embedded one
+    pub fn active() -> &'static SpecDb {
+        DOWNLOADED.as_ref().unwrap_or_else(Self::embedded)
+    }
+
+    /// Parse a database from TOML
+    pub fn parse(content: &str) -> Result<Self> {
+        toml::from_str(content).context("Invalid GPU spec database")
+    }
+
+    /// Serialize the database back to TOML
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string(self).context("Failed to serialize GPU spec database")
+    }
+
+    /// Find the entry describing a detected GPU
+    ///
+    /// The longest alias contained in `name` wins. Otherwise the PCI device ID
+    /// is used when a single entry claims it, or the revision when several
+    /// do, since one die is often sold as several models.
+    pub fn lookup(&self, vendor: &GpuVendor, pci_info: Option<&PciInfo>, name: &str) -> Option<&GpuSpec> {
+        let name_lower = name.to_lowercase();
+        let candidates: Vec<&GpuSpec> = self.gpu.iter().filter(|spec| spec.is_vendor(vendor)).collect();
+
+        let by_name = candidates
+            .iter()
+            .filter_map(|spec| spec.alias_match_len(&name_lower).map(|len| (len, *spec)))
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, spec)| spec);
+        if by_name.is_some() {
+            return by_name;
+        }
+
+        let pci_info = pci_info?;
+        let by_id: Vec<&GpuSpec> = candidates.into_iter().filter(|spec| spec.has_device_id(pci_info.device_id)).collect();
+        if let [spec] = by_id[..] {
+            return Some(spec);
+        }
+        let revision = pci_info.revision?;
+        let mut by_revision = by_id.into_iter().filter(|spec| spec.revisions.iter().any(|id| parse_revision(id) == Some(revision)));
+        match (by_revision.next(), by_revision.next()) {
+            (Some(spec), None) => Some(spec),
+            _ => None,
+        }
+    }
+
+    /// The fields every entry claiming `device_id` agrees on, for a GPU
+    /// `lookup` could not tell apart from its siblings
+    ///
+    /// Models on one die usually share the memory type and often the bus
+    /// width, which beats guessing them from the name.
+    pub fn shared_fields(&self, vendor: &GpuVendor, device_id: u16) -> Option<SpecFields> {
+        let mut siblings = self
+            .gpu
+            .iter()
+            .filter(|spec| spec.is_vendor(vendor) && spec.has_device_id(device_id))
+            .map(|spec| serde_json::to_value(&spec.fields).ok());
+        let Some(serde_json::Value::Object(mut shared)) = siblings.next()? else {
+            return None;
+        };
+        for sibling in siblings {
+            let sibling = sibling?;
+            shared.retain(|field, value| sibling.get(field) == Some(value));
+        }
+        serde_json::from_value(serde_json::Value::Object(shared)).ok()
+    }
+
+    /// The entry for a chip by name
+    pub fn chip(&self, vendor: &GpuVendor, name: &str) -> Option<&ChipSpec> {
+        self.chip
+            .iter()
+            .find(|chip| chip.vendor.eq_ignore_ascii_case(&vendor.to_string()) && chip.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Entries whose name, alias, chip, architecture or device ID contain `query`
+    pub fn search(&self, query: &str) -> Vec<&GpuSpec> {
+        let query = query.trim().to_lowercase();
+        self.gpu.iter().filter(|spec| spec.matches_query(&query)).collect()
+    }
+}
+
+impl GpuSpec {
+    /// Whether this entry belongs to `vendor`
+    pub fn is_vendor(&self, vendor: &GpuVendor) -> bool {
+        self.vendor.eq_ignore_ascii_case(&vendor.to_string())
+    }
+
+    fn has_device_id(&self, device_id: u16) -> bool {
+        self.device_ids.iter().any(|id| parse_device_id(id) == Some(device_id))
+    }
+
+    /// Length of the longest alias found in `name_lower` on a word boundary
+    fn alias_match_len(&self, name_lower: &str) -> Option<usize> {
+        let full_name = self.name.to_lowercase();
+        self.aliases
+            .iter()
+            .map(|alias| alias.to_lowercase())
+            .chain(std::iter::once(full_name))
+            .filter(|alias| contains_word(name_lower, alias))
+            .map(|alias| alias.len())
+            .max()
+    }
+
+    fn matches_query(&self, query: &str) -> bool {
+        let fields = [Some(&self.name), self.fields.chip.as_ref(), self.fields.architecture.as_ref()];
+        fields.into_iter().flatten().chain(&self.aliases).any(|field| field.to_lowercase().contains(query))
+            || self.device_ids.iter().any(|id| id.eq_ignore_ascii_case(query))
+    }
+}
+
+impl ChipSpec {
+    /// Fill in the die facts on a GPU built on this chip
+    fn apply(&self, gpu: &mut GpuInfo) {
+        gpu.die_size_mm2 = self.die_size_mm2;
+        gpu.transistors_million = self.transistors_million;
+        gpu.release_year = self.release_year;
+        gpu.provenance.insert("die", Source::SpecDb);
+        // The models give the size; the chip names where it was made
+        if let Some(ref mut process) = gpu.process {
+            if self.foundry.is_some() {
+                process.foundry = self.foundry.clone();
+                process.node = self.process_node.clone();
+            }
+        }
+    }
+}
+
+impl SpecFields {
+    /// Overwrite everything these fields know about on a detected GPU
+    ///
+    /// Each value that is set is recorded in the GPU's provenance as `source`.
+    pub fn apply(&self, gpu: &mut GpuInfo, source: Source) {
+        if let Some(ref architecture) = self.architecture {
+            gpu.architecture = architecture.clone();
+            gpu.provenance.insert("architecture", source);
+        }
+        if let Some(ref chip) = self.chip {
+            gpu.chip = chip.clone();
+            gpu.provenance.insert("chip", source);
+        }
+        if let Some(nm) = self.process_nm {
+            // A foundry name guessed for the same size still applies
+            if gpu.process.as_ref().is_none_or(|process| process.nm != nm) {
+                gpu.process = Some(Process::nm(nm));
+            }
+            gpu.provenance.insert("process_nm", source);
+        }
+        if self.boost_clock_mhz.is_some() {
+            gpu.reference_boost_mhz = self.boost_clock_mhz;
+            gpu.provenance.insert("boost_clock_mhz", source);
+        }
+        if self.board_power_w.is_some() {
+            gpu.rated_power_w = self.board_power_w;
+            gpu.provenance.insert("board_power_w", source);
+        }
+
+        if let Some(ref mut memory) = gpu.memory {
+            if let Some(memory_type) = self.memory_type.as_ref().and_then(|t| t.parse::<MemoryType>().ok()) {
+                memory.memory_type = memory_type;
+                gpu.provenance.insert("memory_type", source);
+            }
+            if let Some(bus_width) = self.bus_width {
+                memory.bus_width = bus_width;
+                gpu.provenance.insert("bus_width", source);
+            }
+        }
+
+        self.apply_topology(gpu, source);
+
+        if self.fp64_ratio.is_some() {
+            gpu.fp64_ratio = self.fp64_ratio;
+            gpu.provenance.insert("fp64_ratio", source);
+        }
+
+        if self.l1_kb.is_some() || self.l2_kb.is_some() || self.l3_kb.is_some() {
+            let cache = gpu.cache.get_or_insert(Cache {
+                l1_size: None,
+                l2_size: None,
+                l3_size: None,
+            });
+            if let Some(kb) = self.l1_kb {
+                cache.l1_size = Some(kb * 1024);
+                gpu.provenance.insert("l1_kb", source);
+            }
+            if let Some(kb) = self.l2_kb {
+                cache.l2_size = Some(kb * 1024);
+                gpu.provenance.insert("l2_kb", source);
+            }
+            if let Some(kb) = self.l3_kb {
+                cache.l3_size = Some(kb * 1024);
+                gpu.provenance.insert("l3_kb", source);
+            }
+        }
+    }
+
+    /// Patch unit counts into the GPU's topology, creating it if a unit count is known
+    ///
+    /// Counts of units the GPU's vendor does not have are left out.
+    fn apply_topology(&self, gpu: &mut GpuInfo, source: Source) {
+        let compute_units = [("sm_count", self.sm_count), ("compute_units", self.compute_units), ("execution_units", self.execution_units)]
+            .into_iter()
+            .find_map(|(field, count)| Some((field, count?)));
+        let topology = match (gpu.topology.as_mut(), compute_units) {
+            (Some(topology), _) => topology,
+            (None, Some((_, count))) => gpu.topology.insert(Topology::new(&gpu.vendor, count)),
+            (None, None) => return,
+        };
+        let mut patched = Vec::new();
+        if let Some((field, count)) = compute_units {
+            topology.set_compute_units(count);
+            patched.push(field);
+        }
+
+        let counts = [
+            ("gpc_count", self.gpc_count),
+            ("cuda_cores", self.cuda_cores),
+            ("tensor_cores", self.tensor_cores),
+            ("rt_cores", self.rt_cores),
+            ("stream_processors", self.stream_processors),
+            ("shader_engines", self.shader_engines),
+            ("rops", self.rops),
+            ("tmus", self.tmus),
+            ("slices", self.slices),
+            ("subslices", self.subslices),
+        ];
+        for (field, value) in counts {
+            if let (Some(value), Some(slot)) = (value, topology.count_mut(field)) {
+                *slot = Some(value);
+                patched.push(field);
+            }
+        }
+
+        for field in patched {
+            gpu.provenance.insert(field, source);
+        }
+    }
+}
+
+impl Overrides {
+    /// Location of the user's override file
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("gpufetch").join("overrides.toml"))
+    }
+
+    /// Read the override file, treating a missing file as empty
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid overrides in {}", path.display()))
+    }
+
+    /// Read the override file, warning and ignoring it if it is broken
+    fn load_or_warn() -> Self {
+        Self::load().unwrap_or_else(|e| {
+            eprintln!("Warning: ignoring spec overrides: {:#}", e);
+            Self::default()
+        })
+    }
+
+    /// Overrides that apply to this vendor and device ID, in file order
+    fn matching<'a>(&'a self, vendor: &'a GpuVendor, device_id: u16) -> impl Iterator<Item = &'a DeviceOverride> {
+        self.device.iter().filter(move |entry| {
+            parse_device_id(&entry.device_id) == Some(device_id)
+                && entry.vendor.as_ref().is_none_or(|v| v.eq_ignore_ascii_case(&vendor.to_string()))
+        })
+    }
+}
+
+/// Fill in a detected GPU from the spec database, then the user's overrides
+///
+/// Backends call this after their own guesses from the model name or
+/// family, so a per-model spec replaces a guess and an override replaces
+/// both; provenance records which one a field came from.
+pub fn apply_specs(gpu: &mut GpuInfo) {
+    let device_id = gpu.pci_info.as_ref().map(|pci| pci.device_id);
+
+    if let Some(spec) = SpecDb::active().lookup(&gpu.vendor, gpu.pci_info.as_ref(), &gpu.name) {
+        spec.fields.apply(gpu, Source::SpecDb);
+    } else if let Some(fields) = device_id.and_then(|device_id| SpecDb::active().shared_fields(&gpu.vendor, device_id)) {
+        fields.apply(gpu, Source::SpecDb);
+    }
+
+    if let Some(device_id) = device_id {
+        let vendor = gpu.vendor.clone();
+        for entry in USER_OVERRIDES.matching(&vendor, device_id) {
+            entry.fields.apply(gpu, Source::Override);
+        }
+    }
+
+    // After the overrides, which may correct the chip
+    if let Some(chip) = SpecDb::active().chip(&gpu.vendor, &gpu.chip) {
+        chip.apply(gpu);
+    }
+}
+
+/// Parse a PCI device ID written as "73df" or "0x73df"
+fn parse_device_id(id: &str) -> Option<u16> {
+    u16::from_str_radix(id.trim().trim_start_matches("0x"), 16).ok()
+}
+
+/// Parse a PCI revision ID written as "c8" or "0xc8"
+fn parse_revision(id: &str) -> Option<u8> {
+    u8::from_str_radix(id.trim().trim_start_matches("0x"), 16).ok()
+}
+
+/// Whether `needle` occurs in `haystack` without running into a following letter or digit
+fn contains_word(haystack: &str, needle: &str) -> bool {
+    haystack.match_indices(needle).any(|(start, _)| {
+        !haystack[start + needle.len()..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphanumeric())
+    })
+}
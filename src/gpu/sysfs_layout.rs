@@ -0,0 +1,94 @@
+//! Where driver attributes live below a PCI device's sysfs directory.
+//!
+//! Most sit in the device directory itself. hwmon sensors are one level
+//! down in `hwmon/hwmonN`, and i915 keeps its GT frequencies in `drm/cardN`.
+//! Lookups try the places listed here and then, for drivers and kernels
+//! that put a file elsewhere, the directories right below the device. They
+//! never walk the whole tree: it links back up to the device and on to the
+//! driver and the bus, so a full walk can visit much of sysfs.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where a driver puts an attribute
+#[derive(Debug, Clone, Copy)]
+enum Place {
+    /// The PCI device directory
+    Device,
+    /// The `drm/cardN` directory of the device
+    DrmCard,
+}
+
+/// Attributes read through `find` and where they are
+const LAYOUT: [(&str, Place); 6] = [
+    ("mem_info_vram_total", Place::Device),
+    ("pp_dpm_sclk", Place::Device),
+    ("pp_dpm_mclk", Place::Device),
+    ("pp_dpm_fclk", Place::Device),
+    ("gt_max_freq_mhz", Place::DrmCard),
+    ("gt_min_freq_mhz", Place::DrmCard),
+];
+
+/// Sensor attribute prefixes, e.g. "temp1_input" and "power1_average"
+const HWMON_PREFIXES: [&str; 7] = ["temp", "in", "power", "energy", "fan", "pwm", "curr"];
+
+/// The file `name` of the device at `device_path`
+///
+/// Unlisted attributes are looked for in the device directory and the
+/// directories right below it.
+pub fn find(device_path: &Path, name: &str) -> Option<PathBuf> {
+    let dirs = if is_hwmon_attribute(name) {
+        hwmon_dirs(device_path)
+    } else {
+        match LAYOUT.iter().find(|&&(known, _)| known == name).map(|&(_, place)| place) {
+            Some(Place::Device) => vec![device_path.to_path_buf()],
+            Some(Place::DrmCard) => subdirs(&device_path.join("drm"), "card"),
+            None => Vec::new(),
+        }
+    };
+    dirs.into_iter().map(|dir| dir.join(name)).find(|path| path.is_file()).or_else(|| shallow(device_path, name))
+}
+
+/// hwmon directories of a PCI device, in order
+fn hwmon_dirs(device_path: &Path) -> Vec<PathBuf> {
+    subdirs(&device_path.join("hwmon"), "hwmon")
+}
+
+/// The file in the device directory or a directory right below it
+fn shallow(device_path: &Path, name: &str) -> Option<PathBuf> {
+    let direct = device_path.join(name);
+    if direct.is_file() {
+        return Some(direct);
+    }
+    let mut dirs: Vec<PathBuf> = fs::read_dir(device_path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        // Links lead to the driver, the subsystem and other devices
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .map(|entry| entry.path())
+        .collect();
+    dirs.sort();
+    dirs.into_iter().map(|dir| dir.join(name)).find(|path| path.is_file())
+}
+
+/// Directories in `dir` whose names start with `prefix`, sorted
+fn subdirs(dir: &Path, prefix: &str) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(prefix))
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+fn is_hwmon_attribute(name: &str) -> bool {
+    HWMON_PREFIXES.iter().any(|prefix| {
+        name.strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()) && rest.contains('_'))
+    })
+}
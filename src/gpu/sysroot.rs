@@ -0,0 +1,32 @@
+//! The directory `/sys` and `/proc` are read from, along with
+//! `/etc/os-release` for `--system-info`.
+//!
+//! Normally that is `/`. `--sysfs-root` or `GPUFETCH_SYSFS_ROOT` point it at
+//! a captured tree for tests, or at the host's sysfs bind-mounted into a
+//! container, e.g. `/host` holding `/host/sys` and `/host/proc`.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Environment variable naming the root when `--sysfs-root` is not given
+pub const ROOT_ENV: &str = "GPUFETCH_SYSFS_ROOT";
+
+static ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// Read `/sys` and `/proc` under `root` from now on
+///
+/// Must be called before anything is detected; later calls are ignored.
+pub fn set_root(root: PathBuf) {
+    let _ = ROOT.set(root);
+}
+
+/// The directory holding `sys` and `proc`
+pub fn root() -> &'static Path {
+    ROOT.get_or_init(|| env::var_os(ROOT_ENV).map(PathBuf::from).unwrap_or_else(|| PathBuf::from("/")))
+}
+
+/// Where to read an absolute `/sys` or `/proc` path, such as "/sys/class/drm"
+pub fn path(absolute: &str) -> PathBuf {
+    root().join(absolute.trim_start_matches('/'))
+}
@@ -0,0 +1,50 @@
+//! Finding the command-line tools detection runs.
+//!
+//! Programs are looked up on `PATH` in-process, where a `which` probe used
+//! to cost a process spawn per tool, and each answer is cached for the run.
+//! When replaying a snapshot, a tool counts as installed if the snapshot
+//! recorded any output of it.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use tracing::{debug, info};
+
+use crate::gpu::snapshot;
+
+static FOUND: OnceLock<Mutex<HashMap<String, Option<PathBuf>>>> = OnceLock::new();
+
+/// Whether `program` can be run
+pub fn is_installed(program: &str) -> bool {
+    if snapshot::is_replaying() {
+        return snapshot::has_output_of(program);
+    }
+    find(program).is_some()
+}
+
+/// Where `program` is on `PATH`, if it is an executable file there
+pub fn find(program: &str) -> Option<PathBuf> {
+    let mut found = FOUND.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner());
+    found
+        .entry(program.to_string())
+        .or_insert_with(|| {
+            // Logged once, on the first lookup
+            let path = search_path(program);
+            match path {
+                Some(ref path) => info!("found {} at {}", program, path.display()),
+                None => debug!("{} is not on PATH", program),
+            }
+            path
+        })
+        .clone()
+}
+
+fn search_path(program: &str) -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+    env::split_paths(&path).map(|dir| dir.join(program)).find(|candidate| {
+        fs::metadata(candidate).is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+    })
+}
@@ -0,0 +1,307 @@
+//! GPU detection and hotplug events through libudev.
+//!
+//! udev has already matched each DRM card to its PCI device and looked its
+//! name up in the hardware database, so one enumeration of the `drm`
+//! subsystem finds every GPU with its properties. Like NVML, libudev is
+//! loaded at runtime, and only used while `/sys` is this machine's own.
+//!
+//! `--daemon` also listens for udev events of the `drm` subsystem, so an
+//! eGPU being attached or detached, or a monitor being plugged in, shows up
+//! at once instead of at the next refresh.
+
+use anyhow::{anyhow, bail, Result};
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::error::GpufetchError;
+use crate::gpu::backend::Backend;
+use crate::gpu::common::{GpuInfo, PciInfo};
+use crate::gpu::{pci, sysroot};
+
+const LIBRARY: &CStr = c"libudev.so.1";
+
+type Udev = *mut c_void;
+type UdevDevice = *mut c_void;
+type UdevEnumerate = *mut c_void;
+type UdevListEntry = *mut c_void;
+type UdevMonitor = *mut c_void;
+
+/// The libudev entry points gpufetch uses
+struct Library {
+    handle: *mut c_void,
+    context: Udev,
+    unref: unsafe extern "C" fn(Udev) -> Udev,
+    enumerate_new: unsafe extern "C" fn(Udev) -> UdevEnumerate,
+    enumerate_add_match_subsystem: unsafe extern "C" fn(UdevEnumerate, *const c_char) -> c_int,
+    enumerate_scan_devices: unsafe extern "C" fn(UdevEnumerate) -> c_int,
+    enumerate_get_list_entry: unsafe extern "C" fn(UdevEnumerate) -> UdevListEntry,
+    enumerate_unref: unsafe extern "C" fn(UdevEnumerate) -> UdevEnumerate,
+    list_entry_get_next: unsafe extern "C" fn(UdevListEntry) -> UdevListEntry,
+    list_entry_get_name: unsafe extern "C" fn(UdevListEntry) -> *const c_char,
+    device_new_from_syspath: unsafe extern "C" fn(Udev, *const c_char) -> UdevDevice,
+    device_get_parent_with_subsystem_devtype: unsafe extern "C" fn(UdevDevice, *const c_char, *const c_char) -> UdevDevice,
+    device_get_sysname: unsafe extern "C" fn(UdevDevice) -> *const c_char,
+    device_get_syspath: unsafe extern "C" fn(UdevDevice) -> *const c_char,
+    device_get_property_value: unsafe extern "C" fn(UdevDevice, *const c_char) -> *const c_char,
+    device_get_action: unsafe extern "C" fn(UdevDevice) -> *const c_char,
+    device_unref: unsafe extern "C" fn(UdevDevice) -> UdevDevice,
+    monitor_new_from_netlink: unsafe extern "C" fn(Udev, *const c_char) -> UdevMonitor,
+    monitor_filter_add_match_subsystem_devtype: unsafe extern "C" fn(UdevMonitor, *const c_char, *const c_char) -> c_int,
+    monitor_enable_receiving: unsafe extern "C" fn(UdevMonitor) -> c_int,
+    monitor_get_fd: unsafe extern "C" fn(UdevMonitor) -> c_int,
+    monitor_receive_device: unsafe extern "C" fn(UdevMonitor) -> UdevDevice,
+    monitor_unref: unsafe extern "C" fn(UdevMonitor) -> UdevMonitor,
+}
+
+impl Library {
+    /// Load libudev and create a udev context
+    fn open() -> Result<Self> {
+        // libudev reads /sys itself, which may not be the tree gpufetch reads
+        if sysroot::root() != Path::new("/") {
+            return Err(GpufetchError::unavailable(Backend::Udev, "not available with another sysfs root or a snapshot").into());
+        }
+
+        // SAFETY: dlopen with a constant, NUL-terminated library name
+        let handle = unsafe { libc::dlopen(LIBRARY.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        if handle.is_null() {
+            let reason = format!("{} is not installed", LIBRARY.to_string_lossy());
+            return Err(GpufetchError::unavailable(Backend::Udev, reason).into());
+        }
+
+        // SAFETY: the signatures match libudev.h for each symbol
+        match unsafe { Self::resolve(handle) } {
+            Ok(library) if !library.context.is_null() => Ok(library),
+            Ok(_) => Err(anyhow!("udev_new failed")),
+            Err(e) => {
+                // SAFETY: the handle came from dlopen and nothing from it is kept
+                unsafe { libc::dlclose(handle) };
+                Err(e)
+            }
+        }
+    }
+
+    /// Look up the entry points and create the context
+    ///
+    /// # Safety
+    /// `handle` must be a handle returned by dlopen for libudev.
+    unsafe fn resolve(handle: *mut c_void) -> Result<Self> {
+        let new: unsafe extern "C" fn() -> Udev = symbol(handle, c"udev_new")?;
+        let mut library = Library {
+            handle,
+            context: std::ptr::null_mut(),
+            unref: symbol(handle, c"udev_unref")?,
+            enumerate_new: symbol(handle, c"udev_enumerate_new")?,
+            enumerate_add_match_subsystem: symbol(handle, c"udev_enumerate_add_match_subsystem")?,
+            enumerate_scan_devices: symbol(handle, c"udev_enumerate_scan_devices")?,
+            enumerate_get_list_entry: symbol(handle, c"udev_enumerate_get_list_entry")?,
+            enumerate_unref: symbol(handle, c"udev_enumerate_unref")?,
+            list_entry_get_next: symbol(handle, c"udev_list_entry_get_next")?,
+            list_entry_get_name: symbol(handle, c"udev_list_entry_get_name")?,
+            device_new_from_syspath: symbol(handle, c"udev_device_new_from_syspath")?,
+            device_get_parent_with_subsystem_devtype: symbol(handle, c"udev_device_get_parent_with_subsystem_devtype")?,
+            device_get_sysname: symbol(handle, c"udev_device_get_sysname")?,
+            device_get_syspath: symbol(handle, c"udev_device_get_syspath")?,
+            device_get_property_value: symbol(handle, c"udev_device_get_property_value")?,
+            device_get_action: symbol(handle, c"udev_device_get_action")?,
+            device_unref: symbol(handle, c"udev_device_unref")?,
+            monitor_new_from_netlink: symbol(handle, c"udev_monitor_new_from_netlink")?,
+            monitor_filter_add_match_subsystem_devtype: symbol(handle, c"udev_monitor_filter_add_match_subsystem_devtype")?,
+            monitor_enable_receiving: symbol(handle, c"udev_monitor_enable_receiving")?,
+            monitor_get_fd: symbol(handle, c"udev_monitor_get_fd")?,
+            monitor_receive_device: symbol(handle, c"udev_monitor_receive_device")?,
+            monitor_unref: symbol(handle, c"udev_monitor_unref")?,
+        };
+        library.context = new();
+        Ok(library)
+    }
+
+    /// Syspaths of the devices in a subsystem
+    fn enumerate(&self, subsystem: &CStr) -> Vec<String> {
+        let mut syspaths = Vec::new();
+        // SAFETY: the enumeration is created from our context and unreferenced
+        // here; the list entries it owns are only read before that
+        unsafe {
+            let enumerate = (self.enumerate_new)(self.context);
+            if enumerate.is_null() {
+                return syspaths;
+            }
+            if (self.enumerate_add_match_subsystem)(enumerate, subsystem.as_ptr()) >= 0
+                && (self.enumerate_scan_devices)(enumerate) >= 0
+            {
+                let mut entry = (self.enumerate_get_list_entry)(enumerate);
+                while !entry.is_null() {
+                    if let Some(syspath) = string((self.list_entry_get_name)(entry)) {
+                        syspaths.push(syspath);
+                    }
+                    entry = (self.list_entry_get_next)(entry);
+                }
+            }
+            (self.enumerate_unref)(enumerate);
+        }
+        syspaths
+    }
+
+    /// The GPU a DRM card belongs to, from the properties of its PCI device
+    ///
+    /// # Safety
+    /// `card` must be a device from this library that is still referenced.
+    unsafe fn gpu(&self, card: UdevDevice) -> Option<GpuInfo> {
+        let sysname = string((self.device_get_sysname)(card))?;
+        // Cards only; "card0-DP-1" and the like are their connectors
+        if !sysname.strip_prefix("card").is_some_and(|index| index.bytes().all(|b| b.is_ascii_digit())) {
+            return None;
+        }
+        // The parent is owned by the card and not unreferenced separately
+        let parent = (self.device_get_parent_with_subsystem_devtype)(card, c"pci".as_ptr(), std::ptr::null());
+        if parent.is_null() {
+            return None;
+        }
+        let property = |name: &CStr| string((self.device_get_property_value)(parent, name.as_ptr()));
+
+        let syspath = string((self.device_get_syspath)(parent))?;
+        let pci_info = pci::read_pci_info(Path::new(&syspath)).ok()?;
+        // The hardware database has the same names as pci.ids, split in two
+        let name = match (property(c"ID_VENDOR_FROM_DATABASE"), property(c"ID_MODEL_FROM_DATABASE")) {
+            (Some(vendor), Some(model)) => format!("{} {}", vendor, model),
+            _ => pci::device_name(pci_info.vendor_id, pci_info.device_id),
+        };
+        let mut gpu_info = GpuInfo::new(&name, pci::vendor_from_id(pci_info.vendor_id));
+        gpu_info.pci_info = Some(pci_info);
+        gpu_info.kernel_driver = property(c"DRIVER");
+        Some(gpu_info)
+    }
+}
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        // SAFETY: nothing created from the context or the library outlives it
+        unsafe {
+            if !self.context.is_null() {
+                (self.unref)(self.context);
+            }
+            libc::dlclose(self.handle);
+        }
+    }
+}
+
+/// Detect GPUs from the DRM cards udev knows about
+pub fn detect_udev_gpus() -> Result<Vec<GpuInfo>> {
+    let library = Library::open()?;
+    let mut gpus: Vec<GpuInfo> = Vec::new();
+    for syspath in library.enumerate(c"drm") {
+        let Ok(syspath) = CString::new(syspath) else {
+            continue;
+        };
+        // SAFETY: the device is created from our context, used while
+        // referenced and unreferenced afterwards
+        unsafe {
+            let card = (library.device_new_from_syspath)(library.context, syspath.as_ptr());
+            if card.is_null() {
+                continue;
+            }
+            let gpu = library.gpu(card);
+            (library.device_unref)(card);
+            // A GPU with several cards, such as a render-only one, counts once
+            let address = |gpu: &GpuInfo| gpu.pci_info.as_ref().map(PciInfo::address);
+            if let Some(gpu) = gpu.filter(|gpu| !gpus.iter().any(|known| address(known) == address(gpu))) {
+                gpus.push(gpu);
+            }
+        }
+    }
+    Ok(gpus)
+}
+
+/// Events of the `drm` subsystem: cards being added and removed, and their
+/// connectors changing
+pub struct Monitor {
+    library: Library,
+    monitor: UdevMonitor,
+}
+
+impl Monitor {
+    /// Start listening, or fail where libudev is missing or cannot listen
+    pub fn open() -> Result<Self> {
+        let library = Library::open()?;
+        // SAFETY: the monitor is created from our context and freed on drop
+        unsafe {
+            let monitor = (library.monitor_new_from_netlink)(library.context, c"udev".as_ptr());
+            if monitor.is_null() {
+                bail!("udev_monitor_new_from_netlink failed");
+            }
+            let monitor = Monitor { library, monitor };
+            let filtered = (monitor.library.monitor_filter_add_match_subsystem_devtype)(
+                monitor.monitor,
+                c"drm".as_ptr(),
+                std::ptr::null(),
+            );
+            if filtered < 0 || (monitor.library.monitor_enable_receiving)(monitor.monitor) < 0 {
+                bail!("cannot receive udev events");
+            }
+            Ok(monitor)
+        }
+    }
+
+    /// Wait up to `timeout` for events, and say whether any came
+    ///
+    /// Events arriving together, such as those of a dock with several
+    /// outputs, are taken at once.
+    pub fn wait(&self, timeout: Duration) -> bool {
+        // SAFETY: the descriptor belongs to the monitor, which outlives the call
+        let fd = unsafe { (self.library.monitor_get_fd)(self.monitor) };
+        let mut events = 0;
+        let mut timeout_ms = c_int::try_from(timeout.as_millis()).unwrap_or(c_int::MAX);
+        loop {
+            let mut poll_fd = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+            // SAFETY: poll gets one pollfd that lives for the call
+            if unsafe { libc::poll(&mut poll_fd, 1, timeout_ms) } <= 0 {
+                break;
+            }
+            // SAFETY: the monitor socket is non-blocking, so this returns null
+            // once the queue is empty; devices are unreferenced after reading
+            unsafe {
+                let device = (self.library.monitor_receive_device)(self.monitor);
+                if device.is_null() {
+                    break;
+                }
+                let action = string((self.library.device_get_action)(device)).unwrap_or_default();
+                let sysname = string((self.library.device_get_sysname)(device)).unwrap_or_default();
+                tracing::info!("udev: {} {}", action, sysname);
+                (self.library.device_unref)(device);
+            }
+            events += 1;
+            timeout_ms = 0;
+        }
+        events > 0
+    }
+}
+
+impl Drop for Monitor {
+    fn drop(&mut self) {
+        // SAFETY: the monitor is not used after this, and the library it
+        // came from is dropped only afterwards
+        unsafe {
+            (self.library.monitor_unref)(self.monitor);
+        }
+    }
+}
+
+/// Look up a function in the library
+///
+/// # Safety
+/// `T` must be a function pointer type matching the symbol's C signature.
+unsafe fn symbol<T: Copy>(handle: *mut c_void, name: &CStr) -> Result<T> {
+    let address = libc::dlsym(handle, name.as_ptr());
+    if address.is_null() {
+        bail!("{} lacks {}", LIBRARY.to_string_lossy(), name.to_string_lossy());
+    }
+    Ok(std::mem::transmute_copy(&address))
+}
+
+/// A string libudev returned, which may be null
+///
+/// # Safety
+/// `value` must be null or point to a NUL-terminated string.
+unsafe fn string(value: *const c_char) -> Option<String> {
+    (!value.is_null()).then(|| CStr::from_ptr(value).to_string_lossy().into_owned())
+}
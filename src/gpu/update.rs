@@ -0,0 +1,98 @@
+//! Downloaded copies of pci.ids and the GPU spec database.
+//!
+//! `gpufetch update-db` fetches both into the user's cache directory. When a
+//! valid copy is there it is preferred over the one compiled into the binary,
+//! so GPUs released after a build still get their names and specs.
+
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::gpu::pci::PciIds;
+use crate::gpu::specs::SpecDb;
+
+/// Upstream of the PCI ID database
+pub const DEFAULT_PCI_IDS_URL: &str = "https://pci-ids.ucw.cz/v2.2/pci.ids";
+
+/// The spec database on gpufetch's main branch
+pub const DEFAULT_SPECS_URL: &str = "https://raw.githubusercontent.com/harshb20/gpufetch/main/data/specs.toml";
+
+const PCI_IDS_FILE: &str = "pci.ids";
+const SPECS_FILE: &str = "specs.toml";
+
+/// What `update` stored
+pub struct UpdateSummary {
+    pub dir: PathBuf,
+    pub pci_devices: usize,
+    pub spec_entries: usize,
+}
+
+/// Directory holding the downloaded databases
+pub fn cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("gpufetch"))
+}
+
+/// Download both databases, replacing the cached copies only once each
+/// download has been checked to parse
+pub fn update(pci_ids_url: &str, specs_url: &str) -> Result<UpdateSummary> {
+    let dir = cache_dir().context("No cache directory for this user")?;
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let pci_ids = download(pci_ids_url)?;
+    let pci_devices = PciIds::parse(&pci_ids).device_count();
+    if pci_devices == 0 {
+        bail!("{} is not a pci.ids file", pci_ids_url);
+    }
+
+    let specs = download(specs_url)?;
+    let spec_entries = SpecDb::parse(&specs)
+        .with_context(|| format!("Downloaded {} is not a spec database", specs_url))?
+        .gpu
+        .len();
+
+    replace(&dir.join(PCI_IDS_FILE), &pci_ids)?;
+    replace(&dir.join(SPECS_FILE), &specs)?;
+
+    Ok(UpdateSummary { dir, pci_devices, spec_entries })
+}
+
+/// The downloaded pci.ids, if there is a usable one
+pub fn cached_pci_ids() -> Option<PciIds> {
+    let content = fs::read_to_string(cache_dir()?.join(PCI_IDS_FILE)).ok()?;
+    let ids = PciIds::parse(&content);
+    (ids.device_count() > 0).then_some(ids)
+}
+
+/// The downloaded spec database, warning and ignoring it if it is broken
+pub fn cached_specs() -> Option<SpecDb> {
+    let path = cache_dir()?.join(SPECS_FILE);
+    let content = fs::read_to_string(&path).ok()?;
+    SpecDb::parse(&content)
+        .map_err(|e| eprintln!("Warning: ignoring {}: {:#}", path.display(), e))
+        .ok()
+}
+
+/// Fetch a URL with curl, which also handles file:// URLs for offline mirrors
+fn download(url: &str) -> Result<String> {
+    let output = Command::new("curl")
+        .args(["-fsSL", "--max-time", "60", url])
+        .output()
+        .context("Failed to run curl (is it installed?)")?;
+    if !output.status.success() {
+        bail!(
+            "Failed to download {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    String::from_utf8(output.stdout).with_context(|| format!("{} is not UTF-8 text", url))
+}
+
+/// Write a file through a temporary sibling so readers never see half of it
+fn replace(path: &Path, content: &str) -> Result<()> {
+    let partial = path.with_extension("partial");
+    fs::write(&partial, content).with_context(|| format!("Failed to write {}", partial.display()))?;
+    fs::rename(&partial, path).with_context(|| format!("Failed to replace {}", path.display()))
+}
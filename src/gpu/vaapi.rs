@@ -0,0 +1,83 @@
+use crate::gpu::common::{GpuInfo, VaapiInfo};
+use crate::gpu::{drm, snapshot};
+
+/// Codec families VA-API profile names start with, e.g. "HEVCMain10"
+const CODEC_FAMILIES: [&str; 10] = ["MPEG2", "MPEG4", "H263", "H264", "HEVC", "VC1", "VP8", "VP9", "AV1", "JPEG"];
+
+/// Query the VA-API profiles of a GPU through its render node using `vainfo`
+pub fn query(gpu: &mut GpuInfo) {
+    let Some(ref pci_info) = gpu.pci_info else {
+        return;
+    };
+    let Some(render_node) = drm::render_node(&pci_info.address()) else {
+        return;
+    };
+
+    let render_node = render_node.to_string_lossy();
+    let output = snapshot::run("vainfo", &["--display", "drm", "--device", &render_node]);
+    if let Ok(output) = output {
+        if output.status.success() {
+            gpu.vaapi = parse_vainfo(&String::from_utf8_lossy(&output.stdout));
+        }
+    }
+}
+
+/// Parse `vainfo` output into decode and encode profiles
+///
+/// Profile lines look like `VAProfileH264High : VAEntrypointEncSlice`; the
+/// VLD entrypoint means decoding and the `Enc*` ones mean encoding.
+fn parse_vainfo(output: &str) -> Option<VaapiInfo> {
+    let mut info = VaapiInfo {
+        driver: None,
+        decode: Vec::new(),
+        encode: Vec::new(),
+    };
+
+    for line in output.lines() {
+        if let Some(driver) = line.split_once("Driver version:").map(|(_, driver)| driver.trim()) {
+            info.driver = Some(driver.to_string());
+            continue;
+        }
+
+        let Some((profile, entrypoint)) = line.split_once(':') else {
+            continue;
+        };
+        let Some(profile) = profile.trim().strip_prefix("VAProfile") else {
+            continue;
+        };
+        if profile == "None" {
+            continue;
+        }
+
+        let profiles = match entrypoint.trim() {
+            "VAEntrypointVLD" => &mut info.decode,
+            entrypoint if entrypoint.starts_with("VAEntrypointEnc") => &mut info.encode,
+            _ => continue,
+        };
+        if !profiles.iter().any(|p| p == profile) {
+            profiles.push(profile.to_string());
+        }
+    }
+
+    if info.driver.is_none() && info.decode.is_empty() && info.encode.is_empty() {
+        None
+    } else {
+        Some(info)
+    }
+}
+
+/// Codec families covered by a list of profiles, in first-seen order
+pub fn codec_families(profiles: &[String]) -> Vec<&str> {
+    let mut families: Vec<&str> = Vec::new();
+    for profile in profiles {
+        let family = CODEC_FAMILIES
+            .iter()
+            .copied()
+            .find(|family| profile.starts_with(family))
+            .unwrap_or(profile.as_str());
+        if !families.contains(&family) {
+            families.push(family);
+        }
+    }
+    families
+}
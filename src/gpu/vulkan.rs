@@ -0,0 +1,94 @@
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::error::GpufetchError;
+use crate::gpu::backend::Backend;
+use crate::gpu::common::GpuInfo;
+use crate::gpu::{pci, snapshot, specs};
+
+/// Detect GPUs from the physical devices listed by `vulkaninfo --summary`
+///
+/// Devices are matched to sysfs PCI entries by vendor and device ID, the nth
+/// Vulkan device with a pair of IDs taking the nth PCI device with them.
+/// Software rasterizers such as llvmpipe are skipped.
+pub fn detect_vulkan_gpus() -> Result<Vec<GpuInfo>> {
+    let output = snapshot::run("vulkaninfo", &["--summary"])
+        .map_err(|_| GpufetchError::unavailable(Backend::Vulkan, "vulkaninfo is not installed"))?;
+    if !output.status.success() {
+        return Err(GpufetchError::unavailable(Backend::Vulkan, "vulkaninfo found no Vulkan driver").into());
+    }
+
+    let pci_devices = pci::list_pci_devices();
+    let mut claimed: HashMap<(u16, u16), usize> = HashMap::new();
+    let mut gpus = Vec::new();
+
+    for device in parse_summary(&String::from_utf8_lossy(&output.stdout)) {
+        if device.get("deviceType").is_some_and(|kind| kind.ends_with("_CPU")) {
+            continue;
+        }
+        let (Some(name), Some(vendor_id), Some(device_id)) = (
+            device.get("deviceName"),
+            device.get("vendorID").and_then(|id| parse_hex(id)),
+            device.get("deviceID").and_then(|id| parse_hex(id)),
+        ) else {
+            continue;
+        };
+
+        let mut gpu_info = GpuInfo::new(strip_driver_suffix(name), pci::vendor_from_id(vendor_id));
+        gpu_info.driver_version = device.get("driverInfo").or_else(|| device.get("driverVersion")).cloned();
+        gpu_info.is_integrated = device.get("deviceType").is_some_and(|kind| kind.ends_with("_INTEGRATED_GPU"));
+
+        let seen = claimed.entry((vendor_id, device_id)).or_insert(0);
+        gpu_info.pci_info = pci_devices
+            .iter()
+            .filter(|info| info.vendor_id == vendor_id && info.device_id == device_id)
+            .nth(*seen)
+            .cloned();
+        *seen += 1;
+
+        specs::apply_specs(&mut gpu_info);
+        gpus.push(gpu_info);
+    }
+
+    Ok(gpus)
+}
+
+/// The "key = value" properties of each "GPUn:" block
+fn parse_summary(output: &str) -> Vec<HashMap<String, String>> {
+    let mut devices: Vec<HashMap<String, String>> = Vec::new();
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("GPU") && trimmed.ends_with(':') {
+            devices.push(HashMap::new());
+        } else if let (Some(device), Some((key, value))) = (devices.last_mut(), trimmed.split_once(" = ")) {
+            device.insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    devices
+}
+
+/// Parse a "0x1002" style ID
+fn parse_hex(id: &str) -> Option<u16> {
+    u16::from_str_radix(id.trim_start_matches("0x"), 16).ok()
+}
+
+/// Drop the driver's tag from names like "AMD Radeon RX 6700 XT (RADV NAVI22)"
+fn strip_driver_suffix(name: &str) -> &str {
+    if !name.ends_with(')') {
+        return name;
+    }
+
+    // Find the parenthesis opening the last group, which may nest others
+    let mut depth = 0;
+    for (index, c) in name.char_indices().rev() {
+        match c {
+            ')' => depth += 1,
+            '(' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return name[..index].trim_end();
+        }
+    }
+    name
+}
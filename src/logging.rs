@@ -0,0 +1,53 @@
+//! Diagnostic logging for `-v` and `--log-file`.
+//!
+//! Detection code reports what it tried through `tracing`; nothing is
+//! printed unless asked for, so normal output is unaffected.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::level_filters::LevelFilter;
+
+use crate::display::color_policy::ColorPolicy;
+
+/// Level for a number of `-v` flags: backends tried and why they failed at
+/// one, per-GPU details at two, everything at three
+fn level(verbosity: u8) -> LevelFilter {
+    match verbosity {
+        0 => LevelFilter::WARN,
+        1 => LevelFilter::INFO,
+        2 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+/// Send log events to stderr, colored as `color` decides, or to `log_file`
+///
+/// A log file records at least debug level, since it is meant to be attached
+/// to bug reports.
+pub fn init(verbosity: u8, log_file: Option<&Path>, color: ColorPolicy) -> Result<()> {
+    let builder = tracing_subscriber::fmt().with_target(false);
+
+    match log_file {
+        Some(path) => {
+            let file = File::create(path).with_context(|| format!("Failed to create log file {}", path.display()))?;
+            builder
+                .with_max_level(level(verbosity).max(LevelFilter::DEBUG))
+                .with_ansi(false)
+                .with_writer(Mutex::new(file))
+                .init();
+        }
+        None => {
+            builder
+                .with_max_level(level(verbosity))
+                .without_time()
+                .with_ansi(color.is_enabled())
+                .with_writer(io::stderr)
+                .init();
+        }
+    }
+
+    Ok(())
+}
@@ -1,59 +1,520 @@
+mod alerts;
 mod args;
+#[cfg(feature = "opencl")]
+mod bench;
+mod config;
+mod daemon;
 mod display;
+mod error;
 mod gpu;
+mod logging;
+mod pipeline;
+mod privacy;
+mod session;
+mod suggestions;
+mod state;
+mod system;
+mod units;
 mod utils;
+mod watch;
 
+use alerts::Alerts;
 use anyhow::{Context, Result};
-use args::Args;
-use clap::Parser;
-use display::print_gpufetch;
+use args::{Args, Command, DbCommand, OutputFormat};
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, ValueEnum};
+use config::Config;
+use display::color_policy::ColorPolicy;
+use display::image_logo::{ImageLogo, ImageStyle};
+use display::oneline::OnelineField;
+use display::terminal::{Charset, ColorDepth, TerminalCaps};
+use display::{print_gpufetch, CustomLogos, DisplayOptions, Field};
+use error::GpufetchError;
+use gpu::common::GpuInfo;
+use gpu::selector::GpuSelector;
+use gpu::snapshot::{self, TempDir};
+use gpu::specs::{GpuSpec, SpecDb};
 use gpu::GpuManager;
+use pipeline::Pipeline;
+use session::SessionInfo;
+use suggestions::Distro;
+use state::{DefaultGpu, State};
+use system::SystemInfo;
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Duration;
 
-fn main() -> Result<()> {
+fn main() -> ExitCode {
+    match run() {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<ExitCode> {
     // Parse command line arguments
     let args = Args::parse();
-    
+    // A snapshot keeps the files and tool output as they were, serials and
+    // UUIDs included, so there is nothing to mask them in
+    if args.anonymize && matches!(args.command, Some(Command::Dump { .. })) {
+        Args::command()
+            .error(ErrorKind::ArgumentConflict, "--anonymize cannot be used with `gpufetch dump`, whose snapshot holds the unmasked sysfs files and tool output; check the tarball before sharing it publicly")
+            .exit();
+    }
+    // Decide once what the terminal can show; every printer goes through `colored`
+    let mut terminal = TerminalCaps::detect();
+    if args.ascii {
+        terminal.charset = Charset::Ascii;
+    }
+    ColorPolicy::for_stdout(args.no_color, terminal.color_depth).apply_globally();
+    logging::init(args.verbose, args.log_file.as_deref(), ColorPolicy::for_stderr(args.no_color, terminal.color_depth))?;
+    if let Some(ref root) = args.sysfs_root {
+        gpu::sysroot::set_root(root.clone());
+    }
+
+    // Both have to be in place before anything reads sysfs or runs a tool
+    let _replay = args.replay.as_deref().map(snapshot::start_replay).transpose()?;
+    let recording = match args.command {
+        Some(Command::Dump { .. }) => Some(snapshot::start_recording()?),
+        _ => None,
+    };
+
+    // Clearing the default GPU and querying the database do not need detection
+    match args.command {
+        Some(Command::Use { clear: true, .. }) => return clear_default_gpu().map(|()| ExitCode::SUCCESS),
+        Some(Command::Db { ref action }) => return run_db_command(action).map(|()| ExitCode::SUCCESS),
+        Some(Command::UpdateDb { ref pci_ids_url, ref specs_url }) => {
+            return update_databases(pci_ids_url, specs_url).map(|()| ExitCode::SUCCESS)
+        }
+        _ => {}
+    }
+
     // Initialize the GPU manager
-    let gpu_manager = GpuManager::new().context("Failed to initialize GPU manager")?;
-    
+    let mut gpu_manager = GpuManager::new().context("Failed to initialize GPU manager")?;
+    if let Some(selection) = args.backend.clone() {
+        gpu_manager = gpu_manager.with_backends(selection.0);
+    }
+
+    let config = Config::load_or_warn();
+    let units = args.units(config.units);
+    let pipeline = Pipeline::new(&args)?;
+
+    // The daemon detects again on its own schedule and answers from the last result
+    if args.daemon || args.serve.is_some() {
+        let socket = args.daemon.then(|| args.socket.clone().unwrap_or_else(daemon::default_socket_path));
+        let interval = Duration::from_secs(args.refresh_interval);
+        let alerts = Alerts::new(&config.alerts);
+        return daemon::run(gpu_manager, pipeline, units, alerts, socket.as_deref(), args.serve, interval)
+            .map(|()| ExitCode::SUCCESS);
+    }
+
     // Detect available GPUs
-    let gpus = gpu_manager.detect_gpus().context("Failed to detect GPUs")?;
-    
+    let detection = match gpu_manager.detect_gpus() {
+        Ok(detection) => detection,
+        Err(e) => return Ok(e.report(&[], args.error_format)),
+    };
+
+    // A dump is most useful when detection went wrong, so it does not need GPUs
+    if let (Some(Command::Dump { ref output }), Some(ref recording)) = (&args.command, &recording) {
+        return dump_snapshot(recording, detection.gpus, output).map(|()| ExitCode::SUCCESS);
+    }
+
+    let mut gpus = detection.gpus;
+
+    if gpus.is_empty() {
+        return Ok(GpufetchError::NoGpus { filtered: false }.report(&detection.diagnostics, args.error_format));
+    }
+
+    pipeline.narrow(&mut gpus);
     if gpus.is_empty() {
-        println!("No GPUs detected on the system");
-        return Ok(());
+        return Ok(GpufetchError::NoGpus { filtered: true }.report(&[], args.error_format));
+    }
+
+    // Let scripts gate on the hardware: one GPU has to meet every requirement
+    if !args.require.is_empty() {
+        let vulkan_icds = gpu::icd::vulkan_manifest_names();
+        let met = gpus
+            .iter()
+            .any(|gpu| args.require.iter().all(|requirement| requirement.is_met_by(gpu, &vulkan_icds)));
+        if !met {
+            let requirements = args
+                .require
+                .iter()
+                .filter_map(|requirement| requirement.to_possible_value())
+                .map(|value| value.get_name().to_string())
+                .collect();
+            return Ok(GpufetchError::RequirementUnmet { requirements }.report(&[], args.error_format));
+        }
+    }
+
+    if let Some(Command::Use { selector: Some(ref selector), .. }) = args.command {
+        return set_default_gpu(&gpus, selector).map(|()| ExitCode::SUCCESS);
+    }
+
+    // Mask identifiers before anything is printed
+    let mut system = args.system_info.then(SystemInfo::detect);
+    if let Some(anonymizer) = pipeline.anonymizer() {
+        for gpu in &mut gpus {
+            anonymizer.apply(gpu);
+        }
+        if let Some(ref mut system) = system {
+            system.anonymize(anonymizer);
+        }
     }
-    
+
+    pipeline.query_extras(&mut gpus);
+
+    if args.passthrough {
+        display::passthrough::print_passthrough_report(&gpus);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if args.topology {
+        display::pcie_tree::print_pcie_tree(&gpus, terminal.charset);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if args.output == OutputFormat::Csv {
+        display::csv::print_csv(&gpus, units);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if args.output == OutputFormat::Json {
+        display::json::print_json(&gpus, units)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
     // If list-only is specified, just list available GPUs and exit
     if args.list_only {
         println!("Detected GPUs:");
         for (idx, gpu) in gpus.iter().enumerate() {
-            println!("{}: {} ({})", idx, gpu.name, gpu.vendor);
+            let mut tags = Vec::new();
+            if let Some(ref physical_function) = gpu.physical_function {
+                tags.push(format!("VF of {}", physical_function));
+            }
+            if let Some(ref hybrid) = gpu.hybrid {
+                tags.push(hybrid.label().to_string());
+            }
+
+            if tags.is_empty() {
+                println!("{}: {} ({})", idx, gpu.name, gpu.vendor);
+            } else {
+                println!("{}: {} ({}) [{}]", idx, gpu.name, gpu.vendor, tags.join(", "));
+            }
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let logo_image = match args.logo_image {
+        Some(ref path) => {
+            let style = ImageStyle {
+                charset: terminal.charset,
+                color_depth: terminal.color_depth,
+                color: colored::control::SHOULD_COLORIZE.should_colorize(),
+            };
+            Some(ImageLogo::load(path, args.logo_width, style)?)
         }
-        return Ok(());
+        None => None,
+    };
+
+    let display_options = DisplayOptions {
+        color_scheme: args.color_scheme,
+        custom_colors: args.custom_colors.clone(),
+        logo_variant: args.logo_variant,
+        layout: args.layout,
+        detailed: args.detailed,
+        frame: args.frame,
+        fields: args
+            .fields
+            .clone()
+            .or_else(|| config.display.fields.clone())
+            .unwrap_or_else(|| Field::DEFAULT.to_vec()),
+        charset: terminal.charset,
+        color_depth: terminal.color_depth,
+        session: if args.session { SessionInfo::detect() } else { None },
+        system,
+        suggest: args.suggest,
+        distro: if args.suggest { Distro::detect() } else { None },
+        custom_logos: CustomLogos::load(args.logo_file.as_deref(), logo_image, &config)?,
+        // Auto-detected protocols are only trusted when drawing on a terminal
+        graphics: args
+            .logo_mode
+            .protocol(terminal.graphics.filter(|_| io::stdout().is_terminal())),
+        units,
+        memory_bar: config.display.memory_bar.unwrap_or(true),
+    };
+
+    let document = matches!(args.output, OutputFormat::Markdown | OutputFormat::Html);
+    if args.summary && !document {
+        display::summary::print_summary(&gpus, &display_options);
+        let show_all = args.compare || args.gpu_index.is_some_and(|idx| idx < 0);
+        if !show_all || gpus.len() < 2 {
+            return Ok(ExitCode::SUCCESS);
+        }
+        println!();
+    }
+    if args.compare && !document {
+        display::compare::print_comparison(&gpus, &display_options);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Bare invocations show the GPU recorded with `gpufetch use`
+    let gpu_index = match (&args.gpu, args.gpu_index) {
+        (Some(selector), _) => selector.resolve(&gpus)? as i32,
+        (None, Some(idx)) => idx,
+        (None, None) => default_gpu_index(&gpus),
+    };
+
+    if args.oneline {
+        let fields = args.oneline_fields.as_deref().unwrap_or(&OnelineField::DEFAULT);
+        display::oneline::print_oneline(selected_gpus(&gpus, gpu_index), fields, units, terminal.charset, !args.no_newline)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if args.diagram {
+        for (idx, gpu) in selected_gpus(&gpus, gpu_index).iter().enumerate() {
+            if idx > 0 {
+                println!();
+            }
+            display::diagram::print_diagram(gpu, &display_options);
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if args.watch {
+        // Refreshes filter, sort and anonymize as the first detection did
+        let detect = || -> Result<Vec<GpuInfo>> {
+            match gpu_manager.detect_gpus().and_then(|detection| pipeline.refine(detection.gpus)) {
+                // Every GPU shown is detached until one comes back
+                Err(GpufetchError::NoGpus { .. }) => Ok(Vec::new()),
+                result => Ok(result?),
+            }
+        };
+        let selected = selected_gpus(&gpus, gpu_index).to_vec();
+        // GPUs attached later join the comparison, not a single GPU's card
+        let add_new = gpu_index < 0;
+        let interval = Duration::from_secs(args.refresh_interval);
+        let alerts = Alerts::new(&config.alerts);
+        return watch::run(selected, add_new, &display_options, interval, args.record.as_deref(), alerts, detect)
+            .map(|()| ExitCode::SUCCESS);
+    }
+
+    // Benchmark the selected GPU, or each of them one after the other
+    if let Some(Command::Bench) = args.command {
+        let selected = match usize::try_from(gpu_index) {
+            Ok(idx) => gpus.get(idx..=idx).context("GPU index out of range")?,
+            Err(_) => &gpus[..],
+        };
+        return run_bench(selected).map(|()| ExitCode::SUCCESS);
     }
 
     // Choose which GPU to display
-    let gpu_idx = if args.gpu_index >= 0 && args.gpu_index < gpus.len() as i32 {
-        args.gpu_index as usize
-    } else if args.gpu_index >= 0 {
-        println!("GPU index {} out of range, falling back to GPU 0", args.gpu_index);
+    let gpu_idx = if gpu_index >= 0 && gpu_index < gpus.len() as i32 {
+        gpu_index as usize
+    } else if gpu_index >= 0 {
+        println!("GPU index {} out of range, falling back to GPU 0", gpu_index);
         0
+    } else if gpus.len() > 1 {
+        // Negative values mean show all GPUs, side by side
+        if document {
+            display::document::print_document(&gpus, args.output, &display_options, args.embed_logo)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        display::compare::print_comparison(&gpus, &display_options);
+        return Ok(ExitCode::SUCCESS);
     } else {
-        // Negative values mean show all GPUs
-        for (idx, gpu) in gpus.iter().enumerate() {
-            print_gpufetch(gpu, args.color_scheme.clone(), args.logo_variant)?;
-            
-            // Print separator between GPUs
-            if idx < gpus.len() - 1 {
-                println!("\n{}\n", "-".repeat(40));
+        // All of a single GPU is just that GPU
+        0
+    };
+
+    if let Some(ref path) = args.screenshot {
+        return save_screenshot(&gpus[gpu_idx], display_options, args.no_color, args.ascii, path).map(|()| ExitCode::SUCCESS);
+    }
+
+    // Display information about the selected GPU
+    if document {
+        let gpu = std::slice::from_ref(&gpus[gpu_idx]);
+        display::document::print_document(gpu, args.output, &display_options, args.embed_logo)?;
+    } else {
+        print_gpufetch(&gpus[gpu_idx], &display_options)?;
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The GPU at `gpu_index`, GPU 0 when it is out of range, or all of them
+/// for negative indices
+fn selected_gpus(gpus: &[GpuInfo], gpu_index: i32) -> &[GpuInfo] {
+    match usize::try_from(gpu_index) {
+        Ok(idx) => gpus.get(idx..=idx).unwrap_or(&gpus[..1]),
+        Err(_) => gpus,
+    }
+}
+
+/// Run the benchmark on each GPU and print the results
+#[cfg(feature = "opencl")]
+fn run_bench(gpus: &[GpuInfo]) -> Result<()> {
+    for (idx, gpu) in gpus.iter().enumerate() {
+        if idx > 0 {
+            println!();
+        }
+        let result = bench::run(gpu).with_context(|| format!("Failed to benchmark {}", gpu.name))?;
+        display::bench::print_bench(gpu, &result);
+    }
+    Ok(())
+}
+
+/// `bench` runs on OpenCL, which this build left out
+#[cfg(not(feature = "opencl"))]
+fn run_bench(_gpus: &[GpuInfo]) -> Result<()> {
+    anyhow::bail!("gpufetch was built without the \"opencl\" feature, which bench needs")
+}
+
+/// Resolve a selector and record the GPU as the default in the state file
+fn set_default_gpu(gpus: &[GpuInfo], selector: &str) -> Result<()> {
+    let selector: GpuSelector = selector.parse()?;
+    let idx = selector.resolve(gpus)?;
+    let default_gpu = DefaultGpu::from_gpu(&gpus[idx]);
+
+    let mut state = State::load()?;
+    state.default_gpu = Some(default_gpu.clone());
+    state.save()?;
+
+    match default_gpu.pci_address {
+        Some(address) => println!("Default GPU set to {}: {} [{}]", idx, default_gpu.name, address),
+        None => println!("Default GPU set to {}: {}", idx, default_gpu.name),
+    }
+
+    Ok(())
+}
+
+/// Remove the default GPU from the state file
+fn clear_default_gpu() -> Result<()> {
+    let mut state = State::load()?;
+    if state.default_gpu.take().is_some() {
+        state.save()?;
+        println!("Default GPU cleared");
+    } else {
+        println!("No default GPU set");
+    }
+
+    Ok(())
+}
+
+/// Index of the GPU recorded with `gpufetch use`, or 0 if none is usable
+fn default_gpu_index(gpus: &[GpuInfo]) -> i32 {
+    let state = match State::load() {
+        Ok(state) => state,
+        Err(e) => {
+            eprintln!("Warning: ignoring unreadable state file: {:#}", e);
+            return 0;
+        }
+    };
+
+    match state.default_gpu {
+        Some(default_gpu) => match default_gpu.find(gpus) {
+            Some(idx) => idx as i32,
+            None => {
+                eprintln!("Default GPU \"{}\" not found, falling back to GPU 0", default_gpu.name);
+                0
+            }
+        },
+        None => 0,
+    }
+}
+
+/// Print entries from the spec database
+fn run_db_command(action: &DbCommand) -> Result<()> {
+    let db = SpecDb::active();
+
+    match action {
+        DbCommand::Dump { vendor } => {
+            let dump = SpecDb {
+                gpu: db
+                    .gpu
+                    .iter()
+                    .filter(|spec| vendor.is_none_or(|v| spec.is_vendor(&v.vendor())))
+                    .cloned()
+                    .collect(),
+                chip: db
+                    .chip
+                    .iter()
+                    .filter(|chip| vendor.is_none_or(|v| chip.vendor.eq_ignore_ascii_case(&v.vendor().to_string())))
+                    .cloned()
+                    .collect(),
+            };
+            if dump.gpu.is_empty() {
+                println!("No database entries for this vendor");
+            } else {
+                print!("{}", dump.to_toml()?);
             }
         }
-        return Ok(());
+        DbCommand::Search { query } => {
+            let matches = db.search(query);
+            if matches.is_empty() {
+                println!("No database entries match \"{}\"", query);
+            }
+            for spec in matches {
+                println!("{}", describe_spec(spec));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Write a `gpufetch dump` snapshot, with VA-API profiles queried so they are recorded too
+fn dump_snapshot(recording: &TempDir, mut gpus: Vec<GpuInfo>, output: &Path) -> Result<()> {
+    for gpu in &mut gpus {
+        gpu::vaapi::query(gpu);
+    }
+    snapshot::finish_recording(recording, &gpus, output)?;
+    println!("Wrote {} ({} GPUs); attach it to your bug report", output.display(), gpus.len());
+    Ok(())
+}
+
+/// Render the card without terminal limitations and save it as a picture
+///
+/// Only `--ascii` keeps the picture to ASCII.
+fn save_screenshot(gpu: &GpuInfo, options: DisplayOptions, no_color: bool, ascii: bool, path: &Path) -> Result<()> {
+    let options = DisplayOptions {
+        charset: if ascii { Charset::Ascii } else { Charset::Utf8 },
+        color_depth: ColorDepth::TrueColor,
+        graphics: None,
+        ..options
     };
-    
-    // Display information about the selected GPU
-    print_gpufetch(&gpus[gpu_idx], args.color_scheme, args.logo_variant)?;
-    
+    ColorPolicy::for_picture(no_color).apply_globally();
+    display::screenshot::save(&display::card_lines(gpu, &options), path)?;
+    println!("Saved screenshot to {}", path.display());
     Ok(())
 }
+
+/// Download pci.ids and the spec database into the cache
+fn update_databases(pci_ids_url: &str, specs_url: &str) -> Result<()> {
+    let summary = gpu::update::update(pci_ids_url, specs_url)?;
+    println!("Updated databases in {}", summary.dir.display());
+    println!("  pci.ids:    {} devices", summary.pci_devices);
+    println!("  specs.toml: {} GPUs", summary.spec_entries);
+    Ok(())
+}
+
+/// One-line summary of a database entry for search results
+fn describe_spec(spec: &GpuSpec) -> String {
+    let mut details: Vec<String> = [spec.fields.chip.as_deref(), spec.fields.architecture.as_deref()]
+        .into_iter()
+        .flatten()
+        .map(String::from)
+        .collect();
+    if !spec.device_ids.is_empty() {
+        details.push(format!("device {}", spec.device_ids.join(", ")));
+    }
+    if !spec.revisions.is_empty() {
+        details.push(format!("revision {}", spec.revisions.join(", ")));
+    }
+
+    format!("{:<6} {} ({})", spec.vendor, spec.name, details.join(", "))
+}
@@ -0,0 +1,121 @@
+//! What happens to detected GPUs before anything shows them.
+//!
+//! The first detection and every refresh of `--watch` and `--daemon` go
+//! through the same steps, so a flag means the same in each mode:
+//!
+//! 1. `--no-heuristics` drops the guessed values
+//! 2. `--vendor` and `--only` drop the GPUs that don't match
+//! 3. `--sort` orders the rest
+//! 4. `--anonymize` masks identifiers
+//! 5. `--vaapi` and `--detailed` query their extras, which identifiers
+//!    don't go into
+
+use anyhow::{Context, Result};
+
+use crate::args::{Args, KindFilter, SortKey, VendorFilter};
+use crate::error::GpufetchError;
+use crate::gpu;
+use crate::gpu::common::GpuInfo;
+use crate::privacy::Anonymizer;
+
+/// The flags that shape the list of GPUs, apart from the rest of the
+/// arguments so refresh threads can keep them
+pub struct Pipeline {
+    no_heuristics: bool,
+    vendor: Option<VendorFilter>,
+    only: Option<KindFilter>,
+    sort: Option<SortKey>,
+    vaapi: bool,
+    detailed: bool,
+    anonymizer: Option<Anonymizer>,
+}
+
+impl Pipeline {
+    /// The steps `args` ask for, loading the anonymization salt if
+    /// `--anonymize` is one of them
+    pub fn new(args: &Args) -> Result<Self> {
+        let anonymizer = if args.anonymize {
+            Some(Anonymizer::load().context("Failed to set up anonymization")?)
+        } else {
+            None
+        };
+        Ok(Pipeline {
+            no_heuristics: args.no_heuristics,
+            vendor: args.vendor,
+            only: args.only,
+            sort: args.sort,
+            vaapi: args.vaapi,
+            detailed: args.detailed,
+            anonymizer,
+        })
+    }
+
+    /// Steps 1 to 3: keep what was measured of the matching GPUs, in order
+    ///
+    /// Indices count only the GPUs left, so this comes before anything
+    /// picks from the list.
+    pub fn narrow(&self, gpus: &mut Vec<GpuInfo>) {
+        // Keep bug reports to what the system actually reported
+        if self.no_heuristics {
+            for gpu in gpus.iter_mut() {
+                gpu.retain_measured();
+            }
+        }
+        if self.is_filtering() {
+            gpus.retain(|gpu| {
+                self.vendor.is_none_or(|vendor| gpu.vendor == vendor.vendor())
+                    && self.only.is_none_or(|kind| kind.matches(gpu))
+            });
+        }
+        if let Some(key) = self.sort {
+            key.sort(gpus);
+        }
+    }
+
+    /// Step 4, which the first detection also applies to the system header
+    pub fn anonymizer(&self) -> Option<&Anonymizer> {
+        self.anonymizer.as_ref()
+    }
+
+    /// Whether `--vendor` or `--only` may leave GPUs out
+    pub fn is_filtering(&self) -> bool {
+        self.vendor.is_some() || self.only.is_some()
+    }
+
+    /// Step 5: ask the sources only some views show, codecs, engine load and
+    /// OpenGL
+    ///
+    /// Codec support is part of the detailed view. Sampling engine load takes
+    /// a second and loading the OpenGL drivers a fraction of one, so only the
+    /// detailed view waits for them.
+    pub fn query_extras(&self, gpus: &mut [GpuInfo]) {
+        if self.vaapi || self.detailed {
+            for gpu in gpus.iter_mut() {
+                gpu::vaapi::query(gpu);
+            }
+        }
+
+        if self.detailed {
+            for gpu in gpus.iter_mut() {
+                gpu::intel_gpu_top::query(gpu);
+            }
+            gpu::egl::query(gpus);
+        }
+    }
+
+    /// Every step, for a detection a refresh made
+    pub fn refine(&self, mut gpus: Vec<GpuInfo>) -> Result<Vec<GpuInfo>, GpufetchError> {
+        if gpus.is_empty() {
+            return Err(GpufetchError::NoGpus { filtered: false });
+        }
+        self.narrow(&mut gpus);
+        if gpus.is_empty() {
+            return Err(GpufetchError::NoGpus { filtered: true });
+        }
+        if let Some(ref anonymizer) = self.anonymizer {
+            gpus.iter_mut().for_each(|gpu| anonymizer.apply(gpu));
+        }
+        self.query_extras(&mut gpus);
+        Ok(gpus)
+    }
+}
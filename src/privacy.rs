@@ -0,0 +1,116 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::Read;
+
+use crate::gpu::common::GpuInfo;
+use crate::gpu::sysroot;
+use crate::state::State;
+
+/// Replaces identifying values with stable, salted tokens
+///
+/// The salt is generated once per machine and kept in the state file, so the
+/// same UUID always maps to the same token (making multi-GPU output and
+/// repeated dumps comparable) while the original cannot be recovered from
+/// shared output.
+pub struct Anonymizer {
+    salt: String,
+    hostname: Option<String>,
+}
+
+impl Anonymizer {
+    /// Load the per-machine salt, creating and saving one on first use
+    pub fn load() -> Result<Self> {
+        let mut state = State::load()?;
+        let salt = match state.anonymize_salt {
+            Some(ref salt) => salt.clone(),
+            None => {
+                let salt = generate_salt()?;
+                state.anonymize_salt = Some(salt.clone());
+                state.save()?;
+                salt
+            }
+        };
+
+        let hostname = fs::read_to_string(sysroot::path("/proc/sys/kernel/hostname"))
+            .ok()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty());
+
+        Ok(Anonymizer { salt, hostname })
+    }
+
+    /// Mask every identifier in a GPU's information
+    pub fn apply(&self, gpu: &mut GpuInfo) {
+        gpu.uuid = gpu.uuid.as_deref().map(|uuid| self.mask_uuid(uuid));
+        gpu.serial = gpu.serial.as_deref().map(|serial| self.mask_serial(serial));
+
+        // Free-form strings reported by drivers and tools may embed the hostname
+        gpu.name = self.scrub(&gpu.name);
+        gpu.driver_version = gpu.driver_version.as_deref().map(|driver| self.scrub(driver));
+    }
+
+    /// Mask a UUID, keeping its vendor prefix (e.g. "GPU-") and shape
+    pub fn mask_uuid(&self, uuid: &str) -> String {
+        let prefix = match uuid.split_once('-') {
+            Some((prefix, _)) if prefix.len() <= 4 && prefix.chars().all(|c| c.is_ascii_uppercase()) => {
+                &uuid[..=prefix.len()]
+            }
+            _ => "",
+        };
+        let hash = self.digest("uuid", uuid);
+        format!(
+            "{}{}-{}-{}-{}-{}",
+            prefix,
+            &hash[0..8],
+            &hash[8..12],
+            &hash[12..16],
+            &hash[16..20],
+            &hash[20..32]
+        )
+    }
+
+    /// Mask a board or chip serial number
+    pub fn mask_serial(&self, serial: &str) -> String {
+        format!("ANON{}", self.digest("serial", serial)[..12].to_uppercase())
+    }
+
+    /// Mask a hostname
+    pub fn mask_hostname(&self, hostname: &str) -> String {
+        format!("host-{}", &self.digest("hostname", hostname)[..8])
+    }
+
+    /// Replace occurrences of the local hostname in free-form text
+    pub fn scrub(&self, text: &str) -> String {
+        match self.hostname {
+            Some(ref hostname) if text.contains(hostname.as_str()) => {
+                text.replace(hostname.as_str(), &self.mask_hostname(hostname))
+            }
+            _ => text.to_string(),
+        }
+    }
+
+    /// Hex digest of a value, domain-separated by the kind of identifier
+    fn digest(&self, kind: &str, value: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.salt.as_bytes());
+        hasher.update([0]);
+        hasher.update(kind.as_bytes());
+        hasher.update([0]);
+        hasher.update(value.as_bytes());
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+}
+
+/// Generate a random 128-bit salt as a hex string
+fn generate_salt() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    File::open("/dev/urandom")
+        .and_then(|mut urandom| urandom.read_exact(&mut bytes))
+        .context("Failed to read /dev/urandom")?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
@@ -0,0 +1,134 @@
+use std::env;
+use std::fmt;
+use std::fs;
+
+use crate::gpu::{drm, sysroot};
+
+/// Compositors and window managers recognised by process name, with display names
+const COMPOSITORS: [(&str, &str); 16] = [
+    ("gnome-shell", "GNOME Shell"),
+    ("kwin_wayland", "KWin"),
+    ("kwin_x11", "KWin"),
+    ("sway", "Sway"),
+    ("Hyprland", "Hyprland"),
+    ("weston", "Weston"),
+    ("wayfire", "Wayfire"),
+    ("river", "river"),
+    ("labwc", "labwc"),
+    ("niri", "niri"),
+    ("cosmic-comp", "COSMIC"),
+    ("gamescope", "gamescope"),
+    ("mutter", "Mutter"),
+    ("xfwm4", "Xfwm4"),
+    ("picom", "picom"),
+    ("compiz", "Compiz"),
+];
+
+/// Name of the X server process, which does the rendering on X11
+const X_SERVER: &str = "Xorg";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionKind {
+    Wayland,
+    X11,
+}
+
+impl fmt::Display for SessionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionKind::Wayland => write!(f, "Wayland"),
+            SessionKind::X11 => write!(f, "X11"),
+        }
+    }
+}
+
+/// The graphical session gpufetch runs in
+#[derive(Debug, Clone)]
+pub struct SessionInfo {
+    pub kind: SessionKind,
+    pub compositor: Option<String>,
+    /// PCI addresses of the GPUs the compositor (or X server) has open
+    pub render_addresses: Vec<String>,
+}
+
+impl SessionInfo {
+    /// Inspect the environment and running processes; `None` outside a graphical session
+    pub fn detect() -> Option<Self> {
+        let kind = session_kind()?;
+
+        let processes = graphics_processes();
+        let compositor = processes
+            .iter()
+            .find_map(|(_, comm)| COMPOSITORS.iter().find(|(name, _)| name == comm))
+            .map(|(_, display_name)| display_name.to_string());
+
+        // On Wayland the compositor renders; on X11 the X server does, with
+        // any compositing manager on top
+        let mut render_addresses: Vec<String> = processes
+            .iter()
+            .filter(|(_, comm)| kind == SessionKind::X11 || comm != X_SERVER)
+            .flat_map(|(pid, _)| open_gpu_addresses(*pid))
+            .collect();
+        render_addresses.sort();
+        render_addresses.dedup();
+
+        Some(SessionInfo {
+            kind,
+            compositor,
+            render_addresses,
+        })
+    }
+
+    /// Whether the session renders on the GPU at `address`
+    pub fn renders_on(&self, address: &str) -> bool {
+        self.render_addresses.iter().any(|a| a == address)
+    }
+}
+
+/// Session type from `XDG_SESSION_TYPE`, falling back to the display variables
+pub fn session_kind() -> Option<SessionKind> {
+    let var = |key: &str| env::var(key).ok().filter(|value| !value.is_empty());
+
+    match var("XDG_SESSION_TYPE").as_deref() {
+        Some("wayland") => Some(SessionKind::Wayland),
+        Some("x11") => Some(SessionKind::X11),
+        _ if var("WAYLAND_DISPLAY").is_some() => Some(SessionKind::Wayland),
+        _ if var("DISPLAY").is_some() => Some(SessionKind::X11),
+        _ => None,
+    }
+}
+
+/// Running compositors and X servers as (pid, process name)
+fn graphics_processes() -> Vec<(u32, String)> {
+    let Ok(entries) = fs::read_dir(sysroot::path("/proc")) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse::<u32>().ok()?;
+            let comm = fs::read_to_string(entry.path().join("comm")).ok()?;
+            let comm = comm.trim();
+            let known = comm == X_SERVER || COMPOSITORS.iter().any(|(name, _)| *name == comm);
+            known.then(|| (pid, comm.to_string()))
+        })
+        .collect()
+}
+
+/// PCI addresses of the DRM nodes a process has open
+///
+/// Only works for processes of the current user (or as root).
+fn open_gpu_addresses(pid: u32) -> Vec<String> {
+    let Ok(fds) = fs::read_dir(sysroot::path("/proc").join(pid.to_string()).join("fd")) else {
+        return Vec::new();
+    };
+
+    fds.flatten()
+        .filter_map(|fd| fs::read_link(fd.path()).ok())
+        .filter_map(|target| {
+            let node = target.strip_prefix("/dev/dri").ok()?.to_str()?.to_string();
+            drm::node_address(&node)
+        })
+        .collect()
+}
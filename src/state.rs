@@ -0,0 +1,83 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::gpu::common::GpuInfo;
+
+/// Persistent state shared between gpufetch invocations
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct State {
+    /// GPU shown by bare invocations, recorded with `gpufetch use`
+    pub default_gpu: Option<DefaultGpu>,
+
+    /// Per-machine salt for `--anonymize` tokens
+    pub anonymize_salt: Option<String>,
+}
+
+/// Identity of the GPU recorded as the default
+///
+/// The PCI address is preferred when matching since it is stable across
+/// reboots and distinguishes identical cards; the name is the fallback for
+/// backends that cannot report an address.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefaultGpu {
+    pub name: String,
+    pub pci_address: Option<String>,
+}
+
+impl DefaultGpu {
+    pub fn from_gpu(gpu: &GpuInfo) -> Self {
+        DefaultGpu {
+            name: gpu.name.clone(),
+            pci_address: gpu.pci_info.as_ref().map(|pci| pci.address()),
+        }
+    }
+
+    /// Find the recorded GPU among the detected ones
+    pub fn find(&self, gpus: &[GpuInfo]) -> Option<usize> {
+        if let Some(ref address) = self.pci_address {
+            let by_address = gpus.iter().position(|gpu| {
+                gpu.pci_info.as_ref().is_some_and(|pci| pci.address() == *address)
+            });
+            if by_address.is_some() {
+                return by_address;
+            }
+        }
+
+        gpus.iter().position(|gpu| gpu.name == self.name)
+    }
+}
+
+impl State {
+    /// Location of the state file (`$XDG_STATE_HOME/gpufetch/state.toml`)
+    pub fn path() -> Option<PathBuf> {
+        dirs::state_dir()
+            .or_else(dirs::data_local_dir)
+            .map(|dir| dir.join("gpufetch").join("state.toml"))
+    }
+
+    /// Load the state file, returning empty state if it does not exist yet
+    pub fn load() -> Result<Self> {
+        let path = match Self::path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(State::default()),
+        };
+
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Write the state file, creating its directory if needed
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path().context("Could not determine the state directory")?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+
+        let content = toml::to_string(self).context("Failed to serialize state")?;
+        fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
@@ -0,0 +1,184 @@
+//! Packages to install for GPUs without a working driver, for `--suggest`.
+//!
+//! A GPU with no kernel driver bound usually lacks the driver itself
+//! (NVIDIA) or the firmware the in-kernel driver needs before it binds (AMD,
+//! Intel). One bound to an open kernel driver without Mesa found lacks the
+//! userspace half. Package names follow the distribution family named in
+//! os-release.
+
+use std::fs;
+
+use crate::gpu::common::{GpuInfo, GpuVendor};
+use crate::gpu::sysroot;
+use crate::session::{self, SessionKind};
+use crate::system;
+
+/// NVIDIA architectures the open kernel modules support
+const OPEN_MODULE_ARCHITECTURES: [&str; 5] = ["Turing", "Ampere", "Ada Lovelace", "Hopper", "Blackwell"];
+
+/// Distribution families with known package names
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Distro {
+    Debian,
+    Ubuntu,
+    Fedora,
+    Arch,
+    OpenSuse,
+}
+
+impl Distro {
+    /// The family of the running distribution, from its os-release `ID`
+    /// and the `ID_LIKE` ones it derives from
+    pub fn detect() -> Option<Self> {
+        system::distro_ids().iter().find_map(|id| match id.as_str() {
+            "ubuntu" => Some(Distro::Ubuntu),
+            "debian" => Some(Distro::Debian),
+            "fedora" | "rhel" => Some(Distro::Fedora),
+            "arch" => Some(Distro::Arch),
+            id if id.starts_with("opensuse") || id == "suse" => Some(Distro::OpenSuse),
+            _ => None,
+        })
+    }
+
+    /// The command installing `packages`
+    fn install(&self, packages: &[&str]) -> String {
+        let command = match self {
+            Distro::Debian | Distro::Ubuntu => "sudo apt install",
+            Distro::Fedora => "sudo dnf install",
+            Distro::Arch => "sudo pacman -S",
+            Distro::OpenSuse => "sudo zypper install",
+        };
+        format!("{} {}", command, packages.join(" "))
+    }
+}
+
+/// What to install to get `gpu` working, most important first
+///
+/// Without a known distribution, the kind of package is named instead.
+pub fn suggest(gpu: &GpuInfo, distro: Option<Distro>) -> Vec<String> {
+    if gpu.pci_info.is_none() {
+        return Vec::new();
+    }
+
+    let mut suggestions = Vec::new();
+    match gpu.kernel_driver.as_deref() {
+        None => {
+            let suggestion = match gpu.vendor {
+                GpuVendor::Nvidia => Some(nvidia_driver(gpu, distro)),
+                GpuVendor::Amd => Some(firmware("amdgpu", distro, firmware_packages(GpuVendor::Amd, distro))),
+                GpuVendor::Intel => Some(firmware("i915", distro, firmware_packages(GpuVendor::Intel, distro))),
+                _ => None,
+            };
+            suggestions.extend(suggestion);
+            if let Some(framebuffer) = fallback_framebuffer() {
+                suggestions.push(format!(
+                    "until then the display runs on the firmware framebuffer ({}), without acceleration",
+                    framebuffer
+                ));
+            }
+        }
+        Some(driver @ ("amdgpu" | "radeon" | "i915" | "xe" | "nouveau"))
+            if gpu.mesa_version.is_none() && gpu.opengl_version.is_none() =>
+        {
+            let packages = mesa_packages(driver, distro);
+            suggestions.push(match distro {
+                Some(distro) => format!("install Mesa for OpenGL and Vulkan: {}", distro.install(&packages)),
+                None => "install Mesa for OpenGL and Vulkan".to_string(),
+            });
+        }
+        Some(_) => {}
+    }
+    suggestions
+}
+
+fn nvidia_driver(gpu: &GpuInfo, distro: Option<Distro>) -> String {
+    let open_modules = OPEN_MODULE_ARCHITECTURES.contains(&gpu.architecture.as_str());
+    match distro {
+        Some(Distro::Ubuntu) => "install the NVIDIA driver: sudo ubuntu-drivers install".to_string(),
+        Some(Distro::Debian) => {
+            format!("install the NVIDIA driver from non-free: {}", Distro::Debian.install(&["nvidia-driver"]))
+        }
+        Some(Distro::Fedora) => {
+            format!("install the NVIDIA driver from RPM Fusion: {}", Distro::Fedora.install(&["akmod-nvidia"]))
+        }
+        Some(Distro::Arch) => {
+            let package = if open_modules { "nvidia-open" } else { "nvidia-dkms" };
+            format!("install the NVIDIA driver: {}", Distro::Arch.install(&[package]))
+        }
+        Some(Distro::OpenSuse) => {
+            let package = if open_modules { "nvidia-open-driver-G06-signed-kmp-default" } else { "nvidia-video-G05" };
+            format!("install the NVIDIA driver: {}", Distro::OpenSuse.install(&[package]))
+        }
+        None if open_modules => "install the NVIDIA driver's open kernel modules".to_string(),
+        None => "install the proprietary NVIDIA driver".to_string(),
+    }
+}
+
+/// The in-kernel driver refuses to bind without its firmware
+fn firmware(driver: &str, distro: Option<Distro>, packages: &[&str]) -> String {
+    match distro {
+        Some(distro) => format!("install the firmware {} needs: {}", driver, distro.install(packages)),
+        None => format!("install the firmware {} needs, usually packaged as linux-firmware", driver),
+    }
+}
+
+fn firmware_packages(vendor: GpuVendor, distro: Option<Distro>) -> &'static [&'static str] {
+    match (vendor, distro) {
+        (GpuVendor::Amd, Some(Distro::Debian)) => &["firmware-amd-graphics"],
+        (GpuVendor::Amd, Some(Distro::Fedora)) => &["amd-gpu-firmware"],
+        (GpuVendor::Amd, Some(Distro::OpenSuse)) => &["kernel-firmware-amdgpu"],
+        (GpuVendor::Intel, Some(Distro::Debian)) => &["firmware-misc-nonfree"],
+        (GpuVendor::Intel, Some(Distro::Fedora)) => &["intel-gpu-firmware"],
+        (GpuVendor::Intel, Some(Distro::OpenSuse)) => &["kernel-firmware-i915"],
+        _ => &["linux-firmware"],
+    }
+}
+
+/// Mesa's OpenGL and Vulkan drivers for a kernel driver, with the X.Org
+/// driver for AMD GPUs on X11
+fn mesa_packages(kernel_driver: &str, distro: Option<Distro>) -> Vec<&'static str> {
+    let x11 = session::session_kind() == Some(SessionKind::X11);
+    let mut packages = match distro {
+        Some(Distro::Debian | Distro::Ubuntu) => vec!["libgl1-mesa-dri", "mesa-vulkan-drivers"],
+        Some(Distro::Fedora) => vec!["mesa-dri-drivers", "mesa-vulkan-drivers"],
+        Some(Distro::Arch) => vec![
+            "mesa",
+            match kernel_driver {
+                "i915" | "xe" => "vulkan-intel",
+                "nouveau" => "vulkan-nouveau",
+                _ => "vulkan-radeon",
+            },
+        ],
+        Some(Distro::OpenSuse) => vec![
+            "Mesa-dri",
+            match kernel_driver {
+                "i915" | "xe" => "libvulkan_intel",
+                "nouveau" => "Mesa-dri-nouveau",
+                _ => "libvulkan_radeon",
+            },
+        ],
+        None => Vec::new(),
+    };
+    if x11 && kernel_driver == "amdgpu" {
+        match distro {
+            Some(Distro::Debian | Distro::Ubuntu) => packages.push("xserver-xorg-video-amdgpu"),
+            Some(Distro::Arch) => packages.push("xf86-video-amdgpu"),
+            Some(Distro::Fedora) => packages.push("xorg-x11-drv-amdgpu"),
+            Some(Distro::OpenSuse) => packages.push("xf86-video-amdgpu"),
+            None => {}
+        }
+    }
+    packages
+}
+
+/// The firmware-provided framebuffer driving the screen, when no GPU
+/// driver has taken over
+fn fallback_framebuffer() -> Option<&'static str> {
+    let name = fs::read_to_string(sysroot::path("/sys/class/graphics/fb0/name")).ok()?;
+    match name.trim() {
+        name if name.starts_with("simpledrm") => Some("simpledrm"),
+        "EFI VGA" => Some("efifb"),
+        "VESA VGA" => Some("vesafb"),
+        _ => None,
+    }
+}
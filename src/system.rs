@@ -0,0 +1,99 @@
+//! The machine around the GPU, for `--system-info`.
+//!
+//! Everything comes from `/proc` and `/etc/os-release`, read under the
+//! sysfs root so a container bind-mounting the host reports the host.
+
+use std::fs;
+
+use crate::gpu::sysroot;
+use crate::privacy::Anonymizer;
+
+/// Host, operating system, CPU and memory
+#[derive(Debug, Clone, Default)]
+pub struct SystemInfo {
+    pub hostname: Option<String>,
+    /// Distribution name from os-release, e.g. "Ubuntu 24.04 LTS"
+    pub distro: Option<String>,
+    /// Kernel release, e.g. "6.8.0-40-generic"
+    pub kernel: Option<String>,
+    pub cpu: Option<String>,
+    pub memory_bytes: Option<u64>,
+}
+
+impl SystemInfo {
+    /// Read what the system reports; anything unreadable is left out
+    pub fn detect() -> Self {
+        let read = |path: &str| {
+            fs::read_to_string(sysroot::path(path))
+                .ok()
+                .map(|text| text.trim().to_string())
+                .filter(|text| !text.is_empty())
+        };
+
+        SystemInfo {
+            hostname: read("/proc/sys/kernel/hostname"),
+            distro: read("/etc/os-release").and_then(|os_release| distro_name(&os_release)),
+            kernel: read("/proc/sys/kernel/osrelease"),
+            cpu: cpu_name(),
+            memory_bytes: read("/proc/meminfo").and_then(|meminfo| total_memory(&meminfo)),
+        }
+    }
+
+    /// Replace the hostname with its anonymized token
+    pub fn anonymize(&mut self, anonymizer: &Anonymizer) {
+        self.hostname = self.hostname.as_deref().map(|hostname| anonymizer.mask_hostname(hostname));
+    }
+}
+
+/// The distribution's `ID` followed by the `ID_LIKE` ones it derives from,
+/// e.g. ["linuxmint", "ubuntu", "debian"]
+pub fn distro_ids() -> Vec<String> {
+    let Ok(os_release) = fs::read_to_string(sysroot::path("/etc/os-release")) else {
+        return Vec::new();
+    };
+    ["ID", "ID_LIKE"]
+        .iter()
+        .filter_map(|key| os_release_value(&os_release, key))
+        .flat_map(|ids| ids.split_whitespace().map(str::to_string).collect::<Vec<_>>())
+        .collect()
+}
+
+/// The value of a `KEY=value` line, unquoted
+fn os_release_value(os_release: &str, key: &str) -> Option<String> {
+    os_release.lines().find_map(|line| {
+        let value = line.strip_prefix(key)?.strip_prefix('=')?;
+        Some(value.trim_matches('"').to_string())
+    })
+}
+
+/// `PRETTY_NAME`, or `NAME` and `VERSION` for os-release files without one
+fn distro_name(os_release: &str) -> Option<String> {
+    let value = |key: &str| os_release_value(os_release, key);
+
+    value("PRETTY_NAME").or_else(|| match (value("NAME"), value("VERSION")) {
+        (Some(name), Some(version)) => Some(format!("{} {}", name, version)),
+        (name, _) => name,
+    })
+}
+
+/// The CPU's model name, e.g. "AMD Ryzen Z1 Extreme"
+pub fn cpu_name() -> Option<String> {
+    fs::read_to_string(sysroot::path("/proc/cpuinfo")).ok().and_then(|cpuinfo| cpu_model(&cpuinfo))
+}
+
+/// The first CPU's model name; ARM kernels call it "Hardware" or "Model"
+fn cpu_model(cpuinfo: &str) -> Option<String> {
+    ["model name", "Hardware", "Model"].iter().find_map(|key| {
+        cpuinfo.lines().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            (name.trim() == *key && !value.trim().is_empty()).then(|| value.split_whitespace().collect::<Vec<_>>().join(" "))
+        })
+    })
+}
+
+/// `MemTotal` in bytes; meminfo counts in KiB
+fn total_memory(meminfo: &str) -> Option<u64> {
+    let line = meminfo.lines().find_map(|line| line.strip_prefix("MemTotal:"))?;
+    let kib: u64 = line.split_whitespace().next()?.parse().ok()?;
+    Some(kib * 1024)
+}
@@ -0,0 +1,187 @@
+//! Units values are shown in, chosen with `--memory-unit`,
+//! `--frequency-unit` and `--temperature-unit` or the `[units]` config table.
+//!
+//! They apply to everything gpufetch prints: the card and the other views,
+//! every `--watch` frame, `--output json` and csv, `--record` files and the
+//! daemon's answers. Keys and columns of those name the unit their numbers
+//! are in, `vram_bytes`, `clock_mhz` or `temperature_c` in the base units,
+//! and are renamed with them, e.g. to `vram_gib`, `clock_ghz` or
+//! `temperature_f`, so a script never reads a number in a unit it doesn't
+//! expect. Sizes stay in bytes unless a memory unit is chosen, since GiB are
+//! only the default on screen.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryUnit {
+    /// GiB and MiB, counted in powers of 1024; the "24 GB" on a box is 24 GiB
+    #[default]
+    Gib,
+    /// GB and MB, counted in powers of 1000 as drive makers do
+    Gb,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrequencyUnit {
+    #[default]
+    Mhz,
+    Ghz,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemperatureUnit {
+    /// Degrees Celsius
+    #[default]
+    C,
+    /// Degrees Fahrenheit
+    F,
+}
+
+/// User preferences for units; unset fields keep the defaults
+#[derive(Copy, Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Units {
+    /// GiB on screen and bytes in keys and columns when unset
+    pub memory: Option<MemoryUnit>,
+    pub frequency: FrequencyUnit,
+    pub temperature: TemperatureUnit,
+}
+
+impl Units {
+    /// A size in units of 1024 or 1000 to the `power`, e.g. 2 for MiB or MB
+    pub fn scaled(&self, bytes: u64, power: i32) -> f64 {
+        let base: f64 = match self.memory.unwrap_or_default() {
+            MemoryUnit::Gib => 1024.0,
+            MemoryUnit::Gb => 1000.0,
+        };
+        bytes as f64 / base.powi(power)
+    }
+
+    /// Name of the unit of 1024 or 1000 to the `power`
+    pub fn size_label(&self, power: i32) -> &'static str {
+        match (self.memory.unwrap_or_default(), power) {
+            (MemoryUnit::Gb, 1) => "kB",
+            (MemoryUnit::Gb, 2) => "MB",
+            (MemoryUnit::Gb, _) => "GB",
+            (MemoryUnit::Gib, 1) => "KiB",
+            (MemoryUnit::Gib, 2) => "MiB",
+            (MemoryUnit::Gib, _) => "GiB",
+        }
+    }
+
+    /// A memory size such as "10.0 GiB" or "512 MB"
+    pub fn memory(&self, bytes: u64) -> String {
+        let gigabytes = self.scaled(bytes, 3);
+        if gigabytes >= 1.0 {
+            format!("{:.1} {}", gigabytes, self.size_label(3))
+        } else {
+            format!("{:.0} {}", self.scaled(bytes, 2).floor(), self.size_label(2))
+        }
+    }
+
+    /// A clock such as "1710 MHz" or "1.71 GHz"
+    pub fn frequency(&self, mhz: u32) -> String {
+        match self.frequency {
+            FrequencyUnit::Mhz => format!("{} MHz", mhz),
+            FrequencyUnit::Ghz => format!("{:.2} GHz", f64::from(mhz) / 1000.0),
+        }
+    }
+
+    /// A temperature given in Celsius, such as "45°C" or "113°F"
+    pub fn temperature(&self, celsius: f64) -> String {
+        match self.temperature {
+            TemperatureUnit::C => format!("{:.0}°C", celsius),
+            TemperatureUnit::F => format!("{:.0}°F", celsius * 9.0 / 5.0 + 32.0),
+        }
+    }
+
+    /// `name`, a key or column ending in the base unit of its numbers, ending
+    /// in the chosen unit instead, e.g. `clock_ghz` for `clock_mhz`
+    pub fn rename(&self, name: &str) -> String {
+        match (base_unit(name), self.memory, self.frequency, self.temperature) {
+            (Some((stem, BaseUnit::Bytes)), Some(MemoryUnit::Gib), _, _) => format!("{}_gib", stem),
+            (Some((stem, BaseUnit::Bytes)), Some(MemoryUnit::Gb), _, _) => format!("{}_gb", stem),
+            (Some((stem, BaseUnit::Mhz)), _, FrequencyUnit::Ghz, _) => format!("{}_ghz", stem),
+            (Some((stem, BaseUnit::Celsius)), _, _, TemperatureUnit::F) => format!("{}_f", stem),
+            _ => name.to_string(),
+        }
+    }
+
+    /// A number given in the base unit `name` ends in, in the unit `rename`
+    /// gives it; None when that is the base unit itself
+    pub fn convert(&self, name: &str, value: f64) -> Option<f64> {
+        match (base_unit(name)?.1, self.memory, self.frequency, self.temperature) {
+            (BaseUnit::Bytes, Some(MemoryUnit::Gib), _, _) => Some(value / 1024_f64.powi(3)),
+            (BaseUnit::Bytes, Some(MemoryUnit::Gb), _, _) => Some(value / 1e9),
+            (BaseUnit::Mhz, _, FrequencyUnit::Ghz, _) => Some(value / 1000.0),
+            (BaseUnit::Celsius, _, _, TemperatureUnit::F) => Some(value * 9.0 / 5.0 + 32.0),
+            _ => None,
+        }
+    }
+
+    /// `value` as JSON, with `rename` and `convert` applied to every key
+    /// holding a number or null
+    ///
+    /// Provenance maps are left alone: their keys name fields of the model
+    /// rather than hold numbers.
+    pub fn json<T: Serialize + ?Sized>(&self, value: &T) -> serde_json::Result<Value> {
+        let mut json = serde_json::to_value(value)?;
+        self.convert_json(&mut json);
+        Ok(json)
+    }
+
+    fn convert_json(&self, json: &mut Value) {
+        match json {
+            Value::Array(items) => items.iter_mut().for_each(|item| self.convert_json(item)),
+            Value::Object(map) => {
+                for (key, mut value) in std::mem::take(map) {
+                    let key = match value {
+                        Value::Number(ref number) => {
+                            if let Some(converted) = number.as_f64().and_then(|number| self.convert(&key, number)) {
+                                value = serde_json::Number::from_f64(converted).map_or(Value::Null, Value::Number);
+                            }
+                            self.rename(&key)
+                        }
+                        Value::Null => self.rename(&key),
+                        _ => {
+                            if key != "provenance" {
+                                self.convert_json(&mut value);
+                            }
+                            key
+                        }
+                    };
+                    map.insert(key, value);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The unit a key or column holds its numbers in
+#[derive(Clone, Copy)]
+enum BaseUnit {
+    Bytes,
+    Mhz,
+    Celsius,
+}
+
+/// The stem and base unit of `name`, e.g. `clock` and MHz for `clock_mhz`
+///
+/// Cache sizes in the JSON model are in bytes without saying so.
+fn base_unit(name: &str) -> Option<(&str, BaseUnit)> {
+    if let Some(stem) = name.strip_suffix("_bytes") {
+        Some((stem, BaseUnit::Bytes))
+    } else if matches!(name, "l1_size" | "l2_size" | "l3_size") {
+        Some((name, BaseUnit::Bytes))
+    } else if let Some(stem) = name.strip_suffix("_mhz") {
+        Some((stem, BaseUnit::Mhz))
+    } else {
+        name.strip_suffix("_c").map(|stem| (stem, BaseUnit::Celsius))
+    }
+}
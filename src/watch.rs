@@ -0,0 +1,292 @@
+//! `--watch`: the card redrawn every `--refresh-interval` seconds.
+//!
+//! Each refresh detects again and follows the GPUs shown at the start by
+//! PCI address, so a card coming or going does not swap what is on screen:
+//! one that goes is shown as detached with its last values, and not
+//! recorded, until it comes back, and when all GPUs are shown, one that
+//! comes is added at the end.
+//! GPUs with an energy counter also show their average power since the
+//! refresh before.
+//! With `--record FILE`, the values that change while the machine works,
+//! such as clocks, temperatures, power, voltage, fans and memory use, are
+//! appended to the file once per refresh and GPU, in the units chosen for
+//! the card with the keys and columns renamed to match. Every sample is
+//! written out as soon as it is taken, so interrupting gpufetch loses nothing.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::fs::{File, OpenOptions};
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+use crate::alerts::Alerts;
+use crate::daemon;
+use crate::display::{self, DisplayOptions};
+use crate::gpu::common::GpuInfo;
+use crate::units::Units;
+
+/// How `--record` writes samples
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RecordFormat {
+    /// One JSON object per line
+    Jsonl,
+    /// A header, then one row per sample
+    Csv,
+}
+
+/// CSV column names, in output order; new ones only go at the end
+const COLUMNS: [&str; 17] = [
+    "timestamp",
+    "pci_address",
+    "name",
+    "clock_mhz",
+    "temperature_c",
+    "junction_temperature_c",
+    "memory_temperature_c",
+    "power_draw_w",
+    "energy_j",
+    "fan_percent",
+    "fan_rpm",
+    "encoder_utilization_percent",
+    "pstate",
+    "throttle_reasons",
+    "memory_used_bytes",
+    "average_power_w",
+    "core_voltage_mv",
+];
+
+/// The values of one GPU at one refresh
+#[derive(Debug, Serialize)]
+struct Sample<'a> {
+    /// Seconds since the Unix epoch
+    timestamp: f64,
+    pci_address: Option<String>,
+    name: &'a str,
+    clock_mhz: Option<u32>,
+    temperature_c: Option<f64>,
+    junction_temperature_c: Option<f64>,
+    memory_temperature_c: Option<f64>,
+    power_draw_w: Option<f64>,
+    energy_j: Option<f64>,
+    fan_percent: Option<u32>,
+    fan_rpm: Option<u32>,
+    encoder_utilization_percent: Option<u32>,
+    pstate: Option<&'a str>,
+    throttle_reasons: &'a [&'static str],
+    memory_used_bytes: Option<u64>,
+    /// Over the time since the previous refresh
+    average_power_w: Option<f64>,
+    core_voltage_mv: Option<u32>,
+}
+
+impl<'a> Sample<'a> {
+    fn new(timestamp: f64, gpu: &'a GpuInfo) -> Self {
+        let state = gpu.performance_state.as_ref();
+        Sample {
+            timestamp,
+            pci_address: gpu.pci_info.as_ref().map(|pci_info| pci_info.address()),
+            name: &gpu.name,
+            clock_mhz: (gpu.freq_mhz > 0).then_some(gpu.freq_mhz),
+            temperature_c: gpu.temperature_c,
+            junction_temperature_c: gpu.junction_temperature_c,
+            memory_temperature_c: gpu.memory_temperature_c,
+            power_draw_w: gpu.power_draw_w,
+            energy_j: gpu.energy_j,
+            fan_percent: gpu.fan.and_then(|fan| fan.percent),
+            fan_rpm: gpu.fan.and_then(|fan| fan.rpm),
+            encoder_utilization_percent: gpu.encoder_utilization_percent,
+            pstate: state.and_then(|state| state.pstate.as_deref()),
+            throttle_reasons: state.map_or(&[], |state| &state.throttle_reasons),
+            memory_used_bytes: gpu.memory_used_bytes,
+            average_power_w: gpu.average_power_w,
+            core_voltage_mv: gpu.core_voltage_mv,
+        }
+    }
+
+    fn row(&self, units: Units) -> [String; 17] {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        let measure = |column: &str, value: f64| units.convert(column, value).unwrap_or(value).to_string();
+        let celsius = |column: &str, celsius: f64| format!("{:.1}", units.convert(column, celsius).unwrap_or(celsius));
+        [
+            format!("{:.3}", self.timestamp),
+            optional(self.pci_address.clone()),
+            display::csv::escape(self.name),
+            optional(self.clock_mhz.map(|mhz| measure("clock_mhz", f64::from(mhz)))),
+            optional(self.temperature_c.map(|value| celsius("temperature_c", value))),
+            optional(self.junction_temperature_c.map(|value| celsius("junction_temperature_c", value))),
+            optional(self.memory_temperature_c.map(|value| celsius("memory_temperature_c", value))),
+            optional(self.power_draw_w.map(|watts| format!("{:.2}", watts))),
+            optional(self.energy_j.map(|joules| format!("{:.1}", joules))),
+            optional(self.fan_percent.map(|percent| percent.to_string())),
+            optional(self.fan_rpm.map(|rpm| rpm.to_string())),
+            optional(self.encoder_utilization_percent.map(|percent| percent.to_string())),
+            optional(self.pstate.map(str::to_string)),
+            // Spreadsheets split on commas, so reasons are joined with semicolons
+            display::csv::escape(&self.throttle_reasons.join(";")),
+            optional(self.memory_used_bytes.map(|bytes| measure("memory_used_bytes", bytes as f64))),
+            optional(self.average_power_w.map(|watts| format!("{:.2}", watts))),
+            optional(self.core_voltage_mv.map(|millivolts| millivolts.to_string())),
+        ]
+    }
+}
+
+/// The file `--record` appends to
+struct Recorder {
+    file: File,
+    format: RecordFormat,
+    units: Units,
+}
+
+impl Recorder {
+    /// Open `path` for appending, writing the CSV header to new files
+    fn open(path: &Path, units: Units) -> Result<Self> {
+        let csv = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+        let format = if csv { RecordFormat::Csv } else { RecordFormat::Jsonl };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open {} for recording", path.display()))?;
+
+        let empty = file.metadata().map_or(true, |metadata| metadata.len() == 0);
+        if format == RecordFormat::Csv && empty {
+            let header: Vec<String> = COLUMNS.iter().map(|column| units.rename(column)).collect();
+            writeln!(file, "{}", header.join(","))?;
+        }
+        Ok(Recorder { file, format, units })
+    }
+
+    /// Append a sample of each GPU, all with the same timestamp
+    fn record<'a>(&mut self, gpus: impl IntoIterator<Item = &'a GpuInfo>) -> Result<()> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0.0, |elapsed| elapsed.as_secs_f64());
+        let mut lines = String::new();
+        for gpu in gpus {
+            let sample = Sample::new(timestamp, gpu);
+            let line = match self.format {
+                RecordFormat::Jsonl => serde_json::to_string(&self.units.json(&sample)?)?,
+                RecordFormat::Csv => sample.row(self.units).join(","),
+            };
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+        // One write per refresh, so an interrupt never leaves half a line
+        self.file.write_all(lines.as_bytes()).context("Failed to write the recording")
+    }
+}
+
+/// The GPUs shown before, as found by the latest detection, and whether
+/// each one still is
+///
+/// GPUs are matched by PCI address, and by position when they have none.
+/// One that is no longer found keeps its last values and is detached until
+/// it comes back; GPUs found for the first time go at the end if `add_new`.
+fn follow(shown: &[GpuInfo], detected: Vec<GpuInfo>, add_new: bool) -> (Vec<GpuInfo>, Vec<bool>) {
+    let address = |gpu: &GpuInfo| gpu.pci_info.as_ref().map(|pci_info| pci_info.address());
+    let mut detected: Vec<Option<GpuInfo>> = detected.into_iter().map(Some).collect();
+    let (mut gpus, mut attached): (Vec<GpuInfo>, Vec<bool>) = shown
+        .iter()
+        .enumerate()
+        .map(|(index, gpu)| {
+            let found = match address(gpu) {
+                Some(ref shown_address) => detected
+                    .iter_mut()
+                    .find(|candidate| candidate.as_ref().is_some_and(|candidate| address(candidate).as_ref() == Some(shown_address))),
+                None => detected
+                    .get_mut(index)
+                    .filter(|candidate| candidate.as_ref().is_some_and(|candidate| candidate.pci_info.is_none())),
+            };
+            match found.and_then(Option::take) {
+                Some(found) => (found, true),
+                None => (gpu.clone(), false),
+            }
+        })
+        .unzip();
+    for gpu in detected.into_iter().flatten().filter(|_| add_new) {
+        gpus.push(gpu);
+        attached.push(true);
+    }
+    (gpus, attached)
+}
+
+/// `gpus` as drawn, with the ones no longer found named as detached
+fn mark_detached<'a>(gpus: &'a [GpuInfo], attached: &[bool]) -> Cow<'a, [GpuInfo]> {
+    if attached.iter().all(|&attached| attached) {
+        return Cow::Borrowed(gpus);
+    }
+    let marked = gpus
+        .iter()
+        .zip(attached)
+        .map(|(gpu, &attached)| {
+            let mut gpu = gpu.clone();
+            if !attached {
+                gpu.name = format!("{} (detached)", gpu.name);
+            }
+            gpu
+        })
+        .collect();
+    Cow::Owned(marked)
+}
+
+/// Show `gpus` until interrupted, detecting again with `detect` every
+/// `interval`, recording each refresh to `record` and checking `alerts`
+///
+/// GPUs detected for the first time are shown too if `add_new`, as when all
+/// of them are compared.
+pub fn run(
+    mut gpus: Vec<GpuInfo>,
+    add_new: bool,
+    options: &DisplayOptions,
+    interval: Duration,
+    record: Option<&Path>,
+    mut alerts: Alerts,
+    mut detect: impl FnMut() -> Result<Vec<GpuInfo>>,
+) -> Result<()> {
+    let mut recorder = record.map(|path| Recorder::open(path, options.units)).transpose()?;
+    // Frames are drawn over each other on a terminal and follow each other in a pipe
+    let redraw = io::stdout().is_terminal();
+    // When the values shown were detected, for the average power in between
+    let mut sampled = Instant::now();
+
+    // Whether the latest detection found each GPU in `gpus`
+    let mut attached = vec![true; gpus.len()];
+
+    for frame in 0.. {
+        if redraw {
+            print!("\x1b[H\x1b[2J");
+        } else if frame > 0 {
+            println!();
+        }
+        match &*mark_detached(&gpus, &attached) {
+            [gpu] => display::print_gpufetch(gpu, options)?,
+            shown => display::compare::print_comparison(shown, options),
+        }
+        io::stdout().flush()?;
+        if let Some(ref mut recorder) = recorder {
+            // Detached GPUs have nothing new to record
+            recorder.record(gpus.iter().zip(&attached).filter_map(|(gpu, &attached)| attached.then_some(gpu)))?;
+        }
+        alerts.check(&gpus);
+
+        thread::sleep(interval);
+        match detect() {
+            Ok(detected) => {
+                let previous = std::mem::take(&mut gpus);
+                (gpus, attached) = follow(&previous, detected, add_new);
+                let seconds = sampled.elapsed().as_secs_f64();
+                sampled = Instant::now();
+                for ((gpu, previous), &attached) in gpus.iter_mut().zip(&previous).zip(&attached) {
+                    if attached {
+                        gpu.average_power_w = daemon::average_power(gpu, previous, seconds);
+                    }
+                }
+            }
+            // Keep showing the last values; the next refresh may work again
+            Err(e) => warn!("detection failed: {:#}", e),
+        }
+    }
+    Ok(())
+}
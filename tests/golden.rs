@@ -0,0 +1,215 @@
+//! Golden-output tests: each machine under `tests/fixtures` is an unpacked
+//! `gpufetch dump` snapshot, replayed with `--replay`, and its JSON and card
+//! output must match `tests/golden` byte for byte.
+//!
+//! Run with `GPUFETCH_BLESS=1` to rewrite the goldens after an intended
+//! change, and review them in the diff.
+
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// gpufetch replaying a fixture, away from this machine's config and tools
+fn command(fixture: &Path, args: &[&str]) -> Command {
+    let home = env::temp_dir().join("gpufetch-golden-no-home");
+    let mut command = Command::new(env!("CARGO_BIN_EXE_gpufetch"));
+    command
+        .arg("--replay")
+        .arg(fixture)
+        .args(args)
+        .env_clear()
+        .env("LANG", "C.UTF-8")
+        .env("HOME", &home)
+        .env("PATH", "");
+    command
+}
+
+/// Run gpufetch on a fixture and return what it printed
+fn gpufetch(fixture: &Path, args: &[&str]) -> String {
+    let output = command(fixture, args).output().expect("failed to run gpufetch");
+    assert!(
+        output.status.success(),
+        "gpufetch {:?} failed on {}: {}",
+        args,
+        fixture.display(),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("gpufetch printed invalid UTF-8")
+}
+
+/// Compare output with its golden file, or write it when blessing
+fn check(golden: &Path, actual: &str) {
+    if env::var_os("GPUFETCH_BLESS").is_some() {
+        fs::create_dir_all(golden.parent().expect("golden files are in a directory")).expect("failed to create tests/golden");
+        fs::write(golden, actual).expect("failed to write golden file");
+        return;
+    }
+    let expected = fs::read_to_string(golden)
+        .unwrap_or_else(|_| panic!("{} is missing; run with GPUFETCH_BLESS=1 to create it", golden.display()));
+    if expected != actual {
+        let line = expected.lines().zip(actual.lines()).position(|(a, b)| a != b).unwrap_or_else(|| {
+            expected.lines().count().min(actual.lines().count())
+        });
+        panic!(
+            "output differs from {} from line {}:\n-{}\n+{}\nrun with GPUFETCH_BLESS=1 if the change is intended",
+            golden.display(),
+            line + 1,
+            expected.lines().nth(line).unwrap_or("<end of file>"),
+            actual.lines().nth(line).unwrap_or("<end of file>")
+        );
+    }
+}
+
+/// Check the JSON of every GPU of a fixture, and the card of each
+fn golden(name: &str) {
+    let tests = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let fixture = tests.join("fixtures").join(name);
+    let golden = tests.join("golden");
+
+    let json = gpufetch(&fixture, &["--output", "json"]);
+    let gpus: Vec<serde_json::Value> = serde_json::from_str(&json).expect("--output json printed invalid JSON");
+    assert!(!gpus.is_empty(), "no GPU detected in {}", fixture.display());
+    check(&golden.join(format!("{}.json", name)), &json);
+
+    let cards: Vec<String> = (0..gpus.len()).map(|index| gpufetch(&fixture, &["--gpu-index", &index.to_string()])).collect();
+    check(&golden.join(format!("{}.txt", name)), &cards.join("\n"));
+}
+
+macro_rules! golden_tests {
+    ($($name:ident),* $(,)?) => {
+        $(
+            #[test]
+            fn $name() {
+                golden(stringify!($name));
+            }
+        )*
+    };
+}
+
+golden_tests!(
+    a100_nvlink,
+    arc_a770,
+    gtx_1060_nouveau,
+    instinct_mi210,
+    raphael_igpu,
+    optimus_secure_boot,
+    rtx_3060_laptop,
+    rtx_4090,
+    rx_6700_xt,
+    rx_7900_xtx,
+    steam_deck,
+    uhd_620,
+    virtio_gpu,
+);
+
+/// `--watch -d` keeps the values only the detailed view asks for, such as
+/// intel_gpu_top's engine load and VA-API codecs, on every refresh and not
+/// just the first
+#[test]
+fn watch_keeps_detailed_values() {
+    let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/uhd_620");
+    let mut child = command(&fixture, &["--watch", "-d", "--refresh-interval", "1"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to run gpufetch");
+
+    // Watch mode runs until killed, so lines are read on the side
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let (sender, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    // Every frame starts with the card's title; the third one ends the second
+    let mut frames: Vec<Vec<String>> = Vec::new();
+    while frames.len() < 3 {
+        let line = lines.recv_timeout(Duration::from_secs(30)).expect("gpufetch --watch stopped drawing");
+        if line.trim() == "Intel UHD Graphics 620" {
+            frames.push(Vec::new());
+        } else if let Some(frame) = frames.last_mut().filter(|_| !line.trim().is_empty()) {
+            frame.push(line);
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+
+    for label in ["Engine Utilization:", "Video Decode:"] {
+        assert!(
+            frames[0].iter().any(|line| line.contains(label)),
+            "the first frame has no {:?} line:\n{}",
+            label,
+            frames[0].join("\n")
+        );
+    }
+    assert_eq!(frames[0], frames[1], "the second frame differs from the first");
+}
+
+/// `--screenshot` paints the theme's 24-bit colors whatever the terminal,
+/// here without `COLORTERM`
+#[test]
+fn screenshot_keeps_truecolor() {
+    let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/uhd_620");
+    let path = env::temp_dir().join(format!("gpufetch-screenshot-{}.svg", std::process::id()));
+    gpufetch(&fixture, &["--screenshot", path.to_str().expect("temp path is UTF-8")]);
+    let svg = fs::read_to_string(&path).expect("no screenshot was saved");
+    let _ = fs::remove_file(&path);
+
+    // The middle of Intel's logo gradient, which has no named color
+    assert!(svg.contains("#0071c5"), "the screenshot lost the theme's 24-bit colors");
+}
+
+/// JSON and CSV name the chosen units in their keys and columns and give
+/// their numbers in them
+#[test]
+fn output_follows_chosen_units() {
+    let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/rx_7900_xtx");
+    let units = ["--memory-unit", "gib", "--frequency-unit", "ghz", "--temperature-unit", "f"];
+
+    let json = gpufetch(&fixture, &[&["--output", "json"][..], &units].concat());
+    for key in ["\"size_gib\": 24.0", "\"max_freq_ghz\": 2.498", "\"temperature_f\": 100.4"] {
+        assert!(json.contains(key), "the JSON has no {}:\n{}", key, json);
+    }
+    for key in ["\"size_bytes\"", "\"freq_mhz\"", "\"temperature_c\""] {
+        assert!(!json.contains(key), "the JSON kept {}:\n{}", key, json);
+    }
+
+    let csv = gpufetch(&fixture, &[&["--output", "csv"][..], &units].concat());
+    let mut lines = csv.lines();
+    let header: Vec<&str> = lines.next().expect("no CSV header").split(',').collect();
+    let row: Vec<&str> = lines.next().expect("no CSV row").split(',').collect();
+    let value = |column: &str| header.iter().position(|name| *name == column).map(|index| row[index]);
+    assert_eq!(value("vram_gib"), Some("24"));
+    assert_eq!(value("max_clock_ghz"), Some("2.498"));
+    assert_eq!(value("temperature_f"), Some("100"));
+}
+
+/// `--serve` answers a request whose headers never end within the cap with
+/// 431 instead of reading on
+#[test]
+fn serve_caps_request_headers() {
+    let fixture = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/rtx_4090");
+    let mut child = command(&fixture, &["--serve", "127.0.0.1:0"]).stdout(Stdio::piped()).spawn().expect("failed to run gpufetch");
+    let mut banner = String::new();
+    BufReader::new(child.stdout.take().expect("stdout is piped")).read_line(&mut banner).expect("gpufetch printed nothing");
+    let address = banner.trim().trim_start_matches("Serving http://").trim_end_matches("/gpus").to_string();
+
+    let mut stream = TcpStream::connect(&address).expect("failed to connect");
+    let request = format!("GET /gpus HTTP/1.1\r\nX-Padding: {}\r\n", "a".repeat(20_000));
+    stream.write_all(request.as_bytes()).expect("failed to send the request");
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response).expect("no response");
+    let _ = child.kill();
+    let _ = child.wait();
+
+    assert!(response.starts_with("HTTP/1.1 431"), "unexpected response: {}", response);
+}